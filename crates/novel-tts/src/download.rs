@@ -7,6 +7,7 @@ use crate::NovelTTSError;
 use std::{
     io::SeekFrom,
     path::{Path, PathBuf},
+    sync::OnceLock,
 };
 use tokio::fs;
 use tokio::{
@@ -18,11 +19,24 @@ use tokio_util::sync::CancellationToken;
 /// 缓存目录名称
 pub static CACHE_DIR: &str = ".novel-tts";
 
+/// 通过 [`set_cache_dir`] 指定的缓存目录
+static CACHE_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// 指定缓存目录，替代默认的 `~/.novel-tts`
+///
+/// 只能在首次下载或加载模型前设置一次，之后的调用会被忽略。
+pub fn set_cache_dir(dir: PathBuf) {
+    let _ = CACHE_DIR_OVERRIDE.set(dir);
+}
+
 /// 获取缓存目录路径
 ///
 /// # 返回值
 /// 返回Result包装的PathBuf，包含缓存目录的路径
 pub fn get_cache_dir() -> Result<PathBuf> {
+    if let Some(dir) = CACHE_DIR_OVERRIDE.get() {
+        return Ok(dir.clone());
+    }
     Ok(dirs::home_dir()
         .map(|home| home.join(CACHE_DIR))
         .ok_or_else(|| anyhow::anyhow!("No home directory found"))?)
@@ -0,0 +1,229 @@
+//! 规则计划基准:3000 章目录与 20 项搜索页上,对比「每次编译 + 列表项重新解析」的解释求值
+//! (`eval_list` + `eval_value`)与预编译计划 + 共享文档(`Plan` + `Page`),并给出
+//! `Engine::toc` 端到端耗时。
+//!
+//! ```bash
+//! cargo bench -p parse-book-source --bench rule_plan
+//! ```
+
+use async_trait::async_trait;
+use parse_book_source::eval::{Page, Plan, Vars, eval_list, eval_value};
+use parse_book_source::source::Rule;
+use parse_book_source::{BookSource, Engine, FetchError, FetchRequest, Fetcher};
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const CHAPTERS: usize = 3000;
+
+fn toc_html(chapters: usize) -> String {
+    let mut html = String::from(r#"<html><body><div class="box">"#);
+    for i in 0..chapters {
+        if i % 100 == 0 {
+            html.push_str(&format!(
+                r#"<h2 class="module-title type">第{}卷</h2>"#,
+                i / 100 + 1
+            ));
+        }
+        html.push_str(&format!(
+            r#"<div class="module-row-info"><a class="module-row-text" href="/n/{i}.html"><i></i><div class="module-row-title"><span>第{i}章 标题</span></div></a></div>"#
+        ));
+    }
+    html.push_str("</div></body></html>");
+    html
+}
+
+fn search_html(items: usize) -> String {
+    let mut html = String::from(r#"<html><body><ul class="result">"#);
+    for i in 0..items {
+        html.push_str(&format!(
+            r#"<li class="book"><h3><a href="/book/{i}">书名{i}</a></h3><p class="author">作者{i}</p>
+            <p class="intro">简介 {i} —— 一段不算短的简介文字,用来撑起列表项的体量。</p>
+            <img src="/cover/{i}.jpg"><span class="latest">第{i}章 最新</span></li>"#
+        ));
+    }
+    html.push_str("</ul></body></html>");
+    html
+}
+
+fn rule(json: &str) -> Rule {
+    serde_json::from_str(json).expect("rule json")
+}
+
+struct TocRules {
+    list: Rule,
+    name: Rule,
+    url: Rule,
+    is_volume: Rule,
+}
+
+fn toc_rules() -> TocRules {
+    TocRules {
+        list: rule(
+            r#"{"via":"css","select":".box > h2.module-title.type, .box a.module-row-text"}"#,
+        ),
+        name: rule(
+            r#"{"firstOf":[{"via":"css","select":".module-row-title","extract":"text"},{"via":"css","select":"h2","extract":"text"}],
+            "clean":[]}"#,
+        ),
+        url: rule(
+            r#"{"via":"css","select":"a","extract":{"attr":"href"},"clean":[{"regex":"\\.html$","replace":""}]}"#,
+        ),
+        is_volume: rule(r#"{"via":"css","select":"h2","extract":"text"}"#),
+    }
+}
+
+/// 解释求值:每条规则每次现场编译,列表项是序列化后的 HTML 片段、逐项重新解析。
+fn toc_interpreted(html: &str, rules: &TocRules) -> usize {
+    let vars = Vars::new();
+    let mut count = 0;
+    for item in eval_list(&rules.list, html).unwrap() {
+        let title = eval_value(&rules.name, &item, &vars).unwrap();
+        if eval_value(&rules.is_volume, &item, &vars)
+            .unwrap()
+            .is_empty()
+        {
+            black_box(eval_value(&rules.url, &item, &vars).unwrap());
+        }
+        black_box(title);
+        count += 1;
+    }
+    count
+}
+
+/// 预编译计划:规则编译一次,整页解析一次,列表项是共享文档里的节点。
+fn toc_planned(html: &str, plans: &[Plan; 4]) -> usize {
+    let [list, name, url, is_volume] = plans;
+    let vars = Vars::new();
+    let page = Page::new(html);
+    let mut count = 0;
+    for item in list.select(&page, &Vars::new()).unwrap() {
+        let title = name.eval(&item, &vars).unwrap();
+        if is_volume.eval(&item, &vars).unwrap().is_empty() {
+            black_box(url.eval(&item, &vars).unwrap());
+        }
+        black_box(title);
+        count += 1;
+    }
+    count
+}
+
+fn search_rules() -> Vec<Rule> {
+    [
+        r#"{"via":"css","select":"h3 a"}"#,
+        r#"{"via":"css","select":".author","clean":[{"regex":"^作者[::]?","replace":""}]}"#,
+        r#"{"via":"css","select":".intro"}"#,
+        r#"{"via":"css","select":"img","extract":{"attr":"src"}}"#,
+        r#"{"via":"css","select":".latest"}"#,
+        r#"{"via":"css","select":"h3 a","extract":{"attr":"href"}}"#,
+    ]
+    .into_iter()
+    .map(rule)
+    .collect()
+}
+
+/// 跑 `rounds` 轮取中位数。
+fn measure(rounds: usize, mut f: impl FnMut() -> usize) -> Duration {
+    let mut times: Vec<Duration> = (0..rounds)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    times[rounds / 2]
+}
+
+fn report(name: &str, interpreted: Duration, planned: Duration) {
+    println!(
+        "{name:<24} 解释求值 {:>10.2?}   预编译计划 {:>10.2?}   加速 {:.1}x",
+        interpreted,
+        planned,
+        interpreted.as_secs_f64() / planned.as_secs_f64()
+    );
+}
+
+struct StubFetcher(String);
+
+#[async_trait]
+impl Fetcher for StubFetcher {
+    async fn fetch(&self, _req: FetchRequest) -> Result<String, FetchError> {
+        Ok(self.0.clone())
+    }
+}
+
+fn main() {
+    let toc = toc_html(CHAPTERS);
+    let rules = toc_rules();
+    let plans = [
+        Plan::compile(&rules.list),
+        Plan::compile(&rules.name),
+        Plan::compile(&rules.url),
+        Plan::compile(&rules.is_volume),
+    ];
+    assert_eq!(
+        toc_interpreted(&toc, &rules),
+        toc_planned(&toc, &plans),
+        "两条路径的结果数应一致"
+    );
+    report(
+        &format!("目录 {CHAPTERS} 章"),
+        measure(5, || toc_interpreted(&toc, &rules)),
+        measure(5, || toc_planned(&toc, &plans)),
+    );
+
+    let search = search_html(20);
+    let rules = search_rules();
+    let list = rule(r#"{"via":"css","select":"li.book"}"#);
+    let list_plan = Plan::compile(&list);
+    let item_plans: Vec<Plan> = rules.iter().map(Plan::compile).collect();
+    let vars = Vars::new();
+    report(
+        "搜索页 20 项",
+        measure(50, || {
+            let items = eval_list(&list, &search).unwrap();
+            for item in &items {
+                for r in &rules {
+                    black_box(eval_value(r, item, &vars).unwrap());
+                }
+            }
+            items.len()
+        }),
+        measure(50, || {
+            let page = Page::new(&search);
+            let items = list_plan.select(&page, &Vars::new()).unwrap();
+            for item in &items {
+                for p in &item_plans {
+                    black_box(p.eval(item, &vars).unwrap());
+                }
+            }
+            items.len()
+        }),
+    );
+
+    let source = BookSource::from_json(
+        r#"{"schema":"trnovel-booksource/v2","name":"bench","url":"https://bench.example","bookInfo":{},
+        "toc":{"list":{"via":"css","select":".box > h2.module-title.type, .box a.module-row-text"},
+               "name":{"firstOf":[{"via":"css","select":".module-row-title"},{"via":"css","select":"h2"}]},
+               "url":{"via":"css","select":"a","extract":{"attr":"href"}},
+               "isVolume":{"via":"css","select":"h2"}},
+        "content":{"value":{"via":"css","select":"p"}}}"#,
+    )
+    .expect("bench source");
+    let engine = Engine::with_fetcher(source, Arc::new(StubFetcher(toc)));
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("tokio runtime");
+    let elapsed = measure(5, || {
+        runtime
+            .block_on(engine.toc("https://bench.example/toc"))
+            .unwrap()
+            .chapters
+            .len()
+    });
+    println!(
+        "{:<24} {elapsed:>10.2?}",
+        format!("Engine::toc {CHAPTERS} 章")
+    );
+}
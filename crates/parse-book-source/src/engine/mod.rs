@@ -4,21 +4,27 @@
 //! 本文件是公开面(构造器 / 访问器 / 五个操作 / 预热);共享的私有管线在 `internal` 子模块。
 
 mod internal;
+mod plan;
 #[cfg(test)]
 mod tests;
 
 use crate::error::{BookSourceError, Result};
-use crate::eval::{eval_list, eval_value};
+use crate::eval::trace::{self, TraceNode};
+use crate::eval::{Ctx, Page};
+use crate::fetch::cache::{CacheStats, CachingFetcher};
 use crate::fetch::cookie::CookieJar;
-use crate::fetch::{Fetcher, ReqwestFetcher};
+use crate::fetch::{FetchOp, Fetcher, ReqwestFetcher};
 use crate::model::{BookInfo, BookList, Chapter, ExploreEntry, Toc, Volume};
 use crate::source::BookSource;
+use plan::SourcePlan;
 use std::collections::{BTreeMap, HashMap};
-use std::sync::{Arc, RwLock};
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
 
 /// [`Engine::send_templated_full`] 的渲染 + 点击翻页参数束(避免十参数):`render-fetcher` 的
 /// `render`/`ready_for`/`intercept_api` + `search-click-pagination` 的目标页 `page` 与「下一页」
-/// 选择器 `page_by`。普通请求(如 prelude)用 `RenderArgs::default()`(全关闭 = 现状 reqwest 单页)。
+/// 选择器 `page_by`,外加发起请求的操作 `op`。普通请求(如 prelude)用 `RenderArgs::default()`
+/// (全关闭 = 现状 reqwest 单页)。
 #[derive(Clone, Copy, Default)]
 struct RenderArgs<'a> {
     render: bool,
@@ -28,12 +34,35 @@ struct RenderArgs<'a> {
     page: u32,
     /// 「下一页」CSS 选择器(`pageBy.click`)。
     page_by: Option<&'a str>,
+    /// 发起请求的操作(标在 [`crate::FetchRequest::op`] 上);prelude 为 `None`。
+    op: Option<FetchOp>,
+    /// 请求所属的操作(求值追踪按它归组);prelude 也带上所属操作,缺省取 `op`。
+    trace_op: Option<FetchOp>,
+}
+
+/// 求值追踪(见 [`crate::eval::trace`]):只记 `op` 这一操作,每个求值阶段一棵树。
+struct Tracer {
+    op: FetchOp,
+    stages: Mutex<Vec<TraceNode>>,
+}
+
+/// 追踪树根节点用的操作名。
+fn stage_name(op: FetchOp) -> &'static str {
+    match op {
+        FetchOp::Search => "搜索",
+        FetchOp::Explore => "浏览",
+        FetchOp::BookInfo => "书详情",
+        FetchOp::Toc => "目录",
+        FetchOp::Content => "正文",
+    }
 }
 
 /// 书源运行时引擎。
 #[derive(Clone)]
 pub struct Engine {
     source: Arc<BookSource>,
+    /// 构造时从 `source` 编译出的规则计划(选择器/正则/JSONPath 已建好),随 `Clone` 共享。
+    plan: Arc<SourcePlan>,
     fetcher: Arc<dyn Fetcher>,
     /// 登录态请求头(JWT/自定义头/Cookie 同路径),并入引擎构造的每个**同注册域**请求
     /// (跨注册域请求跳过,防页面内容诱导的第三方 URL 外泄凭据,见 [`crate::fetch::cookie::merge_login_into_headers`])。
@@ -59,6 +88,8 @@ pub struct Engine {
     /// 共享(`Arc`),per-source 会话级(引擎重建即清空)。**仅完全成功(无动态源报错)才缓存**:
     /// 有动态源失败时返回已成功的部分入口但不缓存,使下次进入可重试。
     entries_cache: Arc<RwLock<Option<Vec<ExploreEntry>>>>,
+    /// 求值追踪(`trn doctor --trace`),默认关闭;随 `Clone` 的引擎共享。
+    tracer: Option<Arc<Tracer>>,
 }
 
 impl std::fmt::Debug for Engine {
@@ -81,6 +112,7 @@ impl Engine {
     /// 其余构造器([`Engine::new`] / `with_browser_assist`)一律委托,避免新增字段漏改。
     pub fn with_fetcher(source: BookSource, fetcher: Arc<dyn Fetcher>) -> Self {
         Self {
+            plan: Arc::new(SourcePlan::compile(&source)),
             source: Arc::new(source),
             fetcher,
             login_header: BTreeMap::new(),
@@ -89,6 +121,7 @@ impl Engine {
             book_vars: Arc::new(RwLock::new(BTreeMap::new())),
             page_cache: Arc::new(RwLock::new(HashMap::new())),
             entries_cache: Arc::new(RwLock::new(None)),
+            tracer: None,
         }
     }
 
@@ -107,6 +140,63 @@ impl Engine {
         }
     }
 
+    /// 给取页后端套上磁盘 HTTP 缓存(见 [`crate::fetch::cache`]),缓存放在 `root` 下本书源的子目录,
+    /// 命中情况计入 `stats`。链式构造。
+    #[must_use]
+    pub fn with_http_cache(self, root: &Path, stats: Arc<CacheStats>) -> Self {
+        let source = self.source.clone();
+        self.wrap_fetcher(|inner| {
+            Arc::new(CachingFetcher::new(inner, &source, root).with_stats(stats))
+        })
+    }
+
+    /// 用装饰器包住当前取页后端(如 [`crate::fetch::replay::RecordingFetcher`])。链式构造。
+    #[must_use]
+    pub fn wrap_fetcher(mut self, wrap: impl FnOnce(Arc<dyn Fetcher>) -> Arc<dyn Fetcher>) -> Self {
+        self.fetcher = wrap(self.fetcher);
+        self
+    }
+
+    /// 打开求值追踪:之后操作 `op` 的每个求值阶段(请求模板、前置捕获、每页的列表与字段、翻页)
+    /// 各记一棵追踪树,由 [`Engine::take_trace`] 取出。追踪有额外开销,只用于排查书源。链式构造。
+    #[must_use]
+    pub fn with_trace(mut self, op: FetchOp) -> Self {
+        self.tracer = Some(Arc::new(Tracer {
+            op,
+            stages: Mutex::new(Vec::new()),
+        }));
+        self
+    }
+
+    /// 取出并清空已记录的追踪树(按发生顺序);未打开追踪时为空。
+    pub fn take_trace(&self) -> Vec<TraceNode> {
+        self.tracer
+            .as_ref()
+            .and_then(|t| t.stages.lock().ok().map(|mut s| std::mem::take(&mut *s)))
+            .unwrap_or_default()
+    }
+
+    /// 在追踪下运行一段同步求值:`op` 是被追踪的操作时,把 `f` 里的求值记成以 `label` 为根、
+    /// `input`(通常是取到的页面)为输入的一棵树。
+    fn traced<R>(
+        &self,
+        op: Option<FetchOp>,
+        label: impl FnOnce() -> String,
+        input: &str,
+        f: impl FnOnce() -> R,
+    ) -> R {
+        match &self.tracer {
+            Some(tracer) if op == Some(tracer.op) => {
+                let (out, node) = trace::collect(label(), input, f);
+                if let Ok(mut stages) = tracer.stages.lock() {
+                    stages.push(node);
+                }
+                out
+            }
+            _ => f(),
+        }
+    }
+
     /// 注入登录态请求头(登录后由调用方从 per-source 状态取出)。链式构造:
     /// `Engine::new(src)?.with_login_header(state.login_header)`。空 map 等同未登录。
     #[must_use]
@@ -199,64 +289,94 @@ impl Engine {
 
     /// 书籍详情(可选前置请求链 → 取详情页 → 抽取)。
     pub async fn book_info(&self, book_url: &str) -> Result<BookInfo> {
+        let plan = &self.plan.book_info;
         let mut chapter = self.base_vars();
-        self.run_prelude(&self.source.book_info.prelude, &mut chapter)
+        self.run_prelude(FetchOp::BookInfo, &plan.prelude, &mut chapter)
             .await?;
-        let html = self.fetch_checked(book_url).await?;
-        let rules = self.source.book_info.as_book_rules();
-        self.eval_book_info(&rules, &html, &self.flatten(&chapter))
+        let html = self.fetch_checked(FetchOp::BookInfo, book_url).await?;
+        let page = Page::new(&html);
+        self.traced(
+            Some(FetchOp::BookInfo),
+            || "书详情".into(),
+            &html,
+            || plan.fields.eval(&Ctx::Page(&page), &self.flatten(&chapter)),
+        )
     }
 
     /// 目录(章节 + 分卷),支持前置请求链 + 有界分页。
     pub async fn toc(&self, toc_url: &str) -> Result<Toc> {
-        let toc = &self.source.toc;
+        let plan = &self.plan.toc;
         let mut chapter = self.base_vars();
-        self.run_prelude(&toc.prelude, &mut chapter).await?;
-        let vars = self.flatten(&chapter);
-        let pages = self
-            .fetch_pages(toc_url, toc.next_page.as_ref(), toc.max_pages, &vars)
+        self.run_prelude(FetchOp::Toc, &plan.prelude, &mut chapter)
             .await?;
+        let vars = self.flatten(&chapter);
 
+        // 每页只解析一次,章节项与翻页规则都在这份文档上求值;规则已在构造引擎时编译好。
         let mut chapters: Vec<Chapter> = Vec::new();
         let mut volumes: Vec<Volume> = Vec::new();
-        for page in &pages {
-            for item in eval_list(&toc.list, page)? {
-                let title = eval_value(&toc.name, &item, &vars)?;
-                let is_volume = match &toc.is_volume {
-                    Some(r) => !eval_value(r, &item, &vars)?.trim().is_empty(),
-                    None => false,
-                };
-                if is_volume {
-                    volumes.push(Volume {
-                        title,
-                        first_chapter_index: chapters.len(),
-                    });
-                } else {
-                    let url = eval_value(&toc.url, &item, &vars)?;
-                    chapters.push(Chapter {
-                        title,
-                        url,
-                        is_volume: false,
-                    });
+        let each = |i: usize, page: &Page| {
+            let label = || format!("目录 第 {} 页", i + 1);
+            self.traced(Some(FetchOp::Toc), label, page.text(), || -> Result<()> {
+                for item in plan.list.select(page, &vars)? {
+                    let title = plan.name.eval(&item, &vars)?;
+                    let is_volume = match &plan.is_volume {
+                        Some(p) => !p.eval(&item, &vars)?.trim().is_empty(),
+                        None => false,
+                    };
+                    if is_volume {
+                        volumes.push(Volume {
+                            title,
+                            first_chapter_index: chapters.len(),
+                        });
+                    } else {
+                        let url = plan.url.eval(&item, &vars)?;
+                        chapters.push(Chapter {
+                            title,
+                            url,
+                            is_volume: false,
+                        });
+                    }
                 }
-            }
-        }
+                Ok(())
+            })
+        };
+        self.fetch_pages(
+            FetchOp::Toc,
+            toc_url,
+            plan.next_page.as_ref(),
+            self.source.toc.max_pages,
+            &vars,
+            each,
+        )
+        .await?;
         Ok(Toc { chapters, volumes })
     }
 
     /// 正文,支持前置请求链 + 有界分页。
     pub async fn content(&self, chapter_url: &str) -> Result<String> {
-        let c = &self.source.content;
+        let plan = &self.plan.content;
         let mut chapter = self.base_vars();
-        self.run_prelude(&c.prelude, &mut chapter).await?;
-        let vars = self.flatten(&chapter);
-        let pages = self
-            .fetch_pages(chapter_url, c.next_page.as_ref(), c.max_pages, &vars)
+        self.run_prelude(FetchOp::Content, &plan.prelude, &mut chapter)
             .await?;
-        let mut parts = Vec::with_capacity(pages.len());
-        for page in &pages {
-            parts.push(eval_value(&c.value, page, &vars)?);
-        }
+        let vars = self.flatten(&chapter);
+        let mut parts = Vec::new();
+        let each = |i: usize, page: &Page| {
+            let label = || format!("正文 第 {} 页", i + 1);
+            let part = self.traced(Some(FetchOp::Content), label, page.text(), || {
+                plan.value.eval(&Ctx::Page(page), &vars)
+            })?;
+            parts.push(part);
+            Ok(())
+        };
+        self.fetch_pages(
+            FetchOp::Content,
+            chapter_url,
+            plan.next_page.as_ref(),
+            self.source.content.max_pages,
+            &vars,
+            each,
+        )
+        .await?;
         Ok(parts.join("\n"))
     }
 
@@ -270,7 +390,13 @@ impl Engine {
             .ok_or(BookSourceError::Missing("search"))?;
         let mut extra = BTreeMap::new();
         extra.insert("key".to_string(), key.to_string());
-        self.run_list_page(op, 's', &extra, page, page_size).await
+        let plan = self
+            .plan
+            .search
+            .as_ref()
+            .ok_or(BookSourceError::Missing("search"))?;
+        self.run_list_page(op, plan, FetchOp::Search, &extra, page, page_size)
+            .await
     }
 
     /// 浏览选中入口的某一页(由用户递增 `page` 单页取)。用入口变量驱动共享列表页 runner;
@@ -286,8 +412,20 @@ impl Engine {
             .explore
             .as_ref()
             .ok_or(BookSourceError::Missing("explore"))?;
-        self.run_list_page(&op.page, 'e', &entry.vars, page, page_size)
-            .await
+        let plan = self
+            .plan
+            .explore
+            .as_ref()
+            .ok_or(BookSourceError::Missing("explore"))?;
+        self.run_list_page(
+            &op.page,
+            &plan.page,
+            FetchOp::Explore,
+            &entry.vars,
+            page,
+            page_size,
+        )
+        .await
     }
 
     /// 加载 explore 入口(`dynamic-explore-entries`):按声明顺序遍历入口源数组,合并各源产出的
@@ -299,13 +437,14 @@ impl Engine {
         if let Some(cached) = self.entries_cache.read().ok().and_then(|g| g.clone()) {
             return Ok(cached);
         }
-        let Some(op) = self.source.explore.as_ref() else {
+        let (Some(op), Some(plan)) = (self.source.explore.as_ref(), self.plan.explore.as_ref())
+        else {
             return Ok(Vec::new());
         };
         let mut out: Vec<ExploreEntry> = Vec::new();
         let mut first_err: Option<BookSourceError> = None;
-        for src in &op.entries {
-            match self.load_entry_source(src).await {
+        for (src, plan) in op.entries.iter().zip(&plan.entries) {
+            match self.load_entry_source(src, plan.as_ref()).await {
                 Ok(mut entries) => out.append(&mut entries),
                 Err(e) => {
                     first_err.get_or_insert(e);
@@ -0,0 +1,323 @@
+//! 书源的预编译计划:构造引擎时把书源里的全部规则各编译一次——目录、正文、详情与搜索/浏览
+//! 列表的字段规则,以及请求模板、前置链捕获、翻页、总页数等——之后每次取页直接执行
+//! (见 [`crate::eval::plan`])。
+
+use crate::eval::{Ctx, Page, Plan, Vars};
+use crate::model::BookInfo;
+use crate::source::{
+    BookRules, BookSource, Capture, EntrySource, FetchEntrySource, ListPageSpec, Method, PreStep,
+    Request, Rule, UrlOrRule, VarScope, Via,
+};
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub(super) struct SourcePlan {
+    /// `loginCheckJs`;空脚本为 `None`。
+    pub login_check: Option<Plan>,
+    pub toc: TocPlan,
+    pub content: ContentPlan,
+    pub book_info: BookInfoPlan,
+    pub search: Option<ListPlan>,
+    pub explore: Option<ExplorePlan>,
+}
+
+#[derive(Debug)]
+pub(super) struct TocPlan {
+    pub prelude: Vec<PreStepPlan>,
+    pub list: Plan,
+    pub name: Plan,
+    pub url: Plan,
+    pub is_volume: Option<Plan>,
+    pub next_page: Option<Plan>,
+}
+
+#[derive(Debug)]
+pub(super) struct ContentPlan {
+    pub prelude: Vec<PreStepPlan>,
+    pub value: Plan,
+    pub next_page: Option<Plan>,
+}
+
+#[derive(Debug)]
+pub(super) struct BookInfoPlan {
+    pub prelude: Vec<PreStepPlan>,
+    pub fields: BookPlan,
+}
+
+/// 详情 / 列表项的字段规则。
+#[derive(Debug)]
+pub(super) struct BookPlan {
+    pub book_url: Option<Plan>,
+    name: Option<Plan>,
+    author: Option<Plan>,
+    cover: Option<Plan>,
+    intro: Option<Plan>,
+    kind: Option<Plan>,
+    last_chapter: Option<Plan>,
+    toc_url: Option<Plan>,
+    word_count: Option<Plan>,
+}
+
+/// 搜索 / 浏览的一页:前置链、主请求及其 `vars` 捕获、列表与字段、总页数与边界。
+#[derive(Debug)]
+pub(super) struct ListPlan {
+    pub prelude: Vec<PreStepPlan>,
+    pub request: RequestPlan,
+    pub vars: Vec<(String, Plan)>,
+    pub total_pages: Option<RoutedPlan>,
+    pub has_more: Option<RoutedPlan>,
+    pub list: Plan,
+    pub item: BookPlan,
+}
+
+#[derive(Debug)]
+pub(super) struct ExplorePlan {
+    pub page: ListPlan,
+    /// 与 `explore.entries` 一一对应;静态入口为 `None`。
+    pub entries: Vec<Option<FetchEntryPlan>>,
+}
+
+/// 远端抓取入口源。
+#[derive(Debug)]
+pub(super) struct FetchEntryPlan {
+    pub request: RequestPlan,
+    pub list: Plan,
+    pub title: Plan,
+    pub vars: Vec<(String, Plan)>,
+}
+
+/// 模板化请求:url / body / 请求头值。
+#[derive(Debug)]
+pub(super) struct RequestPlan {
+    pub url: Plan,
+    pub method: Method,
+    pub body: Option<Plan>,
+    pub headers: Vec<(String, Plan)>,
+}
+
+/// 前置请求链的一步。
+#[derive(Debug)]
+pub(super) struct PreStepPlan {
+    pub request: RequestPlan,
+    pub capture: Vec<CapturePlan>,
+    pub skip_if_present: Vec<String>,
+}
+
+#[derive(Debug)]
+pub(super) struct CapturePlan {
+    pub name: String,
+    pub scope: VarScope,
+    pub value: Plan,
+}
+
+/// 按 `via` 选求值源的规则(`render-dual-source`):`via:css`/`xpath` 打渲染 DOM
+/// (没抓到 DOM 则退 body),其余打 body。
+#[derive(Debug)]
+pub(super) struct RoutedPlan {
+    plan: Plan,
+    on_dom: bool,
+}
+
+fn compile_opt(rule: Option<&Rule>) -> Option<Plan> {
+    rule.map(Plan::compile)
+}
+
+fn compile_prelude(steps: &[PreStep]) -> Vec<PreStepPlan> {
+    steps.iter().map(PreStepPlan::compile).collect()
+}
+
+fn compile_named<'a>(
+    rules: impl IntoIterator<Item = (&'a String, &'a Rule)>,
+) -> Vec<(String, Plan)> {
+    rules
+        .into_iter()
+        .map(|(name, rule)| (name.clone(), Plan::compile(rule)))
+        .collect()
+}
+
+/// 字符串按模板插值({{base}}/{{key}}/{{page}} 等),规则照常编译。
+fn compile_url(url: &UrlOrRule) -> Plan {
+    match url {
+        UrlOrRule::Str(s) => template(s),
+        UrlOrRule::Rule(r) => Plan::compile(r),
+    }
+}
+
+fn template(s: &str) -> Plan {
+    Plan::compile(&Rule::Template {
+        template: s.to_string(),
+    })
+}
+
+impl SourcePlan {
+    pub fn compile(source: &BookSource) -> Self {
+        let toc = &source.toc;
+        let content = &source.content;
+        let login_check = source.login_check_js.trim();
+        Self {
+            login_check: (!login_check.is_empty()).then(|| {
+                Plan::compile(&Rule::Js {
+                    js: login_check.to_string(),
+                })
+            }),
+            toc: TocPlan {
+                prelude: compile_prelude(&toc.prelude),
+                list: Plan::compile(&toc.list),
+                name: Plan::compile(&toc.name),
+                url: Plan::compile(&toc.url),
+                is_volume: compile_opt(toc.is_volume.as_ref()),
+                next_page: compile_opt(toc.next_page.as_ref()),
+            },
+            content: ContentPlan {
+                prelude: compile_prelude(&content.prelude),
+                value: Plan::compile(&content.value),
+                next_page: compile_opt(content.next_page.as_ref()),
+            },
+            book_info: BookInfoPlan {
+                prelude: compile_prelude(&source.book_info.prelude),
+                fields: BookPlan::compile(&source.book_info.as_book_rules()),
+            },
+            search: source.search.as_ref().map(ListPlan::compile),
+            explore: source.explore.as_ref().map(|op| ExplorePlan {
+                page: ListPlan::compile(&op.page),
+                entries: op
+                    .entries
+                    .iter()
+                    .map(|src| match src {
+                        EntrySource::Static { .. } => None,
+                        EntrySource::Fetch { fetch } => Some(FetchEntryPlan::compile(fetch)),
+                    })
+                    .collect(),
+            }),
+        }
+    }
+}
+
+impl ListPlan {
+    fn compile(spec: &ListPageSpec) -> Self {
+        let req = &spec.request;
+        Self {
+            prelude: compile_prelude(&spec.prelude),
+            request: RequestPlan::of(req),
+            vars: compile_named(&req.vars),
+            total_pages: req.total_pages.as_ref().map(RoutedPlan::compile),
+            has_more: req.has_more.as_ref().map(RoutedPlan::compile),
+            list: Plan::compile(&spec.list),
+            item: BookPlan::compile(&spec.item),
+        }
+    }
+}
+
+impl FetchEntryPlan {
+    fn compile(f: &FetchEntrySource) -> Self {
+        Self {
+            request: RequestPlan::of(&f.request),
+            list: Plan::compile(&f.list),
+            title: Plan::compile(&f.item.title),
+            vars: compile_named(&f.item.vars),
+        }
+    }
+}
+
+impl RequestPlan {
+    fn compile(
+        url: &UrlOrRule,
+        method: Method,
+        body: Option<&UrlOrRule>,
+        headers: &HashMap<String, String>,
+    ) -> Self {
+        Self {
+            url: compile_url(url),
+            method,
+            body: body.map(compile_url),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.clone(), template(value)))
+                .collect(),
+        }
+    }
+
+    fn of(req: &Request) -> Self {
+        Self::compile(&req.url, req.method, req.body.as_ref(), &req.headers)
+    }
+}
+
+impl PreStepPlan {
+    fn compile(step: &PreStep) -> Self {
+        Self {
+            request: RequestPlan::compile(
+                &step.url,
+                step.method,
+                step.body.as_ref(),
+                &step.headers,
+            ),
+            capture: step.capture.iter().map(CapturePlan::compile).collect(),
+            skip_if_present: step.skip_if_present.clone(),
+        }
+    }
+}
+
+impl CapturePlan {
+    fn compile(cap: &Capture) -> Self {
+        Self {
+            name: cap.name.clone(),
+            scope: cap.scope,
+            value: Plan::compile(&cap.value),
+        }
+    }
+}
+
+impl RoutedPlan {
+    fn compile(rule: &Rule) -> Self {
+        Self {
+            plan: Plan::compile(rule),
+            on_dom: matches!(rule.primary_via(), Some(Via::Css | Via::Xpath)),
+        }
+    }
+
+    /// 在 body 或渲染 DOM 上求值。
+    pub fn eval(&self, body: &Page, dom: Option<&Page>, vars: &Vars) -> crate::Result<String> {
+        let page = match dom {
+            Some(dom) if self.on_dom => dom,
+            _ => body,
+        };
+        Ok(self.plan.eval(&Ctx::Page(page), vars)?)
+    }
+}
+
+impl BookPlan {
+    fn compile(rules: &BookRules) -> Self {
+        Self {
+            book_url: compile_opt(rules.book_url.as_ref()),
+            name: compile_opt(rules.name.as_ref()),
+            author: compile_opt(rules.author.as_ref()),
+            cover: compile_opt(rules.cover.as_ref()),
+            intro: compile_opt(rules.intro.as_ref()),
+            kind: compile_opt(rules.kind.as_ref()),
+            last_chapter: compile_opt(rules.last_chapter.as_ref()),
+            toc_url: compile_opt(rules.toc_url.as_ref()),
+            word_count: compile_opt(rules.word_count.as_ref()),
+        }
+    }
+
+    pub fn eval(&self, ctx: &Ctx, vars: &Vars) -> crate::Result<BookInfo> {
+        Ok(BookInfo {
+            name: opt_eval(self.name.as_ref(), ctx, vars)?,
+            author: opt_eval(self.author.as_ref(), ctx, vars)?,
+            cover: opt_eval(self.cover.as_ref(), ctx, vars)?,
+            intro: opt_eval(self.intro.as_ref(), ctx, vars)?,
+            kind: opt_eval(self.kind.as_ref(), ctx, vars)?,
+            last_chapter: opt_eval(self.last_chapter.as_ref(), ctx, vars)?,
+            toc_url: opt_eval(self.toc_url.as_ref(), ctx, vars)?,
+            word_count: opt_eval(self.word_count.as_ref(), ctx, vars)?,
+        })
+    }
+}
+
+/// 求值一个可选规则;None 或空 → 空串。
+pub(super) fn opt_eval(plan: Option<&Plan>, ctx: &Ctx, vars: &Vars) -> crate::Result<String> {
+    Ok(match plan {
+        Some(p) => p.eval(ctx, vars)?,
+        None => String::new(),
+    })
+}
@@ -354,6 +354,83 @@ async fn prelude_captures_token_into_main_request() {
     );
 }
 
+// 追踪只记被追踪的操作:前置请求模板 → 捕获 → 主请求模板 → 列表页,各一棵树。
+#[tokio::test]
+async fn trace_records_stages_of_traced_op() {
+    let json = r#"{
+          "schema":"trnovel-booksource/v2","name":"t","url":"https://x",
+          "search":{
+            "prelude":[{"url":{"template":"{{base}}/prepare"},
+              "capture":[{"name":"token","value":{"via":"raw","clean":[{"trim":true}]},"scope":"chapter"}]}],
+            "request":{"url":{"template":"{{base}}/search?kw={{key}}&token={{token}}"}},
+            "list":{"via":"css","select":".item"},
+            "item":{"name":{"via":"css","select":".t","extract":"text"}}
+          },
+          "bookInfo":{"name":{"via":"css","select":"h1"}},
+          "toc":{"list":{"via":"css","select":"a"},"name":{"via":"css","select":"a"},"url":{"via":"css","select":"a","extract":{"attr":"href"}}},
+          "content":{"value":{"via":"css","select":".c"}}
+        }"#;
+    let src = BookSource::from_json(json).unwrap();
+    let (f, _) = scripted(vec![
+        ("/prepare", " ABC "),
+        (
+            "/search",
+            r#"<div class="item"><span class="t">书名</span></div>"#,
+        ),
+        ("/book", "<h1>书名</h1>"),
+    ]);
+    let engine = Engine::with_fetcher(src, f).with_trace(FetchOp::Search);
+    engine.search("k", 1, 20).await.unwrap();
+    engine.book_info("https://x/book/1").await.unwrap();
+    let stages = engine.take_trace();
+    let labels: Vec<&str> = stages.iter().map(|s| s.label.as_str()).collect();
+    assert_eq!(labels, ["请求", "前置请求 1 捕获", "请求", "搜索 第 1 页"]);
+    let capture = &stages[1].children[0];
+    let steps: Vec<&str> = capture.steps.iter().map(|s| s.output.as_str()).collect();
+    assert_eq!(steps, ["ABC", "ABC"]);
+    let url = &stages[2].children[0];
+    assert_eq!(
+        url.output.as_deref(),
+        Some("https://x/search?kw=k&token=ABC")
+    );
+    assert!(url.vars.contains(&("token".to_string(), "ABC".to_string())));
+    assert!(stages[3].to_string().contains("匹配 1 项"), "{}", stages[3]);
+    assert!(engine.take_trace().is_empty(), "取出后清空");
+}
+
+// 分页目录:每页先抽章节、再在同一份文档上求翻页,追踪里两者交替出现。
+#[tokio::test]
+async fn paged_toc_evaluates_next_page_on_each_parsed_page() {
+    let json = r#"{
+          "schema":"trnovel-booksource/v2","name":"t","url":"https://x",
+          "bookInfo":{},
+          "toc":{"list":{"via":"css","select":"li a"},"name":{"via":"css","select":"a"},
+            "url":{"via":"css","select":"a","extract":{"attr":"href"}},
+            "nextPage":{"via":"css","select":"a.next","extract":{"attr":"href"}}},
+          "content":{"value":{"via":"css","select":".c"}}
+        }"#;
+    let src = BookSource::from_json(json).unwrap();
+    let (f, calls) = scripted(vec![
+        ("/toc/2", r#"<ul><li><a href="/c/2">第二章</a></li></ul>"#),
+        (
+            "/toc",
+            r#"<ul><li><a href="/c/1">第一章</a></li></ul><a class="next" href="https://x/toc/2">下一页</a>"#,
+        ),
+    ]);
+    let engine = Engine::with_fetcher(src, f).with_trace(FetchOp::Toc);
+    let toc = engine.toc("https://x/toc").await.unwrap();
+    let titles: Vec<&str> = toc.chapters.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(titles, ["第一章", "第二章"]);
+    assert_eq!(calls.lock().unwrap().len(), 2);
+    let stages = engine.take_trace();
+    let labels: Vec<&str> = stages.iter().map(|s| s.label.as_str()).collect();
+    assert_eq!(labels, ["目录 第 1 页", "翻页", "目录 第 2 页", "翻页"]);
+    assert_eq!(
+        stages[1].children[0].output.as_deref(),
+        Some("https://x/toc/2")
+    );
+}
+
 // source 作用域 + skipIfPresent:同一引擎跨调用复用 token,prepare 只跑一次。
 #[tokio::test]
 async fn skip_if_present_reuses_source_scope_token() {
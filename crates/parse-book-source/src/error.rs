@@ -33,6 +33,12 @@ impl BookSourceError {
     pub fn is_login_expired(&self) -> bool {
         matches!(self, BookSourceError::LoginExpired)
     }
+
+    /// 是否为临时失败(超时、连不上、429、5xx),稍后重试可能成功;反爬拦截、登录失效、
+    /// 规则求值失败等重试无益。分类与取页层的 `http.retry` 一致。
+    pub fn is_transient(&self) -> bool {
+        matches!(self, BookSourceError::Fetch(e) if crate::fetch::limit::classify(e).is_some())
+    }
 }
 
 /// 取页层错误。
@@ -44,12 +50,18 @@ pub enum FetchError {
     /// 非法请求头。
     #[error("invalid header: {0}")]
     Header(String),
+    /// 代理配置非法(地址解析失败或协议不支持)。
+    #[error("invalid proxy: {0}")]
+    Proxy(String),
     /// 响应解码失败。
     #[error("decode error: {0}")]
     Decode(String),
     /// 被反爬挑战拦截(如 Cloudflare 托管挑战):拿到的是挑战页而非真实内容。
     #[error("blocked by anti-bot challenge: {0}")]
     Challenged(String),
+    /// 回放录制的夹具时没有匹配的请求,或录制时这次取页本就失败(见 [`crate::fetch::replay`])。
+    #[error("replay: {0}")]
+    Replay(String),
     /// 浏览器解挑战失败(仅 `browser` feature)。
     #[cfg(feature = "browser")]
     #[error("browser solve error: {0}")]
@@ -68,7 +80,7 @@ pub enum ConfigError {
 }
 
 /// 规则求值层错误。
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum EvalError {
     /// CSS 选择器非法。
     #[error("invalid css selector: {0}")]
@@ -88,6 +100,12 @@ pub enum EvalError {
     /// 该 via 后端暂未启用(如 xpath)。
     #[error("extraction backend not enabled: {0}")]
     Unsupported(&'static str),
+    /// 模板过滤器非法(未知过滤器 / 参数错)或作用于不合适的值(如对非数字做算术)。
+    #[error("template error: {0}")]
+    Template(String),
+    /// 下标表达式非法。
+    #[error("invalid index: {0}")]
+    Index(String),
     /// clean 编解码算子失败(非法 base64/hex/url 等)。
     #[error("codec error: {0}")]
     Codec(String),
@@ -5,86 +5,107 @@
 //! 后代、也匹配根元素自身(dom_query 解析片段后根入树),这与旧引擎一致,使
 //! `select:"a" + attr:href` 能取「列表项自身的 href」、`select:"h2"` 能判「该项是不是卷」。
 
+use super::index::Pick;
 use crate::error::EvalError;
 use crate::source::{Extract, ExtractOp, Via};
-use dom_query::{Document, Matcher};
+use dom_query::{Document, Matcher, NodeRef};
 use fancy_regex::Regex;
-use jsonpath_rust::JsonPath;
+use jsonpath_rust::parser::model::JpQuery;
+use jsonpath_rust::parser::parse_json_path;
+use jsonpath_rust::query::js_path_process;
 use serde_json::Value;
 use std::sync::LazyLock;
 
-/// 从上下文抽取一个值(值规则)。
-pub fn extract(
-    via: Via,
-    content: &str,
-    select: Option<&str>,
-    index: Option<i64>,
-    ex: &Extract,
-) -> Result<String, EvalError> {
-    match via {
-        Via::Css => html_extract(content, select, index, ex),
-        Via::Json => json_extract(content, select, index, ex),
-        Via::Regex => regex_extract(content, select, index),
-        Via::Raw => Ok(content.to_string()),
-        Via::Xpath => super::xpath::xpath_extract(content, select, index, ex),
-    }
+/// 编译好的 `select`:规则计划编译时建一次,之后每次求值复用(见 [`super::plan`])。
+#[derive(Debug, Clone)]
+pub(crate) enum Selector {
+    /// 省略 `select`:css 作用于整篇、json 取根、regex 为空模式。
+    Whole,
+    Css(Matcher),
+    Json(Box<JpQuery>),
+    Regex(Regex),
+    /// xpath 依赖每次新建的 sxd 文档,求值时按原始表达式走 xpath 后端。
+    Xpath,
+    /// raw 不用 `select`。
+    Raw,
 }
 
-/// 选中所有匹配,返回各自的「子上下文」内容串(列表规则)。
-pub fn select_all(via: Via, content: &str, select: &str) -> Result<Vec<String>, EvalError> {
-    match via {
-        Via::Css => {
-            let doc = Document::from(content.to_string());
-            let matcher =
-                Matcher::new(select).map_err(|_| EvalError::Selector(select.to_string()))?;
-            let sel = doc.select_matcher(&matcher);
-            Ok(sel.nodes().iter().map(|n| n.html().to_string()).collect())
-        }
-        Via::Json => {
-            let value: Value = parse_json_content(content)?;
-            let matched = value
-                .query(select)
-                .map_err(|e| EvalError::JsonPath(e.to_string()))?;
-            // value_to_string:字符串值取内容(不带 JSON 引号),供下游 item 规则求值。
-            Ok(matched.into_iter().map(value_to_string).collect())
-        }
-        Via::Regex => {
-            let re = Regex::new(select).map_err(|e| EvalError::Regex(e.to_string()))?;
-            Ok(re
-                .find_iter(content)
-                .filter_map(|m| m.ok())
-                .map(|m| m.as_str().to_string())
-                .collect())
-        }
-        Via::Raw => Ok(vec![content.to_string()]),
-        Via::Xpath => super::xpath::xpath_select_all(content, select),
+impl Selector {
+    /// 按 `via` 编译 `select`;非法选择器/正则/JSONPath 返回对应的求值错误。
+    pub(crate) fn compile(via: Via, select: Option<&str>) -> Result<Self, EvalError> {
+        Ok(match (via, select) {
+            (Via::Raw, _) => Selector::Raw,
+            (Via::Xpath, _) => Selector::Xpath,
+            (Via::Css | Via::Json, None) => Selector::Whole,
+            (Via::Css, Some(s)) => {
+                Selector::Css(Matcher::new(s).map_err(|_| EvalError::Selector(s.to_string()))?)
+            }
+            (Via::Json, Some(s)) => Selector::Json(Box::new(
+                parse_json_path(s).map_err(|e| EvalError::JsonPath(e.to_string()))?,
+            )),
+            // 无捕获的空模式与历史一致:匹配空串。
+            (Via::Regex, s) => Selector::Regex(
+                Regex::new(s.unwrap_or("")).map_err(|e| EvalError::Regex(e.to_string()))?,
+            ),
+        })
     }
 }
 
 // ───────────────────────── HTML(dom_query)─────────────────────────
 
-fn html_extract(
-    content: &str,
-    select: Option<&str>,
-    index: Option<i64>,
+/// 整篇文档上按选择器取值;省略 select 时作用于根。
+pub(crate) fn doc_extract(
+    doc: &Document,
+    selector: &Selector,
+    index: &Pick,
     ex: &Extract,
-) -> Result<String, EvalError> {
-    let doc = Document::from(content.to_string());
-    // 省略 select 时作用于整篇(根);否则按选择器(self-or-descendant)。
-    // 用 Matcher 区分「选择器非法」(报错)与「合法但无匹配」(返回空)。
-    let sel = match select {
-        Some(s) => {
-            let matcher = Matcher::new(s).map_err(|_| EvalError::Selector(s.to_string()))?;
-            doc.select_matcher(&matcher)
-        }
-        None => doc.select(":root"),
+) -> String {
+    // 用 Matcher 区分「选择器非法」(编译期已报错)与「合法但无匹配」(返回空)。
+    let sel = match selector {
+        Selector::Css(matcher) => doc.select_matcher(matcher),
+        _ => doc.select(":root"),
     };
-    let nodes = sel.nodes();
-    if nodes.is_empty() {
-        return Ok(String::new());
+    pick_node(sel.nodes(), index, ex)
+}
+
+/// 在共享文档里的一个节点上取值(列表项):选择器按 self-or-descendant 匹配,
+/// 省略 select 时作用于节点自身。
+pub(crate) fn node_extract(
+    node: &NodeRef,
+    selector: &Selector,
+    index: &Pick,
+    ex: &Extract,
+) -> String {
+    match selector {
+        Selector::Css(matcher) => pick_node(&node_select(node, matcher), index, ex),
+        _ => pick_node(std::slice::from_ref(node), index, ex),
     }
-    let node = &nodes[resolve_index(index, nodes.len())];
-    Ok(match ex {
+}
+
+/// 整篇文档里选中所有匹配节点(文档顺序)。
+pub(crate) fn doc_select<'a>(doc: &'a Document, matcher: &Matcher) -> Vec<NodeRef<'a>> {
+    doc.select_matcher(matcher).nodes().to_vec()
+}
+
+/// 节点自身及其后代里选中所有匹配节点(文档顺序,自身在前)。
+pub(crate) fn node_select<'a>(node: &NodeRef<'a>, matcher: &Matcher) -> Vec<NodeRef<'a>> {
+    std::iter::once(*node)
+        .chain(node.descendants_it())
+        .filter(|n| n.is_match(matcher))
+        .collect()
+}
+
+fn pick_node(nodes: &[NodeRef], index: &Pick, ex: &Extract) -> String {
+    join_values(
+        index
+            .pick(nodes)
+            .into_iter()
+            .map(|node| node_value(node, ex)),
+    )
+}
+
+pub(crate) fn node_value(node: &NodeRef, ex: &Extract) -> String {
+    match ex {
         // 文本/属性默认去首尾空白(标题/链接等场景几乎总是期望的;与旧引擎一致)。
         Extract::Op(ExtractOp::Text) => node.text().trim().to_string(),
         Extract::Op(ExtractOp::OwnText) => node.immediate_text().trim().to_string(),
@@ -96,7 +117,7 @@ fn html_extract(
             .attr(attr)
             .map(|s| s.trim().to_string())
             .unwrap_or_default(),
-    })
+    }
 }
 
 /// 把正文 HTML 转为可读文本:块级/换行标签 → 换行,去注释,解码常见实体。
@@ -129,7 +150,7 @@ fn decode_entities(s: &str) -> String {
 /// 把上下文解析为 JSON。**空响应**(常见于被反爬拦截 / 网络故障 / 触发风控时返回空体)
 /// 给出明确提示,而非把 serde 的「EOF while parsing a value at line 1 column 0」原样透出,
 /// 让用户能区分「站点没返回内容」与「规则取错了字段」。
-fn parse_json_content(content: &str) -> Result<Value, EvalError> {
+pub(crate) fn parse_json_content(content: &str) -> Result<Value, EvalError> {
     if content.trim().is_empty() {
         return Err(EvalError::Json(
             "响应体为空(疑似被反爬拦截或网络故障)".to_string(),
@@ -138,27 +159,34 @@ fn parse_json_content(content: &str) -> Result<Value, EvalError> {
     serde_json::from_str(content).map_err(|e| EvalError::Json(e.to_string()))
 }
 
-fn json_extract(
-    content: &str,
-    select: Option<&str>,
-    index: Option<i64>,
-    ex: &Extract,
+/// 按 JSONPath 取一个值;省略 select 时取根。JSON 上下文里 extract 无意义,统一取标量字符串。
+pub(crate) fn json_extract(
+    value: &Value,
+    selector: &Selector,
+    index: &Pick,
 ) -> Result<String, EvalError> {
-    let value: Value = parse_json_content(content)?;
-    let path = select.unwrap_or("$");
-    let matched = value
-        .query(path)
-        .map_err(|e| EvalError::JsonPath(e.to_string()))?;
-    if matched.is_empty() {
-        return Ok(String::new());
-    }
-    let v = matched[resolve_index(index, matched.len())];
-    // JSON 上下文里 attr 无意义,统一取标量字符串。
-    let _ = ex;
-    Ok(value_to_string(v))
+    let matched = json_select(value, selector)?;
+    Ok(join_values(
+        index.pick(&matched).into_iter().map(|v| value_to_string(v)),
+    ))
+}
+
+/// 按 JSONPath 选中所有匹配值;省略 select 时为根自身。
+pub(crate) fn json_select<'a>(
+    value: &'a Value,
+    selector: &Selector,
+) -> Result<Vec<&'a Value>, EvalError> {
+    let Selector::Json(path) = selector else {
+        return Ok(vec![value]);
+    };
+    Ok(js_path_process(path, value)
+        .map_err(|e| EvalError::JsonPath(e.to_string()))?
+        .into_iter()
+        .map(|r| r.val())
+        .collect())
 }
 
-fn value_to_string(v: &Value) -> String {
+pub(crate) fn value_to_string(v: &Value) -> String {
     match v {
         Value::String(s) => s.clone(),
         Value::Null => String::new(),
@@ -168,63 +196,71 @@ fn value_to_string(v: &Value) -> String {
 
 // ───────────────────────── Regex ─────────────────────────
 
-fn regex_extract(
-    content: &str,
-    select: Option<&str>,
-    index: Option<i64>,
-) -> Result<String, EvalError> {
-    let pat = select.unwrap_or("");
-    let re = Regex::new(pat).map_err(|e| EvalError::Regex(e.to_string()))?;
+/// 正则取值:有捕获组取第 1 组,否则取整体匹配。
+pub(crate) fn regex_extract(content: &str, selector: &Selector, index: &Pick) -> String {
+    let Selector::Regex(re) = selector else {
+        return String::new();
+    };
     let caps: Vec<String> = re
         .captures_iter(content)
         .filter_map(|c| c.ok())
         .map(|c| {
-            // 有捕获组取第 1 组,否则取整体匹配
             c.get(1)
                 .or_else(|| c.get(0))
                 .map(|m| m.as_str().to_string())
                 .unwrap_or_default()
         })
         .collect();
-    if caps.is_empty() {
-        return Ok(String::new());
-    }
-    Ok(caps[resolve_index(index, caps.len())].clone())
+    join_values(index.pick(&caps).into_iter().cloned())
+}
+
+/// 正则列表:每个整体匹配是一个子上下文。
+pub(crate) fn regex_select(content: &str, re: &Regex) -> Vec<String> {
+    re.find_iter(content)
+        .filter_map(|m| m.ok())
+        .map(|m| m.as_str().to_string())
+        .collect()
 }
 
 // ───────────────────────── 公共 ─────────────────────────
 
-/// 解析索引:None→0;负数从末尾;越界回退到首/末。
-pub(crate) fn resolve_index(index: Option<i64>, len: usize) -> usize {
-    match index {
-        None => 0,
-        Some(i) if i >= 0 => (i as usize).min(len - 1),
-        Some(i) => {
-            let from_end = (-i) as usize;
-            len.saturating_sub(from_end)
-        }
+/// 下标挑出的各项取值:单项原样返回,多项(下标表达式)丢弃空值后以换行连接。
+pub(crate) fn join_values(values: impl Iterator<Item = String>) -> String {
+    let values: Vec<String> = values.collect();
+    match <[String; 1]>::try_from(values) {
+        Ok([one]) => one,
+        Err(values) => values
+            .into_iter()
+            .filter(|v| !v.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n"),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use crate::source::ExtractOp;
+    use crate::eval::{Ctx, Page, Plan, Vars};
+
+    fn plan(j: &str) -> Plan {
+        Plan::compile(&serde_json::from_str(j).expect("rule json"))
+    }
 
     #[test]
     fn empty_json_response_gives_friendly_error() {
         // 回归:空响应(被反爬拦截/网络故障返回空体)应给「响应体为空」提示,
         // 而非透出 serde 的 "EOF while parsing a value at line 1 column 0"。
+        let list = plan(r#"{"via":"json","select":"$.a"}"#);
         for content in ["", "   ", "\n\t "] {
-            let msg = select_all(Via::Json, content, "$.a")
+            let msg = list
+                .select(&Page::new(content), &Vars::new())
                 .unwrap_err()
                 .to_string();
             assert!(msg.contains("响应体为空"), "应提示响应为空,实际: {msg}");
             assert!(!msg.contains("EOF"), "不应透出 serde EOF: {msg}");
         }
         // extract 路径同样覆盖。
-        let ex = Extract::Op(ExtractOp::Text);
-        let msg = extract(Via::Json, "", Some("$.a"), None, &ex)
+        let msg = list
+            .eval(&Ctx::Text("".into()), &Vars::new())
             .unwrap_err()
             .to_string();
         assert!(
@@ -236,7 +272,8 @@ mod tests {
     #[test]
     fn nonempty_invalid_json_still_errors_normally() {
         // 非空但非法 JSON 仍按原样报错,不被误判为「空响应」。
-        let msg = select_all(Via::Json, "{not json", "$.a")
+        let msg = plan(r#"{"via":"json","select":"$.a"}"#)
+            .select(&Page::new("{not json"), &Vars::new())
             .unwrap_err()
             .to_string();
         assert!(
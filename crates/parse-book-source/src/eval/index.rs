@@ -0,0 +1,248 @@
+//! 下标表达式:叶子规则 `index` 的字符串形式,从匹配结果里挑出若干项。
+//!
+//! 逗号分隔若干项,每项是单个下标或 `起:止[:步长]` 切片(语义同 Python 切片,边界可省、
+//! 可为负数从末尾数,步长为负时倒序);项前加 `!` 表示排除:
+//!
+//! | 表达式 | 含义 |
+//! |--------|------|
+//! | `"2:"` | 跳过前两项 |
+//! | `"3:-2"` | 第 3 项到倒数第 2 项之前 |
+//! | `"0,2,5"` | 第 0、2、5 项 |
+//! | `"::2"` / `"::-1"` | 隔一项取一项 / 倒序 |
+//! | `"!0,!-1"` | 除去首尾(只有排除项时从全部里排除) |
+//!
+//! 结果按表达式书写顺序排列、去重;越界的下标直接丢弃。整数 `index` 保持原语义:
+//! 只取一项,越界回退到首/末。所有后端(css/xpath/json/regex)共用这里的挑选逻辑。
+
+use crate::error::EvalError;
+use crate::source::Index;
+
+/// 编译好的下标。
+#[derive(Debug, Clone, Default)]
+pub(crate) enum Pick {
+    /// 未写 `index`:取值时取第一项,列表取全部。
+    #[default]
+    Default,
+    /// 整数下标:只取一项。
+    At(i64),
+    Expr(Vec<Term>),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Term {
+    exclude: bool,
+    span: Span,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Span {
+    At(i64),
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        step: i64,
+    },
+}
+
+impl Pick {
+    /// 编译 `index`;表达式非法返回 [`EvalError::Index`]。
+    pub(crate) fn compile(index: Option<&Index>) -> Result<Self, EvalError> {
+        match index {
+            None => Ok(Pick::Default),
+            Some(Index::At(i)) => Ok(Pick::At(*i)),
+            Some(Index::Expr(expr)) => parse(expr).map(Pick::Expr),
+        }
+    }
+
+    /// 取值用:挑出要抽取的项(无 `index` 或整数下标时至多一项)。
+    pub(crate) fn pick<'t, T>(&self, items: &'t [T]) -> Vec<&'t T> {
+        if items.is_empty() {
+            return Vec::new();
+        }
+        match self {
+            Pick::Default => vec![&items[0]],
+            Pick::At(i) => vec![&items[resolve_index(Some(*i), items.len())]],
+            Pick::Expr(terms) => positions(terms, items.len())
+                .into_iter()
+                .map(|i| &items[i])
+                .collect(),
+        }
+    }
+
+    /// 列表用:按下标筛选列表项(无 `index` 时保留全部)。
+    pub(crate) fn filter<T>(&self, items: Vec<T>) -> Vec<T> {
+        if items.is_empty() {
+            return items;
+        }
+        let keep = match self {
+            Pick::Default => return items,
+            Pick::At(i) => vec![resolve_index(Some(*i), items.len())],
+            Pick::Expr(terms) => positions(terms, items.len()),
+        };
+        let mut slots: Vec<Option<T>> = items.into_iter().map(Some).collect();
+        keep.into_iter().filter_map(|i| slots[i].take()).collect()
+    }
+}
+
+/// 解析索引:None→0;负数从末尾;越界回退到首/末。
+pub(crate) fn resolve_index(index: Option<i64>, len: usize) -> usize {
+    match index {
+        None => 0,
+        Some(i) if i >= 0 => (i as usize).min(len - 1),
+        Some(i) => {
+            let from_end = (-i) as usize;
+            len.saturating_sub(from_end)
+        }
+    }
+}
+
+fn parse(expr: &str) -> Result<Vec<Term>, EvalError> {
+    let err = |msg: &str| EvalError::Index(format!("{expr}: {msg}"));
+    let int = |s: &str| -> Result<Option<i64>, EvalError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(None);
+        }
+        s.parse().map(Some).map_err(|_| err("下标须为整数"))
+    };
+    let mut terms = Vec::new();
+    for raw in expr.split(',') {
+        let raw = raw.trim();
+        let (exclude, body) = match raw.strip_prefix('!') {
+            Some(body) => (true, body.trim()),
+            None => (false, raw),
+        };
+        if body.is_empty() {
+            return Err(err("存在空项"));
+        }
+        let parts: Vec<&str> = body.split(':').collect();
+        let span = match parts[..] {
+            [at] => Span::At(int(at)?.ok_or_else(|| err("存在空项"))?),
+            [start, end] => Span::Slice {
+                start: int(start)?,
+                end: int(end)?,
+                step: 1,
+            },
+            [start, end, step] => Span::Slice {
+                start: int(start)?,
+                end: int(end)?,
+                step: match int(step)? {
+                    None => 1,
+                    Some(0) => return Err(err("步长不能为 0")),
+                    Some(step) => step,
+                },
+            },
+            _ => return Err(err("切片最多三段 起:止:步长")),
+        };
+        terms.push(Term { exclude, span });
+    }
+    Ok(terms)
+}
+
+/// 按书写顺序展开包含项(没有包含项时为全部),去重后去掉排除项。
+fn positions(terms: &[Term], len: usize) -> Vec<usize> {
+    let mut seen = vec![false; len];
+    let mut excluded = vec![false; len];
+    for term in terms.iter().filter(|t| t.exclude) {
+        for i in term.span.expand(len) {
+            excluded[i] = true;
+        }
+    }
+    let mut includes = terms.iter().filter(|t| !t.exclude).peekable();
+    let order: Vec<usize> = if includes.peek().is_none() {
+        (0..len).collect()
+    } else {
+        includes.flat_map(|t| t.span.expand(len)).collect()
+    };
+    order
+        .into_iter()
+        .filter(|&i| !excluded[i] && !std::mem::replace(&mut seen[i], true))
+        .collect()
+}
+
+impl Span {
+    /// 展开为 `0..len` 内的下标。
+    fn expand(self, len: usize) -> Vec<usize> {
+        let len = len as i64;
+        let abs = |i: i64| if i < 0 { i + len } else { i };
+        match self {
+            Span::At(i) => {
+                let i = abs(i);
+                if (0..len).contains(&i) {
+                    vec![i as usize]
+                } else {
+                    Vec::new()
+                }
+            }
+            Span::Slice { start, end, step } if step > 0 => {
+                let start = start.map_or(0, |s| abs(s).clamp(0, len));
+                let end = end.map_or(len, |e| abs(e).clamp(0, len));
+                (start..end)
+                    .step_by(step as usize)
+                    .map(|i| i as usize)
+                    .collect()
+            }
+            Span::Slice { start, end, step } => {
+                let start = start.map_or(len - 1, |s| abs(s).clamp(-1, len - 1));
+                let end = end.map_or(-1, |e| abs(e).clamp(-1, len - 1));
+                let mut out = Vec::new();
+                let mut i = start;
+                while i > end {
+                    out.push(i as usize);
+                    i += step;
+                }
+                out
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pick(expr: &str, len: usize) -> Vec<usize> {
+        let items: Vec<usize> = (0..len).collect();
+        Pick::compile(Some(&Index::Expr(expr.into())))
+            .unwrap()
+            .filter(items)
+    }
+
+    #[test]
+    fn slices_lists_and_exclusions() {
+        assert_eq!(pick("2:", 5), [2, 3, 4]);
+        assert_eq!(pick("1:-1", 5), [1, 2, 3]);
+        assert_eq!(pick("::2", 5), [0, 2, 4]);
+        assert_eq!(pick("::-1", 4), [3, 2, 1, 0]);
+        assert_eq!(pick("-2:0:-1", 5), [3, 2, 1]);
+        assert_eq!(pick("0, 2, 9, -1", 5), [0, 2, 4]);
+        assert_eq!(pick("!0,!-1", 5), [1, 2, 3]);
+        assert_eq!(pick("1:,!3", 5), [1, 2, 4]);
+        assert_eq!(pick("!:2", 5), [2, 3, 4]);
+        assert_eq!(pick("3,1,3", 5), [3, 1]);
+        assert!(pick("5:", 3).is_empty());
+    }
+
+    #[test]
+    fn integer_index_keeps_clamping() {
+        let items = [10, 20, 30];
+        assert_eq!(Pick::At(-1).pick(&items), [&30]);
+        assert_eq!(Pick::At(9).pick(&items), [&30]);
+        assert_eq!(Pick::Default.pick(&items), [&10]);
+        assert_eq!(Pick::Default.filter(items.to_vec()), items);
+        assert_eq!(Pick::At(1).filter(items.to_vec()), [20]);
+    }
+
+    #[test]
+    fn malformed_expressions_are_rejected() {
+        for bad in ["", "a", "1,,2", "::0", "1:2:3:4", "!"] {
+            assert!(
+                matches!(
+                    Pick::compile(Some(&Index::Expr(bad.into()))),
+                    Err(EvalError::Index(_))
+                ),
+                "{bad:?} 应报错"
+            );
+        }
+    }
+}
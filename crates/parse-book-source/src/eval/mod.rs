@@ -3,53 +3,32 @@
 //! 两个入口:
 //! - [`eval_value`]:值规则 → 一个字符串。
 //! - [`eval_list`]:列表规则 → 多个「子上下文」内容串(每个供后续 item 规则求值)。
+//!
+//! 二者都先把规则编译成 [`Plan`] 再求值;引擎对书源里的规则只编译一次,并让列表项共享
+//! 同一份解析好的文档(见 [`plan`])。
 
-// 求值子模块:抽取后端(css/json/regex/raw 分派)、XPath 后端、确定性 transform 算子、JS 逃生舱。
+// 求值子模块:抽取后端(css/json/regex/raw 分派)、下标表达式、XPath 后端、确定性 transform 算子、JS 逃生舱、求值追踪。
 pub(crate) mod backend;
+pub(crate) mod index;
 #[cfg(feature = "js")]
 pub(crate) mod js;
+pub mod plan;
+pub mod template;
+pub mod trace;
 pub(crate) mod transform;
 mod xpath;
 
 use crate::error::EvalError;
-use crate::source::{CleanStep, Rule};
-use fancy_regex::Regex;
+use crate::source::Rule;
+pub use plan::{Ctx, Page, Plan};
 use std::collections::HashMap;
-use std::sync::LazyLock;
 
 /// 模板插值变量表(`{{key}}` / `{{page}}` / `{{base}}` / 命名捕获)。
 pub type Vars = HashMap<String, String>;
 
-/// 对当前上下文求一个值。
+/// 对当前上下文求一个值(一次性规则:现场编译;反复求值的规则应先编译成 [`Plan`])。
 pub fn eval_value(rule: &Rule, ctx: &str, vars: &Vars) -> Result<String, EvalError> {
-    match rule {
-        Rule::Literal { literal } => Ok(literal.clone()),
-        Rule::Template { template } => Ok(interpolate(template, vars)),
-        Rule::FirstOf { first_of } => {
-            for r in first_of {
-                let v = eval_value(r, ctx, vars)?;
-                if !v.trim().is_empty() {
-                    return Ok(v);
-                }
-            }
-            Ok(String::new())
-        }
-        Rule::Concat { concat, join } => {
-            let mut parts = Vec::new();
-            for r in concat {
-                let v = eval_value(r, ctx, vars)?;
-                if !v.trim().is_empty() {
-                    parts.push(v);
-                }
-            }
-            Ok(parts.join(join))
-        }
-        Rule::Js { js } => run_js(js, ctx, vars),
-        Rule::Leaf(l) => {
-            let raw = backend::extract(l.via, ctx, l.select.as_deref(), l.index, &l.extract)?;
-            apply_clean(raw, &l.clean, vars)
-        }
-    }
+    Plan::compile(rule).eval(&Ctx::Text(ctx.into()), vars)
 }
 
 /// 执行一段 JS(逃生舱):以 `result` 为当前上下文、注入变量 + `crypto` 助手。
@@ -68,84 +47,12 @@ fn run_js(script: &str, result: &str, vars: &Vars) -> Result<String, EvalError>
 
 /// 选中所有匹配,返回各自的子上下文内容串。
 pub fn eval_list(rule: &Rule, ctx: &str) -> Result<Vec<String>, EvalError> {
-    match rule {
-        Rule::Leaf(l) => match l.select.as_deref() {
-            Some(sel) => backend::select_all(l.via, ctx, sel),
-            // 无选择器:把当前上下文作为单一项(而非把空串当非法选择器)。
-            None => Ok(vec![ctx.to_string()]),
-        },
-        Rule::FirstOf { first_of } => {
-            for r in first_of {
-                let v = eval_list(r, ctx)?;
-                if !v.is_empty() {
-                    return Ok(v);
-                }
-            }
-            Ok(Vec::new())
-        }
-        // literal/template/concat 作为列表无意义:退化为单值(若非空)。
-        other => {
-            let v = eval_value(other, ctx, &Vars::new())?;
-            Ok(if v.is_empty() { Vec::new() } else { vec![v] })
-        }
-    }
-}
-
-/// 应用清洗流水线。步内固定顺序:
-/// `regex→replace → trim → prepend → append → decode → encode → hash → cipher → fontMap → cn`。
-/// 编解码/加解密会失败(非法输入、错密钥),故返回 `Result`(显式报错,不静默空)。
-fn apply_clean(mut s: String, steps: &[CleanStep], vars: &Vars) -> Result<String, EvalError> {
-    for step in steps {
-        if let Some(pat) = &step.regex {
-            // 非法正则是配置错误,显式报错(与抽取层 regex_extract 及下方 crypto 步一致),不静默跳过。
-            let re = Regex::new(pat).map_err(|e| EvalError::Regex(e.to_string()))?;
-            let rep = step.replace.as_deref().unwrap_or("");
-            s = re.replace_all(&s, rep).into_owned();
-        }
-        if step.trim.unwrap_or(false) {
-            s = s.trim().to_string();
-        }
-        if let Some(p) = &step.prepend {
-            s = format!("{p}{s}");
-        }
-        if let Some(a) = &step.append {
-            s = format!("{s}{a}");
-        }
-        if let Some(c) = step.decode {
-            s = transform::decode(&s, c)?;
-        }
-        if let Some(c) = step.encode {
-            s = transform::encode(&s, c)?;
-        }
-        if let Some(h) = &step.hash {
-            s = transform::hash(&s, h)?;
-        }
-        if let Some(c) = &step.cipher {
-            s = transform::cipher(&s, c)?;
-        }
-        if let Some(table) = &step.font_map {
-            s = transform::font_map(&s, table)?;
-        }
-        if let Some(cn) = step.cn {
-            s = transform::cn_convert(&s, cn);
-        }
-        if let Some(js) = &step.js {
-            s = run_js(js, &s, vars)?;
-        }
-    }
-    Ok(s)
-}
-
-/// 把 `{{key}}` 替换为变量值,未知键替换为空串。
-pub(crate) fn interpolate(template: &str, vars: &Vars) -> String {
-    static RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{\{\s*([\w.\-]+)\s*\}\}").unwrap());
-    RE.replace_all(template, |c: &fancy_regex::Captures| {
-        c.get(1)
-            .and_then(|m| vars.get(m.as_str()))
-            .cloned()
-            .unwrap_or_default()
-    })
-    .into_owned()
+    let page = Page::new(ctx);
+    Ok(Plan::compile(rule)
+        .select(&page, &Vars::new())?
+        .iter()
+        .map(|item| item.text().into_owned())
+        .collect())
 }
 
 #[cfg(test)]
@@ -0,0 +1,834 @@
+//! 规则计划:把 [`Rule`] 语法树编译成可直接执行的形状。
+//!
+//! 编译时建好选择器、正则与 JSONPath,求值时不再重复编译;取页结果包成 [`Page`],HTML/JSON
+//! 只解析一次,列表项是共享文档里的节点(或 JSON 值)而非重新序列化的片段,逐项求值不再
+//! 重新解析。
+//!
+//! 非法选择器/正则在编译时记下错误、求值到它时才报出,与解释求值的时机一致:书源里某条
+//! 用不到的坏规则不会拖垮整个书源。
+
+use super::backend::{self, Selector};
+use super::index::Pick;
+use super::template::Template;
+use super::{Vars, run_js, trace, transform};
+use crate::error::EvalError;
+use crate::source::{CleanStep, Condition, Extract, Index, LeafRule, Rule, Via};
+use dom_query::{Document, NodeRef};
+use fancy_regex::Regex;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::cell::OnceCell;
+
+/// 编译好的规则,与 [`Rule`] 一一对应。
+#[derive(Debug, Clone)]
+pub enum Plan {
+    Literal(String),
+    Template(Result<Template, EvalError>),
+    FirstOf(Vec<Plan>),
+    Concat {
+        parts: Vec<Plan>,
+        join: String,
+    },
+    When {
+        cond: Box<CondPlan>,
+        then: Box<Plan>,
+        otherwise: Option<Box<Plan>>,
+    },
+    Split {
+        value: Box<Plan>,
+        by: String,
+    },
+    Map {
+        list: Box<Plan>,
+        with: Box<Plan>,
+    },
+    Join {
+        list: Box<Plan>,
+        with: String,
+    },
+    Js(String),
+    Leaf(Box<LeafPlan>),
+}
+
+/// 编译好的叶子规则。
+#[derive(Debug, Clone)]
+pub struct LeafPlan {
+    /// 追踪里显示的规则摘要。
+    label: String,
+    via: Via,
+    /// 原始 `select`(xpath 与错误信息用)。
+    select: Option<String>,
+    selector: Result<Selector, EvalError>,
+    index: Result<Pick, EvalError>,
+    extract: Extract,
+    clean: Vec<CleanPlan>,
+}
+
+/// 编译好的 `when` 条件。
+#[derive(Debug, Clone)]
+pub struct CondPlan {
+    var: Option<String>,
+    value: Option<Plan>,
+    regex: Option<Result<Regex, EvalError>>,
+    not: bool,
+}
+
+/// 编译好的清洗步:`regex` 预先编译,其余算子照旧。
+#[derive(Debug, Clone)]
+struct CleanPlan {
+    step: CleanStep,
+    regex: Option<Result<Regex, EvalError>>,
+}
+
+/// 一次取页的响应体,HTML 文档与 JSON 值按需各解析一次。
+///
+/// 含 `dom_query` 文档(非 `Send`),只在同步求值里使用,不要跨 `.await` 持有。
+pub struct Page<'a> {
+    text: &'a str,
+    html: OnceCell<Document>,
+    json: OnceCell<Result<Value, EvalError>>,
+}
+
+impl<'a> Page<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            html: OnceCell::new(),
+            json: OnceCell::new(),
+        }
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    fn html(&self) -> &Document {
+        self.html.get_or_init(|| Document::from(self.text))
+    }
+
+    fn json(&self) -> Result<&Value, EvalError> {
+        self.json
+            .get_or_init(|| backend::parse_json_content(self.text))
+            .as_ref()
+            .map_err(Clone::clone)
+    }
+}
+
+impl std::fmt::Debug for Page<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Page")
+            .field("len", &self.text.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// 求值上下文。
+#[derive(Debug, Clone)]
+pub enum Ctx<'a> {
+    /// 一段文本(临时值、正则列表项),按需现场解析。
+    Text(Cow<'a, str>),
+    /// 整个响应页,共享解析结果。
+    Page(&'a Page<'a>),
+    /// 共享文档里的一个列表项节点。
+    Node(NodeRef<'a>),
+    /// 共享 JSON 里的一个列表项(对象/数组)。
+    Json(&'a Value),
+}
+
+impl<'a> Ctx<'a> {
+    /// 上下文的文本形态:节点取其 HTML,JSON 取其字符串值。与解释求值里列表项的子上下文串一致。
+    pub fn text(&self) -> Cow<'a, str> {
+        match self {
+            Ctx::Text(s) => s.clone(),
+            Ctx::Page(p) => Cow::Borrowed(p.text()),
+            Ctx::Node(n) => Cow::Owned(n.html().to_string()),
+            Ctx::Json(v) => Cow::Owned(backend::value_to_string(v)),
+        }
+    }
+
+    /// 上下文作为值时的文本(`join` 用):节点取去首尾空白的文本,其余同 [`Ctx::text`]。
+    fn value(&self) -> Cow<'a, str> {
+        match self {
+            Ctx::Node(n) => Cow::Owned(n.text().trim().to_string()),
+            other => other.text(),
+        }
+    }
+}
+
+impl Plan {
+    /// 编译一条规则。从不失败:非法模式在求值时报错。
+    pub fn compile(rule: &Rule) -> Self {
+        match rule {
+            Rule::Literal { literal } => Plan::Literal(literal.clone()),
+            Rule::Template { template } => Plan::Template(Template::parse(template)),
+            Rule::FirstOf { first_of } => {
+                Plan::FirstOf(first_of.iter().map(Plan::compile).collect())
+            }
+            Rule::Concat { concat, join } => Plan::Concat {
+                parts: concat.iter().map(Plan::compile).collect(),
+                join: join.clone(),
+            },
+            Rule::When {
+                when,
+                then,
+                otherwise,
+            } => Plan::When {
+                cond: Box::new(CondPlan::compile(when)),
+                then: Box::new(Plan::compile(then)),
+                otherwise: otherwise.as_deref().map(|r| Box::new(Plan::compile(r))),
+            },
+            Rule::Split { split, by } => Plan::Split {
+                value: Box::new(Plan::compile(split)),
+                by: by.clone(),
+            },
+            Rule::Map { map, with } => Plan::Map {
+                list: Box::new(Plan::compile(map)),
+                with: Box::new(Plan::compile(with)),
+            },
+            Rule::Join { join, with } => Plan::Join {
+                list: Box::new(Plan::compile(join)),
+                with: with.clone(),
+            },
+            Rule::Js { js } => Plan::Js(js.clone()),
+            Rule::Leaf(l) => Plan::Leaf(Box::new(LeafPlan {
+                label: leaf_label(l),
+                via: l.via,
+                select: l.select.clone(),
+                selector: Selector::compile(l.via, l.select.as_deref()),
+                index: Pick::compile(l.index.as_ref()),
+                extract: l.extract.clone(),
+                clean: l
+                    .clean
+                    .iter()
+                    .map(|step| CleanPlan {
+                        step: step.clone(),
+                        regex: step.regex.as_deref().map(|pat| {
+                            Regex::new(pat).map_err(|e| EvalError::Regex(e.to_string()))
+                        }),
+                    })
+                    .collect(),
+            })),
+        }
+    }
+
+    /// 对上下文求一个值。
+    pub fn eval(&self, ctx: &Ctx, vars: &Vars) -> Result<String, EvalError> {
+        if !trace::active() {
+            return self.eval_value(ctx, vars);
+        }
+        trace::enter(self.label(), &ctx.text());
+        let out = self.eval_value(ctx, vars);
+        trace::exit(out.as_ref().map(|v| Some(v.as_str())));
+        out
+    }
+
+    /// 追踪里显示的规则摘要。
+    fn label(&self) -> String {
+        match self {
+            Plan::Literal(literal) => format!("literal {literal:?}"),
+            Plan::Template(Ok(template)) => format!("template {:?}", template.source()),
+            Plan::Template(Err(_)) => "template".into(),
+            Plan::FirstOf(_) => "firstOf".into(),
+            Plan::Concat { join, .. } => format!("concat join {join:?}"),
+            Plan::When { .. } => "when".into(),
+            Plan::Split { by, .. } => format!("split by {by:?}"),
+            Plan::Map { .. } => "map".into(),
+            Plan::Join { with, .. } => format!("join with {with:?}"),
+            Plan::Js(js) => format!("js {:?}", trace::truncate(&trace::excerpt(js), 40)),
+            Plan::Leaf(l) => l.label.clone(),
+        }
+    }
+
+    fn eval_value(&self, ctx: &Ctx, vars: &Vars) -> Result<String, EvalError> {
+        match self {
+            Plan::Literal(literal) => Ok(literal.clone()),
+            Plan::Template(template) => {
+                let template = template.as_ref().map_err(Clone::clone)?;
+                trace::note_vars(vars, template.names());
+                template.render(vars)
+            }
+            Plan::FirstOf(plans) => {
+                for p in plans {
+                    let v = p.eval(ctx, vars)?;
+                    if !v.trim().is_empty() {
+                        return Ok(v);
+                    }
+                }
+                Ok(String::new())
+            }
+            Plan::Concat { parts, join } => {
+                let mut out = Vec::new();
+                for p in parts {
+                    let v = p.eval(ctx, vars)?;
+                    if !v.trim().is_empty() {
+                        out.push(v);
+                    }
+                }
+                Ok(out.join(join))
+            }
+            Plan::When {
+                cond,
+                then,
+                otherwise,
+            } => match (cond.test(ctx, vars)?, otherwise) {
+                (true, _) => then.eval(ctx, vars),
+                (false, Some(otherwise)) => otherwise.eval(ctx, vars),
+                (false, None) => Ok(String::new()),
+            },
+            Plan::Split { value, by } => Ok(split(&value.eval(ctx, vars)?, by).join("\n")),
+            Plan::Map { .. } => Ok(self
+                .list(ctx, vars)?
+                .iter()
+                .map(|item| item.text())
+                .collect::<Vec<_>>()
+                .join("\n")),
+            Plan::Join { list, with } => {
+                let mut out = Vec::new();
+                for item in list.list(ctx, vars)? {
+                    // 叶子规则的 `extract`/`clean` 作用于每一项,与单值求值一致。
+                    let v = match list.as_ref() {
+                        Plan::Leaf(l) => l.item_value(&item, vars)?,
+                        _ => item.value().into_owned(),
+                    };
+                    if !v.trim().is_empty() {
+                        out.push(v);
+                    }
+                }
+                Ok(out.join(with))
+            }
+            Plan::Js(js) => {
+                trace::note_vars(vars, vars.keys().map(String::as_str));
+                run_js(js, &ctx.text(), vars)
+            }
+            Plan::Leaf(l) => {
+                if trace::active() {
+                    l.note_matches(ctx);
+                }
+                let raw = l.extract(ctx)?;
+                trace::note_step(|| "抽取".into(), &raw);
+                l.apply_clean(raw, vars)
+            }
+        }
+    }
+
+    /// 选中所有匹配,返回各自的子上下文。HTML/JSON 列表项指向 `page` 里共享的解析结果。
+    pub fn select<'p>(&self, page: &'p Page<'p>, vars: &Vars) -> Result<Vec<Ctx<'p>>, EvalError> {
+        self.list(&Ctx::Page(page), vars)
+    }
+
+    /// 在任意上下文上选中所有匹配(`map`/`join` 的输入可以是列表项里的子列表)。
+    fn list<'p>(&self, ctx: &Ctx<'p>, vars: &Vars) -> Result<Vec<Ctx<'p>>, EvalError> {
+        if !trace::active() {
+            return self.list_items(ctx, vars);
+        }
+        trace::enter(self.label(), &ctx.text());
+        let out = self.list_items(ctx, vars);
+        if let Ok(items) = &out {
+            trace::note_matches(items.iter().map(Ctx::value));
+        }
+        trace::exit(out.as_ref().map(|_| None));
+        out
+    }
+
+    fn list_items<'p>(&self, ctx: &Ctx<'p>, vars: &Vars) -> Result<Vec<Ctx<'p>>, EvalError> {
+        match self {
+            Plan::Leaf(l) => l.list(ctx),
+            Plan::FirstOf(plans) => {
+                for p in plans {
+                    let v = p.list(ctx, vars)?;
+                    if !v.is_empty() {
+                        return Ok(v);
+                    }
+                }
+                Ok(Vec::new())
+            }
+            Plan::When {
+                cond,
+                then,
+                otherwise,
+            } => match (cond.test(ctx, vars)?, otherwise) {
+                (true, _) => then.list(ctx, vars),
+                (false, Some(otherwise)) => otherwise.list(ctx, vars),
+                (false, None) => Ok(Vec::new()),
+            },
+            Plan::Split { value, by } => Ok(split(&value.eval(ctx, vars)?, by)
+                .into_iter()
+                .map(|item| Ctx::Text(Cow::Owned(item.to_string())))
+                .collect()),
+            Plan::Map { list, with } => {
+                let mut out = Vec::new();
+                for item in list.list(ctx, vars)? {
+                    let v = with.eval(&item, vars)?;
+                    if !v.trim().is_empty() {
+                        out.push(Ctx::Text(Cow::Owned(v)));
+                    }
+                }
+                Ok(out)
+            }
+            // literal/template/concat/join 作为列表无意义:退化为单值(若非空)。
+            other => {
+                let v = other.eval(ctx, vars)?;
+                Ok(if v.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![Ctx::Text(Cow::Owned(v))]
+                })
+            }
+        }
+    }
+}
+
+/// 叶子规则摘要,如 `css ".title" [0] {"attr":"href"}`。
+fn leaf_label(l: &LeafRule) -> String {
+    let mut label = match l.via {
+        Via::Css => "css",
+        Via::Xpath => "xpath",
+        Via::Json => "json",
+        Via::Regex => "regex",
+        Via::Raw => "raw",
+    }
+    .to_string();
+    if let Some(select) = &l.select {
+        label.push_str(&format!(" {select:?}"));
+    }
+    match &l.index {
+        Some(Index::At(i)) => label.push_str(&format!(" [{i}]")),
+        Some(Index::Expr(expr)) => label.push_str(&format!(" [{expr}]")),
+        None => {}
+    }
+    if l.extract != Extract::default() {
+        label.push(' ');
+        label.push_str(&serde_json::to_string(&l.extract).unwrap_or_default());
+    }
+    label
+}
+
+/// 按分隔符切分,项去首尾空白、丢弃空项。
+fn split<'s>(value: &'s str, by: &str) -> Vec<&'s str> {
+    value
+        .split(by)
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+impl CondPlan {
+    fn compile(cond: &Condition) -> Self {
+        Self {
+            var: cond.var.clone(),
+            value: cond.value.as_deref().map(Plan::compile),
+            regex: cond
+                .matches
+                .as_deref()
+                .map(|pat| Regex::new(pat).map_err(|e| EvalError::Regex(e.to_string()))),
+            not: cond.not,
+        }
+    }
+
+    /// 取条件的对象(变量 / 规则值 / 当前上下文文本),按正则或非空判断。
+    fn test(&self, ctx: &Ctx, vars: &Vars) -> Result<bool, EvalError> {
+        let subject = match (&self.var, &self.value) {
+            (Some(var), _) => Cow::Owned(vars.get(var).cloned().unwrap_or_default()),
+            (None, Some(value)) => Cow::Owned(value.eval(ctx, vars)?),
+            (None, None) => ctx.text(),
+        };
+        let hit = match &self.regex {
+            Some(re) => re
+                .as_ref()
+                .map_err(Clone::clone)?
+                .is_match(&subject)
+                .map_err(|e| EvalError::Regex(e.to_string()))?,
+            None => !subject.trim().is_empty(),
+        } != self.not;
+        if let Some(var) = &self.var {
+            trace::note_vars(vars, std::iter::once(var.as_str()));
+        }
+        trace::note_step(
+            || format!("条件{}", if hit { "成立" } else { "不成立" }),
+            &subject,
+        );
+        Ok(hit)
+    }
+}
+
+impl LeafPlan {
+    fn selector(&self) -> Result<&Selector, EvalError> {
+        self.selector.as_ref().map_err(Clone::clone)
+    }
+
+    fn index(&self) -> Result<&Pick, EvalError> {
+        self.index.as_ref().map_err(Clone::clone)
+    }
+
+    fn extract(&self, ctx: &Ctx) -> Result<String, EvalError> {
+        if self.via == Via::Raw {
+            return Ok(ctx.text().into_owned());
+        }
+        if self.via == Via::Xpath {
+            return super::xpath::xpath_extract(
+                &ctx.text(),
+                self.select.as_deref(),
+                self.index()?,
+                &self.extract,
+            );
+        }
+        let selector = self.selector()?;
+        let (index, ex) = (self.index()?, &self.extract);
+        match (self.via, ctx) {
+            (Via::Css, Ctx::Page(page)) => {
+                Ok(backend::doc_extract(page.html(), selector, index, ex))
+            }
+            (Via::Css, Ctx::Node(node)) => Ok(backend::node_extract(node, selector, index, ex)),
+            (Via::Css, ctx) => Ok(backend::doc_extract(
+                &Document::from(ctx.text().as_ref()),
+                selector,
+                index,
+                ex,
+            )),
+            (Via::Json, Ctx::Page(page)) => backend::json_extract(page.json()?, selector, index),
+            (Via::Json, Ctx::Json(value)) => backend::json_extract(value, selector, index),
+            (Via::Json, ctx) => {
+                backend::json_extract(&backend::parse_json_content(&ctx.text())?, selector, index)
+            }
+            (_, ctx) => Ok(backend::regex_extract(&ctx.text(), selector, index)),
+        }
+    }
+
+    /// 列表项本身作为值:节点按 `extract` 取文本或属性,其余取项的文本,再走清洗流水线。
+    fn item_value(&self, item: &Ctx, vars: &Vars) -> Result<String, EvalError> {
+        let raw = match item {
+            Ctx::Node(node) => backend::node_value(node, &self.extract),
+            other => other.value().into_owned(),
+        };
+        self.apply_clean(raw, vars)
+    }
+
+    /// 在上下文上选中所有匹配,再按 `index` 筛选列表项。
+    fn list<'p>(&self, ctx: &Ctx<'p>) -> Result<Vec<Ctx<'p>>, EvalError> {
+        if self.select.is_none() {
+            return Ok(vec![ctx.clone()]);
+        }
+        let items = self.matches(ctx)?;
+        Ok(self.index()?.filter(items))
+    }
+
+    /// 追踪用:记下选择器在上下文上的全部匹配(`index` 筛选前);选择器有错时留给抽取报。
+    fn note_matches(&self, ctx: &Ctx) {
+        if self.select.is_none() || self.via == Via::Raw {
+            return;
+        }
+        if let Ok(items) = self.matches(ctx) {
+            trace::note_matches(items.iter().map(Ctx::value));
+        }
+    }
+
+    /// 选中全部匹配:整页与共享文档里的节点 / JSON 值直接在原解析结果上选,
+    /// 文本上下文现场解析,列表项转为独立的文本。
+    fn matches<'p>(&self, ctx: &Ctx<'p>) -> Result<Vec<Ctx<'p>>, EvalError> {
+        Ok(match (self.via, self.selector()?, ctx) {
+            (_, _, Ctx::Page(page)) => self.select(page)?,
+            (Via::Css, Selector::Css(matcher), Ctx::Node(node)) => {
+                backend::node_select(node, matcher)
+                    .into_iter()
+                    .map(Ctx::Node)
+                    .collect()
+            }
+            (Via::Json, selector, Ctx::Json(value)) => {
+                json_items(backend::json_select(value, selector)?)
+            }
+            (_, _, ctx) => {
+                let text = ctx.text();
+                let page = Page::new(&text);
+                self.select(&page)?
+                    .iter()
+                    .map(|item| Ctx::Text(Cow::Owned(item.text().into_owned())))
+                    .collect()
+            }
+        })
+    }
+
+    fn select<'p>(&self, page: &'p Page<'p>) -> Result<Vec<Ctx<'p>>, EvalError> {
+        // 无选择器:把当前上下文作为单一项(而非把空串当非法选择器)。
+        let Some(select) = self.select.as_deref() else {
+            return Ok(vec![Ctx::Page(page)]);
+        };
+        Ok(match (self.via, self.selector()?) {
+            (Via::Css, Selector::Css(matcher)) => backend::doc_select(page.html(), matcher)
+                .into_iter()
+                .map(Ctx::Node)
+                .collect(),
+            (Via::Json, selector) => json_items(backend::json_select(page.json()?, selector)?),
+            (Via::Regex, Selector::Regex(re)) => backend::regex_select(page.text(), re)
+                .into_iter()
+                .map(|s| Ctx::Text(Cow::Owned(s)))
+                .collect(),
+            (Via::Xpath, _) => super::xpath::xpath_select_all(page.text(), select)?
+                .into_iter()
+                .map(|s| Ctx::Text(Cow::Owned(s)))
+                .collect(),
+            _ => vec![Ctx::Page(page)],
+        })
+    }
+
+    /// 应用清洗流水线。步内固定顺序:
+    /// `regex→replace → trim → prepend → append → decode → encode → hash → cipher → fontMap → cn`。
+    /// 编解码/加解密会失败(非法输入、错密钥),故返回 `Result`(显式报错,不静默空)。
+    fn apply_clean(&self, mut s: String, vars: &Vars) -> Result<String, EvalError> {
+        for CleanPlan { step, regex } in &self.clean {
+            if let Some(re) = regex {
+                // 非法正则是配置错误,显式报错(与抽取层及下方 crypto 步一致),不静默跳过。
+                let re = re.as_ref().map_err(Clone::clone)?;
+                let rep = step.replace.as_deref().unwrap_or("");
+                s = re.replace_all(&s, rep).into_owned();
+            }
+            if step.trim.unwrap_or(false) {
+                s = s.trim().to_string();
+            }
+            if let Some(p) = &step.prepend {
+                s = format!("{p}{s}");
+            }
+            if let Some(a) = &step.append {
+                s = format!("{s}{a}");
+            }
+            if let Some(c) = step.decode {
+                s = transform::decode(&s, c)?;
+            }
+            if let Some(c) = step.encode {
+                s = transform::encode(&s, c)?;
+            }
+            if let Some(h) = &step.hash {
+                s = transform::hash(&s, h)?;
+            }
+            if let Some(c) = &step.cipher {
+                s = transform::cipher(&s, c)?;
+            }
+            if let Some(table) = &step.font_map {
+                s = transform::font_map(&s, table)?;
+            }
+            if let Some(cn) = step.cn {
+                s = transform::cn_convert(&s, cn);
+            }
+            if let Some(js) = &step.js {
+                trace::note_vars(vars, vars.keys().map(String::as_str));
+                s = run_js(js, &s, vars)?;
+            }
+            trace::note_step(|| serde_json::to_string(step).unwrap_or_default(), &s);
+        }
+        Ok(s)
+    }
+}
+
+/// JSON 列表项:对象/数组共享原值,字符串值取内容作子上下文(可能是一段 HTML 或 JSON 文本),
+/// 与解释求值一致。
+fn json_items(values: Vec<&Value>) -> Vec<Ctx<'_>> {
+    values
+        .into_iter()
+        .map(|value| match value {
+            Value::Object(_) | Value::Array(_) => Ctx::Json(value),
+            other => Ctx::Text(Cow::Owned(backend::value_to_string(other))),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(j: &str) -> Plan {
+        Plan::compile(&serde_json::from_str(j).expect("rule json"))
+    }
+
+    #[test]
+    fn list_items_share_one_document() {
+        let html = r#"<ul><li><a href="/1">甲</a></li><li><a href="/2">乙</a><b>新</b></li></ul>"#;
+        let page = Page::new(html);
+        let items = plan(r#"{"via":"css","select":"li"}"#)
+            .select(&page, &Vars::new())
+            .unwrap();
+        assert!(items.iter().all(|item| matches!(item, Ctx::Node(_))));
+
+        let name = plan(r#"{"via":"css","select":"a"}"#);
+        let url = plan(r#"{"via":"css","select":"a","extract":{"attr":"href"}}"#);
+        let own = plan(r#"{"via":"css","extract":{"attr":"href"}}"#);
+        let got: Vec<_> = items
+            .iter()
+            .map(|item| {
+                (
+                    name.eval(item, &Vars::new()).unwrap(),
+                    url.eval(item, &Vars::new()).unwrap(),
+                    own.eval(item, &Vars::new()).unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            got,
+            [
+                ("甲".into(), "/1".into(), String::new()),
+                ("乙".into(), "/2".into(), String::new())
+            ]
+        );
+        // 选择器既匹配后代也匹配列表项自身;项与项之间互不可见。
+        let anchors = plan(r#"{"via":"css","select":"a"}"#)
+            .select(&page, &Vars::new())
+            .unwrap();
+        assert_eq!(own.eval(&anchors[1], &Vars::new()).unwrap(), "/2");
+        let bold = plan(r#"{"via":"css","select":"b"}"#);
+        assert_eq!(bold.eval(&items[0], &Vars::new()).unwrap(), "");
+        // 文本形态与解释求值的子上下文串一致。
+        assert_eq!(anchors[0].text(), r#"<a href="/1">甲</a>"#);
+    }
+
+    #[test]
+    fn json_list_items_are_shared_values() {
+        let page = Page::new(r#"{"data":[{"name":"甲","tags":["a"]},"<b>乙</b>"]}"#);
+        let items = plan(r#"{"via":"json","select":"$.data[*]"}"#)
+            .select(&page, &Vars::new())
+            .unwrap();
+        assert!(matches!(items[0], Ctx::Json(_)));
+        assert!(matches!(items[1], Ctx::Text(_)));
+        let name = plan(r#"{"via":"json","select":"$.name"}"#);
+        assert_eq!(name.eval(&items[0], &Vars::new()).unwrap(), "甲");
+        let bold = plan(r#"{"via":"css","select":"b"}"#);
+        assert_eq!(bold.eval(&items[1], &Vars::new()).unwrap(), "乙");
+    }
+
+    #[test]
+    fn when_branches_on_context_or_variable() {
+        let rule = plan(
+            r#"{"when":{"matches":"^\\d+$"},"then":{"template":"第{{n}}页"},"else":{"literal":"其他"}}"#,
+        );
+        let vars: Vars = [("n".to_string(), "3".to_string())].into();
+        assert_eq!(rule.eval(&Ctx::Text("12".into()), &vars).unwrap(), "第3页");
+        assert_eq!(rule.eval(&Ctx::Text("ab".into()), &vars).unwrap(), "其他");
+
+        let by_var = plan(r#"{"when":{"var":"page","not":true},"then":{"literal":"首页"}}"#);
+        assert_eq!(by_var.eval(&Ctx::Text("".into()), &vars).unwrap(), "首页");
+        let vars: Vars = [("page".to_string(), "2".to_string())].into();
+        assert_eq!(by_var.eval(&Ctx::Text("".into()), &vars).unwrap(), "");
+
+        let bad = plan(r#"{"when":{"matches":"("},"then":{"literal":"x"}}"#);
+        assert!(matches!(
+            bad.eval(&Ctx::Text("x".into()), &Vars::new()),
+            Err(EvalError::Regex(_))
+        ));
+    }
+
+    #[test]
+    fn split_map_and_join_lists() {
+        let page = Page::new(
+            r#"<div class="tags">玄幻, 仙侠 ,,都市</div><ul><li><a href="/1">甲</a></li><li><a href="/2">乙</a></li></ul>"#,
+        );
+        let tags = plan(r#"{"split":{"via":"css","select":".tags"},"by":","}"#);
+        let items = tags.select(&page, &Vars::new()).unwrap();
+        let texts: Vec<_> = items.iter().map(Ctx::text).collect();
+        assert_eq!(texts, ["玄幻", "仙侠", "都市"]);
+        assert_eq!(
+            tags.eval(&Ctx::Page(&page), &Vars::new()).unwrap(),
+            "玄幻\n仙侠\n都市"
+        );
+
+        let hrefs = plan(
+            r#"{"map":{"via":"css","select":"li"},"with":{"via":"css","select":"a","extract":{"attr":"href"}}}"#,
+        );
+        let items = hrefs.select(&page, &Vars::new()).unwrap();
+        assert_eq!(
+            items.iter().map(Ctx::text).collect::<Vec<_>>(),
+            ["/1", "/2"]
+        );
+
+        let names = plan(r#"{"join":{"via":"css","select":"li"},"with":" / "}"#);
+        assert_eq!(
+            names.eval(&Ctx::Page(&page), &Vars::new()).unwrap(),
+            "甲 / 乙"
+        );
+        // 列表项上的子列表:在共享节点里选,不越出该项。
+        let li = plan(r#"{"via":"css","select":"li"}"#)
+            .select(&page, &Vars::new())
+            .unwrap();
+        let own = plan(r#"{"join":{"via":"css","select":"a","extract":{"attr":"href"}}}"#);
+        assert_eq!(own.eval(&li[1], &Vars::new()).unwrap(), "/2");
+        // 内层叶子的 extract 与 clean 作用于每一项。
+        let hrefs = plan(
+            r#"{"join":{"via":"css","select":"a","extract":{"attr":"href"},"clean":[{"prepend":"https://x"}]},"with":" "}"#,
+        );
+        assert_eq!(
+            hrefs.eval(&Ctx::Page(&page), &Vars::new()).unwrap(),
+            "https://x/1 https://x/2"
+        );
+    }
+
+    #[test]
+    fn combinators_deserialize_by_key() {
+        let rule: Rule = serde_json::from_str(
+            r#"{"map":{"split":{"via":"raw"}},"with":{"when":{"value":{"via":"raw"}},"then":{"via":"raw"}}}"#,
+        )
+        .unwrap();
+        let Rule::Map { map, with } = rule else {
+            panic!("应解析为 map: {rule:?}");
+        };
+        assert!(matches!(*map, Rule::Split { ref by, .. } if by == ","));
+        assert!(matches!(
+            *with,
+            Rule::When {
+                otherwise: None,
+                ..
+            }
+        ));
+        let join: Rule = serde_json::from_str(r#"{"join":{"via":"css","select":"a"}}"#).unwrap();
+        assert_eq!(join.primary_via(), Some(Via::Css));
+    }
+
+    #[test]
+    fn index_expressions_behave_alike_across_backends() {
+        let html = Page::new("<ul><li>0</li><li>1</li><li>2</li><li>3</li><li>4</li></ul>");
+        let json = Page::new(r#"{"a":["0","1","2","3","4"]}"#);
+        let text = Page::new("c0 c1 c2 c3 c4");
+        let cases = [
+            (&html, r#""via":"css","select":"li""#),
+            (&html, r#""via":"xpath","select":"//li""#),
+            (&json, r#""via":"json","select":"$.a[*]""#),
+            (&text, r#""via":"regex","select":"c\\d""#),
+        ];
+        for (page, leaf) in cases {
+            let values = |index: &str| {
+                let rule = plan(&format!(r#"{{{leaf},"index":{index}}}"#));
+                let items = rule.select(page, &Vars::new()).unwrap();
+                let listed: Vec<_> = items
+                    .iter()
+                    .map(|item| item.text().chars().find(char::is_ascii_digit))
+                    .collect();
+                (listed, rule.eval(&Ctx::Page(page), &Vars::new()).unwrap())
+            };
+            let (listed, value) = values(r#""2:""#);
+            assert_eq!(listed, [Some('2'), Some('3'), Some('4')], "{leaf}");
+            assert_eq!(value.replace('c', ""), "2\n3\n4", "{leaf}");
+            let (listed, _) = values(r#""!0,!-1""#);
+            assert_eq!(listed.len(), 3, "{leaf}");
+            let (listed, value) = values("-1");
+            assert_eq!(listed.len(), 1, "{leaf}");
+            assert_eq!(value.replace('c', ""), "4", "{leaf}");
+        }
+        let bad = plan(r#"{"via":"css","select":"li","index":"1::0"}"#);
+        assert!(matches!(
+            bad.select(&html, &Vars::new()),
+            Err(EvalError::Index(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_patterns_fail_when_evaluated() {
+        let bad = plan(r#"{"firstOf":[{"via":"raw"},{"via":"css","select":"a[["}]}"#);
+        // 第一条就有值,坏选择器不会被求值。
+        assert_eq!(bad.eval(&Ctx::Text("x".into()), &Vars::new()).unwrap(), "x");
+        let bad = plan(r#"{"via":"css","select":"a[["}"#);
+        assert!(matches!(
+            bad.eval(&Ctx::Text("x".into()), &Vars::new()),
+            Err(EvalError::Selector(_))
+        ));
+        let bad = plan(r#"{"via":"raw","clean":[{"regex":"("}]}"#);
+        assert!(matches!(
+            bad.eval(&Ctx::Text("x".into()), &Vars::new()),
+            Err(EvalError::Regex(_))
+        ));
+    }
+}
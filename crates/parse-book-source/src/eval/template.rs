@@ -0,0 +1,343 @@
+//! 模板插值与过滤器管道:`{{name}}`、`{{name | filter:arg | ...}}`。
+//!
+//! 过滤器按书写顺序依次作用于变量值,编解码与哈希复用 [`transform`] 的实现:
+//!
+//! | 过滤器 | 作用 |
+//! |--------|------|
+//! | `urlencode[:字符集]` | URL 百分号编码,字符集缺省 UTF-8,可写 `gbk` / `gb18030` / `big5` 等 |
+//! | `base64` / `base64url` / `hex` | 编码 UTF-8 字节 |
+//! | `md5` / `sha1` / `sha256` / `sha512`[`:base64`] | 哈希,缺省输出小写 hex |
+//! | `ms` / `s` | 把毫秒时间戳取整为毫秒 / 秒(配合 `now`) |
+//! | `add:n` / `sub:n` / `mul:n` / `div:n` | 整数运算(如 `{{page \| sub:1 \| mul:20}}` 算偏移) |
+//! | `lower` / `upper` / `trim` | 大小写 / 去首尾空白 |
+//! | `default:x` | 值为空时取 `x` |
+//!
+//! 未知变量取空串;`now` 未被变量覆盖时为当前 Unix 毫秒时间戳。花括号里不是
+//! 「变量名 + 过滤器」形状的内容原样保留(如 JSON 请求体里的 `{{`)。
+
+use super::{Vars, transform};
+use crate::error::EvalError;
+use crate::source::{Codec, HashAlgo, HashOut, HashStep};
+use encoding_rs::Encoding;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 解析好的模板:文本段与带过滤器的变量段交替。
+#[derive(Debug, Clone)]
+pub struct Template {
+    src: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    Var { name: String, filters: Vec<Filter> },
+}
+
+#[derive(Debug, Clone)]
+enum Filter {
+    UrlEncode(&'static Encoding),
+    Encode(Codec),
+    Hash(HashAlgo, HashOut),
+    Millis,
+    Secs,
+    Arith(Arith, i64),
+    Lower,
+    Upper,
+    Trim,
+    Default(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Arith {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Template {
+    /// 解析模板;未知过滤器或非法参数报错。
+    pub fn parse(src: &str) -> Result<Self, EvalError> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut rest = src;
+        while let Some(open) = rest.find("{{") {
+            text.push_str(&rest[..open]);
+            let after = &rest[open + 2..];
+            let Some(close) = after.find("}}") else {
+                text.push_str(&rest[open..]);
+                rest = "";
+                break;
+            };
+            match parse_var(&after[..close])? {
+                Some(var) => {
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(var);
+                    rest = &after[close + 2..];
+                }
+                // 不是变量:`{{` 原样保留,从其后继续找(内层可能还有变量)。
+                None => {
+                    text.push_str("{{");
+                    rest = after;
+                }
+            }
+        }
+        text.push_str(rest);
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self {
+            src: src.to_string(),
+            parts,
+        })
+    }
+
+    /// 模板原文。
+    pub fn source(&self) -> &str {
+        &self.src
+    }
+
+    /// 模板引用的变量名(按出现顺序,可能重复)。
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Var { name, .. } => Some(name.as_str()),
+            Part::Text(_) => None,
+        })
+    }
+
+    /// 代入变量求值。
+    pub fn render(&self, vars: &Vars) -> Result<String, EvalError> {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Var { name, filters } => {
+                    let mut value = lookup(name, vars);
+                    for filter in filters {
+                        value = filter.apply(value)?;
+                    }
+                    out.push_str(&value);
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// 解析并求值一段模板(一次性;反复求值的模板应先 [`Template::parse`])。
+pub fn render(src: &str, vars: &Vars) -> Result<String, EvalError> {
+    Template::parse(src)?.render(vars)
+}
+
+fn lookup(name: &str, vars: &Vars) -> String {
+    match vars.get(name) {
+        Some(value) => value.clone(),
+        None if name == "now" => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis().to_string())
+            .unwrap_or_default(),
+        None => String::new(),
+    }
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// 解析 `{{` 与 `}}` 之间的内容;不是变量形状返回 `None`。
+fn parse_var(inner: &str) -> Result<Option<Part>, EvalError> {
+    let mut pieces = inner.split('|');
+    let name = pieces.next().unwrap_or_default().trim();
+    if !is_name(name) {
+        return Ok(None);
+    }
+    let filters = pieces.map(parse_filter).collect::<Result<_, _>>()?;
+    Ok(Some(Part::Var {
+        name: name.to_string(),
+        filters,
+    }))
+}
+
+fn parse_filter(src: &str) -> Result<Filter, EvalError> {
+    let src = src.trim();
+    let (name, arg) = match src.split_once(':') {
+        Some((name, arg)) => (name.trim(), Some(arg.trim())),
+        None => (src, None),
+    };
+    let err = |msg: &str| EvalError::Template(format!("{src}: {msg}"));
+    let int = || {
+        arg.and_then(|a| a.parse::<i64>().ok())
+            .ok_or_else(|| err("需要整数参数"))
+    };
+    let hash = |algo| {
+        let out = match arg {
+            None | Some("hex") => HashOut::Hex,
+            Some("base64") => HashOut::Base64,
+            Some(_) => return Err(err("输出只能是 hex 或 base64")),
+        };
+        Ok(Filter::Hash(algo, out))
+    };
+    Ok(match name {
+        "urlencode" => Filter::UrlEncode(match arg {
+            None => encoding_rs::UTF_8,
+            Some(label) => {
+                Encoding::for_label(label.as_bytes()).ok_or_else(|| err("未知字符集"))?
+            }
+        }),
+        "base64" => Filter::Encode(Codec::Base64),
+        "base64url" => Filter::Encode(Codec::Base64url),
+        "hex" => Filter::Encode(Codec::Hex),
+        "md5" => hash(HashAlgo::Md5)?,
+        "sha1" => hash(HashAlgo::Sha1)?,
+        "sha256" => hash(HashAlgo::Sha256)?,
+        "sha512" => hash(HashAlgo::Sha512)?,
+        "ms" => Filter::Millis,
+        "s" => Filter::Secs,
+        "add" => Filter::Arith(Arith::Add, int()?),
+        "sub" => Filter::Arith(Arith::Sub, int()?),
+        "mul" => Filter::Arith(Arith::Mul, int()?),
+        "div" => match int()? {
+            0 => return Err(err("除数不能为 0")),
+            n => Filter::Arith(Arith::Div, n),
+        },
+        "lower" => Filter::Lower,
+        "upper" => Filter::Upper,
+        "trim" => Filter::Trim,
+        "default" => Filter::Default(arg.unwrap_or_default().to_string()),
+        _ => return Err(err("未知过滤器")),
+    })
+}
+
+impl Filter {
+    fn apply(&self, value: String) -> Result<String, EvalError> {
+        let int = |value: &str| {
+            value
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| EvalError::Template(format!("「{value}」不是整数,无法做数值过滤")))
+        };
+        Ok(match self {
+            Filter::UrlEncode(encoding) => transform::url_encode(&value, encoding),
+            Filter::Encode(codec) => transform::encode(&value, *codec)?,
+            Filter::Hash(algo, output) => transform::hash(
+                &value,
+                &HashStep {
+                    algo: *algo,
+                    output: *output,
+                    hmac_key: None,
+                    hmac_key_enc: Default::default(),
+                },
+            )?,
+            Filter::Millis => int(&value)?.to_string(),
+            Filter::Secs => (int(&value)? / 1000).to_string(),
+            Filter::Arith(op, n) => {
+                let v = int(&value)?;
+                match op {
+                    Arith::Add => v.saturating_add(*n),
+                    Arith::Sub => v.saturating_sub(*n),
+                    Arith::Mul => v.saturating_mul(*n),
+                    Arith::Div => v / n,
+                }
+                .to_string()
+            }
+            Filter::Lower => value.to_lowercase(),
+            Filter::Upper => value.to_uppercase(),
+            Filter::Trim => value.trim().to_string(),
+            Filter::Default(fallback) if value.trim().is_empty() => fallback.clone(),
+            Filter::Default(_) => value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vars {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn plain_variables_and_unknown_keys() {
+        let v = vars(&[("key", "诡秘"), ("page", "2")]);
+        assert_eq!(
+            render("/s?q={{key}}&p={{ page }}&x={{missing}}", &v).unwrap(),
+            "/s?q=诡秘&p=2&x="
+        );
+    }
+
+    #[test]
+    fn urlencode_respects_charset() {
+        let v = vars(&[("key", "诡秘 之主")]);
+        assert_eq!(
+            render("{{key|urlencode}}", &v).unwrap(),
+            "%E8%AF%A1%E7%A7%98%20%E4%B9%8B%E4%B8%BB"
+        );
+        assert_eq!(
+            render("{{key | urlencode:gbk}}", &v).unwrap(),
+            "%B9%EE%C3%D8%20%D6%AE%D6%F7"
+        );
+    }
+
+    #[test]
+    fn hash_encode_and_arithmetic_chain() {
+        let v = vars(&[("key", "abc"), ("page", "3")]);
+        assert_eq!(
+            render("{{key | md5}}", &v).unwrap(),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(render("{{key | base64}}", &v).unwrap(), "YWJj");
+        assert_eq!(render("{{key | upper | hex}}", &v).unwrap(), "414243");
+        assert_eq!(render("{{page | sub:1 | mul:20}}", &v).unwrap(), "40");
+        assert_eq!(render("{{missing | default:全部}}", &v).unwrap(), "全部");
+    }
+
+    #[test]
+    fn now_is_a_millisecond_timestamp() {
+        let ms: i64 = render("{{now | ms}}", &Vars::new())
+            .unwrap()
+            .parse()
+            .unwrap();
+        let s: i64 = render("{{now | s}}", &Vars::new())
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(ms > 1_600_000_000_000);
+        assert!((ms / 1000 - s).abs() <= 1);
+        // 变量表里的 now 优先。
+        assert_eq!(
+            render("{{now | s}}", &vars(&[("now", "1700000000123")])).unwrap(),
+            "1700000000"
+        );
+    }
+
+    #[test]
+    fn non_variable_braces_are_kept() {
+        let v = vars(&[("key", "k")]);
+        assert_eq!(
+            render(r#"{"a":{{key}},"b":"{{ x y }}"}"#, &v).unwrap(),
+            r#"{"a":k,"b":"{{ x y }}"}"#
+        );
+        assert_eq!(render("{{key", &v).unwrap(), "{{key");
+    }
+
+    #[test]
+    fn bad_filters_are_reported() {
+        assert!(matches!(
+            Template::parse("{{key | rot13}}"),
+            Err(EvalError::Template(_))
+        ));
+        assert!(Template::parse("{{key | urlencode:klingon}}").is_err());
+        assert!(Template::parse("{{page | div:0}}").is_err());
+        assert!(render("{{key | add:1}}", &vars(&[("key", "abc")])).is_err());
+    }
+}
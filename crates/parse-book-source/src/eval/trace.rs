@@ -0,0 +1,302 @@
+//! 求值追踪:记下每个规则节点的输入摘录、匹配、`clean` 各步输出,以及模板 / JS 看到的变量,
+//! 让书源作者分清字段为空是取页、选择器、下标还是某个清洗步弄丢的。
+//!
+//! 求值是同步的(见 [`Page`](super::Page)),追踪状态放在线程局部:[`collect`] 在闭包运行期间
+//! 打开记录,期间每次 [`Plan::eval`](super::Plan::eval) / [`Plan::select`](super::Plan::select)
+//! 都成为树上的一个节点。未打开时埋点只做一次线程局部检查。
+//!
+//! 摘录截断到 [`EXCERPT_CHARS`] 个字符;每个节点最多留 [`MAX_CHILDREN`] 个子节点(长目录的
+//! 逐项求值只留前面一段,其余计入 [`TraceNode::omitted`])。
+
+use super::Vars;
+use crate::error::EvalError;
+use std::cell::RefCell;
+use std::fmt;
+
+/// 摘录保留的字符数。
+pub const EXCERPT_CHARS: usize = 200;
+/// 每个节点保留的子节点数。
+pub const MAX_CHILDREN: usize = 64;
+/// 每个节点保留的匹配项摘录数(匹配总数另记)。
+pub const MAX_MATCHES: usize = 8;
+/// [`TraceNode`] 的 `Display` 里每段摘录显示的字符数。
+const SHOWN_CHARS: usize = 60;
+
+/// 追踪树上的一个节点:一条规则的一次求值,或引擎的一个阶段(如「目录 第 1 页」)。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceNode {
+    /// 规则种类与关键参数(如 `css ".title" [0]`),或阶段名。
+    pub label: String,
+    /// 输入(当前上下文)摘录。
+    pub input: String,
+    /// 选择器匹配到的项数(叶子规则与列表规则);值规则没有选择器时为 `None`。
+    pub match_count: Option<usize>,
+    /// 前 [`MAX_MATCHES`] 个匹配项的摘录。
+    pub matches: Vec<String>,
+    /// 抽取与 `clean` 各步之后的值。
+    pub steps: Vec<TraceStep>,
+    /// 模板引用的变量与 JS 可见的变量。
+    pub vars: Vec<(String, String)>,
+    /// 求值结果摘录;列表规则与阶段节点为 `None`。
+    pub output: Option<String>,
+    /// 求值失败时的错误。
+    pub error: Option<String>,
+    pub children: Vec<TraceNode>,
+    /// 超出 [`MAX_CHILDREN`] 未记录的子节点数。
+    pub omitted: usize,
+}
+
+/// 一步处理及其输出摘录。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceStep {
+    pub step: String,
+    pub output: String,
+}
+
+thread_local! {
+    static STACK: RefCell<Vec<TraceNode>> = const { RefCell::new(Vec::new()) };
+}
+
+/// 打开追踪运行 `f`,返回结果与以 `label` 为根、`input` 为输入的追踪树。可嵌套。
+pub fn collect<R>(label: impl Into<String>, input: &str, f: impl FnOnce() -> R) -> (R, TraceNode) {
+    /// `f` panic 时把栈恢复到进入前的深度。
+    struct Guard(usize);
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            STACK.with_borrow_mut(|stack| stack.truncate(self.0));
+        }
+    }
+    let guard = Guard(STACK.with_borrow(Vec::len));
+    STACK.with_borrow_mut(|stack| stack.push(TraceNode::new(label.into(), input)));
+    let out = f();
+    let node = STACK
+        .with_borrow_mut(|stack| stack.drain(guard.0..).next())
+        .unwrap_or_default();
+    (out, node)
+}
+
+/// 当前线程是否在追踪。
+pub(crate) fn active() -> bool {
+    STACK.with_borrow(|stack| !stack.is_empty())
+}
+
+/// 开一个子节点(调用方先确认 [`active`])。
+pub(crate) fn enter(label: String, input: &str) {
+    STACK.with_borrow_mut(|stack| stack.push(TraceNode::new(label, input)));
+}
+
+/// 结束当前节点并挂到父节点下;`output` 为 `None` 表示列表求值(只记匹配)。
+pub(crate) fn exit(output: Result<Option<&str>, &EvalError>) {
+    STACK.with_borrow_mut(|stack| {
+        let Some(mut node) = stack.pop() else {
+            return;
+        };
+        match output {
+            Ok(value) => node.output = value.map(excerpt),
+            Err(e) => node.error = Some(e.to_string()),
+        }
+        if let Some(parent) = stack.last_mut() {
+            if parent.children.len() < MAX_CHILDREN {
+                parent.children.push(node);
+            } else {
+                parent.omitted += 1;
+            }
+        }
+    });
+}
+
+/// 修改当前节点;未在追踪时什么也不做。
+pub(crate) fn note(f: impl FnOnce(&mut TraceNode)) {
+    STACK.with_borrow_mut(|stack| {
+        if let Some(node) = stack.last_mut() {
+            f(node);
+        }
+    });
+}
+
+/// 记下匹配项。
+pub(crate) fn note_matches(items: impl ExactSizeIterator<Item = impl AsRef<str>>) {
+    note(|node| {
+        node.match_count = Some(items.len());
+        node.matches = items
+            .take(MAX_MATCHES)
+            .map(|item| excerpt(item.as_ref()))
+            .collect();
+    });
+}
+
+/// 记下一步处理后的值。
+pub(crate) fn note_step(step: impl FnOnce() -> String, output: &str) {
+    note(|node| {
+        node.steps.push(TraceStep {
+            step: step(),
+            output: excerpt(output),
+        })
+    });
+}
+
+/// 记下变量(按名排序,重复的只留一次)。
+pub(crate) fn note_vars<'v>(vars: &Vars, names: impl Iterator<Item = &'v str>) {
+    note(|node| {
+        for name in names {
+            if !node.vars.iter().any(|(n, _)| n == name) {
+                let value = vars.get(name).map(String::as_str).unwrap_or_default();
+                node.vars.push((name.to_string(), excerpt(value)));
+            }
+        }
+        node.vars.sort();
+    });
+}
+
+/// 摘录:空白折叠成单个空格,截到 [`EXCERPT_CHARS`] 个字符。
+pub fn excerpt(text: &str) -> String {
+    truncate(
+        &text.split_whitespace().collect::<Vec<_>>().join(" "),
+        EXCERPT_CHARS,
+    )
+}
+
+pub(crate) fn truncate(text: &str, chars: usize) -> String {
+    match text.char_indices().nth(chars) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+impl TraceNode {
+    fn new(label: String, input: &str) -> Self {
+        Self {
+            label,
+            input: excerpt(input),
+            ..Default::default()
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let pad = "  ".repeat(depth);
+        let shown = |s: &str| format!("«{}»", truncate(s, SHOWN_CHARS));
+        write!(f, "{pad}▸ {}", self.label)?;
+        match (&self.error, &self.output, self.match_count) {
+            (Some(e), _, _) => write!(f, "  ✗ {e}")?,
+            (None, Some(out), _) => write!(f, " = {}", shown(out))?,
+            (None, None, Some(n)) => write!(f, " → {n} 项")?,
+            (None, None, None) => {}
+        }
+        writeln!(f)?;
+        if !self.input.is_empty() {
+            writeln!(f, "{pad}    输入 {}", shown(&self.input))?;
+        }
+        if let Some(n) = self.match_count {
+            write!(f, "{pad}    匹配 {n} 项")?;
+            for sample in &self.matches {
+                write!(f, " {}", shown(sample))?;
+            }
+            writeln!(f, "{}", if n > self.matches.len() { " …" } else { "" })?;
+        }
+        for step in &self.steps {
+            writeln!(f, "{pad}    {} → {}", step.step, shown(&step.output))?;
+        }
+        for (name, value) in &self.vars {
+            writeln!(f, "{pad}    变量 {name} = {}", shown(value))?;
+        }
+        for child in &self.children {
+            child.write(f, depth + 1)?;
+        }
+        if self.omitted > 0 {
+            writeln!(f, "{pad}  … 另有 {} 个节点未记录", self.omitted)?;
+        }
+        Ok(())
+    }
+}
+
+/// 缩进树,摘录截到 60 个字符。
+impl fmt::Display for TraceNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::{Ctx, Page, Plan};
+
+    fn plan(j: &str) -> Plan {
+        Plan::compile(&serde_json::from_str(j).expect("rule json"))
+    }
+
+    #[test]
+    fn records_matches_clean_steps_and_vars() {
+        let page = Page::new(r#"<h1> 诡秘之主 </h1><p class="a">作者:乌贼</p><p class="a">x</p>"#);
+        let rule = plan(
+            r#"{"firstOf":[
+                {"via":"css","select":".missing"},
+                {"via":"css","select":"p.a","clean":[{"regex":"^作者:"},{"append":"著"}]}
+            ]}"#,
+        );
+        let vars: Vars = [("key".to_string(), "诡秘".to_string())].into();
+        let (out, root) = collect("书详情", page.text(), || {
+            rule.eval(&Ctx::Page(&page), &vars)
+        });
+        assert_eq!(out.unwrap(), "乌贼著");
+        assert!(!active());
+        assert_eq!(root.label, "书详情");
+        let first_of = &root.children[0];
+        assert_eq!(first_of.output.as_deref(), Some("乌贼著"));
+        let [missing, author] = &first_of.children[..] else {
+            panic!("firstOf 应有两个子节点: {first_of:?}");
+        };
+        assert_eq!(missing.match_count, Some(0));
+        assert_eq!(missing.output.as_deref(), Some(""));
+        assert_eq!(author.match_count, Some(2));
+        assert_eq!(author.matches, ["作者:乌贼", "x"]);
+        let steps: Vec<_> = author.steps.iter().map(|s| s.output.as_str()).collect();
+        assert_eq!(steps, ["作者:乌贼", "乌贼", "乌贼著"]);
+
+        let template = plan(r#"{"template":"{{base}}/s?q={{key | urlencode}}"}"#);
+        let (_, root) = collect("请求", "", || template.eval(&Ctx::Text("".into()), &vars));
+        assert_eq!(
+            root.children[0].vars,
+            [
+                ("base".to_string(), String::new()),
+                ("key".to_string(), "诡秘".to_string())
+            ]
+        );
+        let shown = root.to_string();
+        assert!(shown.contains("变量 key = «诡秘»"), "{shown}");
+    }
+
+    #[test]
+    fn list_nodes_and_errors() {
+        let page = Page::new("<li>1</li><li>2</li><li>3</li>");
+        let list = plan(r#"{"via":"css","select":"li","index":"1:"}"#);
+        let bad = plan(r#"{"via":"css","select":"a[["}"#);
+        let (_, root) = collect("目录", page.text(), || {
+            let _ = list.select(&page, &Vars::new());
+            let _ = bad.eval(&Ctx::Page(&page), &Vars::new());
+        });
+        let [list, bad] = &root.children[..] else {
+            panic!("应有两个子节点: {root:?}");
+        };
+        assert_eq!(list.output, None);
+        assert_eq!(list.match_count, Some(2));
+        assert!(list.to_string().contains("→ 2 项"));
+        assert!(bad.error.as_deref().is_some_and(|e| e.contains("a[[")));
+    }
+
+    #[test]
+    fn children_are_capped() {
+        let rule = plan(r#"{"via":"raw"}"#);
+        let (_, root) = collect("长目录", "", || {
+            for _ in 0..MAX_CHILDREN + 5 {
+                let _ = rule.eval(&Ctx::Text("x".into()), &Vars::new());
+            }
+        });
+        assert_eq!(root.children.len(), MAX_CHILDREN);
+        assert_eq!(root.omitted, 5);
+        assert_eq!(
+            excerpt(&"字".repeat(EXCERPT_CHARS + 1)).chars().count(),
+            EXCERPT_CHARS + 1
+        );
+    }
+}
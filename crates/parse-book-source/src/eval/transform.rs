@@ -46,12 +46,16 @@ pub fn encode(s: &str, codec: Codec) -> Result<String, EvalError> {
         Codec::Base64 => B64_STD.encode(s.as_bytes()),
         Codec::Base64url => B64_URL.encode(s.as_bytes()),
         Codec::Hex => hex::encode(s.as_bytes()),
-        Codec::Url => {
-            percent_encoding::utf8_percent_encode(s, percent_encoding::NON_ALPHANUMERIC).to_string()
-        }
+        Codec::Url => url_encode(s, encoding_rs::UTF_8),
     })
 }
 
+/// 按 `encoding` 编出字节后做 URL 百分号编码(GBK 站点的搜索关键字等)。
+pub fn url_encode(s: &str, encoding: &'static encoding_rs::Encoding) -> String {
+    let (bytes, _, _) = encoding.encode(s);
+    percent_encoding::percent_encode(&bytes, percent_encoding::NON_ALPHANUMERIC).to_string()
+}
+
 fn decode_bytes(s: &str, codec: Codec) -> Result<Vec<u8>, EvalError> {
     match codec {
         Codec::Base64 => B64_STD
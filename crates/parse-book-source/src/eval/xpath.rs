@@ -13,7 +13,8 @@
 //! 失败安全降级:HTML 桥接为空 / XPath 合法但无匹配 → 空结果(交 `firstOf` 兜底);
 //! 仅 XPath 表达式本身语法非法 → `EvalError::Xpath`。
 
-use super::backend::{clean_html, resolve_index};
+use super::backend::{clean_html, join_values};
+use super::index::Pick;
 use crate::error::EvalError;
 use crate::source::{Extract, ExtractOp};
 use sxd_document::Package;
@@ -25,7 +26,7 @@ use sxd_xpath::{Context, Factory, Value};
 pub fn xpath_extract(
     content: &str,
     select: Option<&str>,
-    index: Option<i64>,
+    index: &Pick,
     ex: &Extract,
 ) -> Result<String, EvalError> {
     let Some(select) = select else {
@@ -41,13 +42,12 @@ pub fn xpath_extract(
         Value::Boolean(b) => b.to_string(),
         Value::Number(n) => fmt_number(n),
         Value::String(s) => s.trim().to_string(),
-        Value::Nodeset(ns) => {
-            let nodes = ns.document_order();
-            if nodes.is_empty() {
-                return Ok(String::new());
-            }
-            extract_from_node(nodes[resolve_index(index, nodes.len())], ex)
-        }
+        Value::Nodeset(ns) => join_values(
+            index
+                .pick(&ns.document_order())
+                .into_iter()
+                .map(|node| extract_from_node(*node, ex)),
+        ),
     })
 }
 
@@ -283,7 +283,7 @@ mod tests {
         let v = xpath_extract(
             DIRTY,
             Some("//div[@class='title']"),
-            None,
+            &Pick::Default,
             &Extract::default(),
         )
         .unwrap();
@@ -296,7 +296,7 @@ mod tests {
         let v = xpath_extract(
             DIRTY,
             Some("//ul[@id='toc']/li[1]/a/@href"),
-            None,
+            &Pick::Default,
             &Extract::default(),
         )
         .unwrap();
@@ -305,13 +305,14 @@ mod tests {
 
     #[test]
     fn element_attr_extract() {
-        let v = xpath_extract(DIRTY, Some("//img"), None, &attr("src")).unwrap();
+        let v = xpath_extract(DIRTY, Some("//img"), &Pick::Default, &attr("src")).unwrap();
         assert_eq!(v, "/cover.jpg");
     }
 
     #[test]
     fn index_negative_picks_last() {
-        let v = xpath_extract(DIRTY, Some("//ul/li/a"), Some(-1), &Extract::default()).unwrap();
+        let v =
+            xpath_extract(DIRTY, Some("//ul/li/a"), &Pick::At(-1), &Extract::default()).unwrap();
         assert_eq!(v, "第二章未闭合");
     }
 
@@ -320,13 +321,20 @@ mod tests {
         let items = xpath_select_all(DIRTY, "//ul[@id='toc']/li").unwrap();
         assert_eq!(items.len(), 2, "脏 HTML(未闭合 li/br)仍被宽松解析为 2 项");
         // 子上下文可被下游继续求值(取各 li 内的 a 文本)。
-        let t0 = xpath_extract(&items[0], Some("//a"), None, &Extract::default()).unwrap();
+        let t0 =
+            xpath_extract(&items[0], Some("//a"), &Pick::Default, &Extract::default()).unwrap();
         assert_eq!(t0, "第一章");
     }
 
     #[test]
     fn empty_match_is_empty_not_error() {
-        let v = xpath_extract(DIRTY, Some("//nonexistent"), None, &Extract::default()).unwrap();
+        let v = xpath_extract(
+            DIRTY,
+            Some("//nonexistent"),
+            &Pick::Default,
+            &Extract::default(),
+        )
+        .unwrap();
         assert_eq!(v, "");
         let l = xpath_select_all(DIRTY, "//nonexistent").unwrap();
         assert!(l.is_empty());
@@ -334,13 +342,19 @@ mod tests {
 
     #[test]
     fn invalid_xpath_errors() {
-        let r = xpath_extract(DIRTY, Some("//["), None, &Extract::default());
+        let r = xpath_extract(DIRTY, Some("//["), &Pick::Default, &Extract::default());
         assert!(matches!(r, Err(EvalError::Xpath(_))));
     }
 
     #[test]
     fn count_scalar_number() {
-        let v = xpath_extract(DIRTY, Some("count(//ul/li)"), None, &Extract::default()).unwrap();
+        let v = xpath_extract(
+            DIRTY,
+            Some("count(//ul/li)"),
+            &Pick::Default,
+            &Extract::default(),
+        )
+        .unwrap();
         assert_eq!(v, "2");
     }
 }
@@ -15,7 +15,12 @@ pub struct EscalatingFetcher {
 
 impl EscalatingFetcher {
     /// `browser` 为 `None` 时退化为纯 reqwest(撞挑战即返回 `Challenged` 由上层降级)。
+    /// 书源配了 `http.proxy` 时浏览器与 reqwest 走同一代理。
     pub fn new(source: &BookSource, browser: Option<BrowserFetcher>) -> Result<Self, FetchError> {
+        let browser = match &source.http.proxy {
+            Some(proxy) => browser.map(|b| b.with_proxy(Some(proxy.clone()))),
+            None => browser,
+        };
         Ok(Self {
             reqwest: ReqwestFetcher::new(source)?,
             browser,
@@ -1,6 +1,7 @@
 //! 浏览器取页器 [`BrowserFetcher`]:headful 解 Cloudflare 挑战、渲染取页(DOM 轮询 / CDP 拦截)、手动登录。
 
 use super::*;
+use chromiumoxide::auth::Credentials;
 use serde::{Deserialize, Serialize};
 use std::{
     fs::{self, File, OpenOptions, TryLockError},
@@ -22,6 +23,8 @@ struct Resident {
     handler: tokio::task::JoinHandle<()>,
     /// 本实例启动时的 headless 标志;请求标志不一致时拆掉重建。
     headless: bool,
+    /// 本实例启动时的代理;与请求方的代理不一致时拆掉重建(代理是启动参数,开页时改不了)。
+    proxy: Option<Proxy>,
     /// profile owner marker 路径。显式拆池时只删除匹配本进程/本浏览器的 marker。
     marker_path: PathBuf,
     /// 浏览器主进程 pid。用于下次启动恢复上次崩溃留下的孤儿浏览器。
@@ -127,6 +130,12 @@ impl BrowserFetcher {
         Self { exe, opts }
     }
 
+    /// 换用 `proxy` 启动浏览器(`None` = 直连)。
+    pub fn with_proxy(mut self, proxy: Option<Proxy>) -> Self {
+        self.opts.proxy = proxy;
+        self
+    }
+
     /// 交互 UI 回调(供上层在升级前征求授权)。
     pub fn ui(&self) -> Option<&Arc<dyn BrowserUi>> {
         self.opts.ui.as_ref()
@@ -146,7 +155,7 @@ impl BrowserFetcher {
     }
 
     async fn solve_inner(&self, browser: &Browser, url: &str) -> Result<Clearance, FetchError> {
-        let page = browser.new_page(url).await.map_err(browser_err)?;
+        let page = self.open_page(browser, url).await.map_err(browser_err)?;
 
         let user_agent: String = page
             .evaluate("navigator.userAgent")
@@ -261,6 +270,28 @@ impl BrowserFetcher {
             .map_err(|e| FetchError::Browser(format!("渲染后取 DOM 失败: {e}")))
     }
 
+    /// 开一张新页导航到 `url`。代理需认证时先开空白页、登记凭据(CDP 应答代理的 407)再导航,
+    /// 否则首个请求就会卡在代理认证上。
+    async fn open_page(
+        &self,
+        browser: &Browser,
+        url: &str,
+    ) -> Result<Page, chromiumoxide::error::CdpError> {
+        let Some((username, password)) = self
+            .opts
+            .proxy
+            .as_ref()
+            .and_then(crate::fetch::proxy::credentials)
+        else {
+            return browser.new_page(url).await;
+        };
+        let page = browser.new_page("about:blank").await?;
+        page.authenticate(Credentials { username, password })
+            .await?;
+        page.goto(url).await?;
+        Ok(page)
+    }
+
     /// 实际启动一个浏览器进程(**不取锁**;调用方须已持 [`BROWSER_LOCK`] 并确保 profile 无其它
     /// 实例占用)。恢复上次崩溃残留 → 清单例锁 → 按 `headless` 配参 → `Browser::launch` +
     /// 写 owner marker → spawn handler 事件循环。
@@ -294,6 +325,11 @@ impl BrowserFetcher {
             // 番茄渲染流已验证可用,不动)。
             builder = builder.arg("--disable-blink-features=AutomationControlled");
         }
+        if let Some(proxy) = &self.opts.proxy {
+            for arg in crate::fetch::proxy::browser_args(proxy)? {
+                builder = builder.arg(arg);
+            }
+        }
         let config = builder.build().map_err(FetchError::Browser)?;
         let (mut browser, mut handler) = Browser::launch(config).await.map_err(browser_err)?;
         let browser_pid = browser
@@ -408,7 +444,9 @@ impl BrowserFetcher {
             // 快路检查:存在 + handler 未结束 + headless 一致;否则拆旧建新(懒启动/重建)。
             let healthy = matches!(
                 pool.as_ref(),
-                Some(r) if !r.handler.is_finished() && r.headless == headless
+                Some(r) if !r.handler.is_finished()
+                    && r.headless == headless
+                    && r.proxy == self.opts.proxy
             );
             if !healthy {
                 drop_resident(pool); // 同步拆(可能已死,不能 await close)
@@ -417,6 +455,7 @@ impl BrowserFetcher {
                     browser: spawned.browser,
                     handler: spawned.handler,
                     headless,
+                    proxy: self.opts.proxy.clone(),
                     marker_path: spawned.marker_path,
                     browser_pid: spawned.browser_pid,
                     profile_dir: spawned.profile_dir,
@@ -426,7 +465,7 @@ impl BrowserFetcher {
             // 开页(带超时):成功即返回;失败/超时说明实例已坏 → 拆掉,循环重建一次。
             let opened = {
                 let browser = &pool.as_ref().expect("健康检查后池必为 Some").browser;
-                tokio::time::timeout(OPEN_PAGE_TIMEOUT, browser.new_page(url)).await
+                tokio::time::timeout(OPEN_PAGE_TIMEOUT, self.open_page(browser, url)).await
             };
             match opened {
                 Ok(Ok(page)) => return Ok(page),
@@ -753,7 +792,7 @@ impl BrowserFetcher {
         criteria: &LoginCriteria,
         signal: &LoginSignal,
     ) -> Result<LoginOutcome, FetchError> {
-        let page = browser.new_page(url).await.map_err(browser_err)?;
+        let page = self.open_page(browser, url).await.map_err(browser_err)?;
         // 立即前置窗口,让用户看到登录页(绝不模拟点击/填表,纯人工)。
         let _ = page.execute(BringToFrontParams::default()).await;
 
@@ -8,7 +8,7 @@
 
 use crate::error::FetchError;
 use crate::fetch::{FetchRequest, FetchResponse, Fetcher, ReqwestFetcher};
-use crate::source::BookSource;
+use crate::source::{BookSource, Proxy};
 use async_trait::async_trait;
 use chromiumoxide::cdp::browser_protocol::network::Cookie;
 use chromiumoxide::cdp::browser_protocol::page::BringToFrontParams;
@@ -154,6 +154,8 @@ pub struct BrowserOptions {
     pub poll_interval: Duration,
     /// 交互式 UI 回调(可选;授权 + Turnstile 点击提示)。
     pub ui: Option<Arc<dyn BrowserUi>>,
+    /// 浏览器走的代理(与 reqwest 同一份配置,见 [`BrowserFetcher::with_proxy`])。
+    pub proxy: Option<Proxy>,
 }
 
 impl Default for BrowserOptions {
@@ -165,6 +167,7 @@ impl Default for BrowserOptions {
             login_timeout: Duration::from_secs(300),
             poll_interval: Duration::from_millis(800),
             ui: None,
+            proxy: None,
         }
     }
 }
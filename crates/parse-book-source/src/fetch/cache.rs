@@ -0,0 +1,497 @@
+//! HTTP 响应缓存:包住任意 [`Fetcher`] 的装饰器,把引擎操作的主取页响应存到磁盘。
+//!
+//! - 只缓存带 [`FetchRequest::op`] 的非渲染 GET:前置链、预热、JS host 请求一律直通——它们多半
+//!   图的是 cookie / token 这类副作用,缓存会把副作用吞掉。渲染取页另有引擎的 `page_cache`。
+//! - 新鲜期:书源 `http.cache` 声明了该操作的秒数就用它(优先于响应头,`0` = 该操作不缓存);
+//!   否则看 `Cache-Control`:`no-store` 不存,`no-cache` 每次都重新验证,`max-age=N` 新鲜 N 秒。
+//! - 过期后带 `If-None-Match` / `If-Modified-Since` 发条件请求,`304` 则沿用缓存正文并刷新存入时间。
+//! - 键是「方法 + URL + 请求体」,不含请求头(Cookie 每次都可能变);回放的响应剥掉 `Set-Cookie`,
+//!   不会把过期的会话 cookie 再灌回 cookie 库。
+//! - 每个书源一个子目录([`source_dir`]),[`purge_source`] 整个删掉。命中情况计入 [`CacheStats`]。
+
+use super::{FetchOp, FetchRequest, FetchResponse, Fetcher};
+use crate::error::FetchError;
+use crate::source::{BookSource, CacheTtl, Method};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 缓存命中情况的计数器,可在多个 [`CachingFetcher`] 间共享(整个应用一份)。
+#[derive(Debug, Default)]
+pub struct CacheStats {
+    hits: AtomicU64,
+    revalidated: AtomicU64,
+    misses: AtomicU64,
+    bypassed: AtomicU64,
+}
+
+/// [`CacheStats`] 某一时刻的读数。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheCounts {
+    /// 新鲜命中,没发请求。
+    pub hits: u64,
+    /// 条件请求得到 `304`,沿用缓存正文。
+    pub revalidated: u64,
+    /// 没有可用缓存,取了完整响应。
+    pub misses: u64,
+    /// 不参与缓存的请求(前置链、POST、渲染取页、TTL 为 0 的操作等)。
+    pub bypassed: u64,
+}
+
+impl CacheStats {
+    pub fn counts(&self) -> CacheCounts {
+        CacheCounts {
+            hits: self.hits.load(Ordering::Relaxed),
+            revalidated: self.revalidated.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            bypassed: self.bypassed.load(Ordering::Relaxed),
+        }
+    }
+
+    fn bump(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// `Cache-Control` 里与私有缓存相关的指令。
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Directives {
+    no_store: bool,
+    no_cache: bool,
+    max_age: Option<u64>,
+}
+
+fn parse_cache_control(value: &str) -> Directives {
+    let mut d = Directives::default();
+    for part in value.split(',') {
+        let (name, arg) = match part.split_once('=') {
+            Some((n, a)) => (n.trim(), Some(a.trim().trim_matches('"'))),
+            None => (part.trim(), None),
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "no-store" => d.no_store = true,
+            "no-cache" => d.no_cache = true,
+            "max-age" => d.max_age = arg.and_then(|a| a.parse().ok()),
+            _ => {}
+        }
+    }
+    d
+}
+
+/// 一条响应能存多久:`None` = 不存;`Some(0)` = 存下但每次用前都要重新验证。
+fn freshness(ttl: Option<u64>, headers: &HashMap<String, String>) -> Option<u64> {
+    if let Some(ttl) = ttl {
+        return (ttl > 0).then_some(ttl);
+    }
+    let d = parse_cache_control(headers.get("cache-control").map_or("", String::as_str));
+    if d.no_store {
+        None
+    } else if d.no_cache {
+        Some(0)
+    } else {
+        Some(d.max_age.unwrap_or(0))
+    }
+}
+
+fn ttl_for(ttl: &CacheTtl, op: FetchOp) -> Option<u64> {
+    match op {
+        FetchOp::Search => ttl.search,
+        FetchOp::Explore => ttl.explore,
+        FetchOp::BookInfo => ttl.book_info,
+        FetchOp::Toc => ttl.toc,
+        FetchOp::Content => ttl.content,
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// 落盘的一条缓存。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    /// 存入(或最近一次 304 刷新)的 Unix 秒。
+    stored_at: u64,
+    status: u16,
+    /// 响应头(已剥掉 `Set-Cookie`)。
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+impl Entry {
+    fn new(url: &str, resp: &FetchResponse, now: u64) -> Self {
+        let mut headers = resp.headers.clone();
+        headers.remove("set-cookie");
+        Self {
+            url: url.to_string(),
+            stored_at: now,
+            status: resp.status,
+            headers,
+            body: resp.body.clone(),
+        }
+    }
+
+    fn is_fresh(&self, ttl: Option<u64>, now: u64) -> bool {
+        freshness(ttl, &self.headers).is_some_and(|age| now.saturating_sub(self.stored_at) < age)
+    }
+
+    fn has_validator(&self) -> bool {
+        self.headers.contains_key("etag") || self.headers.contains_key("last-modified")
+    }
+
+    fn response(&self) -> FetchResponse {
+        FetchResponse {
+            body: self.body.clone(),
+            status: self.status,
+            headers: self.headers.clone(),
+            dom_html: None,
+        }
+    }
+}
+
+/// 书源在缓存根目录下的子目录:书源 URL 的 sha256 前 16 位。
+pub fn source_dir(root: &Path, source_url: &str) -> PathBuf {
+    let digest = hex::encode(Sha256::digest(source_url.as_bytes()));
+    root.join(&digest[..16])
+}
+
+/// 删掉一个书源的全部缓存,返回删掉的文件数;目录不存在算 0。
+pub fn purge_source(root: &Path, source_url: &str) -> std::io::Result<usize> {
+    let dir = source_dir(root, source_url);
+    let count = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).count(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    std::fs::remove_dir_all(&dir)?;
+    Ok(count)
+}
+
+/// 带磁盘缓存的取页装饰器,见模块文档。
+pub struct CachingFetcher {
+    inner: Arc<dyn Fetcher>,
+    dir: PathBuf,
+    ttl: CacheTtl,
+    stats: Arc<CacheStats>,
+}
+
+impl CachingFetcher {
+    /// 包住 `inner`,`source` 的缓存放在 `root` 下的 [`source_dir`]。
+    pub fn new(inner: Arc<dyn Fetcher>, source: &BookSource, root: &Path) -> Self {
+        Self {
+            inner,
+            dir: source_dir(root, &source.url),
+            ttl: source.http.cache.clone().unwrap_or_default(),
+            stats: Arc::default(),
+        }
+    }
+
+    /// 改用外部共享的计数器。链式构造。
+    #[must_use]
+    pub fn with_stats(mut self, stats: Arc<CacheStats>) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    pub fn stats(&self) -> &Arc<CacheStats> {
+        &self.stats
+    }
+
+    fn entry_path(&self, req: &FetchRequest) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{:?}\n{}\n", req.method, req.url));
+        if let Some(body) = &req.body {
+            hasher.update(body);
+        }
+        self.dir
+            .join(format!("{}.json", hex::encode(hasher.finalize())))
+    }
+
+    /// 读不出来或格式不对都当没有缓存。
+    async fn read(path: &Path) -> Option<Entry> {
+        let bytes = tokio::fs::read(path).await.ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    /// 先写临时文件再改名,并发读不会读到半截;写失败只是少一条缓存,忽略。
+    async fn write(path: &Path, entry: &Entry) {
+        static SEQ: AtomicU64 = AtomicU64::new(0);
+        let Ok(bytes) = serde_json::to_vec(entry) else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = tokio::fs::create_dir_all(dir).await;
+        }
+        let tmp = path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            SEQ.fetch_add(1, Ordering::Relaxed)
+        ));
+        if tokio::fs::write(&tmp, bytes).await.is_ok()
+            && tokio::fs::rename(&tmp, path).await.is_err()
+        {
+            let _ = tokio::fs::remove_file(&tmp).await;
+        }
+    }
+}
+
+#[async_trait]
+impl Fetcher for CachingFetcher {
+    async fn fetch(&self, req: FetchRequest) -> Result<String, FetchError> {
+        self.fetch_full(req).await.map(|r| r.body)
+    }
+
+    async fn fetch_full(&self, mut req: FetchRequest) -> Result<FetchResponse, FetchError> {
+        let ttl = req.op.map(|op| ttl_for(&self.ttl, op));
+        if req.op.is_none() || req.render || req.method != Method::Get || ttl == Some(Some(0)) {
+            CacheStats::bump(&self.stats.bypassed);
+            return self.inner.fetch_full(req).await;
+        }
+        let ttl = ttl.flatten();
+        let path = self.entry_path(&req);
+        let now = now_secs();
+        let cached = Self::read(&path).await;
+        if let Some(entry) = &cached {
+            if entry.is_fresh(ttl, now) {
+                CacheStats::bump(&self.stats.hits);
+                return Ok(entry.response());
+            }
+            if let Some(etag) = entry.headers.get("etag") {
+                req.headers.insert("If-None-Match".into(), etag.clone());
+            }
+            if let Some(modified) = entry.headers.get("last-modified") {
+                req.headers
+                    .insert("If-Modified-Since".into(), modified.clone());
+            }
+        }
+
+        let url = req.url.clone();
+        let resp = self.inner.fetch_full(req).await?;
+        if resp.status == 304
+            && let Some(mut entry) = cached
+        {
+            CacheStats::bump(&self.stats.revalidated);
+            // 304 可能带来新的缓存指令与校验值。
+            for name in ["cache-control", "etag", "last-modified"] {
+                if let Some(value) = resp.headers.get(name) {
+                    entry.headers.insert(name.to_string(), value.clone());
+                }
+            }
+            entry.stored_at = now;
+            Self::write(&path, &entry).await;
+            return Ok(entry.response());
+        }
+
+        CacheStats::bump(&self.stats.misses);
+        let entry = Entry::new(&url, &resp, now);
+        let storable = (200..300).contains(&resp.status)
+            && freshness(ttl, &entry.headers).is_some_and(|age| age > 0 || entry.has_validator());
+        if storable {
+            Self::write(&path, &entry).await;
+        } else if cached.is_some() {
+            let _ = tokio::fs::remove_file(&path).await;
+        }
+        Ok(resp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::book_source;
+    use std::sync::Mutex;
+
+    /// 依次吐出预设响应,并记下收到的请求。
+    #[derive(Default)]
+    struct Scripted {
+        responses: Mutex<Vec<FetchResponse>>,
+        requests: Mutex<Vec<FetchRequest>>,
+    }
+
+    impl Scripted {
+        fn new(responses: Vec<FetchResponse>) -> Arc<Self> {
+            Arc::new(Self {
+                responses: Mutex::new(responses),
+                requests: Mutex::default(),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.requests.lock().unwrap().len()
+        }
+
+        fn last_header(&self, name: &str) -> Option<String> {
+            self.requests
+                .lock()
+                .unwrap()
+                .last()?
+                .headers
+                .get(name)
+                .cloned()
+        }
+    }
+
+    #[async_trait]
+    impl Fetcher for Scripted {
+        async fn fetch(&self, req: FetchRequest) -> Result<String, FetchError> {
+            self.fetch_full(req).await.map(|r| r.body)
+        }
+
+        async fn fetch_full(&self, req: FetchRequest) -> Result<FetchResponse, FetchError> {
+            self.requests.lock().unwrap().push(req);
+            Ok(self.responses.lock().unwrap().remove(0))
+        }
+    }
+
+    fn resp(status: u16, body: &str, headers: &[(&str, &str)]) -> FetchResponse {
+        FetchResponse {
+            body: body.to_string(),
+            status,
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            dom_html: None,
+        }
+    }
+
+    fn req(op: Option<FetchOp>) -> FetchRequest {
+        FetchRequest {
+            op,
+            ..FetchRequest::get("/book/1")
+        }
+    }
+
+    fn temp_root(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("trnovel-http-cache-{name}"));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn caching(inner: Arc<Scripted>, root: &Path, ttl: Option<CacheTtl>) -> CachingFetcher {
+        let mut source = book_source("https://c.example");
+        source.http.cache = ttl;
+        CachingFetcher::new(inner, &source, root)
+    }
+
+    #[test]
+    fn parses_cache_control() {
+        assert_eq!(
+            parse_cache_control("public, Max-Age=60"),
+            Directives {
+                max_age: Some(60),
+                ..Default::default()
+            }
+        );
+        assert!(parse_cache_control("no-cache, no-store").no_store);
+        let headers = |v: &str| HashMap::from([("cache-control".to_string(), v.to_string())]);
+        assert_eq!(freshness(None, &headers("no-store")), None);
+        assert_eq!(freshness(None, &headers("no-cache, max-age=60")), Some(0));
+        assert_eq!(freshness(None, &HashMap::new()), Some(0));
+        // 书源声明的 TTL 优先于响应头。
+        assert_eq!(freshness(Some(300), &headers("no-store")), Some(300));
+        assert_eq!(freshness(Some(0), &headers("max-age=60")), None);
+    }
+
+    #[tokio::test]
+    async fn serves_fresh_entries_without_refetching() {
+        let root = temp_root("fresh");
+        let inner = Scripted::new(vec![resp(
+            200,
+            "详情",
+            &[("cache-control", "max-age=600"), ("set-cookie", "sid=1")],
+        )]);
+        let cache = caching(inner.clone(), &root, None);
+        let first = cache
+            .fetch_full(req(Some(FetchOp::BookInfo)))
+            .await
+            .unwrap();
+        assert!(first.headers.contains_key("set-cookie"));
+        let second = cache
+            .fetch_full(req(Some(FetchOp::BookInfo)))
+            .await
+            .unwrap();
+        assert_eq!(second.body, "详情");
+        // 回放的响应不带 Set-Cookie。
+        assert!(!second.headers.contains_key("set-cookie"));
+        assert_eq!(inner.calls(), 1);
+        let counts = cache.stats().counts();
+        assert_eq!((counts.hits, counts.misses), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn revalidates_with_validators() {
+        let root = temp_root("revalidate");
+        let inner = Scripted::new(vec![
+            resp(
+                200,
+                "目录",
+                &[("etag", "\"v1\""), ("last-modified", "Mon, 19 Oct 2026")],
+            ),
+            resp(304, "", &[("etag", "\"v1\"")]),
+        ]);
+        let cache = caching(inner.clone(), &root, None);
+        cache.fetch_full(req(Some(FetchOp::Toc))).await.unwrap();
+        let again = cache.fetch_full(req(Some(FetchOp::Toc))).await.unwrap();
+        assert_eq!((again.status, again.body.as_str()), (200, "目录"));
+        assert_eq!(inner.calls(), 2);
+        assert_eq!(
+            inner.last_header("If-None-Match").as_deref(),
+            Some("\"v1\"")
+        );
+        assert_eq!(
+            inner.last_header("If-Modified-Since").as_deref(),
+            Some("Mon, 19 Oct 2026")
+        );
+        assert_eq!(cache.stats().counts().revalidated, 1);
+    }
+
+    #[tokio::test]
+    async fn ttl_override_and_bypass() {
+        let root = temp_root("ttl");
+        let inner = Scripted::new(vec![
+            resp(200, "正文", &[("cache-control", "no-store")]),
+            resp(200, "搜索", &[("cache-control", "max-age=600")]),
+            resp(200, "搜索", &[("cache-control", "max-age=600")]),
+            resp(200, "前置", &[("cache-control", "max-age=600")]),
+            resp(200, "前置", &[("cache-control", "max-age=600")]),
+        ]);
+        let ttl = CacheTtl {
+            content: Some(3600),
+            search: Some(0),
+            ..Default::default()
+        };
+        let cache = caching(inner.clone(), &root, Some(ttl));
+        // 书源给正文声明了 TTL:响应头 no-store 也照存。
+        for _ in 0..2 {
+            cache.fetch_full(req(Some(FetchOp::Content))).await.unwrap();
+        }
+        assert_eq!(inner.calls(), 1);
+        // TTL 为 0 的操作与不带操作的请求都直通。
+        for op in [Some(FetchOp::Search), Some(FetchOp::Search), None, None] {
+            cache.fetch_full(req(op)).await.unwrap();
+        }
+        assert_eq!(inner.calls(), 5);
+        assert_eq!(cache.stats().counts().bypassed, 4);
+    }
+
+    #[tokio::test]
+    async fn purges_one_source() {
+        let root = temp_root("purge");
+        let inner = Scripted::new(vec![resp(200, "x", &[("cache-control", "max-age=60")])]);
+        let cache = caching(inner, &root, None);
+        cache.fetch_full(req(Some(FetchOp::Explore))).await.unwrap();
+        assert_eq!(purge_source(&root, "https://c.example").unwrap(), 1);
+        assert!(!source_dir(&root, "https://c.example").exists());
+        assert_eq!(purge_source(&root, "https://c.example").unwrap(), 0);
+    }
+}
@@ -0,0 +1,353 @@
+//! 按注册域共享的限速,以及重试的退避与 `Retry-After` 计算。
+//!
+//! 限速状态放在进程级表里,以注册域([`registrable_domain`])为键:同一站点的请求不论来自哪个
+//! 书源、哪个 [`Engine`](crate::Engine) 还是 JS host 的专属 fetcher,都经同一个 [`HostGate`]。
+//! 门里有可选的令牌桶(书源配了 `rateLimit` / `concurrentRate` 才有;多个配置取最严)和
+//! `Retry-After` 暂停期:服务端要求稍后再来时,整个域的后续请求都等到暂停期过去。
+//!
+//! 锁只在读改状态时瞬时持有,sleep 前释放(不跨 await 持锁,符合 design D10)。
+
+use crate::error::FetchError;
+use crate::fetch::cookie::registrable_domain;
+use crate::source::{RateLimit, Retry, RetryOn};
+use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// 速率:`per_ms` 毫秒内至多 `max_count` 次。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Rate {
+    max_count: u64,
+    per_ms: u64,
+}
+
+impl Rate {
+    pub(crate) fn from_config(rl: &RateLimit) -> Option<Self> {
+        if rl.max_count == 0 || rl.per_ms == 0 {
+            return None;
+        }
+        Some(Self {
+            max_count: rl.max_count,
+            per_ms: rl.per_ms,
+        })
+    }
+
+    /// 解析 `concurrentRate` 字符串:`"N/ms"`(N 次每 ms)或纯毫秒间隔(`"1000"` = 每 1000ms 一次)。
+    pub(crate) fn from_rate_str(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if s.is_empty() {
+            return None;
+        }
+        let (max_count, per_ms) = match s.split_once('/') {
+            Some((n, ms)) => (n.trim().parse().ok()?, ms.trim().parse().ok()?),
+            None => (1, s.parse().ok()?),
+        };
+        Self::from_config(&RateLimit { max_count, per_ms })
+    }
+
+    /// 补充一个令牌的间隔。
+    fn interval(self) -> Duration {
+        Duration::from_nanos(self.per_ms.saturating_mul(1_000_000) / self.max_count)
+    }
+
+    /// 是否比 `other` 更严(间隔更长,间隔相同则可突发的更少)。
+    fn stricter_than(self, other: Rate) -> bool {
+        (self.interval(), other.max_count) > (other.interval(), self.max_count)
+    }
+}
+
+/// 令牌桶:容量 `max_count`,每个间隔补一个。令牌可预约成负数,后来的请求依次排在后面。
+#[derive(Debug)]
+struct Bucket {
+    rate: Rate,
+    tokens: f64,
+    last: Instant,
+}
+
+impl Bucket {
+    fn new(rate: Rate, now: Instant) -> Self {
+        Self {
+            rate,
+            tokens: rate.max_count as f64,
+            last: now,
+        }
+    }
+
+    /// 在 `at` 时刻预约一个令牌,返回还需等待的时长。
+    fn reserve(&mut self, at: Instant) -> Duration {
+        let interval = self.rate.interval();
+        if interval.is_zero() {
+            return Duration::ZERO;
+        }
+        let elapsed = at.saturating_duration_since(self.last);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() / interval.as_secs_f64())
+            .min(self.rate.max_count as f64);
+        self.last = self.last.max(at);
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            interval.mul_f64(-self.tokens)
+        }
+    }
+}
+
+/// 一个注册域的限速门。
+#[derive(Debug, Default)]
+pub(crate) struct HostGate(Mutex<GateState>);
+
+#[derive(Debug, Default)]
+struct GateState {
+    bucket: Option<Bucket>,
+    paused_until: Option<Instant>,
+}
+
+/// 进程内所有注册域的门。
+static GATES: LazyLock<Mutex<HashMap<String, Arc<HostGate>>>> = LazyLock::new(Default::default);
+
+/// 取 `url` 所属注册域的门;`rate` 比门上已有的更严时收紧。
+pub(crate) fn gate(url: &str, rate: Option<Rate>) -> Arc<HostGate> {
+    let gate = GATES
+        .lock()
+        .expect("host gates mutex poisoned")
+        .entry(registrable_domain(url))
+        .or_default()
+        .clone();
+    if let Some(rate) = rate {
+        gate.tighten(rate);
+    }
+    gate
+}
+
+impl HostGate {
+    fn tighten(&self, rate: Rate) {
+        let mut state = self.0.lock().expect("host gate mutex poisoned");
+        match &mut state.bucket {
+            Some(bucket) if !rate.stricter_than(bucket.rate) => {}
+            Some(bucket) => {
+                bucket.rate = rate;
+                bucket.tokens = bucket.tokens.min(rate.max_count as f64);
+            }
+            None => state.bucket = Some(Bucket::new(rate, Instant::now())),
+        }
+    }
+
+    /// 等到可以发下一个请求:先等暂停期结束,再等令牌。
+    pub(crate) async fn acquire(&self) {
+        let wait = {
+            let mut state = self.0.lock().expect("host gate mutex poisoned");
+            let now = Instant::now();
+            let start = state.paused_until.map_or(now, |until| until.max(now));
+            let queued = match &mut state.bucket {
+                Some(bucket) => bucket.reserve(start),
+                None => Duration::ZERO,
+            };
+            start - now + queued
+        }; // 锁在此释放,sleep 不持锁
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// 整个域暂停 `wait`(`Retry-After`);已有更长的暂停期则保留。
+    pub(crate) fn pause(&self, wait: Duration) {
+        let until = Instant::now() + wait;
+        let mut state = self.0.lock().expect("host gate mutex poisoned");
+        state.paused_until = Some(state.paused_until.map_or(until, |prev| prev.max(until)));
+    }
+}
+
+/// 失败所属的可重试类别;反爬挑战、非法请求头、4xx(429 除外)等不可重试。
+pub(crate) fn classify(error: &FetchError) -> Option<RetryOn> {
+    let FetchError::Http(e) = error else {
+        return None;
+    };
+    if e.is_timeout() {
+        return Some(RetryOn::Timeout);
+    }
+    if e.is_connect() {
+        return Some(RetryOn::Connect);
+    }
+    match e.status()?.as_u16() {
+        429 => Some(RetryOn::TooManyRequests),
+        500..=599 => Some(RetryOn::ServerError),
+        _ => None,
+    }
+}
+
+/// 第 `attempt` 次重试(从 1 起)前的退避时长。
+pub(crate) fn backoff(retry: &Retry, attempt: u32) -> Duration {
+    let growth = u64::from(retry.factor.max(1)).saturating_pow(attempt.saturating_sub(1));
+    let ms = retry
+        .backoff_ms
+        .saturating_mul(growth)
+        .min(retry.max_backoff_ms);
+    let ms = if retry.jitter && ms > 1 {
+        // 在 [ms/2, ms] 内随机:保留下限,又把同时失败的请求错开。
+        let half = ms / 2;
+        half + random() % (ms - half + 1)
+    } else {
+        ms
+    };
+    Duration::from_millis(ms)
+}
+
+/// 无需额外依赖的随机数:每个 `RandomState` 的密钥都不同。
+fn random() -> u64 {
+    RandomState::new().hash_one(Instant::now())
+}
+
+/// 解析 `Retry-After`:秒数或 HTTP 日期(`Wed, 21 Oct 2015 07:28:00 GMT`);已过去的日期为零。
+pub(crate) fn retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = UNIX_EPOCH + Duration::from_secs(http_date(value)?);
+    Some(at.duration_since(now).unwrap_or_default())
+}
+
+/// IMF-fixdate → Unix 秒。
+fn http_date(value: &str) -> Option<u64> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let [_, day, month, year, time, "GMT"] = value.split_whitespace().collect::<Vec<_>>()[..]
+    else {
+        return None;
+    };
+    let day: u64 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as u64 + 1;
+    let year: u64 = year.parse().ok()?;
+    let mut hms = time.split(':').map(|p| p.parse::<u64>().ok());
+    let (h, m, s) = (hms.next()??, hms.next()??, hms.next()??);
+    if year < 1970 || !(1..=31).contains(&day) || h > 23 || m > 59 || s > 60 {
+        return None;
+    }
+    // 公历日期 → 距 1970-01-01 的天数(以 3 月为年首,闰日落在年末)。
+    let (y, mp) = if month > 2 {
+        (year, month - 3)
+    } else {
+        (year - 1, month + 9)
+    };
+    let era = y / 400;
+    let yoe = y % 400;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = (era * 146_097 + doe).checked_sub(719_468)?;
+    Some(days * 86_400 + h * 3600 + m * 60 + s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(max_count: u64, per_ms: u64) -> Rate {
+        Rate::from_config(&RateLimit { max_count, per_ms }).unwrap()
+    }
+
+    #[test]
+    fn bucket_allows_burst_then_spaces_requests() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(rate(2, 1000), now);
+        assert_eq!(bucket.reserve(now), Duration::ZERO);
+        assert_eq!(bucket.reserve(now), Duration::ZERO);
+        // 桶空:第三、四个请求依次排到 500ms、1000ms 后。
+        assert_eq!(bucket.reserve(now), Duration::from_millis(500));
+        assert_eq!(bucket.reserve(now), Duration::from_millis(1000));
+        // 过了 2s 补回两个(减去已预约的两个)后又能立即发。
+        assert_eq!(
+            bucket.reserve(now + Duration::from_millis(2500)),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn gates_are_shared_per_registrable_domain_and_tightened() {
+        let a = gate("https://www.limit-test.example.com/a", Some(rate(10, 1000)));
+        let b = gate("https://m.limit-test.example.com/b", Some(rate(1, 1000)));
+        assert!(Arc::ptr_eq(&a, &b));
+        let c = gate("https://other.limit-test.example.com/c", None);
+        assert_eq!(
+            c.0.lock().unwrap().bucket.as_ref().unwrap().rate,
+            rate(1, 1000)
+        );
+        assert!(!Arc::ptr_eq(
+            &a,
+            &gate("https://limit-test.example.org", None)
+        ));
+        assert_eq!(Rate::from_rate_str(" 3/1500 "), Some(rate(3, 1500)));
+        assert_eq!(Rate::from_rate_str("800"), Some(rate(1, 800)));
+    }
+
+    #[tokio::test]
+    async fn pause_delays_every_request_to_the_domain() {
+        let gate = gate("https://pause-test.example.net", None);
+        gate.pause(Duration::from_millis(150));
+        let start = Instant::now();
+        gate.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(140));
+    }
+
+    #[test]
+    fn backoff_grows_caps_and_jitters() {
+        let retry = Retry {
+            max: 5,
+            backoff_ms: 100,
+            max_backoff_ms: 1000,
+            jitter: false,
+            ..Default::default()
+        };
+        let delays: Vec<u64> = (1..=5)
+            .map(|n| backoff(&retry, n).as_millis() as u64)
+            .collect();
+        assert_eq!(delays, [100, 200, 400, 800, 1000]);
+        let jittered = Retry {
+            jitter: true,
+            ..retry
+        };
+        for _ in 0..50 {
+            let ms = backoff(&jittered, 3).as_millis();
+            assert!((200..=400).contains(&ms), "{ms}");
+        }
+    }
+
+    #[tokio::test]
+    async fn only_transient_failures_are_worth_retrying() {
+        use crate::BookSourceError;
+        // 没人监听的端口:连接失败。
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let refused = reqwest::get(format!("http://127.0.0.1:{port}/"))
+            .await
+            .unwrap_err();
+        assert!(BookSourceError::Fetch(FetchError::Http(refused)).is_transient());
+        assert!(!BookSourceError::Fetch(FetchError::Challenged("cf".into())).is_transient());
+        assert!(!BookSourceError::LoginExpired.is_transient());
+        assert!(!BookSourceError::Missing("content").is_transient());
+    }
+
+    #[test]
+    fn parses_retry_after_seconds_and_dates() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_445_412_400);
+        assert_eq!(retry_after(" 120 ", now), Some(Duration::from_secs(120)));
+        // 1445412480 = 2015-10-21 07:28:00 UTC
+        assert_eq!(
+            retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now),
+            Some(Duration::from_secs(80))
+        );
+        assert_eq!(
+            retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            http_date("Thu, 29 Feb 2024 00:00:00 GMT"),
+            Some(1_709_164_800)
+        );
+        assert_eq!(retry_after("soon", now), None);
+    }
+}
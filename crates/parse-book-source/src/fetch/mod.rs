@@ -4,68 +4,21 @@
 
 #[cfg(feature = "browser")]
 pub mod browser;
+pub mod cache;
 pub mod cookie;
+pub(crate) mod limit;
+pub mod proxy;
+pub mod replay;
 
 use crate::error::FetchError;
 use crate::fetch::cookie::{merge_cookie_str, sanitize_header_value};
-use crate::source::{BookSource, Charset, Method, RateLimit, Retry};
+use crate::source::{BookSource, Charset, Method, Retry};
 use async_trait::async_trait;
+use limit::Rate;
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::Mutex;
-use std::time::{Duration, Instant};
-
-/// 简单令牌桶式限速器:保证两次请求间隔 >= `interval`。
-/// 锁仅用于读改时间戳,在 `.await` 前释放(不跨 await 持锁,符合 design D10)。
-struct RateLimiter {
-    interval: Duration,
-    last: Mutex<Option<Instant>>,
-}
-
-impl RateLimiter {
-    fn from_config(rl: &RateLimit) -> Option<Self> {
-        if rl.max_count == 0 || rl.per_ms == 0 {
-            return None;
-        }
-        Some(Self {
-            interval: Duration::from_millis(rl.per_ms / rl.max_count),
-            last: Mutex::new(None),
-        })
-    }
-
-    /// 解析 `concurrentRate` 字符串:`"N/ms"`(N 次每 ms)或纯毫秒间隔(`"1000"` = 每 1000ms 一次)。
-    fn from_rate_str(s: &str) -> Option<Self> {
-        let s = s.trim();
-        if s.is_empty() {
-            return None;
-        }
-        let (max_count, per_ms) = match s.split_once('/') {
-            Some((n, ms)) => (n.trim().parse().ok()?, ms.trim().parse().ok()?),
-            None => (1, s.parse().ok()?),
-        };
-        Self::from_config(&RateLimit { max_count, per_ms })
-    }
-
-    async fn acquire(&self) {
-        let wait = {
-            let mut last = self.last.lock().expect("rate limiter mutex poisoned");
-            let now = Instant::now();
-            let wait = match *last {
-                Some(prev) => self
-                    .interval
-                    .checked_sub(now.duration_since(prev))
-                    .unwrap_or(Duration::ZERO),
-                None => Duration::ZERO,
-            };
-            // 预约下一次可用时刻(即便本次需等待),使并发请求依次错开。
-            *last = Some(now + wait);
-            wait
-        }; // 锁在此释放,sleep 不持锁
-        if !wait.is_zero() {
-            tokio::time::sleep(wait).await;
-        }
-    }
-}
+use std::time::{Duration, SystemTime};
 
 /// 判定一次响应是否为反爬挑战(纯函数,便于离线测试)。
 ///
@@ -83,8 +36,21 @@ pub fn is_challenge(status: u16, cf_mitigated: Option<&str>, body: &str) -> bool
             || body.contains("<title>Just a moment"))
 }
 
-/// 一次取页请求(URL 已是最终待请求地址或相对路径)。
-#[derive(Debug, Clone, Default)]
+/// 发起取页的引擎操作。只有五个操作的主取页带上它,前置链 / 预热 / JS host 请求为 `None`;
+/// 供 [`cache::CachingFetcher`] 这类装饰器按操作区分策略。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FetchOp {
+    Search,
+    Explore,
+    BookInfo,
+    Toc,
+    Content,
+}
+
+/// 一次取页请求(URL 已是最终待请求地址或相对路径)。可序列化,供 [`replay`] 录制成夹具。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct FetchRequest {
     pub url: String,
     pub method: Method,
@@ -108,6 +74,8 @@ pub struct FetchRequest {
     /// (如番茄 search)靠点它递增页码。`Some` 且 `page > 1` + 有 `intercept_api` 时启用点击翻页;
     /// 其它路径忽略(默认 `None` = 现状单拦截 / `{{page}}` URL 模板翻页)。
     pub page_by: Option<String>,
+    /// 发起本请求的引擎操作(见 [`FetchOp`]);普通 fetcher 忽略。
+    pub op: Option<FetchOp>,
 }
 
 impl FetchRequest {
@@ -124,7 +92,8 @@ impl FetchRequest {
 ///
 /// 供 `net.connect` 读取 `Set-Cookie` / `Location` / 状态码等(`fetch` 只回 body)。
 /// 同名多值头(如多个 `Set-Cookie`)以 `\n` 连接。
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct FetchResponse {
     pub body: String,
     pub status: u16,
@@ -155,12 +124,14 @@ pub trait Fetcher: Send + Sync {
 }
 
 /// 基于 reqwest + rustls + cookie_store 的默认取页实现(含限速与重试)。
+///
+/// 限速与 `Retry-After` 暂停按注册域在进程内共享(见 [`limit`]),不随实例隔离。
 pub struct ReqwestFetcher {
     client: reqwest::Client,
     base: String,
     charset: Charset,
     retry: Option<Retry>,
-    limiter: Option<RateLimiter>,
+    rate: Option<Rate>,
     /// 书源静态 `http.cookies` 合成串:除进 `default_headers` 外留存一份,
     /// 供请求级 `Cookie` 出现时在 [`final_header_value`] 合并(reqwest 的 `default_headers`
     /// 对同名请求级头是「整体替换」而非合并语义)。
@@ -168,7 +139,7 @@ pub struct ReqwestFetcher {
 }
 
 impl ReqwestFetcher {
-    /// 依据书源的 `http` 配置构建客户端(默认头、静态 cookie、超时)。
+    /// 依据书源的 `http` 配置构建客户端(默认头、静态 cookie、超时、代理)。
     pub fn new(source: &BookSource) -> Result<Self, FetchError> {
         let http = &source.http;
         let mut headers = HeaderMap::new();
@@ -201,6 +172,9 @@ impl ReqwestFetcher {
         if let Some(ms) = http.timeout {
             builder = builder.timeout(Duration::from_millis(ms));
         }
+        if let Some(p) = &http.proxy {
+            builder = builder.proxy(proxy::reqwest_proxy(p)?);
+        }
         let client = builder.build()?;
 
         Ok(Self {
@@ -209,17 +183,17 @@ impl ReqwestFetcher {
             charset: http.charset,
             retry: http.retry.clone(),
             // 限速来源:优先 http.rateLimit,否则 concurrentRate("N/ms" 或间隔)。
-            limiter: http
+            rate: http
                 .rate_limit
                 .as_ref()
-                .and_then(RateLimiter::from_config)
-                .or_else(|| RateLimiter::from_rate_str(&source.concurrent_rate)),
+                .and_then(Rate::from_config)
+                .or_else(|| Rate::from_rate_str(&source.concurrent_rate)),
             static_cookie,
         })
     }
 
     /// 发起一次请求并解码(单次,不含重试),返回完整响应(body + 状态码 + 响应头)。
-    async fn send_once(&self, url: &str, req: &FetchRequest) -> Result<FetchResponse, FetchError> {
+    async fn send_once(&self, url: &str, req: &FetchRequest) -> Result<FetchResponse, Failure> {
         let mut builder = match req.method {
             Method::Get => self.client.get(url),
             Method::Post => self.client.post(url),
@@ -253,12 +227,18 @@ impl ReqwestFetcher {
         let bytes = resp.bytes().await?;
         let text = self.decode(&bytes);
         if is_challenge(status.as_u16(), cf_mitigated.as_deref(), &text) {
-            return Err(FetchError::Challenged(format!(
-                "Cloudflare/反爬挑战 @ {url}"
-            )));
+            return Err(FetchError::Challenged(format!("Cloudflare/反爬挑战 @ {url}")).into());
         }
         if let Some(e) = status_err {
-            return Err(FetchError::Http(e));
+            // 429 / 503 可带 Retry-After(秒数或 HTTP 日期)。
+            let retry_after = matches!(status.as_u16(), 429 | 503)
+                .then(|| headers.get("retry-after"))
+                .flatten()
+                .and_then(|v| limit::retry_after(v, SystemTime::now()));
+            return Err(Failure {
+                error: FetchError::Http(e),
+                retry_after,
+            });
         }
         Ok(FetchResponse {
             body: text,
@@ -279,29 +259,38 @@ impl ReqwestFetcher {
                 req.url
             )));
         }
-        // 限速(如配置):错开请求间隔。
-        if let Some(limiter) = &self.limiter {
-            limiter.acquire().await;
-        }
         let url = self.resolve(&req.url);
-
-        // 重试:失败后按 backoff 退避,最多重试 retry.max 次。
-        let max = self.retry.as_ref().map(|r| r.max).unwrap_or(0);
-        let backoff = self.retry.as_ref().map(|r| r.backoff_ms).unwrap_or(0);
+        // 同一注册域共用的门:令牌桶限速 + Retry-After 暂停,每次尝试(含重试)都过一次。
+        let gate = limit::gate(&url, self.rate);
+        let max_wait = Duration::from_millis(
+            self.retry
+                .as_ref()
+                .map_or(Retry::default().max_backoff_ms, |r| r.max_backoff_ms),
+        );
         let mut attempt = 0u32;
         loop {
-            match self.send_once(&url, &req).await {
+            gate.acquire().await;
+            let Failure { error, retry_after } = match self.send_once(&url, &req).await {
                 Ok(resp) => return Ok(resp),
-                Err(e) => {
-                    // 反爬挑战重试无意义(仍会被挑战),直接返回交上层升级/降级。
-                    if matches!(e, FetchError::Challenged(_)) || attempt >= max {
-                        return Err(e);
-                    }
-                    attempt += 1;
-                    if backoff > 0 {
-                        tokio::time::sleep(Duration::from_millis(backoff)).await;
-                    }
-                }
+                Err(failure) => failure,
+            };
+            // 服务端要求稍后再来:整个域一起暂停(封顶 max_wait,免得一个超长值卡死全站)。
+            if let Some(wait) = retry_after {
+                gate.pause(wait.min(max_wait));
+            }
+            // 只重试配置的失败类别;反爬挑战等重试无意义,直接返回交上层升级/降级。
+            let Some(retry) = self.retry.as_ref().filter(|r| {
+                attempt < r.max
+                    && limit::classify(&error).is_some_and(|class| r.on.contains(&class))
+                    && retry_after.is_none_or(|wait| wait <= max_wait)
+            }) else {
+                return Err(error);
+            };
+            attempt += 1;
+            // 退避与 Retry-After 取大者:暂停期由下一轮 acquire 等待。
+            let backoff = limit::backoff(retry, attempt);
+            if !backoff.is_zero() {
+                tokio::time::sleep(backoff).await;
             }
         }
     }
@@ -337,6 +326,21 @@ impl ReqwestFetcher {
     }
 }
 
+/// 单次请求的失败,附带服务端要求的 `Retry-After`。
+struct Failure {
+    error: FetchError,
+    retry_after: Option<Duration>,
+}
+
+impl<E: Into<FetchError>> From<E> for Failure {
+    fn from(error: E) -> Self {
+        Self {
+            error: error.into(),
+            retry_after: None,
+        }
+    }
+}
+
 /// 计算一个请求级 header 的最终出站值(纯函数,便于离线单测):
 ///
 /// - 值一律剥 CR/LF——纵深防御:已落盘的脏 loginHeader/cookie(多 `Set-Cookie` 以 `\n` 连接)
@@ -423,4 +427,63 @@ mod tests {
         assert_eq!(final_header_value(None, "Cookie", "a=1\nb=2"), "a=1b=2");
         assert_eq!(final_header_value(None, "X-Test", "42"), "42");
     }
+
+    fn response(status: &str, extra: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {status}\r\n{extra}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+    }
+
+    #[tokio::test]
+    async fn retries_transient_failures_and_honours_retry_after() {
+        use crate::testutil::{book_source, spawn_scripted_server};
+        use crate::{FetchRequest, Fetcher, ReqwestFetcher, source::Retry};
+        use std::sync::atomic::Ordering;
+        use std::time::{Duration, Instant};
+
+        let fetcher = |base: &str| {
+            let mut source = book_source(base);
+            source.http.retry = Some(Retry {
+                max: 3,
+                backoff_ms: 20,
+                max_backoff_ms: 1500,
+                jitter: false,
+                ..Default::default()
+            });
+            ReqwestFetcher::new(&source).unwrap()
+        };
+
+        // 503 → 429(Retry-After: 1)→ 200:重试两次,第二次至少等满 1 秒。
+        let (base, served) = spawn_scripted_server(vec![
+            response("503 Service Unavailable", "", ""),
+            response("429 Too Many Requests", "Retry-After: 1\r\n", ""),
+            response("200 OK", "", "ok"),
+        ]);
+        let start = Instant::now();
+        assert_eq!(
+            fetcher(&base).fetch(FetchRequest::get("/a")).await.unwrap(),
+            "ok"
+        );
+        assert!(start.elapsed() >= Duration::from_millis(950));
+        assert_eq!(served.load(Ordering::SeqCst), 3);
+
+        // 404 不在默认的重试类别里:只请求一次。
+        let (base, served) = spawn_scripted_server(vec![
+            response("404 Not Found", "", ""),
+            response("200 OK", "", "ok"),
+        ]);
+        assert!(fetcher(&base).fetch(FetchRequest::get("/b")).await.is_err());
+        assert_eq!(served.load(Ordering::SeqCst), 1);
+
+        // Retry-After 超过 maxBackoffMs:不再重试,立即返回。
+        let (base, served) = spawn_scripted_server(vec![
+            response("429 Too Many Requests", "Retry-After: 120\r\n", ""),
+            response("200 OK", "", "ok"),
+        ]);
+        let start = Instant::now();
+        assert!(fetcher(&base).fetch(FetchRequest::get("/c")).await.is_err());
+        assert!(start.elapsed() < Duration::from_millis(900));
+        assert_eq!(served.load(Ordering::SeqCst), 1);
+    }
 }
@@ -0,0 +1,403 @@
+//! 录制与回放:离线、可复现地检验书源。
+//!
+//! [`RecordingFetcher`] 包住真实取页后端,把每次取页的完整请求与结果(body / 状态码 / 响应头 /
+//! 渲染 DOM,或失败原因)按顺序写成目录里的 `0001.json`、`0002.json`……;[`ReplayFetcher`] 读回
+//! 这个目录,按 [`MatchRules`] 找到对应的录制结果返回,不再联网。用户把录制目录附在问题报告里,
+//! 就能原样重放当时的站点响应。
+//!
+//! 录制时请求的 `Cookie` / `Authorization` 与响应的 `Set-Cookie` 会被抹掉,免得登录态随夹具外传。
+
+use super::{FetchRequest, FetchResponse, Fetcher};
+use crate::error::FetchError;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 夹具目录里可选的匹配规则文件名。
+pub const MATCH_RULES_FILE: &str = "match.json";
+
+const REDACTED: &str = "<redacted>";
+
+/// 录下的一次取页:请求,加上响应或失败原因之一。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Exchange {
+    pub request: FetchRequest,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<FetchResponse>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RecordedError>,
+}
+
+/// 录制时取页失败的原因。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedError {
+    /// 是否被反爬挑战拦截;回放时还原成 [`FetchError::Challenged`],诊断结论与录制时一致。
+    #[serde(default)]
+    pub challenged: bool,
+    pub message: String,
+}
+
+impl RecordedError {
+    fn to_fetch_error(&self) -> FetchError {
+        if self.challenged {
+            FetchError::Challenged(self.message.clone())
+        } else {
+            FetchError::Replay(self.message.clone())
+        }
+    }
+}
+
+fn redact(headers: &mut HashMap<String, String>, names: &[&str]) {
+    for (name, value) in headers.iter_mut() {
+        if names.iter().any(|n| name.eq_ignore_ascii_case(n)) {
+            *value = REDACTED.to_string();
+        }
+    }
+}
+
+/// 第 `seq` 条(1 基)录制的文件名。
+fn exchange_file(seq: usize) -> String {
+    format!("{seq:04}.json")
+}
+
+fn is_exchange_file(name: &str) -> bool {
+    name.strip_suffix(".json")
+        .is_some_and(|stem| stem.len() >= 4 && stem.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// 按顺序录下每次取页的装饰器。
+pub struct RecordingFetcher {
+    inner: Arc<dyn Fetcher>,
+    dir: PathBuf,
+    seq: AtomicUsize,
+}
+
+impl RecordingFetcher {
+    /// 录到 `dir`(不存在则创建);目录里上一次录制的 `NNNN.json` 会先删掉,其它文件不动。
+    pub fn new(inner: Arc<dyn Fetcher>, dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        for entry in std::fs::read_dir(&dir)?.filter_map(|e| e.ok()) {
+            if is_exchange_file(&entry.file_name().to_string_lossy()) {
+                std::fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(Self {
+            inner,
+            dir,
+            seq: AtomicUsize::new(0),
+        })
+    }
+
+    /// 已录下的取页次数。
+    pub fn recorded(&self) -> usize {
+        self.seq.load(Ordering::Relaxed)
+    }
+}
+
+#[async_trait]
+impl Fetcher for RecordingFetcher {
+    async fn fetch(&self, req: FetchRequest) -> Result<String, FetchError> {
+        self.fetch_full(req).await.map(|r| r.body)
+    }
+
+    async fn fetch_full(&self, req: FetchRequest) -> Result<FetchResponse, FetchError> {
+        let mut request = req.clone();
+        redact(&mut request.headers, &["cookie", "authorization"]);
+        let result = self.inner.fetch_full(req).await;
+        let exchange = match &result {
+            Ok(resp) => {
+                let mut response = resp.clone();
+                redact(&mut response.headers, &["set-cookie"]);
+                Exchange {
+                    request,
+                    response: Some(response),
+                    error: None,
+                }
+            }
+            Err(e) => Exchange {
+                request,
+                response: None,
+                error: Some(RecordedError {
+                    challenged: matches!(e, FetchError::Challenged(_)),
+                    message: e.to_string(),
+                }),
+            },
+        };
+        // 序号在取页返回后才分配,文件顺序即完成顺序;写失败不影响本次取页。
+        let seq = self.seq.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Ok(json) = serde_json::to_vec_pretty(&exchange) {
+            let _ = tokio::fs::write(self.dir.join(exchange_file(seq)), json).await;
+        }
+        result
+    }
+}
+
+/// 回放时怎样认定「同一个请求」。方法、URL、`render` 与 `page` 总要一致。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MatchRules {
+    /// 比较 URL 时去掉这些查询参数(时间戳、签名等每次都变的参数)。
+    pub ignore_query: Vec<String>,
+    /// 是否比较请求体。
+    pub body: bool,
+    /// 额外要求一致的请求头(大小写不敏感)。
+    pub headers: Vec<String>,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        Self {
+            ignore_query: Vec::new(),
+            body: true,
+            headers: Vec::new(),
+        }
+    }
+}
+
+impl MatchRules {
+    fn url(&self, url: &str) -> String {
+        let url = url.split('#').next().unwrap_or(url);
+        let Some((path, query)) = url.split_once('?') else {
+            return url.to_string();
+        };
+        let kept: Vec<&str> = query
+            .split('&')
+            .filter(|pair| {
+                let name = pair.split('=').next().unwrap_or(pair);
+                !pair.is_empty() && !self.ignore_query.iter().any(|q| q == name)
+            })
+            .collect();
+        if kept.is_empty() {
+            path.to_string()
+        } else {
+            format!("{path}?{}", kept.join("&"))
+        }
+    }
+
+    fn header<'a>(req: &'a FetchRequest, name: &str) -> Option<&'a str> {
+        req.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// `recorded` 与 `req` 是否算同一个请求。
+    pub fn matches(&self, recorded: &FetchRequest, req: &FetchRequest) -> bool {
+        recorded.method == req.method
+            && recorded.render == req.render
+            && recorded.page == req.page
+            && self.url(&recorded.url) == self.url(&req.url)
+            && (!self.body || recorded.body == req.body)
+            && self
+                .headers
+                .iter()
+                .all(|h| Self::header(recorded, h) == Self::header(req, h))
+    }
+}
+
+/// 从录制目录回放取页结果的后端。
+///
+/// 同一个请求录了多次时按录制顺序依次返回,用完后一直返回最后一次;没录到的请求返回
+/// [`FetchError::Replay`]。
+pub struct ReplayFetcher {
+    rules: MatchRules,
+    /// 录制结果与是否已回放过。
+    exchanges: Mutex<Vec<(Exchange, bool)>>,
+}
+
+impl ReplayFetcher {
+    pub fn new(exchanges: Vec<Exchange>, rules: MatchRules) -> Self {
+        Self {
+            rules,
+            exchanges: Mutex::new(exchanges.into_iter().map(|e| (e, false)).collect()),
+        }
+    }
+
+    /// 读取录制目录;目录里有 [`MATCH_RULES_FILE`] 就用它作匹配规则,否则用默认规则。
+    pub fn load(dir: &Path) -> std::io::Result<Self> {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .filter_map(|e| e.ok())
+            .filter(|e| is_exchange_file(&e.file_name().to_string_lossy()))
+            .map(|e| e.path())
+            .collect();
+        files.sort();
+        let mut exchanges = Vec::with_capacity(files.len());
+        for path in files {
+            let json = std::fs::read(&path)?;
+            let exchange = serde_json::from_slice(&json).map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("{}: {e}", path.display()),
+                )
+            })?;
+            exchanges.push(exchange);
+        }
+        let rules = match std::fs::read(dir.join(MATCH_RULES_FILE)) {
+            Ok(json) => serde_json::from_slice(&json)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => MatchRules::default(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self::new(exchanges, rules))
+    }
+
+    /// 录制的取页条数。
+    pub fn len(&self) -> usize {
+        self.exchanges.lock().map(|e| e.len()).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn find(&self, req: &FetchRequest) -> Option<Exchange> {
+        let mut exchanges = self.exchanges.lock().ok()?;
+        let mut last = None;
+        for (i, (exchange, used)) in exchanges.iter().enumerate() {
+            if self.rules.matches(&exchange.request, req) {
+                if !used {
+                    last = Some(i);
+                    break;
+                }
+                last = Some(i);
+            }
+        }
+        let i = last?;
+        exchanges[i].1 = true;
+        Some(exchanges[i].0.clone())
+    }
+}
+
+#[async_trait]
+impl Fetcher for ReplayFetcher {
+    async fn fetch(&self, req: FetchRequest) -> Result<String, FetchError> {
+        self.fetch_full(req).await.map(|r| r.body)
+    }
+
+    async fn fetch_full(&self, req: FetchRequest) -> Result<FetchResponse, FetchError> {
+        let exchange = self.find(&req).ok_or_else(|| {
+            FetchError::Replay(format!("录制里没有 {:?} {}", req.method, req.url))
+        })?;
+        match (exchange.response, exchange.error) {
+            (Some(resp), _) => Ok(resp),
+            (None, Some(e)) => Err(e.to_fetch_error()),
+            (None, None) => Err(FetchError::Replay(format!(
+                "录制的 {} 既没有响应也没有错误",
+                req.url
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::Method;
+
+    /// 按 URL 返回固定 body;`/blocked` 返回反爬挑战。
+    struct Site;
+
+    #[async_trait]
+    impl Fetcher for Site {
+        async fn fetch(&self, req: FetchRequest) -> Result<String, FetchError> {
+            self.fetch_full(req).await.map(|r| r.body)
+        }
+
+        async fn fetch_full(&self, req: FetchRequest) -> Result<FetchResponse, FetchError> {
+            if req.url == "/blocked" {
+                return Err(FetchError::Challenged("挑战".into()));
+            }
+            Ok(FetchResponse {
+                body: format!("page {}", req.url),
+                status: 200,
+                headers: HashMap::from([("set-cookie".to_string(), "sid=secret".to_string())]),
+                dom_html: Some("<div>dom</div>".into()),
+            })
+        }
+    }
+
+    fn get(url: &str) -> FetchRequest {
+        let mut req = FetchRequest::get(url);
+        req.headers.insert("Cookie".into(), "sid=secret".into());
+        req
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("trnovel-replay-{name}"));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn records_then_replays_offline() {
+        let dir = temp_dir("roundtrip");
+        let recorder = RecordingFetcher::new(Arc::new(Site), &dir).unwrap();
+        recorder.fetch_full(get("/a?t=1")).await.unwrap();
+        assert!(recorder.fetch_full(get("/blocked")).await.is_err());
+        assert_eq!(recorder.recorded(), 2);
+
+        let first = std::fs::read_to_string(dir.join("0001.json")).unwrap();
+        assert!(!first.contains("secret"), "登录态不该写进夹具: {first}");
+
+        std::fs::write(dir.join(MATCH_RULES_FILE), r#"{"ignoreQuery":["t"]}"#).unwrap();
+        let replay = ReplayFetcher::load(&dir).unwrap();
+        assert_eq!(replay.len(), 2);
+        let resp = replay.fetch_full(get("/a?t=2")).await.unwrap();
+        assert_eq!(resp.body, "page /a?t=1");
+        assert_eq!(resp.dom_html.as_deref(), Some("<div>dom</div>"));
+        assert!(matches!(
+            replay.fetch_full(get("/blocked")).await,
+            Err(FetchError::Challenged(_))
+        ));
+        assert!(matches!(
+            replay.fetch_full(get("/missing")).await,
+            Err(FetchError::Replay(_))
+        ));
+
+        // 重新录制时清掉上一轮的夹具。
+        RecordingFetcher::new(Arc::new(Site), &dir).unwrap();
+        assert_eq!(ReplayFetcher::load(&dir).unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn repeated_requests_replay_in_order() {
+        let exchange = |body: &str| Exchange {
+            request: FetchRequest {
+                method: Method::Post,
+                body: Some("k=1".into()),
+                ..FetchRequest::get("/s")
+            },
+            response: Some(FetchResponse {
+                body: body.into(),
+                status: 200,
+                ..Default::default()
+            }),
+            error: None,
+        };
+        let replay = ReplayFetcher::new(
+            vec![exchange("第一次"), exchange("第二次")],
+            MatchRules::default(),
+        );
+        let req = FetchRequest {
+            method: Method::Post,
+            body: Some("k=1".into()),
+            ..FetchRequest::get("/s")
+        };
+        for expected in ["第一次", "第二次", "第二次"] {
+            assert_eq!(replay.fetch(req.clone()).await.unwrap(), expected);
+        }
+        // 请求体不同就不算同一个请求。
+        let other = FetchRequest {
+            body: Some("k=2".into()),
+            ..req
+        };
+        assert!(replay.fetch(other).await.is_err());
+    }
+}
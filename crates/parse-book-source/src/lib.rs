@@ -9,7 +9,8 @@
 //! - `source` — v2 配置(serde 镜像 `book-source.schema.json`),其中 `Rule` 既是配置、
 //!   也是供求值器遍历的语法树;按 `rule`/`clean`/`http`/`op` 分文件。
 //! - `eval` — 规则解释器(Interpreter + Composite),含抽取后端 `backend`(css/json/regex/raw)、
-//!   `xpath` 后端、确定性算子 `transform`、JS 逃生舱 `js`。
+//!   `xpath` 后端、确定性算子 `transform`、JS 逃生舱 `js`;规则在 `plan` 里预编译一次
+//!   (选择器/正则缓存),引擎按书源持有编译结果,见 `benches/rule_plan.rs`。
 //! - `fetch` — 取页端口(Ports & Adapters),含 `cookie` 库与浏览器反爬 `browser`。
 //! - `host` — JS host 桥(`js-host`),含持久状态 `state`。
 //! - `engine` — 用例(search/explore/book_info/toc/content)+ 有界分页。
@@ -60,6 +61,8 @@ pub(crate) mod testutil {
     use crate::source::BookSource;
     use std::io::{Read, Write};
     use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     /// 最小书源(仅为构造取页器:base 指向本地测试服务)。
     pub(crate) fn book_source(base: &str) -> BookSource {
@@ -111,4 +114,26 @@ pub(crate) mod testutil {
         });
         (base, handle)
     }
+
+    /// 依次用 `responses` 应答每个连接的服务(每个连接一个原始响应),返回已应答的连接数计数。
+    /// 用 `localhost` 作 base:与其它测试的 `127.0.0.1` 分属不同注册域,不共享限速门。
+    pub(crate) fn spawn_scripted_server(responses: Vec<String>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://localhost:{}", listener.local_addr().unwrap().port());
+        let served = Arc::new(AtomicUsize::new(0));
+        let counter = served.clone();
+        std::thread::spawn(move || {
+            for raw in responses {
+                let Ok((mut stream, _)) = listener.accept() else {
+                    return;
+                };
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf);
+                counter.fetch_add(1, Ordering::SeqCst);
+                let _ = stream.write_all(raw.as_bytes());
+                let _ = stream.flush();
+            }
+        });
+        (base, served)
+    }
 }
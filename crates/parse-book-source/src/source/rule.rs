@@ -47,6 +47,17 @@ pub enum ExtractOp {
     OuterHtml,
 }
 
+/// 叶子规则的下标:整数只取一项(负数从末尾数,越界回退到首/末);字符串是下标表达式,
+/// 如 `"2:"`(跳过前两项)、`"3:-2"`、`"0,2,5"`、`"::-1"`(倒序)、`"!0,!-1"`(去掉首尾),
+/// 取值时各项结果以换行连接,列表规则按它筛选列表项。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[serde(untagged)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Index {
+    At(i64),
+    Expr(String),
+}
+
 /// 叶子规则:在当前上下文做一次抽取。
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Hash)]
 #[serde(rename_all = "camelCase")]
@@ -57,7 +68,7 @@ pub struct LeafRule {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub select: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub index: Option<i64>,
+    pub index: Option<Index>,
     #[serde(default)]
     pub extract: Extract,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -82,9 +93,36 @@ pub enum Rule {
         #[serde(default)]
         join: String,
     },
+    /// 条件分支:`when` 成立求 `then`,否则求 `else`(缺省为空)。作列表规则时按同样的条件选分支。
+    When {
+        when: Condition,
+        then: Box<Rule>,
+        #[serde(rename = "else", default, skip_serializing_if = "Option::is_none")]
+        otherwise: Option<Box<Rule>>,
+    },
+    /// 把子规则的结果按 `by`(缺省 `,`)切成列表项,项去首尾空白、丢弃空项。
+    /// 作值规则时各项以换行连接。
+    Split {
+        split: Box<Rule>,
+        #[serde(default = "default_split_by")]
+        by: String,
+    },
+    /// 对列表规则 `map` 的每一项求 `with`,结果(丢弃空值)作为新的列表项。
+    /// 作值规则时各项以换行连接。
+    Map { map: Box<Rule>, with: Box<Rule> },
+    /// 把列表规则 `join` 的各项以 `with` 连接成一个值,丢弃空项。列表为叶子规则时每项按其
+    /// `extract`(默认取文本)与 `clean` 求值。
+    Join {
+        join: Box<Rule>,
+        #[serde(default)]
+        with: String,
+    },
     /// 字面量。
     Literal { literal: String },
-    /// 模板插值(`{{key}}`/`{{page}}`/命名变量)。
+    /// 模板插值(`{{key}}`/`{{page}}`/`{{now}}`/命名变量),变量后可接过滤器管道,
+    /// 如 `{{key | urlencode:gbk}}`、`{{now | ms}}`、`{{key | md5}}`、`{{page | sub:1}}`。
+    /// 过滤器:`urlencode[:字符集]`、`base64`/`base64url`/`hex`、`md5`/`sha1`/`sha256`/`sha512`[`:base64`]、
+    /// `ms`/`s`、`add:n`/`sub:n`/`mul:n`/`div:n`、`lower`/`upper`/`trim`、`default:x`。
     Template { template: String },
     /// JS 逻辑编排逃生舱(值规则):以当前上下文为 `result`、注入 `baseUrl`/变量 + `crypto`
     /// 助手求值,返回字符串。求值需启用 `js` feature(否则返回 `Unsupported("js")`)。
@@ -94,21 +132,51 @@ pub enum Rule {
     Leaf(LeafRule),
 }
 
+fn default_split_by() -> String {
+    ",".into()
+}
+
+/// `when` 的条件:测变量(`var`)、一条规则的结果(`value`)或当前上下文(都不写时)。
+/// 写了 `matches` 则按正则匹配判断,否则去首尾空白后非空即成立;`not` 取反。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Condition {
+    /// 变量名(如 `page`、请求级捕获);优先于 `value`。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub var: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Box<Rule>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matches: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub not: bool,
+}
+
 impl Rule {
     /// 决定本规则数据源的「主 `via`」(`render-dual-source` 的双源路由用):叶子取其 `via`,
-    /// 组合子(`firstOf`/`concat`)取首个子规则的主 via,纯值规则(literal/template/js)无源 → `None`。
+    /// 组合子(`firstOf`/`concat`)取首个子规则的主 via,`when` 取分支的、`split`/`map`/`join`
+    /// 取其输入规则的主 via,纯值规则(literal/template/js)无源 → `None`。
     /// 引擎据此把 `via:css`/`xpath` 的规则(如 `totalPages`)路由到渲染 DOM、其余路由到 body。
     pub fn primary_via(&self) -> Option<Via> {
         match self {
             Rule::Leaf(l) => Some(l.via),
             Rule::FirstOf { first_of } => first_of.first().and_then(Rule::primary_via),
             Rule::Concat { concat, .. } => concat.first().and_then(Rule::primary_via),
+            Rule::When {
+                then, otherwise, ..
+            } => then
+                .primary_via()
+                .or_else(|| otherwise.as_deref().and_then(Rule::primary_via)),
+            Rule::Split { split: rule, .. }
+            | Rule::Map { map: rule, .. }
+            | Rule::Join { join: rule, .. } => rule.primary_via(),
             Rule::Literal { .. } | Rule::Template { .. } | Rule::Js { .. } => None,
         }
     }
 }
 
-/// URL 字段:可为字符串模板,或一条规则。
+/// URL 字段:可为字符串模板(语法同 `template` 规则,支持过滤器),或一条规则。
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
 #[serde(untagged)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -36,6 +36,9 @@ pub fn Layout(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
                     Some(Commands::History) => {
                         navigate.push("/select-history");
                     }
+                    Some(Commands::Shelf) => {
+                        navigate.push("/bookshelf");
+                    }
                     Some(Commands::Local { path }) => {
                         if let Some(path) = path {
                             navigate.push_with_state("/select-file", path);
@@ -11,18 +11,22 @@ use ratatui_kit::{
 use tokio::sync::Notify;
 
 use crate::{
-    AppearanceConfig, History, ReaderDisplayConfig, TRNovel, TTSConfig,
+    AppearanceConfig, Bookshelf, History, ReaderDisplayConfig, TRNovel, TTSConfig,
     book_source::BookSourceCache,
     components::{Loading, WarningModal},
     errors::Errors,
     novel::{local_novel::LocalNovel, network_novel::NetworkNovel},
     pages::{
         ReadNovel,
+        bookshelf::BookshelfPage,
+        cache_setting::CacheSetting,
+        downloads::DownloadsPage,
         home::Home,
         local_novel::SelectFile,
         network_novel::{
             book_detail::BookDetail, book_source_login::BookSourceLogin,
-            book_source_manager::BookSourceManager, select_books::SelectBooks,
+            book_source_manager::BookSourceManager, change_source::ChangeSource,
+            global_search::GlobalSearchPage, select_books::SelectBooks,
         },
         select_history::SelectHistory,
         theme_setting::ThemeSetting,
@@ -41,12 +45,14 @@ pub fn App(props: &AppProps, mut hooks: Hooks) -> impl Into<AnyElement<'static>>
     hooks.use_terminal_size();
     let mut loading = hooks.use_state(|| true);
     // 主题 / TTS 模型句柄 / 浏览器提示已改为全局 Atom(见 `crate::state` 与 `browser_assist`);
-    // 此处只保留带 Drop 兜底存档的缓存(History/BookSourceCache/TTSConfig),仍由 App use_state 持有。
+    // 此处只保留带 Drop 兜底存档的缓存(History/BookSourceCache/TTSConfig),仍由 App use_state 持有;
+    // 书架(Bookshelf)每次改动即落盘,放在这里只为书架页、历史页与阅读页共享同一份。
     let history_state = hooks.use_state(|| None::<History>);
+    let bookshelf_state = hooks.use_state(|| None::<Bookshelf>);
     let book_sources_catch_state = hooks.use_state(|| None::<BookSourceCache>);
     let mut tts_config = hooks.use_state(TTSConfig::default);
     let error = hooks.use_state(|| None::<String>);
-    // keybindings.toml 的合并告警:非致命,启动后一次性弹出、ESC 关闭即消。
+    // keybindings.toml 的合并告警与损坏的缓存文件:非致命,启动后一次性弹出、ESC 关闭即消。
     let keymap_warnings = hooks.use_state(|| None::<String>);
 
     hooks.use_future(async move {
@@ -65,9 +71,15 @@ pub fn App(props: &AppProps, mut hooks: Hooks) -> impl Into<AnyElement<'static>>
             let history = History::load()?;
             history_state.write().replace(history);
 
+            let bookshelf = Bookshelf::load()?;
+            bookshelf_state.write().replace(bookshelf);
+
             let book_sources = BookSourceCache::load()?;
             book_sources_catch_state.write().replace(book_sources);
 
+            // 续传上次退出时没下完的任务。
+            crate::download::start()?;
+
             let tts_config_cache = TTSConfig::load()?;
             tts_config.set(tts_config_cache);
 
@@ -75,8 +87,10 @@ pub fn App(props: &AppProps, mut hooks: Hooks) -> impl Into<AnyElement<'static>>
             crate::state::READER_DISPLAY.set(ReaderDisplayConfig::load()?);
 
             // 按键配置:任何问题都降级为告警,不进 Err 路径(不阻断启动)。
-            let (keymap, warnings) = crate::keymap::load_keymap();
+            let (keymap, mut warnings) = crate::keymap::load_keymap();
             crate::state::KEYMAP.set(keymap);
+            // 上面读到的损坏文件已留底,一并提示。
+            warnings.extend(crate::cache::store::take_warnings());
             if !warnings.is_empty() {
                 keymap_warnings.write().replace(warnings.join("\n"));
             }
@@ -97,6 +111,7 @@ pub fn App(props: &AppProps, mut hooks: Hooks) -> impl Into<AnyElement<'static>>
         "/"=>Layout{
             "/home"=>Home,
             "/select-history"=>SelectHistory,
+            "/bookshelf"=>BookshelfPage,
             // 本地小说
             "/select-file"=> SelectFile,
             "/local-novel"=> ReadNovel<LocalNovel>,
@@ -105,9 +120,14 @@ pub fn App(props: &AppProps, mut hooks: Hooks) -> impl Into<AnyElement<'static>>
             "/book-source-login"=> BookSourceLogin,
             "/select-books"=> SelectBooks,
             "/book-detail"=> BookDetail,
+            "/change-source"=> ChangeSource,
+            "/global-search"=> GlobalSearchPage,
+            "/downloads"=> DownloadsPage,
             "/network-novel"=> ReadNovel<NetworkNovel>,
             // 主题设置
             "/theme-setting"=> ThemeSetting,
+            // 缓存管理
+            "/cache-setting"=> CacheSetting,
         }
     );
 
@@ -136,13 +156,15 @@ pub fn App(props: &AppProps, mut hooks: Hooks) -> impl Into<AnyElement<'static>>
                     PaletteProvider(palette: palette){
                         Border(style: Style::new().bg(palette.bg), borders: Borders::NONE){
                             ContextProvider(value:Context::owned(history_state)){
-                                ContextProvider(value:Context::owned(book_sources_catch_state)){
-                                    ContextProvider(value:Context::owned(tts_config)){
-                                        RouterProvider(
-                                            routes: routes,
-                                            index_path: "/home",
-                                            state: RouteState::new(props.trnovel.clone())
-                                        )
+                                ContextProvider(value:Context::owned(bookshelf_state)){
+                                    ContextProvider(value:Context::owned(book_sources_catch_state)){
+                                        ContextProvider(value:Context::owned(tts_config)){
+                                            RouterProvider(
+                                                routes: routes,
+                                                index_path: "/home",
+                                                state: RouteState::new(props.trnovel.clone())
+                                            )
+                                        }
                                     }
                                 }
                             }
@@ -0,0 +1,826 @@
+//! 备份与恢复:把 `~/.novel` 下的用户数据打成一个带版本号的 zip,换机器或重装后一键还原。
+//!
+//! 能重新生成的数据不进备份:章节阅读缓存、浏览器配置目录、字体匹配缓存(已下载的章节与
+//! 书籍快照保留)。登录态(`source-state/`)默认不备份;`--include-login` 时用口令派生的密钥
+//! (PBKDF2-SHA256)以 AES-256-GCM 加密后写入。
+//!
+//! 恢复分合并与替换两种模式;写回后把各数据文件按当前版本的结构重新读写一遍,旧格式随之升级。
+
+use crate::{
+    Bookshelf, Downloads, LocalNovelCache, NetworkNovelCache, Result,
+    book_source::BookSourceCache,
+    cache::store,
+    data_dir::{self, DataLayout},
+    history::History,
+};
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng, rand_core::RngCore},
+};
+use base64::{Engine as _, prelude::BASE64_STANDARD};
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::{self, File},
+    io::{BufWriter, Read, Seek, Write},
+    path::{Component, Path, PathBuf},
+};
+use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+/// 备份格式版本;结构变化时加一,并在 [`Archive::read`] 里兼容旧版本。
+pub const FORMAT_VERSION: u32 = 1;
+
+/// 口令派生密钥的迭代次数(写进清单,以后调高也能解开旧备份)。
+pub const PBKDF2_ROUNDS: u32 = 600_000;
+
+/// 免交互时从这个环境变量读口令。
+pub const PASSPHRASE_ENV: &str = "TRNOVEL_BACKUP_PASSPHRASE";
+
+const MANIFEST: &str = "manifest.json";
+const DATA_DIR: &str = "data/";
+const LOGIN_DIR: &str = "login/";
+
+/// 相对数据目录的路径(`/` 分隔)→ 文件内容。
+pub type Files = BTreeMap<String, Vec<u8>>;
+
+/// 恢复模式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, ValueEnum)]
+pub enum RestoreMode {
+    /// 与现有数据合并:历史、书架、书源、下载队列按条目合并,其余文件已存在的保留
+    #[default]
+    Merge,
+    /// 清空现有数据后整体换成备份里的(替换前自动备份一份到 ~/.novel/backups)
+    Replace,
+}
+
+/// 备份清单,存为压缩包里的 `manifest.json`。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub version: u32,
+    pub app_version: String,
+    pub created_at: DateTime<Local>,
+    /// 数据文件数(不含登录态)。
+    pub files: usize,
+    /// 登录态的加密参数;没备份登录态时为 `None`。
+    #[serde(default)]
+    pub login: Option<LoginKdf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginKdf {
+    /// Base64 编码的随机盐。
+    pub salt: String,
+    pub rounds: u32,
+}
+
+/// 数据目录里一个文件属于哪一类。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Skip,
+    Data,
+    Login,
+}
+
+/// 按相对 `~/.novel` 的路径(`/` 分隔)判断文件是否进备份。
+fn scope(path: &str) -> Scope {
+    let mut parts = path.split('/');
+    let first = parts.next().unwrap_or_default();
+    if path.ends_with(".tmp")
+        || path.ends_with(".part")
+        || path.ends_with(".lock")
+        || path.contains(".corrupt-")
+    {
+        return Scope::Skip;
+    }
+    match first {
+        // 同步配置含 WebDAV 密码、代理配置可能含代理密码,与登录态一样加密。
+        "source-state" | "sync.json" | "proxy.json" => Scope::Login,
+        // 同步基准只对本机有意义,恢复到别的设备会把远端条目误判为已删除。
+        "sync-base.json" => Scope::Skip,
+        // 书源健康记录反映的是本机网络状况,换台设备该降级的书源未必一样。
+        "source-health.json" => Scope::Skip,
+        "browser-profile" | "gen-fontmap" | "backups" | "tts" => Scope::Skip,
+        // 章节目录下只留下载的正文与书籍快照,阅读缓存随时能重新拉。
+        "chapters" if path.split('/').any(|p| p == "downloads") || path.ends_with(".json") => {
+            Scope::Data
+        }
+        "chapters" => Scope::Skip,
+        _ => Scope::Data,
+    }
+}
+
+/// 收集数据目录下要备份的文件,返回 `(数据文件, 登录态文件)`,键为 `/` 分隔的相对路径。
+fn collect(layout: &DataLayout) -> Result<(Files, Files)> {
+    let mut data = BTreeMap::new();
+    let mut login = BTreeMap::new();
+    for root in layout.roots() {
+        for entry in walkdir::WalkDir::new(&root)
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Some(name) = relative_name(&root, entry.path()) else {
+                continue;
+            };
+            match scope(&name) {
+                Scope::Skip => {}
+                Scope::Data => {
+                    data.insert(name, fs::read(entry.path())?);
+                }
+                Scope::Login => {
+                    login.insert(name, fs::read(entry.path())?);
+                }
+            }
+        }
+    }
+    Ok((data, login))
+}
+
+fn relative_name(root: &Path, path: &Path) -> Option<String> {
+    let parts: Vec<String> = path
+        .strip_prefix(root)
+        .ok()?
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Some(parts.join("/"))
+}
+
+/// 把数据目录写成备份;传了口令才带上登录态。
+pub fn write_backup(
+    layout: &DataLayout,
+    w: impl Write + Seek,
+    passphrase: Option<&str>,
+) -> Result<Manifest> {
+    let (data, login) = collect(layout)?;
+    let kdf = passphrase.filter(|_| !login.is_empty()).map(|passphrase| {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let kdf = LoginKdf {
+            salt: BASE64_STANDARD.encode(salt),
+            rounds: PBKDF2_ROUNDS,
+        };
+        (derive_key(passphrase, &salt, kdf.rounds), kdf)
+    });
+    let manifest = Manifest {
+        version: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: Local::now(),
+        files: data.len(),
+        login: kdf.as_ref().map(|(_, kdf)| kdf.clone()),
+    };
+
+    let mut zip = ZipWriter::new(w);
+    let options = SimpleFileOptions::default();
+    zip.start_file(MANIFEST, options)?;
+    serde_json::to_writer_pretty(&mut zip, &manifest)?;
+    for (name, bytes) in &data {
+        zip.start_file(format!("{DATA_DIR}{name}"), options)?;
+        zip.write_all(bytes)?;
+    }
+    if let Some((key, _)) = &kdf {
+        for (name, bytes) in &login {
+            zip.start_file(format!("{LOGIN_DIR}{name}.enc"), options)?;
+            zip.write_all(&encrypt(key, bytes)?)?;
+        }
+    }
+    zip.finish()?.flush()?;
+    Ok(manifest)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], rounds: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<sha2::Sha256>(passphrase.as_bytes(), salt, rounds, &mut key);
+    key
+}
+
+/// 加密结果为 `nonce(12 字节) || 密文`。
+fn encrypt(key: &[u8; 32], plain: &[u8]) -> Result<Vec<u8>> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let sealed = cipher
+        .encrypt(&nonce, plain)
+        .map_err(|_| "加密登录态失败")?;
+    Ok([nonce.as_slice(), &sealed].concat())
+}
+
+fn decrypt(key: &[u8; 32], sealed: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < 12 {
+        return Err("登录态数据已损坏".into());
+    }
+    let (nonce, sealed) = sealed.split_at(12);
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key))
+        .decrypt(Nonce::from_slice(nonce), sealed)
+        .map_err(|_| "口令错误或登录态数据已损坏".into())
+}
+
+/// 读进内存的备份。
+#[derive(Debug)]
+pub struct Archive {
+    pub manifest: Manifest,
+    pub data: Files,
+    /// 加密的登录态(键不含 `.enc` 后缀)。
+    login: Files,
+}
+
+impl Archive {
+    /// 读取备份;兼容没有清单的旧备份(直接把 `~/.novel` 打成的 zip,视为版本 0)。
+    pub fn read(r: impl Read + Seek) -> Result<Self> {
+        let mut zip = ZipArchive::new(r)?;
+        let mut entries = BTreeMap::new();
+        for index in 0..zip.len() {
+            let mut file = zip.by_index(index)?;
+            if !file.is_file() {
+                continue;
+            }
+            // 拒绝 `../` 之类跳出数据目录的路径。
+            let Some(path) = file.enclosed_name() else {
+                continue;
+            };
+            let name = path
+                .components()
+                .filter_map(|c| match c {
+                    Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+            entries.insert(name, bytes);
+        }
+
+        let Some(manifest) = entries.remove(MANIFEST) else {
+            return Ok(Self::legacy(entries));
+        };
+        let manifest: Manifest = serde_json::from_slice(&manifest)?;
+        if manifest.version > FORMAT_VERSION {
+            return Err(format!(
+                "备份来自更新的版本(格式 v{},TRNovel {}),请先升级 TRNovel",
+                manifest.version, manifest.app_version
+            )
+            .into());
+        }
+        let mut data = BTreeMap::new();
+        let mut login = BTreeMap::new();
+        for (name, bytes) in entries {
+            if let Some(name) = name.strip_prefix(DATA_DIR) {
+                data.insert(name.to_string(), bytes);
+            } else if let Some(name) = name
+                .strip_prefix(LOGIN_DIR)
+                .and_then(|name| name.strip_suffix(".enc"))
+            {
+                login.insert(name.to_string(), bytes);
+            }
+        }
+        Ok(Self {
+            manifest,
+            data,
+            login,
+        })
+    }
+
+    /// 版本 0:手动打包的 `~/.novel`,可能带一层 `.novel/` 目录;登录态是明文,按普通数据恢复。
+    fn legacy(entries: Files) -> Self {
+        let data: Files = entries
+            .into_iter()
+            .map(|(name, bytes)| match name.strip_prefix(".novel/") {
+                Some(name) => (name.to_string(), bytes),
+                None => (name, bytes),
+            })
+            .filter(|(name, _)| scope(name) != Scope::Skip)
+            .collect();
+        Self {
+            manifest: Manifest {
+                version: 0,
+                app_version: "未知".to_string(),
+                created_at: Local::now(),
+                files: data.len(),
+                login: None,
+            },
+            data,
+            login: BTreeMap::new(),
+        }
+    }
+
+    pub fn has_login(&self) -> bool {
+        !self.login.is_empty()
+    }
+
+    /// 用口令解开登录态。
+    pub fn decrypt_login(&self, passphrase: &str) -> Result<Files> {
+        let Some(kdf) = &self.manifest.login else {
+            return Ok(BTreeMap::new());
+        };
+        let salt = BASE64_STANDARD
+            .decode(&kdf.salt)
+            .map_err(|_| "备份清单里的盐已损坏")?;
+        let key = derive_key(passphrase, &salt, kdf.rounds);
+        self.login
+            .iter()
+            .map(|(name, sealed)| Ok((name.clone(), decrypt(&key, sealed)?)))
+            .collect()
+    }
+}
+
+/// 恢复结果统计。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RestoreReport {
+    /// 新写入(或替换)的文件数。
+    pub written: usize,
+    /// 与现有数据按条目合并的文件数。
+    pub merged: usize,
+    /// 合并模式下已存在而保留原样的文件数。
+    pub kept: usize,
+}
+
+/// 把文件写回数据目录。`files` 的键为相对路径,已包含解密后的登录态。
+pub fn restore(layout: &DataLayout, files: &Files, mode: RestoreMode) -> Result<RestoreReport> {
+    let mut report = RestoreReport::default();
+    if mode == RestoreMode::Replace {
+        let (data, login) = collect(layout)?;
+        // 备份里没有登录态(没备份或恢复时跳过)时保留本机的登录态,免得替换完全部重登。
+        let replace_login = files.keys().any(|name| scope(name) == Scope::Login);
+        let login = login.keys().filter(|_| replace_login);
+        for name in data.keys().chain(login) {
+            fs::remove_file(layout.path(name))?;
+        }
+    }
+    for (name, bytes) in files {
+        let path = layout.path(name);
+        // 与正常保存一样按文件加锁,合并的读改写不会和运行中的 trn 交错。
+        let _lock = store::lock(&path)?;
+        let bytes = match fs::read(&path) {
+            Ok(existing) if mode == RestoreMode::Merge => {
+                match merge_file(name, &existing, bytes) {
+                    Some(merged) => {
+                        report.merged += 1;
+                        merged
+                    }
+                    None => {
+                        report.kept += 1;
+                        continue;
+                    }
+                }
+            }
+            _ => {
+                report.written += 1;
+                bytes.clone()
+            }
+        };
+        // 登录态、同步与代理配置含密码和 cookie,与平时保存一样只给当前用户读写。
+        if scope(name) == Scope::Login {
+            store::write_atomic_private(&path, &bytes)?;
+        } else {
+            store::write_atomic(&path, &bytes)?;
+        }
+    }
+    Ok(report)
+}
+
+/// 合并同名文件;返回 `None` 表示保留现有文件。
+///
+/// 列表型数据按条目合并,同一条目以现有的为准(历史记录取最近读过的一条);
+/// 文件解析失败时不冒险合并,保留现有文件。
+fn merge_file(name: &str, existing: &[u8], incoming: &[u8]) -> Option<Vec<u8>> {
+    let (mut existing, incoming): (Value, Value) = (
+        serde_json::from_slice(existing).ok()?,
+        serde_json::from_slice(incoming).ok()?,
+    );
+    match name {
+        "history.json" => merge_history(&mut existing, incoming),
+        "bookshelf.json" => {
+            merge_keyed(&mut existing, &incoming, "books", |b| key_of(b, &["id"]));
+            merge_keyed(&mut existing, &incoming, "groups", |g| {
+                g.as_str().map(String::from)
+            });
+        }
+        "downloads.json" => {
+            merge_keyed(&mut existing, &incoming, "tasks", |t| {
+                key_of(t, &["bookUrl"])
+            });
+        }
+        "book_sources.json" => {
+            merge_keyed(&mut existing, &incoming, "book_sources", |s| {
+                key_of(s, &["url", "name"])
+            });
+        }
+        _ => return None,
+    }
+    serde_json::to_vec_pretty(&existing).ok()
+}
+
+fn key_of(value: &Value, fields: &[&str]) -> Option<String> {
+    let parts: Option<Vec<&str>> = fields.iter().map(|f| value.get(f)?.as_str()).collect();
+    Some(parts?.join("\n"))
+}
+
+/// 把 `incoming[field]` 里现有没有的条目追加到 `existing[field]`。
+fn merge_keyed(
+    existing: &mut Value,
+    incoming: &Value,
+    field: &str,
+    key: impl Fn(&Value) -> Option<String>,
+) {
+    let Some(incoming) = incoming.get(field).and_then(Value::as_array) else {
+        return;
+    };
+    let Some(object) = existing.as_object_mut() else {
+        return;
+    };
+    let list = object
+        .entry(field)
+        .or_insert_with(|| Value::Array(Vec::new()));
+    let Some(list) = list.as_array_mut() else {
+        return;
+    };
+    let mut known: HashSet<String> = list.iter().filter_map(&key).collect();
+    for item in incoming {
+        if let Some(k) = key(item)
+            && known.insert(k)
+        {
+            list.push(item.clone());
+        }
+    }
+}
+
+/// 历史记录按 id 合并,同一本书保留最近读过的一条,再按阅读时间倒序截断到上限。
+fn merge_history(existing: &mut Value, incoming: Value) {
+    let read_at = |entry: &Value| {
+        entry
+            .get(1)
+            .and_then(|item| item.get("last_read_at"))
+            .and_then(Value::as_str)
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+    };
+    let Some(object) = existing.as_object_mut() else {
+        return;
+    };
+    let mut entries: Vec<Value> = object
+        .get("histories")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    for entry in incoming
+        .get("histories")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let id = entry.get(0);
+        match entries.iter_mut().find(|e| e.get(0) == id) {
+            Some(current) if read_at(entry) > read_at(current) => *current = entry.clone(),
+            Some(_) => {}
+            None => entries.push(entry.clone()),
+        }
+    }
+    entries.sort_by_key(|e| std::cmp::Reverse(read_at(e)));
+    entries.truncate(History::MAX_LEN);
+    if object.get("local_path").is_none_or(Value::is_null)
+        && let Some(path) = incoming.get("local_path")
+    {
+        object.insert("local_path".to_string(), path.clone());
+    }
+    object.insert("histories".to_string(), Value::Array(entries));
+}
+
+/// 恢复后按当前版本的结构把数据文件重新读写一遍,旧版本缓存里缺的字段补上默认值。
+/// 个别文件读不懂时保留原样,返回告警。
+pub fn migrate(layout: &DataLayout) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut check = |name: &str, result: Result<()>| {
+        if let Err(e) = result {
+            warnings.push(format!("{name} 升级失败,已保留原样:{e}"));
+        }
+    };
+    // 这两个类型在 drop 时自动落盘,load 一次即按新结构写回。
+    check(
+        "history.json",
+        History::load().map(drop).map_err(Into::into),
+    );
+    check("book_sources.json", BookSourceCache::load().map(drop));
+    check("bookshelf.json", Bookshelf::load().and_then(|s| s.save()));
+    check("downloads.json", Downloads::load().and_then(|d| d.save()));
+    for (dir, rewrite) in [
+        (
+            "network",
+            rewrite::<NetworkNovelCache> as fn(&Path) -> Result<()>,
+        ),
+        ("local", rewrite::<LocalNovelCache>),
+    ] {
+        let Ok(entries) = fs::read_dir(layout.path(dir)) else {
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                check(
+                    &format!("{dir}/{}", entry.file_name().to_string_lossy()),
+                    rewrite(&path),
+                );
+            }
+        }
+    }
+    warnings
+}
+
+fn rewrite<T: Serialize + DeserializeOwned>(path: &Path) -> Result<()> {
+    let value: T = serde_json::from_slice(&fs::read(path)?)?;
+    store::save_json(path, &value)
+}
+
+/// 读口令:优先环境变量 [`PASSPHRASE_ENV`],否则在终端里输入(不回显)。
+fn read_passphrase(prompt: &str, confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    let passphrase = rpassword::prompt_password(prompt)?;
+    if confirm && rpassword::prompt_password("再输入一次:")? != passphrase {
+        return Err("两次输入的口令不一致".into());
+    }
+    Ok(passphrase)
+}
+
+/// 默认备份文件名:当前目录下的 `trnovel-backup-<时间>.zip`。
+pub fn default_path() -> PathBuf {
+    PathBuf::from(format!(
+        "trnovel-backup-{}.zip",
+        Local::now().format("%Y%m%d-%H%M%S")
+    ))
+}
+
+fn save_backup(layout: &DataLayout, path: &Path, passphrase: Option<&str>) -> Result<Manifest> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("part");
+    let manifest = write_backup(layout, BufWriter::new(File::create(&tmp)?), passphrase)?;
+    fs::rename(tmp, path)?;
+    Ok(manifest)
+}
+
+/// `backup` 子命令。
+pub fn run_backup(out: Option<&Path>, include_login: bool) {
+    let layout = match data_dir::layout() {
+        Ok(layout) => layout,
+        Err(e) => return eprintln!("✗ 找不到数据目录: {e}"),
+    };
+    let passphrase = if include_login {
+        match read_passphrase("设置加密登录态的口令:", true) {
+            Ok(p) if !p.is_empty() => Some(p),
+            Ok(_) => return eprintln!("✗ 口令不能为空"),
+            Err(e) => return eprintln!("✗ {e}"),
+        }
+    } else {
+        None
+    };
+    let path = out.map_or_else(default_path, Path::to_path_buf);
+    match save_backup(layout, &path, passphrase.as_deref()) {
+        Ok(manifest) => {
+            println!("✓ 已备份 {} 个文件 → {}", manifest.files, path.display());
+            if manifest.login.is_some() {
+                println!("  登录态已加密写入,恢复时需要输入同一口令。");
+            } else {
+                println!("  未包含登录态(需要时加 --include-login)。");
+            }
+        }
+        Err(e) => eprintln!("✗ 备份失败: {e}"),
+    }
+}
+
+/// `restore` 子命令。
+pub fn run_restore(path: &Path, mode: RestoreMode) {
+    let layout = match data_dir::layout() {
+        Ok(layout) => layout,
+        Err(e) => return eprintln!("✗ 找不到数据目录: {e}"),
+    };
+    let archive = match File::open(path).map_err(Into::into).and_then(Archive::read) {
+        Ok(archive) => archive,
+        Err(e) => return eprintln!("✗ 读取备份失败: {e}"),
+    };
+    let manifest = &archive.manifest;
+    println!(
+        "备份:格式 v{} · TRNovel {} · {} · {} 个文件",
+        manifest.version,
+        manifest.app_version,
+        manifest.created_at.format("%Y-%m-%d %H:%M"),
+        archive.data.len()
+    );
+
+    let mut files = archive.data.clone();
+    if archive.has_login() {
+        match read_passphrase("输入登录态口令(直接回车跳过登录态):", false) {
+            Ok(p) if p.is_empty() => println!("  已跳过登录态。"),
+            Ok(p) => match archive.decrypt_login(&p) {
+                Ok(login) => files.extend(login),
+                Err(e) => return eprintln!("✗ {e}"),
+            },
+            Err(e) => return eprintln!("✗ {e}"),
+        }
+    }
+
+    if mode == RestoreMode::Replace {
+        let safety = layout.path("backups").join(format!(
+            "before-restore-{}.zip",
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        match save_backup(layout, &safety, None) {
+            Ok(_) => println!("  替换前的数据已备份到 {}", safety.display()),
+            Err(e) => return eprintln!("✗ 替换前备份现有数据失败,已取消恢复: {e}"),
+        }
+    }
+
+    match restore(layout, &files, mode) {
+        Ok(report) => {
+            println!(
+                "✓ 已恢复:写入 {} 个,合并 {} 个,保留现有 {} 个",
+                report.written, report.merged, report.kept
+            );
+            for warning in migrate(layout) {
+                eprintln!("  ⚠ {warning}");
+            }
+        }
+        Err(e) => eprintln!("✗ 恢复失败: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn dir(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("trnovel-backup-{name}"));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn put(root: &Path, name: &str, text: &str) {
+        let path = root.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+
+    #[test]
+    fn backup_skips_caches_and_encrypts_login_state() {
+        let root = dir("scope");
+        put(&root, "history.json", "{}");
+        put(&root, "network/a.json", "{}");
+        put(&root, "chapters/src/a.txt", "缓存正文");
+        put(&root, "chapters/src/book.toc.json", "[]");
+        put(&root, "chapters/src/downloads/a.txt", "下载正文");
+        put(&root, "browser-profile/Default/Cookies", "x");
+        put(&root, "source-state/s.json", "{\"cookies\":{}}");
+
+        let mut out = Cursor::new(Vec::new());
+        write_backup(&DataLayout::single(root.clone()), &mut out, None).unwrap();
+        out.set_position(0);
+        let archive = Archive::read(&mut out).unwrap();
+        assert_eq!(
+            archive.data.keys().collect::<Vec<_>>(),
+            [
+                "chapters/src/book.toc.json",
+                "chapters/src/downloads/a.txt",
+                "history.json",
+                "network/a.json"
+            ]
+        );
+        assert!(!archive.has_login());
+
+        let mut out = Cursor::new(Vec::new());
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        // 测试里用很少的迭代次数,直接构造清单走同一条加解密路径。
+        let key = derive_key("口令", &salt, 10);
+        let mut zip = ZipWriter::new(&mut out);
+        let manifest = Manifest {
+            version: FORMAT_VERSION,
+            app_version: String::new(),
+            created_at: Local::now(),
+            files: 0,
+            login: Some(LoginKdf {
+                salt: BASE64_STANDARD.encode(salt),
+                rounds: 10,
+            }),
+        };
+        zip.start_file(MANIFEST, SimpleFileOptions::default())
+            .unwrap();
+        serde_json::to_writer(&mut zip, &manifest).unwrap();
+        zip.start_file(
+            "login/source-state/s.json.enc",
+            SimpleFileOptions::default(),
+        )
+        .unwrap();
+        zip.write_all(&encrypt(&key, b"{\"cookies\":{}}").unwrap())
+            .unwrap();
+        zip.finish().unwrap();
+        out.set_position(0);
+
+        let archive = Archive::read(&mut out).unwrap();
+        assert!(archive.decrypt_login("错的").is_err());
+        let login = archive.decrypt_login("口令").unwrap();
+        assert_eq!(login["source-state/s.json"], b"{\"cookies\":{}}");
+    }
+
+    #[test]
+    fn merge_keeps_existing_entries_and_adds_missing_ones() {
+        let root = dir("merge");
+        put(
+            &root,
+            "history.json",
+            r#"{"local_path":null,"histories":[["a",{"type":"Local","last_read_at":"2026-01-02T00:00:00+08:00"}]]}"#,
+        );
+        put(
+            &root,
+            "bookshelf.json",
+            r#"{"books":[{"id":"a","group":"在读"}],"groups":["x"]}"#,
+        );
+        put(&root, "appearance.json", "{\"mine\":true}");
+
+        let files = BTreeMap::from([
+            (
+                "history.json".to_string(),
+                br#"{"local_path":"/novels","histories":[
+                    ["a",{"type":"Local","last_read_at":"2026-03-01T00:00:00+08:00"}],
+                    ["b",{"type":"Local","last_read_at":"2026-02-01T00:00:00+08:00"}]]}"#
+                    .to_vec(),
+            ),
+            (
+                "bookshelf.json".to_string(),
+                r#"{"books":[{"id":"a","group":"读完"},{"id":"b","group":"想读"}],"groups":["x","y"]}"#
+                    .as_bytes()
+                    .to_vec(),
+            ),
+            ("appearance.json".to_string(), b"{\"mine\":false}".to_vec()),
+            ("network/new.json".to_string(), b"{}".to_vec()),
+        ]);
+        let report = restore(
+            &DataLayout::single(root.clone()),
+            &files,
+            RestoreMode::Merge,
+        )
+        .unwrap();
+        assert_eq!(
+            report,
+            RestoreReport {
+                written: 1,
+                merged: 2,
+                kept: 1
+            }
+        );
+
+        let read = |name: &str| -> Value {
+            serde_json::from_slice(&fs::read(root.join(name)).unwrap()).unwrap()
+        };
+        let history = read("history.json");
+        // 同一本书取最近读过的一条,整体按阅读时间倒序。
+        assert_eq!(
+            history["histories"][0][1]["last_read_at"],
+            "2026-03-01T00:00:00+08:00"
+        );
+        assert_eq!(history["histories"][1][0], "b");
+        assert_eq!(history["local_path"], "/novels");
+        let shelf = read("bookshelf.json");
+        assert_eq!(shelf["books"][0]["group"], "在读");
+        assert_eq!(shelf["books"][1]["id"], "b");
+        assert_eq!(shelf["groups"], serde_json::json!(["x", "y"]));
+        assert_eq!(read("appearance.json")["mine"], true);
+
+        restore(
+            &DataLayout::single(root.clone()),
+            &files,
+            RestoreMode::Replace,
+        )
+        .unwrap();
+        assert_eq!(read("appearance.json")["mine"], false);
+        assert_eq!(read("bookshelf.json")["books"][0]["group"], "读完");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restored_login_files_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let root = dir("private");
+        let files = BTreeMap::from([
+            ("proxy.json".to_string(), b"{}".to_vec()),
+            ("source-state/s.json".to_string(), b"{}".to_vec()),
+        ]);
+        restore(
+            &DataLayout::single(root.clone()),
+            &files,
+            RestoreMode::Merge,
+        )
+        .unwrap();
+        let mode = |name: &str| fs::metadata(root.join(name)).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode("proxy.json"), 0o600);
+        assert_eq!(mode("source-state/s.json"), 0o600);
+    }
+}
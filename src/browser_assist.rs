@@ -56,9 +56,16 @@ fn cached_decision() -> Option<AuthDecision> {
 // ───────────────────────── 持久化:「总是允许」标记 ─────────────────────────
 
 fn flag_path() -> Option<std::path::PathBuf> {
-    crate::utils::novel_catch_dir()
-        .ok()
-        .map(|d| d.join("browser_assist.on"))
+    crate::data_dir::data_path("browser_assist.on").ok()
+}
+
+/// 浏览器默认参数,profile 放在数据目录的 `browser-profile` 下。
+pub fn browser_options() -> BrowserOptions {
+    let mut opts = BrowserOptions::default();
+    if let Ok(dir) = crate::data_dir::data_path("browser-profile") {
+        opts.profile_dir = dir;
+    }
+    opts
 }
 
 /// 是否已「总是允许」浏览器辅助验证。
@@ -155,17 +162,16 @@ impl BrowserUi for TuiBrowserUi {
 /// - 否则 → 纯 reqwest。
 ///
 /// 是否真的开浏览器由 `TuiBrowserUi::authorize` 在撞挑战时把关(设置开关 ∨ 首次弹窗)。
-pub fn build_engine(source: BookSource) -> parse_book_source::Result<Engine> {
+pub fn build_engine(mut source: BookSource) -> parse_book_source::Result<Engine> {
+    // 书源没配代理时套用全局默认代理(reqwest 与浏览器走同一个)。
+    crate::cache::apply_global_proxy(&mut source);
     // 登录态(loginHeader/cookies)注入每个请求;加载时做 TTL 清理(过期清登录态)。
     // 空态(未登录 / 无需登录的书源)→ 注入为空,行为与现状一致(向后兼容)。
     let state = crate::cache::load_source_state(&source.url);
     let engine = if matches!(source.http.fetcher, FetchMode::Reqwest) {
         Engine::new(source)?
     } else {
-        let mut opts = BrowserOptions::default();
-        if let Ok(dir) = crate::utils::novel_catch_dir() {
-            opts.profile_dir = dir.join("browser-profile");
-        }
+        let mut opts = browser_options();
         opts.total_timeout = Duration::from_secs(90);
         opts.ui = browser_ui();
         match BrowserFetcher::detect(opts) {
@@ -173,6 +179,11 @@ pub fn build_engine(source: BookSource) -> parse_book_source::Result<Engine> {
             None => Engine::new(source)?,
         }
     };
+    // 操作的主取页走磁盘 HTTP 缓存;数据目录不可用时不缓存。
+    let engine = match crate::storage::http_cache_dir() {
+        Ok(dir) => engine.with_http_cache(&dir, crate::storage::http_cache_stats()),
+        Err(_) => engine,
+    };
     Ok(engine
         .with_login_header(state.login_header)
         .with_cookies(&state.cookies))
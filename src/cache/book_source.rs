@@ -1,10 +1,9 @@
 use std::{
-    fs::File,
     ops::{Deref, DerefMut},
     path::PathBuf,
 };
 
-use crate::{Result, utils::novel_catch_dir};
+use crate::{Result, cache::store, data_dir::data_path};
 use parse_book_source::BookSource;
 use serde::{Deserialize, Serialize};
 
@@ -19,20 +18,17 @@ pub struct BookSourceCache {
 
 impl BookSourceCache {
     pub fn get_cache_file_path() -> Result<PathBuf> {
-        Ok(PathBuf::new()
-            .join(novel_catch_dir()?)
-            .join("book_sources.json"))
+        data_path("book_sources.json")
     }
 
     pub fn load() -> Result<Self> {
-        match File::open(Self::get_cache_file_path()?) {
-            Ok(file) => {
+        match store::load_json::<serde_json::Value>(&Self::get_cache_file_path()?)? {
+            Some(value) => {
                 // 缓存结构为 {"book_sources": [<BookSource>...]}。不能直接反序列化:裸 serde
                 // 会跳过命名 fontMap 的展开(把 `"fontMap":"名"` 字符串引用当 BTreeMap 解析而
                 // 失败,报 "untagged enum Rule" 不匹配),导致外部生成/手改的 v2 缓存加载失败。
                 // 改走 BookSource::from_value_many(与 from_path / trn import 一致),逐源展开后
                 // 再反序列化,保证有效 v2 源能 round-trip 进缓存。
-                let value: serde_json::Value = serde_json::from_reader(file)?;
                 let sources_value = match value {
                     serde_json::Value::Object(mut map) => map
                         .remove("book_sources")
@@ -44,14 +40,12 @@ impl BookSourceCache {
                     .map_err(parse_book_source::BookSourceError::from)?;
                 Ok(BookSourceCache { book_sources })
             }
-            Err(_) => Ok(Default::default()),
+            None => Ok(Default::default()),
         }
     }
 
     pub fn save(&self) -> Result<()> {
-        let file = File::create(Self::get_cache_file_path()?)?;
-        serde_json::to_writer_pretty(file, self)?;
-        Ok(())
+        store::save_json(&Self::get_cache_file_path()?, self)
     }
 
     pub fn find_book_source_index(
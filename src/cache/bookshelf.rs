@@ -0,0 +1,419 @@
+use crate::{HistoryItem, Result, cache::store, data_dir::data_path};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{path::Path, path::PathBuf};
+
+/// 默认分组,书架为空时也会显示。
+pub const DEFAULT_GROUPS: [&str; 3] = ["在读", "想读", "读完"];
+
+/// 书架上的一本书。
+///
+/// `id` 与历史记录相同:本地为小说路径,网络为 book 链接,打开时沿用历史记录的跳转方式。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShelfBook {
+    pub id: String,
+    /// 最近一次阅读的快照(标题、进度、章节名、最后阅读时间)。
+    pub item: HistoryItem,
+    pub group: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    pub added_at: DateTime<Local>,
+    /// 上次看到的章节数,用来判断书是否更新过。
+    #[serde(default)]
+    pub chapter_count: usize,
+    /// 书籍最近一次更新的时间:本地为文件修改时间,网络为发现章节数增加的时间。
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Local>>,
+    /// 追更检查看到的最新章节数(仅网络小说);比 `chapter_count` 多出的部分即「新章节」。
+    #[serde(default)]
+    pub latest_chapter_count: usize,
+    /// 追更检查看到的最新章节名。
+    #[serde(default)]
+    pub latest_chapter: String,
+    /// 最近一次追更检查的时间。
+    #[serde(default)]
+    pub checked_at: Option<DateTime<Local>>,
+}
+
+impl ShelfBook {
+    pub fn title(&self) -> &str {
+        match &self.item {
+            HistoryItem::Local(item) => &item.title,
+            HistoryItem::Network(item) => &item.title,
+        }
+    }
+
+    pub fn percent(&self) -> f64 {
+        match &self.item {
+            HistoryItem::Local(item) => item.percent,
+            HistoryItem::Network(item) => item.percent,
+        }
+    }
+
+    /// 追更检查发现、但还没打开看过的新章节数。
+    pub fn new_chapters(&self) -> usize {
+        self.latest_chapter_count.saturating_sub(self.chapter_count)
+    }
+
+    pub fn last_read_at(&self) -> DateTime<Local> {
+        match &self.item {
+            HistoryItem::Local(item) => item.last_read_at,
+            HistoryItem::Network(item) => item.last_read_at,
+        }
+    }
+}
+
+/// 书架排序方式;置顶的书总排在最前。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShelfSort {
+    #[default]
+    LastRead,
+    Progress,
+    Title,
+    Updated,
+}
+
+impl ShelfSort {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ShelfSort::LastRead => "最近阅读",
+            ShelfSort::Progress => "阅读进度",
+            ShelfSort::Title => "书名",
+            ShelfSort::Updated => "更新时间",
+        }
+    }
+
+    /// 循环切换到下一种排序。
+    pub fn next(self) -> Self {
+        match self {
+            ShelfSort::LastRead => ShelfSort::Progress,
+            ShelfSort::Progress => ShelfSort::Title,
+            ShelfSort::Title => ShelfSort::Updated,
+            ShelfSort::Updated => ShelfSort::LastRead,
+        }
+    }
+}
+
+/// 书架视图的筛选条件。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShelfFilter {
+    All,
+    Group(String),
+    Tag(String),
+}
+
+impl ShelfFilter {
+    pub fn label(&self) -> String {
+        match self {
+            ShelfFilter::All => "全部".to_string(),
+            ShelfFilter::Group(group) => group.clone(),
+            ShelfFilter::Tag(tag) => format!("#{tag}"),
+        }
+    }
+
+    fn matches(&self, book: &ShelfBook) -> bool {
+        match self {
+            ShelfFilter::All => true,
+            ShelfFilter::Group(group) => &book.group == group,
+            ShelfFilter::Tag(tag) => book.tags.contains(tag),
+        }
+    }
+}
+
+/// 书架:与历史记录分开保存在 `~/.novel/bookshelf.json`,只由用户手动增删,
+/// 不设容量上限,不会像历史记录那样自动淘汰旧条目。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bookshelf {
+    pub books: Vec<ShelfBook>,
+    /// 用户新建的分组(默认分组之外),按创建顺序。
+    #[serde(default)]
+    pub groups: Vec<String>,
+    #[serde(default)]
+    pub sort: ShelfSort,
+}
+
+impl Bookshelf {
+    pub fn path() -> Result<PathBuf> {
+        data_path("bookshelf.json")
+    }
+
+    pub fn load() -> Result<Self> {
+        Ok(store::load_json(&Self::path()?)?.unwrap_or_default())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        store::save_json(&Self::path()?, self)
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.books.iter().any(|book| book.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut ShelfBook> {
+        self.books.iter_mut().find(|book| book.id == id)
+    }
+
+    /// 加入书架(默认放进「在读」);已在书架上时返回 `false`。
+    pub fn add(&mut self, id: &str, item: HistoryItem) -> bool {
+        if self.contains(id) {
+            return false;
+        }
+        self.books.push(ShelfBook {
+            id: id.to_string(),
+            updated_at: local_modified(id, &item),
+            item,
+            group: DEFAULT_GROUPS[0].to_string(),
+            tags: Vec::new(),
+            pinned: false,
+            added_at: Local::now(),
+            chapter_count: 0,
+            latest_chapter_count: 0,
+            latest_chapter: String::new(),
+            checked_at: None,
+        });
+        true
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        self.books.retain(|book| book.id != id);
+    }
+
+    /// 换源后让书架条目改指新书,分组、标签与置顶保留;不在书架上的书忽略。
+    pub fn replace(&mut self, old_id: &str, new_id: &str, item: HistoryItem) {
+        if !self.contains(old_id) {
+            return;
+        }
+        if old_id != new_id {
+            self.remove(new_id);
+        }
+        if let Some(book) = self.get_mut(old_id) {
+            book.id = new_id.to_string();
+            book.item = item;
+        }
+    }
+
+    /// 阅读后刷新书架上的快照;不在书架上的书忽略。章节数变多即记为一次更新。
+    pub fn record_read(&mut self, id: &str, item: HistoryItem, chapter_count: usize) {
+        let Some(book) = self.get_mut(id) else {
+            return;
+        };
+        if let Some(modified) = local_modified(id, &item) {
+            book.updated_at = Some(modified);
+        } else if book.chapter_count != 0
+            && chapter_count > book.chapter_count.max(book.latest_chapter_count)
+        {
+            book.updated_at = Some(Local::now());
+        }
+        if chapter_count > 0 {
+            book.chapter_count = chapter_count;
+            book.latest_chapter_count = book.latest_chapter_count.max(chapter_count);
+        }
+        book.item = item;
+    }
+
+    /// 记录一次追更检查的结果。首次检查(从未打开过目录)只建立基线,不算新章节。
+    pub fn record_update(&mut self, id: &str, chapter_count: usize, latest_chapter: &str) {
+        let Some(book) = self.get_mut(id) else {
+            return;
+        };
+        let now = Local::now();
+        if book.chapter_count == 0 {
+            book.chapter_count = chapter_count;
+        } else if chapter_count > book.chapter_count.max(book.latest_chapter_count) {
+            book.updated_at = Some(now);
+        }
+        book.latest_chapter_count = chapter_count;
+        book.latest_chapter = latest_chapter.to_string();
+        book.checked_at = Some(now);
+    }
+
+    /// 移动到分组;分组不存在时新建。
+    pub fn move_to_group(&mut self, id: &str, group: &str) {
+        let group = group.trim();
+        if group.is_empty() {
+            return;
+        }
+        if !self.all_groups().iter().any(|g| g == group) {
+            self.groups.push(group.to_string());
+        }
+        if let Some(book) = self.get_mut(id) {
+            book.group = group.to_string();
+        }
+    }
+
+    /// 用空格或逗号分隔的文本整体替换标签(去重、去空)。
+    pub fn set_tags(&mut self, id: &str, text: &str) {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in text.split([' ', ',', ',', '、']).map(str::trim) {
+            let tag = tag.trim_start_matches('#');
+            if !tag.is_empty() && !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+        if let Some(book) = self.get_mut(id) {
+            book.tags = tags;
+        }
+    }
+
+    pub fn toggle_pin(&mut self, id: &str) {
+        if let Some(book) = self.get_mut(id) {
+            book.pinned = !book.pinned;
+        }
+    }
+
+    /// 默认分组 + 用户分组 + 书籍里出现但未登记的分组(例如手改过配置文件)。
+    pub fn all_groups(&self) -> Vec<String> {
+        let mut groups: Vec<String> = DEFAULT_GROUPS.iter().map(|g| g.to_string()).collect();
+        for group in self
+            .groups
+            .iter()
+            .chain(self.books.iter().map(|b| &b.group))
+        {
+            if !groups.contains(group) {
+                groups.push(group.clone());
+            }
+        }
+        groups
+    }
+
+    /// 筛选标签页:全部、各分组、各标签。
+    pub fn filters(&self) -> Vec<ShelfFilter> {
+        let mut filters = vec![ShelfFilter::All];
+        filters.extend(self.all_groups().into_iter().map(ShelfFilter::Group));
+        let mut tags: Vec<&String> = self.books.iter().flat_map(|b| &b.tags).collect();
+        tags.sort();
+        tags.dedup();
+        filters.extend(tags.into_iter().cloned().map(ShelfFilter::Tag));
+        filters
+    }
+
+    /// 按筛选条件与当前排序列出书籍,置顶的在前。
+    pub fn view(&self, filter: &ShelfFilter) -> Vec<ShelfBook> {
+        let mut books: Vec<ShelfBook> = self
+            .books
+            .iter()
+            .filter(|book| filter.matches(book))
+            .cloned()
+            .collect();
+        books.sort_by(|a, b| {
+            b.pinned.cmp(&a.pinned).then_with(|| match self.sort {
+                ShelfSort::LastRead => b.last_read_at().cmp(&a.last_read_at()),
+                ShelfSort::Progress => b.percent().total_cmp(&a.percent()),
+                ShelfSort::Title => a.title().cmp(b.title()),
+                ShelfSort::Updated => b.updated_at.cmp(&a.updated_at),
+            })
+        });
+        books
+    }
+}
+
+/// 本地小说文件的修改时间;网络小说返回 `None`。
+fn local_modified(id: &str, item: &HistoryItem) -> Option<DateTime<Local>> {
+    if !matches!(item, HistoryItem::Local(_)) {
+        return None;
+    }
+    let modified = Path::new(id).metadata().ok()?.modified().ok()?;
+    Some(modified.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LocalHistoryItem, NetworkHistoryItem};
+    use chrono::Duration;
+
+    fn network(title: &str, percent: f64, minutes_ago: i64) -> HistoryItem {
+        HistoryItem::Network(NetworkHistoryItem {
+            current_chapter: String::new(),
+            last_read_at: Local::now() - Duration::minutes(minutes_ago),
+            percent,
+            title: title.to_string(),
+            book_source: "测试".to_string(),
+        })
+    }
+
+    fn shelf() -> Bookshelf {
+        let mut shelf = Bookshelf::default();
+        shelf.add("a", network("甲", 10.0, 30));
+        shelf.add("b", network("乙", 80.0, 10));
+        shelf.add(
+            "c",
+            HistoryItem::Local(LocalHistoryItem {
+                current_chapter: String::new(),
+                last_read_at: Local::now() - Duration::minutes(20),
+                percent: 50.0,
+                title: "丙".to_string(),
+            }),
+        );
+        shelf
+    }
+
+    fn titles(books: &[ShelfBook]) -> Vec<&str> {
+        books.iter().map(ShelfBook::title).collect()
+    }
+
+    #[test]
+    fn sorts_with_pinned_first() {
+        let mut shelf = shelf();
+        assert_eq!(titles(&shelf.view(&ShelfFilter::All)), ["乙", "丙", "甲"]);
+        shelf.sort = ShelfSort::Progress;
+        shelf.toggle_pin("a");
+        assert_eq!(titles(&shelf.view(&ShelfFilter::All)), ["甲", "乙", "丙"]);
+    }
+
+    #[test]
+    fn groups_and_tags_filter_books() {
+        let mut shelf = shelf();
+        shelf.move_to_group("a", "武侠");
+        shelf.set_tags("b", "#完本, 推荐 完本");
+        assert_eq!(shelf.all_groups(), ["在读", "想读", "读完", "武侠"]);
+        assert_eq!(
+            titles(&shelf.view(&ShelfFilter::Group("武侠".into()))),
+            ["甲"]
+        );
+        assert_eq!(shelf.books[1].tags, ["完本", "推荐"]);
+        assert_eq!(
+            titles(&shelf.view(&ShelfFilter::Tag("推荐".into()))),
+            ["乙"]
+        );
+        assert_eq!(shelf.filters().len(), 1 + 4 + 2);
+    }
+
+    #[test]
+    fn record_read_tracks_updates_only_for_shelf_books() {
+        let mut shelf = shelf();
+        assert!(!shelf.add("a", network("甲", 0.0, 0)));
+        shelf.record_read("a", network("甲", 20.0, 0), 100);
+        assert_eq!(shelf.books[0].updated_at, None);
+        shelf.record_read("a", network("甲", 20.0, 0), 120);
+        assert!(shelf.books[0].updated_at.is_some());
+        assert_eq!(shelf.books[0].percent(), 20.0);
+
+        shelf.record_read("x", network("未上架", 0.0, 0), 1);
+        assert_eq!(shelf.books.len(), 3);
+    }
+
+    #[test]
+    fn record_update_counts_new_chapters_until_read() {
+        let mut shelf = shelf();
+        shelf.record_update("a", 100, "第一百章");
+        assert_eq!(shelf.books[0].new_chapters(), 0);
+        assert_eq!(shelf.books[0].updated_at, None);
+
+        shelf.record_update("a", 103, "第一百零三章");
+        assert_eq!(shelf.books[0].new_chapters(), 3);
+        assert_eq!(shelf.books[0].latest_chapter, "第一百零三章");
+        let updated_at = shelf.books[0].updated_at;
+        assert!(updated_at.is_some());
+
+        // 打开阅读后目录已包含新章节,角标清零,也不会再记一次更新。
+        shelf.record_read("a", network("甲", 30.0, 0), 103);
+        assert_eq!(shelf.books[0].new_chapters(), 0);
+        assert_eq!(shelf.books[0].updated_at, updated_at);
+    }
+}
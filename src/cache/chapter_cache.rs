@@ -0,0 +1,413 @@
+use crate::{Result, cache::store, data_dir::data_path, utils::get_md5_string};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{
+    fs::{self, File},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+/// 每写这么多章重新统计一次容量,把别的进程 / 实例写入或删除的章节算进来。
+const RESYNC_EVERY: usize = 256;
+
+/// 超出容量上限时先删哪些章节。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EvictionPolicy {
+    /// 最久没读过的先删(读取命中会刷新时间)。
+    #[default]
+    Lru,
+    /// 最早缓存的先删(读取不影响顺序)。
+    Fifo,
+}
+
+/// 网络章节离线缓存的配置,保存在 `~/.novel/chapter-cache.json`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChapterCacheConfig {
+    /// 章节正文总容量上限(MB);0 表示不缓存。
+    #[serde(default = "default_max_size_mb")]
+    pub max_size_mb: u64,
+    #[serde(default)]
+    pub eviction: EvictionPolicy,
+    /// 阅读时在后台预取后面几章;0 表示不预取。
+    #[serde(default = "default_prefetch")]
+    pub prefetch: usize,
+}
+
+impl ChapterCacheConfig {
+    pub fn path() -> Result<PathBuf> {
+        data_path("chapter-cache.json")
+    }
+
+    pub fn load() -> Result<Self> {
+        Ok(store::load_json(&Self::path()?)?.unwrap_or_default())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        store::save_json(&Self::path()?, self)
+    }
+
+    pub fn max_size_bytes(&self) -> u64 {
+        self.max_size_mb * 1024 * 1024
+    }
+}
+
+impl Default for ChapterCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_size_mb: default_max_size_mb(),
+            eviction: EvictionPolicy::default(),
+            prefetch: default_prefetch(),
+        }
+    }
+}
+
+fn default_max_size_mb() -> u64 {
+    200
+}
+
+fn default_prefetch() -> usize {
+    3
+}
+
+/// 网络小说的离线缓存:`~/.novel/chapters/<书源>/` 下按章节链接存正文,
+/// 另存每本书的详情与目录快照,没网时也能打开读过的书。
+///
+/// 只有章节正文计入容量并参与淘汰;详情 / 目录快照很小,随书保留。
+/// 下载管理器下载的章节另存在 `<书源>/downloads/` 下,不计容量、不被淘汰,只随任务删除。
+#[derive(Debug, Clone)]
+pub struct ChapterCache {
+    root: PathBuf,
+    config: ChapterCacheConfig,
+    usage: Arc<Mutex<Usage>>,
+}
+
+/// 章节正文占用的字节数,写入时累加,不必每写一章都遍历整个缓存目录。
+#[derive(Debug, Default)]
+struct Usage {
+    /// 还没统计过时为 `None`,第一次写入时遍历一次。
+    total: Option<u64>,
+    /// 上次统计以来写入的章数。
+    writes: usize,
+}
+
+impl ChapterCache {
+    pub fn open() -> Result<Self> {
+        Ok(Self::new(
+            data_path("chapters")?,
+            ChapterCacheConfig::load()?,
+        ))
+    }
+
+    pub fn new(root: PathBuf, config: ChapterCacheConfig) -> Self {
+        Self {
+            root,
+            config,
+            usage: Arc::default(),
+        }
+    }
+
+    pub fn config(&self) -> &ChapterCacheConfig {
+        &self.config
+    }
+
+    fn source_dir(&self, source_url: &str) -> PathBuf {
+        self.root.join(get_md5_string(source_url))
+    }
+
+    fn chapter_path(&self, source_url: &str, chapter_url: &str) -> PathBuf {
+        self.source_dir(source_url)
+            .join(get_md5_string(chapter_url))
+            .with_extension("txt")
+    }
+
+    fn download_path(&self, source_url: &str, chapter_url: &str) -> PathBuf {
+        self.source_dir(source_url)
+            .join("downloads")
+            .join(get_md5_string(chapter_url))
+            .with_extension("txt")
+    }
+
+    fn book_path(&self, source_url: &str, book_url: &str, kind: &str) -> PathBuf {
+        self.source_dir(source_url)
+            .join(format!("{}.{kind}.json", get_md5_string(book_url)))
+    }
+
+    /// 读取缓存的章节正文(已下载的优先);LRU 策略下命中会刷新该章的时间。
+    pub fn chapter(&self, source_url: &str, chapter_url: &str) -> Option<String> {
+        if let Ok(text) = fs::read_to_string(self.download_path(source_url, chapter_url)) {
+            return Some(text);
+        }
+        let path = self.chapter_path(source_url, chapter_url);
+        let text = fs::read_to_string(&path).ok()?;
+        if self.config.eviction == EvictionPolicy::Lru
+            && let Ok(file) = File::options().append(true).open(&path)
+        {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(text)
+    }
+
+    pub fn contains(&self, source_url: &str, chapter_url: &str) -> bool {
+        self.download_path(source_url, chapter_url).exists()
+            || self.chapter_path(source_url, chapter_url).exists()
+    }
+
+    /// 保存下载的章节正文;不受容量上限约束。
+    pub fn put_download(&self, source_url: &str, chapter_url: &str, text: &str) -> Result<()> {
+        write_file(
+            &self.download_path(source_url, chapter_url),
+            text.as_bytes(),
+        )
+    }
+
+    /// 删除下载的章节正文(删除下载任务时调用);文件不存在不算错。
+    pub fn remove_download(&self, source_url: &str, chapter_url: &str) -> Result<()> {
+        match fs::remove_file(self.download_path(source_url, chapter_url)) {
+            Err(error) if error.kind() != ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// 写入章节正文,超出容量后按淘汰策略清理。
+    ///
+    /// 容量按写入累加,只有超出上限或每 [`RESYNC_EVERY`] 章才遍历一次缓存目录。
+    pub fn put_chapter(&self, source_url: &str, chapter_url: &str, text: &str) -> Result<()> {
+        if self.config.max_size_mb == 0 {
+            return Ok(());
+        }
+        let path = self.chapter_path(source_url, chapter_url);
+        let mut usage = self.usage.lock().unwrap();
+        let replaced = fs::metadata(&path).map_or(0, |meta| meta.len());
+        write_file(&path, text.as_bytes())?;
+        usage.writes += 1;
+        let total = match usage.total {
+            Some(total) if usage.writes < RESYNC_EVERY => {
+                total.saturating_sub(replaced) + text.len() as u64
+            }
+            _ => {
+                usage.writes = 0;
+                self.size()
+            }
+        };
+        usage.total = Some(total);
+        if total > self.config.max_size_bytes() {
+            usage.total = Some(self.evict_files()?.1);
+        }
+        Ok(())
+    }
+
+    /// 读取书籍快照(`kind` 区分详情 / 目录等)。
+    pub fn book<T: DeserializeOwned>(
+        &self,
+        source_url: &str,
+        book_url: &str,
+        kind: &str,
+    ) -> Option<T> {
+        let file = File::open(self.book_path(source_url, book_url, kind)).ok()?;
+        serde_json::from_reader(file).ok()
+    }
+
+    pub fn put_book<T: Serialize>(
+        &self,
+        source_url: &str,
+        book_url: &str,
+        kind: &str,
+        value: &T,
+    ) -> Result<()> {
+        write_file(
+            &self.book_path(source_url, book_url, kind),
+            &serde_json::to_vec(value)?,
+        )
+    }
+
+    /// 一本书在缓存里的全部文件:详情 / 目录快照,以及目录快照里各章的缓存与下载正文。
+    pub fn book_files(&self, source_url: &str, book_url: &str) -> Vec<PathBuf> {
+        let toc: Option<(Vec<parse_book_source::Chapter>, serde_json::Value)> =
+            self.book(source_url, book_url, "toc");
+        let chapters = toc.map(|(chapters, _)| chapters).unwrap_or_default();
+        ["info", "toc"]
+            .iter()
+            .map(|kind| self.book_path(source_url, book_url, kind))
+            .chain(chapters.iter().flat_map(|chapter| {
+                [
+                    self.chapter_path(source_url, &chapter.url),
+                    self.download_path(source_url, &chapter.url),
+                ]
+            }))
+            .filter(|path| path.exists())
+            .collect()
+    }
+
+    /// 已缓存的章节正文总字节数。
+    pub fn size(&self) -> u64 {
+        self.chapter_files().iter().map(|f| f.1).sum()
+    }
+
+    /// 章节正文超出容量上限时,按修改时间从旧到新删除,返回删掉的章节数。
+    ///
+    /// LRU 靠读取时刷新修改时间实现,FIFO 不刷新,所以两者都按修改时间排序即可。
+    pub fn evict(&self) -> Result<usize> {
+        let (removed, total) = self.evict_files()?;
+        self.usage.lock().unwrap().total = Some(total);
+        Ok(removed)
+    }
+
+    /// 返回 `(删掉的章节数, 剩下的总字节数)`。
+    fn evict_files(&self) -> Result<(usize, u64)> {
+        let mut files = self.chapter_files();
+        let mut total: u64 = files.iter().map(|f| f.1).sum();
+        let limit = self.config.max_size_bytes();
+        if total <= limit {
+            return Ok((0, total));
+        }
+        files.sort_by_key(|f| f.2);
+        let mut removed = 0;
+        for (path, size, _) in files {
+            if total <= limit {
+                break;
+            }
+            fs::remove_file(path)?;
+            total -= size;
+            removed += 1;
+        }
+        Ok((removed, total))
+    }
+
+    fn chapter_files(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        walkdir::WalkDir::new(&self.root)
+            .max_depth(2)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "txt"))
+            .filter_map(|entry| {
+                let meta = entry.metadata().ok()?;
+                Some((entry.into_path(), meta.len(), meta.modified().ok()?))
+            })
+            .collect()
+    }
+}
+
+/// 先写临时文件再改名,预取被取消或进程退出时不会留下半截正文。
+fn write_file(path: &Path, bytes: &[u8]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    store::write_atomic(path, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn cache(name: &str, eviction: EvictionPolicy) -> ChapterCache {
+        let root = std::env::temp_dir().join(format!("trnovel-chapter-cache-{name}"));
+        let _ = fs::remove_dir_all(&root);
+        ChapterCache::new(
+            root,
+            ChapterCacheConfig {
+                max_size_mb: 1,
+                eviction,
+                prefetch: 0,
+            },
+        )
+    }
+
+    fn age(cache: &ChapterCache, url: &str, secs: u64) {
+        let file = File::options()
+            .append(true)
+            .open(cache.chapter_path("src", url))
+            .unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    fn half_mb() -> String {
+        "字".repeat(512 * 1024 / 3)
+    }
+
+    #[test]
+    fn stores_chapters_and_book_snapshots_per_source() {
+        let cache = cache("store", EvictionPolicy::Lru);
+        cache.put_chapter("src", "/1.html", "第一章").unwrap();
+        assert_eq!(cache.chapter("src", "/1.html").as_deref(), Some("第一章"));
+        assert_eq!(cache.chapter("other", "/1.html"), None);
+
+        cache
+            .put_book("src", "/book", "toc", &vec!["第一章".to_string()])
+            .unwrap();
+        let toc: Option<Vec<String>> = cache.book("src", "/book", "toc");
+        assert_eq!(toc, Some(vec!["第一章".to_string()]));
+        // 快照不计入正文容量。
+        assert_eq!(cache.size(), "第一章".len() as u64);
+    }
+
+    #[test]
+    fn downloads_are_never_evicted() {
+        let cache = cache("download", EvictionPolicy::Lru);
+        cache.put_download("src", "/1", &half_mb()).unwrap();
+        cache.put_download("src", "/2", &half_mb()).unwrap();
+        cache.put_chapter("src", "/3", &half_mb()).unwrap();
+        assert!(cache.contains("src", "/1") && cache.contains("src", "/2"));
+        assert_eq!(cache.size(), half_mb().len() as u64);
+
+        cache.remove_download("src", "/1").unwrap();
+        assert_eq!(cache.chapter("src", "/1"), None);
+        cache.remove_download("src", "/1").unwrap();
+    }
+
+    #[test]
+    fn tracks_size_without_walking_on_every_write() {
+        let cache = cache("usage", EvictionPolicy::Lru);
+        cache.put_chapter("src", "/1", &half_mb()).unwrap();
+        assert_eq!(cache.usage.lock().unwrap().total, Some(cache.size()));
+
+        // 别的实例写入的章节要等下次统计才算进来,累加值此时只含自己写的。
+        let other = ChapterCache::new(cache.root.clone(), *cache.config());
+        other.put_chapter("src", "/2", "第二章").unwrap();
+        cache.put_chapter("src", "/1", "第一章").unwrap();
+        assert_eq!(
+            cache.usage.lock().unwrap().total,
+            Some("第一章".len() as u64)
+        );
+
+        cache.put_chapter("src", "/3", &half_mb()).unwrap();
+        cache.put_chapter("src", "/4", &half_mb()).unwrap();
+        assert_eq!(cache.usage.lock().unwrap().total, Some(cache.size()));
+        assert!(cache.size() <= cache.config().max_size_bytes());
+        assert!(cache.contains("src", "/4"));
+    }
+
+    #[test]
+    fn lru_keeps_recently_read_chapters() {
+        let cache = cache("lru", EvictionPolicy::Lru);
+        cache.put_chapter("src", "/1", &half_mb()).unwrap();
+        age(&cache, "/1", 20);
+        cache.put_chapter("src", "/2", &half_mb()).unwrap();
+        age(&cache, "/2", 10);
+        assert!(cache.chapter("src", "/1").is_some());
+
+        cache.put_chapter("src", "/3", &half_mb()).unwrap();
+        assert!(cache.contains("src", "/1"));
+        assert!(!cache.contains("src", "/2"));
+        assert!(cache.contains("src", "/3"));
+    }
+
+    #[test]
+    fn fifo_drops_oldest_written_chapters() {
+        let cache = cache("fifo", EvictionPolicy::Fifo);
+        cache.put_chapter("src", "/1", &half_mb()).unwrap();
+        age(&cache, "/1", 20);
+        cache.put_chapter("src", "/2", &half_mb()).unwrap();
+        age(&cache, "/2", 10);
+        assert!(cache.chapter("src", "/1").is_some());
+
+        cache.put_chapter("src", "/3", &half_mb()).unwrap();
+        assert!(!cache.contains("src", "/1"));
+        assert!(cache.contains("src", "/2"));
+        assert!(cache.contains("src", "/3"));
+    }
+}
@@ -0,0 +1,299 @@
+use crate::{Result, cache::store, data_dir::data_path};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::PathBuf,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DownloadStatus {
+    /// 排队中(包括上次退出时没下完、下次启动自动续传的任务)。
+    Queued,
+    Running,
+    Paused,
+    Done,
+    /// 全部章节都试过,但有章节重试后仍失败。
+    Failed,
+}
+
+impl DownloadStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DownloadStatus::Queued => "排队中",
+            DownloadStatus::Running => "下载中",
+            DownloadStatus::Paused => "已暂停",
+            DownloadStatus::Done => "已完成",
+            DownloadStatus::Failed => "部分失败",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadChapter {
+    /// 在目录里的下标。
+    pub index: usize,
+    pub title: String,
+    pub url: String,
+    #[serde(default)]
+    pub done: bool,
+    #[serde(default)]
+    pub failed: bool,
+}
+
+/// 一本书的下载任务;同一本书只有一个任务,追加的章节范围合并进来。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadTask {
+    pub book_url: String,
+    pub title: String,
+    pub source_url: String,
+    pub source_name: String,
+    /// 书籍级捕获变量,续传时注回引擎(同 [`crate::NetworkNovelCache::book_vars`])。
+    #[serde(default)]
+    pub book_vars: BTreeMap<String, String>,
+    /// 按目录顺序排列的待下载章节。
+    pub chapters: Vec<DownloadChapter>,
+    pub status: DownloadStatus,
+    pub created_at: DateTime<Local>,
+    /// 任务级错误(书源被删、引擎构建失败等),章节级失败记在章节上。
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl DownloadTask {
+    pub fn done_count(&self) -> usize {
+        self.chapters.iter().filter(|c| c.done).count()
+    }
+
+    pub fn failed_count(&self) -> usize {
+        self.chapters.iter().filter(|c| c.failed).count()
+    }
+
+    /// 还需要去下载的章节(未完成且本轮没失败过)。
+    pub fn pending(&self) -> impl Iterator<Item = &DownloadChapter> {
+        self.chapters.iter().filter(|c| !c.done && !c.failed)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.chapters.iter().all(|c| c.done)
+    }
+
+    /// 下载完成后按结果收尾:全部成功为完成,否则为部分失败。
+    pub fn finish(&mut self) {
+        self.status = if self.is_complete() {
+            DownloadStatus::Done
+        } else {
+            DownloadStatus::Failed
+        };
+    }
+}
+
+/// 下载队列,保存在 `~/.novel/downloads.json`,重启后未完成的任务自动续传。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Downloads {
+    pub tasks: Vec<DownloadTask>,
+}
+
+impl Downloads {
+    pub fn path() -> Result<PathBuf> {
+        data_path("downloads.json")
+    }
+
+    pub fn load() -> Result<Self> {
+        Ok(store::load_json(&Self::path()?)?.unwrap_or_default())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let bytes = serde_json::to_vec(self)?;
+        let path = Self::path()?;
+        let _lock = store::lock(&path)?;
+        store::write_atomic(&path, &bytes)
+    }
+
+    pub fn get(&self, book_url: &str) -> Option<&DownloadTask> {
+        self.tasks.iter().find(|t| t.book_url == book_url)
+    }
+
+    pub fn get_mut(&mut self, book_url: &str) -> Option<&mut DownloadTask> {
+        self.tasks.iter_mut().find(|t| t.book_url == book_url)
+    }
+
+    /// 加入队列。同一本书已有任务时合并章节:已下载的保留,失败的重新排队,
+    /// 已暂停的任务保持暂停。
+    pub fn enqueue(&mut self, task: DownloadTask) {
+        let Some(existing) = self.get_mut(&task.book_url) else {
+            self.tasks.push(task);
+            return;
+        };
+        let known: HashSet<usize> = existing.chapters.iter().map(|c| c.index).collect();
+        existing.chapters.extend(
+            task.chapters
+                .into_iter()
+                .filter(|c| !known.contains(&c.index)),
+        );
+        existing.chapters.sort_by_key(|c| c.index);
+        for chapter in &mut existing.chapters {
+            chapter.failed = false;
+        }
+        existing.book_vars = task.book_vars;
+        existing.error = None;
+        if existing.status != DownloadStatus::Paused {
+            existing.status = DownloadStatus::Queued;
+        }
+    }
+
+    /// 失败的章节重新排队。
+    pub fn retry(&mut self, book_url: &str) {
+        if let Some(task) = self.get_mut(book_url) {
+            for chapter in &mut task.chapters {
+                chapter.failed = false;
+            }
+            task.error = None;
+            task.status = DownloadStatus::Queued;
+        }
+    }
+
+    /// 下载任务跑完一批后取下一批:下载期间追加或重试进来的章节接着下;没有待下章节时
+    /// 按结果收尾并返回 `None`,任务已被删除时同样返回 `None`。
+    pub fn next_batch(&mut self, book_url: &str) -> Option<Vec<DownloadChapter>> {
+        let task = self.get_mut(book_url)?;
+        let pending: Vec<DownloadChapter> = task.pending().cloned().collect();
+        if pending.is_empty() {
+            task.finish();
+            return None;
+        }
+        Some(pending)
+    }
+
+    /// 任务是否还有章节等着下载(未暂停、未收尾)。
+    pub fn has_work(&self, book_url: &str) -> bool {
+        self.get(book_url).is_some_and(|task| {
+            matches!(
+                task.status,
+                DownloadStatus::Queued | DownloadStatus::Running
+            ) && task.pending().next().is_some()
+        })
+    }
+
+    pub fn remove(&mut self, book_url: &str) -> Option<DownloadTask> {
+        let index = self.tasks.iter().position(|t| t.book_url == book_url)?;
+        Some(self.tasks.remove(index))
+    }
+
+    /// 已下载完成的章节下标,阅读页目录据此打勾。
+    pub fn downloaded(&self, book_url: &str) -> HashSet<usize> {
+        self.get(book_url)
+            .map(|task| {
+                task.chapters
+                    .iter()
+                    .filter(|c| c.done)
+                    .map(|c| c.index)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// 需要(重新)启动下载的任务;上次退出时正在下载的也算。
+    pub fn resumable(&self) -> Vec<String> {
+        self.tasks
+            .iter()
+            .filter(|t| matches!(t.status, DownloadStatus::Queued | DownloadStatus::Running))
+            .map(|t| t.book_url.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(indices: &[usize]) -> DownloadTask {
+        DownloadTask {
+            book_url: "/book".to_string(),
+            title: "测试".to_string(),
+            source_url: "src".to_string(),
+            source_name: "测试源".to_string(),
+            book_vars: BTreeMap::new(),
+            chapters: indices
+                .iter()
+                .map(|&index| DownloadChapter {
+                    index,
+                    title: format!("第{index}章"),
+                    url: format!("/{index}"),
+                    done: false,
+                    failed: false,
+                })
+                .collect(),
+            status: DownloadStatus::Queued,
+            created_at: Local::now(),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn enqueue_merges_ranges_and_keeps_progress() {
+        let mut downloads = Downloads::default();
+        downloads.enqueue(task(&[0, 1, 2]));
+        {
+            let task = downloads.get_mut("/book").unwrap();
+            task.chapters[0].done = true;
+            task.chapters[1].failed = true;
+            task.finish();
+            assert_eq!(task.status, DownloadStatus::Failed);
+        }
+
+        downloads.enqueue(task(&[2, 3, 4]));
+        let task = downloads.get("/book").unwrap();
+        assert_eq!(downloads.tasks.len(), 1);
+        assert_eq!(
+            task.chapters.iter().map(|c| c.index).collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4]
+        );
+        assert_eq!(task.status, DownloadStatus::Queued);
+        assert_eq!(task.pending().count(), 4);
+        assert_eq!(downloads.downloaded("/book"), HashSet::from([0]));
+        assert_eq!(downloads.resumable(), ["/book"]);
+    }
+
+    #[test]
+    fn retry_requeues_failed_chapters() {
+        let mut downloads = Downloads::default();
+        downloads.enqueue(task(&[0, 1]));
+        let task = downloads.get_mut("/book").unwrap();
+        task.chapters[0].done = true;
+        task.chapters[1].failed = true;
+        task.finish();
+
+        downloads.retry("/book");
+        let task = downloads.get("/book").unwrap();
+        assert_eq!(task.status, DownloadStatus::Queued);
+        assert_eq!(task.pending().map(|c| c.index).collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    fn range_enqueued_while_running_is_picked_up_before_finishing() {
+        let mut downloads = Downloads::default();
+        downloads.enqueue(task(&[0, 1]));
+        downloads.get_mut("/book").unwrap().status = DownloadStatus::Running;
+        // 第一批下到一半,又追加了一个范围。
+        downloads.get_mut("/book").unwrap().chapters[0].done = true;
+        downloads.enqueue(task(&[2, 3]));
+        assert!(downloads.has_work("/book"));
+        downloads.get_mut("/book").unwrap().chapters[1].done = true;
+
+        let next = downloads.next_batch("/book").unwrap();
+        assert_eq!(next.iter().map(|c| c.index).collect::<Vec<_>>(), [2, 3]);
+        assert_ne!(downloads.get("/book").unwrap().status, DownloadStatus::Done);
+
+        for chapter in &mut downloads.get_mut("/book").unwrap().chapters {
+            chapter.done = true;
+        }
+        assert_eq!(downloads.next_batch("/book"), None);
+        assert_eq!(downloads.get("/book").unwrap().status, DownloadStatus::Done);
+        assert!(!downloads.has_work("/book"));
+    }
+}
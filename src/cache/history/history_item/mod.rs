@@ -1,6 +1,7 @@
 pub mod local_history_item;
 pub use local_history_item::LocalHistoryItem;
 pub mod network_history_item;
+use chrono::{DateTime, Local};
 pub use network_history_item::NetworkHistoryItem;
 use serde::{Deserialize, Serialize};
 
@@ -14,6 +15,15 @@ pub enum HistoryItem {
     Network(NetworkHistoryItem),
 }
 
+impl HistoryItem {
+    pub fn last_read_at(&self) -> DateTime<Local> {
+        match self {
+            HistoryItem::Local(item) => item.last_read_at,
+            HistoryItem::Network(item) => item.last_read_at,
+        }
+    }
+}
+
 impl From<LocalNovelCache> for HistoryItem {
     fn from(item: LocalNovelCache) -> Self {
         HistoryItem::Local(item.into())
@@ -1,7 +1,7 @@
-use crate::utils::novel_catch_dir;
+use crate::{cache::store, data_dir::data_path};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::{fs::File, path::PathBuf};
+use std::{collections::HashSet, path::PathBuf};
 pub mod history_item;
 pub use history_item::*;
 
@@ -10,35 +10,82 @@ pub use history_item::*;
 /// ID:
 /// - 本地为小说路径
 /// - 网络为book链接
+///
+/// 保存时先读盘上的版本再合并(同一本书取最近读过的一条),多个 trn 同时运行也不会互相覆盖。
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct History {
     pub local_path: Option<PathBuf>,
     pub histories: Vec<(String, HistoryItem)>,
+    /// 加载时盘上已有的 ID:之后从盘上消失的,是被其他进程删掉了。
+    #[serde(skip)]
+    loaded: HashSet<String>,
+    /// 本进程删掉的 ID,合并时不再从盘上带回来。
+    #[serde(skip)]
+    removed: HashSet<String>,
+}
+
+/// 盘上的历史记录。`History` 在 drop 时会落盘,读写盘一律走这个结构。
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HistoryFile {
+    local_path: Option<PathBuf>,
+    histories: Vec<(String, HistoryItem)>,
 }
 
 impl History {
-    const MAX_LEN: usize = 100;
+    /// 最多保留的历史记录条数。
+    pub const MAX_LEN: usize = 100;
     pub fn get_cache_file_path() -> Result<PathBuf> {
-        Ok(PathBuf::new().join(novel_catch_dir()?).join("history.json"))
+        Ok(data_path("history.json")?)
     }
 
     pub fn load() -> Result<Self> {
-        match File::open(Self::get_cache_file_path()?) {
-            Ok(file) => Ok(serde_json::from_reader(file)?),
-            Err(_) => Ok(Self {
-                histories: vec![],
-                local_path: None,
-            }),
-        }
+        let file: HistoryFile =
+            store::load_json(&Self::get_cache_file_path()?)?.unwrap_or_default();
+        Ok(Self {
+            loaded: file.histories.iter().map(|(id, _)| id.clone()).collect(),
+            local_path: file.local_path,
+            histories: file.histories,
+            removed: HashSet::new(),
+        })
     }
 
+    /// 加锁读出盘上的版本,与内存中的合并后写回。
     pub fn save(&self) -> Result<()> {
-        let file = File::create(Self::get_cache_file_path()?)?;
-        serde_json::to_writer_pretty(file, self)?;
+        let path = Self::get_cache_file_path()?;
+        let _lock = store::lock(&path)?;
+        let on_disk: Option<HistoryFile> = store::load_json(&path)?;
+        let file = HistoryFile {
+            local_path: self
+                .local_path
+                .clone()
+                .or_else(|| on_disk.as_ref().and_then(|file| file.local_path.clone())),
+            histories: merge(
+                &self.histories,
+                &self.loaded,
+                &self.removed,
+                on_disk.map(|file| file.histories),
+            ),
+        };
+        store::write_atomic(&path, &serde_json::to_vec_pretty(&file)?)?;
         Ok(())
     }
 
+    /// 整体替换记录;不在新列表里的视为删除。
+    pub fn set_histories(&mut self, histories: Vec<(String, HistoryItem)>) {
+        let kept: HashSet<&str> = histories.iter().map(|(id, _)| id.as_str()).collect();
+        let removed: Vec<String> = self
+            .histories
+            .iter()
+            .map(|(id, _)| id.clone())
+            .chain(self.loaded.iter().cloned())
+            .filter(|id| !kept.contains(id.as_str()))
+            .collect();
+        self.removed.extend(removed);
+        self.histories = histories;
+    }
+
     pub fn add(&mut self, path: &str, history_item: HistoryItem) {
+        self.removed.remove(path);
         match self.histories.iter().position(|item| item.0 == path) {
             Some(index) => {
                 self.histories.remove(index);
@@ -55,12 +102,13 @@ impl History {
 
     pub fn remove(&mut self, path: &str) {
         if let Some(index) = self.histories.iter().position(|item| item.0 == path) {
-            self.histories.remove(index);
+            self.remove_index(index);
         }
     }
 
     pub fn remove_index(&mut self, index: usize) {
-        self.histories.remove(index);
+        let (id, _) = self.histories.remove(index);
+        self.removed.insert(id);
     }
 }
 
@@ -69,3 +117,85 @@ impl Drop for History {
         let _ = self.save();
     }
 }
+
+/// 合并盘上的记录:同一本书取最近读过的一条;本进程删掉的(`removed`)、或加载时还在
+/// (`loaded`)而盘上已没有的(被其他进程删掉)不再保留。
+///
+/// 盘上的文件读不出来(不存在或损坏已留底)时为 `None`:这时无从判断别的进程删过什么,
+/// 内存里的记录全部保留。
+fn merge(
+    histories: &[(String, HistoryItem)],
+    loaded: &HashSet<String>,
+    removed: &HashSet<String>,
+    on_disk: Option<Vec<(String, HistoryItem)>>,
+) -> Vec<(String, HistoryItem)> {
+    let on_disk = on_disk.map(|entries| {
+        let ids: HashSet<String> = entries.iter().map(|(id, _)| id.clone()).collect();
+        (ids, entries)
+    });
+    let mut merged: Vec<(String, HistoryItem)> = histories
+        .iter()
+        .filter(|(id, _)| match &on_disk {
+            Some((ids, _)) => !loaded.contains(id) || ids.contains(id),
+            None => true,
+        })
+        .cloned()
+        .collect();
+    for (id, item) in on_disk.map(|(_, entries)| entries).unwrap_or_default() {
+        if removed.contains(&id) {
+            continue;
+        }
+        match merged.iter_mut().find(|(mine, _)| *mine == id) {
+            Some(mine) if item.last_read_at() > mine.1.last_read_at() => mine.1 = item,
+            Some(_) => {}
+            None => merged.push((id, item)),
+        }
+    }
+    merged.sort_by_key(|(_, item)| std::cmp::Reverse(item.last_read_at()));
+    merged.truncate(History::MAX_LEN);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Local};
+
+    fn item(minutes_ago: i64) -> HistoryItem {
+        HistoryItem::Network(NetworkHistoryItem {
+            current_chapter: "第一章".into(),
+            last_read_at: Local::now() - Duration::minutes(minutes_ago),
+            percent: 0.0,
+            title: "书".into(),
+            book_source: "源".into(),
+        })
+    }
+
+    fn ids(entries: &[(String, HistoryItem)]) -> Vec<&str> {
+        entries.iter().map(|(id, _)| id.as_str()).collect()
+    }
+
+    #[test]
+    fn save_merges_entries_written_by_another_process() {
+        // 本进程加载了 a、b、c,删掉了 b、读了 d。
+        let histories = vec![("d".into(), item(0)), ("a".into(), item(10))];
+        let loaded = ["a".to_string(), "b".to_string(), "c".to_string()].into();
+        let removed = ["b".to_string()].into();
+        // 盘上:另一个进程读了 a、新增了 e、删掉了 c;b 还在。
+        let on_disk = vec![
+            ("a".into(), item(1)),
+            ("b".into(), item(10)),
+            ("e".into(), item(5)),
+        ];
+        let merged = merge(&histories, &loaded, &removed, Some(on_disk));
+        assert_eq!(ids(&merged), ["d", "a", "e"]);
+    }
+
+    #[test]
+    fn unreadable_file_keeps_loaded_entries() {
+        let histories = vec![("a".into(), item(10)), ("b".into(), item(5))];
+        let loaded = ["a".to_string(), "b".to_string()].into();
+        let merged = merge(&histories, &loaded, &HashSet::new(), None);
+        assert_eq!(ids(&merged), ["b", "a"]);
+    }
+}
@@ -1,14 +1,12 @@
+use crate::cache::store;
 use crate::errors::{Errors, Result};
 use crate::novel::Novel;
 use crate::novel::VolumeMarker;
 use crate::novel::local_novel::LocalNovel;
-use crate::utils::{get_path_md5, novel_catch_dir};
+use crate::{data_dir::data_path, utils::get_path_md5};
 
 use serde::{Deserialize, Serialize};
-use std::{
-    fs::File,
-    path::{Path, PathBuf},
-};
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LocalNovelCache {
@@ -25,19 +23,11 @@ pub struct LocalNovelCache {
 impl LocalNovelCache {
     pub fn save(&self) -> Result<()> {
         let cache_path = Self::cache_path(&self.path)?;
-        let file = File::create(cache_path)?;
-        serde_json::to_writer_pretty(file, self)?;
-        Ok(())
+        store::save_json(&cache_path, self)
     }
 
     pub fn cache_path<T: AsRef<Path>>(path: T) -> Result<PathBuf> {
-        let novel_catch_dir = PathBuf::new().join(novel_catch_dir()?).join("local");
-
-        if !novel_catch_dir.exists() {
-            std::fs::create_dir_all(&novel_catch_dir)?;
-        }
-
-        Ok(novel_catch_dir
+        Ok(data_path("local")?
             .join(get_path_md5(path)?)
             .with_extension("json"))
     }
@@ -64,8 +54,8 @@ impl TryFrom<&Path> for LocalNovelCache {
     type Error = Errors;
     fn try_from(value: &Path) -> Result<Self> {
         let cache_path = Self::cache_path(value)?;
-        let file = File::open(cache_path)?;
-        Ok(serde_json::from_reader(file)?)
+        store::load_json(&cache_path)?
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound).into())
     }
 }
 
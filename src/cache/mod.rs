@@ -7,7 +7,20 @@ pub mod source_state;
 pub use source_state::{load_source_state, save_source_state, source_state_path};
 pub mod history;
 pub use history::*;
+pub mod bookshelf;
+pub use bookshelf::*;
+pub mod chapter_cache;
+pub use chapter_cache::*;
+pub mod downloads;
+pub use downloads::*;
 pub mod setting;
 pub use setting::*;
 pub mod tts;
 pub use tts::*;
+pub mod store;
+pub mod sync_config;
+pub use sync_config::SyncConfig;
+pub mod proxy_config;
+pub use proxy_config::{ProxyConfig, apply_global_proxy, http_client};
+pub mod source_health;
+pub use source_health::SourceHealth;
@@ -1,12 +1,14 @@
 use crate::{
     Result,
+    cache::store,
+    data_dir::data_path,
     errors::Errors,
     novel::{Novel, network_novel::NetworkNovel},
-    utils::{get_md5_string, novel_catch_dir},
+    utils::get_md5_string,
 };
 use parse_book_source::BookListItem;
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeMap, fmt::Display, fs::File, path::PathBuf};
+use std::{collections::BTreeMap, fmt::Display, path::PathBuf};
 
 /// 历史记录（一个用于展示，一个用于缓存，方便下次快速访问）
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -28,19 +30,11 @@ pub struct NetworkNovelCache {
 impl NetworkNovelCache {
     pub fn save(&self) -> Result<()> {
         let cache_path = Self::cache_path(&self.book_list_item.book_url)?;
-        let file = File::create(cache_path)?;
-        serde_json::to_writer_pretty(file, self)?;
-        Ok(())
+        store::save_json(&cache_path, self)
     }
 
     pub fn cache_path<T: Display>(url: T) -> Result<PathBuf> {
-        let novel_catch_dir = PathBuf::new().join(novel_catch_dir()?).join("network");
-
-        if !novel_catch_dir.exists() {
-            std::fs::create_dir_all(&novel_catch_dir)?;
-        }
-
-        Ok(novel_catch_dir
+        Ok(data_path("network")?
             .join(get_md5_string(url))
             .with_extension("json"))
     }
@@ -93,7 +87,7 @@ impl TryFrom<&str> for NetworkNovelCache {
     type Error = Errors;
     fn try_from(value: &str) -> Result<Self> {
         let cache_path = Self::cache_path(value)?;
-        let file = File::open(cache_path)?;
-        Ok(serde_json::from_reader(file)?)
+        store::load_json(&cache_path)?
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound).into())
     }
 }
@@ -0,0 +1,89 @@
+use crate::{Result, cache::store, data_dir::data_path};
+use parse_book_source::fetch::proxy::reqwest_proxy;
+use parse_book_source::source::Proxy;
+use parse_book_source::{BookSource, BookSourceError};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// 全局默认代理,保存在 `~/.novel/proxy.json`(可能含代理密码,unix 下以 0600 落盘)。
+/// 书源自己的 `http.proxy` 优先,没配的书源套用这里的代理。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ProxyConfig(pub Proxy);
+
+impl ProxyConfig {
+    pub fn path() -> Result<PathBuf> {
+        data_path("proxy.json")
+    }
+
+    /// 读取配置;没配置过返回 `None`。
+    pub fn load() -> Result<Option<Self>> {
+        store::load_json(&Self::path()?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        store::save_json_private(&Self::path()?, self)
+    }
+
+    /// 删除配置,之后所有书源直连(书源自己配了代理的除外)。
+    pub fn clear() -> Result<()> {
+        match std::fs::remove_file(Self::path()?) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// 书源没配代理时填入本代理。
+    pub fn apply(&self, source: &mut BookSource) {
+        if source.http.proxy.is_none() {
+            source.http.proxy = Some(self.0.clone());
+        }
+    }
+}
+
+/// 不经书源引擎的直接下载(导出封面、生成字体映射)用的客户端:优先用书源的代理,
+/// 没有时用全局默认代理,都没配时直连。
+pub fn http_client(source_proxy: Option<&Proxy>) -> Result<reqwest::Client> {
+    let global = ProxyConfig::load().ok().flatten();
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = source_proxy.or(global.as_ref().map(|config| &config.0)) {
+        builder = builder.proxy(reqwest_proxy(proxy).map_err(BookSourceError::from)?);
+    }
+    Ok(builder.build()?)
+}
+
+/// 给书源填入全局默认代理;没配置或读取失败时保持直连。
+pub fn apply_global_proxy(source: &mut BookSource) {
+    if let Ok(Some(config)) = ProxyConfig::load() {
+        config.apply(source);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(proxy: Option<&str>) -> BookSource {
+        let mut source = crate::testutil::source("t");
+        source.http.proxy = proxy.map(|url| Proxy {
+            url: url.to_string(),
+            ..Default::default()
+        });
+        source
+    }
+
+    #[test]
+    fn source_proxy_wins_over_global() {
+        let global = ProxyConfig(Proxy {
+            url: "socks5://127.0.0.1:1080".into(),
+            ..Default::default()
+        });
+        let mut direct = source(None);
+        global.apply(&mut direct);
+        assert_eq!(direct.http.proxy.as_ref(), Some(&global.0));
+
+        let mut own = source(Some("http://10.0.0.1:8080"));
+        global.apply(&mut own);
+        assert_eq!(own.http.proxy.unwrap().url, "http://10.0.0.1:8080");
+    }
+}
@@ -1,9 +1,10 @@
 use crate::Result;
-use crate::utils::novel_catch_dir;
+use crate::cache::store;
+use crate::data_dir::data_path;
 use ratatui_kit::Palette;
 use ratatui_kit_themes::{IntoKitPalette, ThemeName, terminal_background};
 use serde::{Deserialize, Serialize};
-use std::{fs::File, io::ErrorKind, path::PathBuf};
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -23,21 +24,15 @@ impl AppearanceConfig {
     const DEFAULT_THEME: ThemeName = ThemeName::TokyoNight;
 
     pub fn path() -> Result<PathBuf> {
-        Ok(novel_catch_dir()?.join("appearance.json"))
+        data_path("appearance.json")
     }
 
     pub fn load() -> Result<Self> {
-        match File::open(Self::path()?) {
-            Ok(file) => Ok(serde_json::from_reader(file).unwrap_or_default()),
-            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Self::default()),
-            Err(error) => Err(error.into()),
-        }
+        Ok(store::load_json(&Self::path()?)?.unwrap_or_default())
     }
 
     pub fn save(&self) -> Result<()> {
-        let file = File::create(Self::path()?)?;
-        serde_json::to_writer_pretty(file, self)?;
-        Ok(())
+        store::save_json(&Self::path()?, self)
     }
 
     pub fn theme_name(&self) -> ThemeName {
@@ -75,21 +70,15 @@ pub struct ReaderDisplayConfig {
 
 impl ReaderDisplayConfig {
     pub fn path() -> Result<PathBuf> {
-        Ok(novel_catch_dir()?.join("reader-display.json"))
+        data_path("reader-display.json")
     }
 
     pub fn load() -> Result<Self> {
-        match File::open(Self::path()?) {
-            Ok(file) => Ok(serde_json::from_reader(file).unwrap_or_default()),
-            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Self::default()),
-            Err(error) => Err(error.into()),
-        }
+        Ok(store::load_json(&Self::path()?)?.unwrap_or_default())
     }
 
     pub fn save(&self) -> Result<()> {
-        let file = File::create(Self::path()?)?;
-        serde_json::to_writer_pretty(file, self)?;
-        Ok(())
+        store::save_json(&Self::path()?, self)
     }
 }
 
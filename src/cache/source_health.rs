@@ -0,0 +1,302 @@
+//! 书源健康记录,保存在 `~/.novel/source-health.json`。
+//!
+//! 每次联网操作(搜索、详情、目录、正文)结束后记一笔:成功计入耗时,失败归类为反爬拦截、
+//! 登录失效、解析为空或网络错误。连续失败达到 [`DEGRADE_AFTER`] 次的书源视为降级,
+//! 聚合搜索、换源等多书源操作直接跳过,直到它再次成功(单独打开该书源)或 `trn doctor`
+//! 体检通过为止。记录读写失败不影响操作本身。
+//!
+//! 记录先攒在内存里,每 [`FLUSH_EVERY`] 条在阻塞线程池里合并进文件,退出时由 [`flush`]
+//! 写完剩下的;阅读预取、聚合搜索不必每个请求都读写一遍文件。
+
+use crate::{Result, cache::store, data_dir::data_path};
+use chrono::{DateTime, Local};
+use parse_book_source::{BookSource, BookSourceError};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::PathBuf, sync::Mutex, time::Duration};
+
+/// 连续失败多少次后降级。
+pub const DEGRADE_AFTER: u32 = 3;
+
+/// 攒够这么多条记录写一次盘。
+const FLUSH_EVERY: usize = 16;
+
+/// 还没写盘的记录,按发生的先后排列。
+static PENDING: Mutex<Vec<(String, Event)>> = Mutex::new(Vec::new());
+
+/// 同一时间只有一个写盘任务,先攒的记录先写。
+static WRITER: Mutex<()> = Mutex::new(());
+
+/// 一次操作的结果。
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Event {
+    Success(Duration),
+    Failure(FailureKind),
+    /// 体检通过。
+    Diagnosed,
+}
+
+/// 失败类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FailureKind {
+    /// 被反爬挑战拦截。
+    Challenge,
+    /// 登录态失效。
+    LoginExpired,
+    /// 规则求值失败,或目录、正文解析出来是空的(多半是站点改版)。
+    ParseEmpty,
+    /// 网络错误、HTTP 错误或超时。
+    Network,
+}
+
+impl FailureKind {
+    /// 按引擎错误归类;配置缺失等与站点无关的错误返回 `None`,不计入健康记录。
+    pub fn of(error: &BookSourceError) -> Option<Self> {
+        match error {
+            e if e.is_challenge() => Some(FailureKind::Challenge),
+            BookSourceError::LoginExpired => Some(FailureKind::LoginExpired),
+            BookSourceError::Eval(_) => Some(FailureKind::ParseEmpty),
+            BookSourceError::Fetch(_) => Some(FailureKind::Network),
+            BookSourceError::Config(_) | BookSourceError::Missing(_) => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FailureKind::Challenge => "反爬拦截",
+            FailureKind::LoginExpired => "登录失效",
+            FailureKind::ParseEmpty => "解析为空",
+            FailureKind::Network => "网络错误",
+        }
+    }
+}
+
+/// 单个书源的健康记录。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SourceRecord {
+    pub successes: u32,
+    pub failures: u32,
+    /// 最近一次成功以来的失败次数。
+    pub consecutive_failures: u32,
+    /// 成功操作的耗时(毫秒),按滑动平均更新。
+    pub latency_ms: u64,
+    /// 最近一次失败的类型;成功后保留,便于看出上次出过什么问题。
+    pub last_error: Option<FailureKind>,
+    pub updated_at: Option<DateTime<Local>>,
+}
+
+impl SourceRecord {
+    fn succeed(&mut self, latency: Duration) {
+        let latency = latency.as_millis() as u64;
+        // 新样本占 1/4 权重,偶尔一次慢请求不至于让徽标剧烈跳动。
+        self.latency_ms = if self.successes == 0 {
+            latency
+        } else {
+            (self.latency_ms * 3 + latency) / 4
+        };
+        self.successes += 1;
+        self.consecutive_failures = 0;
+        self.updated_at = Some(Local::now());
+    }
+
+    fn fail(&mut self, kind: FailureKind) {
+        self.failures += 1;
+        self.consecutive_failures += 1;
+        self.last_error = Some(kind);
+        self.updated_at = Some(Local::now());
+    }
+
+    fn apply(&mut self, event: Event) {
+        match event {
+            Event::Success(latency) => self.succeed(latency),
+            Event::Failure(kind) => self.fail(kind),
+            Event::Diagnosed => {
+                self.consecutive_failures = 0;
+                self.updated_at = Some(Local::now());
+            }
+        }
+    }
+
+    /// 成功率(百分比);还没有记录时为 `None`。
+    pub fn success_rate(&self) -> Option<u32> {
+        let total = self.successes + self.failures;
+        (total > 0).then(|| self.successes * 100 / total)
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.consecutive_failures >= DEGRADE_AFTER
+    }
+}
+
+/// 全部书源的健康记录,键为书源网址。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SourceHealth(pub HashMap<String, SourceRecord>);
+
+impl SourceHealth {
+    pub fn path() -> Result<PathBuf> {
+        data_path("source-health.json")
+    }
+
+    /// 读取盘上的记录,并叠加还没写盘的记录。
+    pub fn load() -> Result<Self> {
+        let mut health: Self = store::load_json(&Self::path()?)?.unwrap_or_default();
+        health.apply(&PENDING.lock().unwrap());
+        Ok(health)
+    }
+
+    fn apply(&mut self, events: &[(String, Event)]) {
+        for (source_url, event) in events {
+            self.0.entry(source_url.clone()).or_default().apply(*event);
+        }
+    }
+
+    pub fn get(&self, source_url: &str) -> Option<&SourceRecord> {
+        self.0.get(source_url)
+    }
+
+    pub fn is_degraded(&self, source_url: &str) -> bool {
+        self.get(source_url).is_some_and(SourceRecord::is_degraded)
+    }
+
+    /// 给多书源操作挑书源:返回 `(参与的书源, 跳过的降级书源)`。参与的书源里最近失败过的
+    /// 排到最后,并发有限时先跑靠谱的书源。
+    pub fn triage(&self, sources: &[BookSource]) -> (Vec<BookSource>, Vec<BookSource>) {
+        let (skipped, mut active): (Vec<BookSource>, Vec<BookSource>) = sources
+            .iter()
+            .cloned()
+            .partition(|source| self.is_degraded(&source.url));
+        active.sort_by_key(|source| {
+            self.get(&source.url)
+                .map_or(0, |record| record.consecutive_failures)
+        });
+        (active, skipped)
+    }
+}
+
+/// 记一笔;攒够 [`FLUSH_EVERY`] 条时在阻塞线程池里写盘,不占用异步工作线程。
+fn push(source_url: &str, event: Event) {
+    let full = {
+        let mut pending = PENDING.lock().unwrap();
+        pending.push((source_url.to_string(), event));
+        pending.len() >= FLUSH_EVERY
+    };
+    // 不在 tokio 运行时里时留到下一次或退出时再写。
+    if full && let Ok(runtime) = tokio::runtime::Handle::try_current() {
+        runtime.spawn_blocking(write_pending);
+    }
+}
+
+/// 加锁读出盘上的记录,合并攒下的记录后写回;多个进程同时记录时不会互相覆盖。
+fn write_pending() -> Result<()> {
+    let _writer = WRITER.lock().unwrap();
+    let events = std::mem::take(&mut *PENDING.lock().unwrap());
+    if events.is_empty() {
+        return Ok(());
+    }
+    let path = SourceHealth::path()?;
+    let _lock = store::lock(&path)?;
+    let mut health: SourceHealth = store::load_json(&path)?.unwrap_or_default();
+    health.apply(&events);
+    store::write_atomic(&path, &serde_json::to_vec_pretty(&health)?)
+}
+
+/// 把还没写盘的记录写完,退出前调用。
+pub async fn flush() {
+    let _ = tokio::task::spawn_blocking(write_pending).await;
+}
+
+/// 记一次成功操作及其耗时。
+pub fn record_success(source_url: &str, latency: Duration) {
+    push(source_url, Event::Success(latency));
+}
+
+/// 记一次失败。
+pub fn record_failure(source_url: &str, kind: FailureKind) {
+    push(source_url, Event::Failure(kind));
+}
+
+/// 按引擎错误记一次失败;与站点无关的错误不记。
+pub fn record_error(source_url: &str, error: &BookSourceError) {
+    if let Some(kind) = FailureKind::of(error) {
+        record_failure(source_url, kind);
+    }
+}
+
+/// 体检通过:清零连续失败,书源重新参与多书源操作。
+pub fn mark_diagnosed(source_url: &str) {
+    push(source_url, Event::Diagnosed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::source;
+    use parse_book_source::FetchError;
+
+    #[test]
+    fn consecutive_failures_degrade_until_success() {
+        let mut record = SourceRecord::default();
+        record.succeed(Duration::from_millis(400));
+        for _ in 0..DEGRADE_AFTER {
+            assert!(!record.is_degraded());
+            record.fail(FailureKind::Network);
+        }
+        assert!(record.is_degraded());
+        assert_eq!(record.success_rate(), Some(25));
+
+        record.succeed(Duration::from_millis(800));
+        assert!(!record.is_degraded());
+        assert_eq!(record.latency_ms, 500);
+        assert_eq!(record.last_error, Some(FailureKind::Network));
+    }
+
+    #[test]
+    fn pending_events_apply_in_order() {
+        let url = "https://a.example".to_string();
+        let failure = (url.clone(), Event::Failure(FailureKind::Network));
+        let mut health = SourceHealth::default();
+        health.apply(&vec![failure.clone(); DEGRADE_AFTER as usize]);
+        assert!(health.is_degraded(&url));
+
+        health.apply(&[(url.clone(), Event::Diagnosed), failure]);
+        let record = health.get(&url).unwrap();
+        assert!(!record.is_degraded());
+        assert_eq!(record.consecutive_failures, 1);
+        assert_eq!(record.failures, DEGRADE_AFTER + 1);
+    }
+
+    #[test]
+    fn triage_skips_degraded_and_ranks_flaky_last() {
+        let (a, b, c) = (source("a"), source("b"), source("c"));
+        let mut health = SourceHealth::default();
+        health
+            .0
+            .entry(a.url.clone())
+            .or_default()
+            .fail(FailureKind::ParseEmpty);
+        for _ in 0..DEGRADE_AFTER {
+            health
+                .0
+                .entry(b.url.clone())
+                .or_default()
+                .fail(FailureKind::Challenge);
+        }
+        let (active, skipped) = health.triage(&[a, b, c]);
+        let names = |list: &[BookSource]| list.iter().map(|s| s.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&active), ["c", "a"]);
+        assert_eq!(names(&skipped), ["b"]);
+    }
+
+    #[test]
+    fn classifies_engine_errors() {
+        let challenged = BookSourceError::Fetch(FetchError::Challenged("cf".into()));
+        assert_eq!(FailureKind::of(&challenged), Some(FailureKind::Challenge));
+        assert_eq!(
+            FailureKind::of(&BookSourceError::LoginExpired),
+            Some(FailureKind::LoginExpired)
+        );
+        assert_eq!(FailureKind::of(&BookSourceError::Missing("search")), None);
+    }
+}
@@ -0,0 +1,214 @@
+//! 缓存与配置文件的读写。
+//!
+//! - 写:先写同目录下的临时文件再改名,崩溃或磁盘写满时旧文件保持完整;
+//! - 锁:写入方持有 `<文件>.lock` 上的咨询锁,多个 trn 进程不会同时改同一个文件;
+//! - 读:文件损坏时改名为 `<文件>.corrupt-<时间>` 留底再回落默认值,并记一条启动告警。
+
+use crate::Result;
+use chrono::Local;
+use serde::{Serialize, de::DeserializeOwned};
+use std::{
+    fs::{self, File},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+/// 读取时发现的损坏文件,启动后一次性提示。
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// 文件锁,drop 时释放。
+#[derive(Debug)]
+pub struct FileLock(File);
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = self.0.unlock();
+    }
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// 对 `path` 加独占锁(锁在旁边的 `.lock` 文件上,改名替换目标文件不影响锁)。
+pub fn lock(path: &Path) -> Result<FileLock> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(sibling(path, ".lock"))?;
+    file.lock()?;
+    Ok(FileLock(file))
+}
+
+/// 先写临时文件、落盘后再改名。不加锁,需要时由调用方先 [`lock`]。
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    write_file(path, bytes, false)
+}
+
+/// 同 [`write_atomic`],但文件只有当前用户可读写(unix 下为 0600),用于含密码或登录态的文件。
+pub fn write_atomic_private(path: &Path, bytes: &[u8]) -> Result<()> {
+    write_file(path, bytes, true)
+}
+
+fn write_file(path: &Path, bytes: &[u8], private: bool) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // 进程号区分多个 trn 进程,序号区分同一进程里并发写同一文件的任务。
+    static SEQ: AtomicU64 = AtomicU64::new(0);
+    let tmp = sibling(
+        path,
+        &format!(
+            ".{}-{}.tmp",
+            std::process::id(),
+            SEQ.fetch_add(1, Ordering::Relaxed)
+        ),
+    );
+    let result = (|| {
+        let mut options = File::options();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        if private {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        #[cfg(not(unix))]
+        let _ = private;
+        let mut file = options.open(&tmp)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    Ok(result?)
+}
+
+/// 加锁后以缩进格式原子写入 JSON。
+pub fn save_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<()> {
+    let bytes = serde_json::to_vec_pretty(value)?;
+    let _lock = lock(path)?;
+    write_atomic(path, &bytes)
+}
+
+/// 同 [`save_json`],但文件只有当前用户可读写(unix 下为 0600),用于含密码的配置。
+pub fn save_json_private<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<()> {
+    let bytes = serde_json::to_vec_pretty(value)?;
+    let _lock = lock(path)?;
+    write_atomic_private(path, &bytes)
+}
+
+/// 读取 JSON。文件不存在返回 `None`;内容损坏时留底后同样返回 `None`。
+pub fn load_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+    match serde_json::from_slice(&bytes) {
+        Ok(value) => Ok(Some(value)),
+        Err(error) => {
+            quarantine(path, &error.to_string())?;
+            Ok(None)
+        }
+    }
+}
+
+/// 把损坏的文件改名留底,并记一条告警。
+pub fn quarantine(path: &Path, reason: &str) -> Result<PathBuf> {
+    let backup = sibling(
+        path,
+        &format!(".corrupt-{}", Local::now().format("%Y%m%d-%H%M%S")),
+    );
+    fs::rename(path, &backup)?;
+    let message = format!(
+        "{} 已损坏({reason}),已备份为 {} 并使用默认值",
+        path.display(),
+        backup.display()
+    );
+    WARNINGS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(message);
+    Ok(backup)
+}
+
+/// 取走目前为止记下的损坏告警。
+pub fn take_warnings() -> Vec<String> {
+    std::mem::take(&mut *WARNINGS.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("trnovel-store-{name}"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn save_replaces_file_without_leaving_temp_files() {
+        let dir = dir("save");
+        let path = dir.join("a.json");
+        save_json(&path, &vec![1, 2]).unwrap();
+        save_json(&path, &vec![3]).unwrap();
+        assert_eq!(load_json::<Vec<i32>>(&path).unwrap(), Some(vec![3]));
+        let names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|name| name.ends_with(".tmp"))
+            .collect();
+        assert!(names.is_empty(), "{names:?}");
+    }
+
+    #[test]
+    fn concurrent_unlocked_writes_stay_whole() {
+        let dir = dir("concurrent");
+        let path = dir.join("a.txt");
+        let texts: Vec<String> = (0..8).map(|n| n.to_string().repeat(64 * 1024)).collect();
+        std::thread::scope(|scope| {
+            for text in &texts {
+                let path = &path;
+                scope.spawn(move || {
+                    for _ in 0..10 {
+                        write_atomic(path, text.as_bytes()).unwrap();
+                    }
+                });
+            }
+        });
+        // 同一进程的任务各用各的临时文件,最后留下的总是某一次完整的写入。
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(texts.contains(&written));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn corrupt_file_is_kept_as_backup() {
+        let dir = dir("corrupt");
+        let path = dir.join("a.json");
+        fs::write(&path, "{ 半截").unwrap();
+        assert_eq!(load_json::<Vec<i32>>(&path).unwrap(), None);
+        assert!(!path.exists());
+        let backup = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .find(|p| p.to_string_lossy().contains(".corrupt-"))
+            .expect("应留下损坏文件的备份");
+        assert_eq!(fs::read_to_string(backup).unwrap(), "{ 半截");
+        assert!(take_warnings().iter().any(|w| w.contains("a.json")));
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{Result, cache::store, data_dir::data_path};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// WebDAV 同步配置,保存在 `~/.novel/sync.json`(含密码,unix 下以 0600 落盘)。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConfig {
+    /// WebDAV 地址,例如 `https://dav.example.com/remote.php/dav/files/me`。
+    pub url: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    /// 远端存放同步数据的目录(相对 `url`)。
+    #[serde(default = "default_dir")]
+    pub dir: String,
+    /// 启动与退出时自动同步。
+    #[serde(default = "default_auto")]
+    pub auto: bool,
+}
+
+fn default_dir() -> String {
+    "trnovel".to_string()
+}
+
+fn default_auto() -> bool {
+    true
+}
+
+impl SyncConfig {
+    pub fn new(url: String, username: String, password: String) -> Self {
+        Self {
+            url,
+            username,
+            password,
+            dir: default_dir(),
+            auto: default_auto(),
+        }
+    }
+
+    pub fn path() -> Result<PathBuf> {
+        data_path("sync.json")
+    }
+
+    /// 读取配置;没配置过返回 `None`。
+    pub fn load() -> Result<Option<Self>> {
+        store::load_json(&Self::path()?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        store::save_json_private(&Self::path()?, self)
+    }
+
+    /// 远端同步文件的完整地址。
+    pub fn remote_url(&self, name: &str) -> String {
+        format!(
+            "{}/{}/{name}",
+            self.url.trim_end_matches('/'),
+            self.dir.trim_matches('/')
+        )
+    }
+
+    /// 远端目录地址(以 `/` 结尾,供 MKCOL 使用)。
+    pub fn remote_dir(&self) -> String {
+        format!(
+            "{}/{}/",
+            self.url.trim_end_matches('/'),
+            self.dir.trim_matches('/')
+        )
+    }
+}
@@ -1,8 +1,7 @@
-use crate::{errors::Result, utils::novel_catch_dir};
+use crate::{cache::store, data_dir::data_path, errors::Result};
 use novel_tts::kokoro_tts::Voice;
 use serde::{Deserialize, Serialize};
 use std::fmt;
-use std::fs::File;
 use strum::EnumIter;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, EnumIter, Hash, PartialEq, Eq)]
@@ -365,20 +364,15 @@ impl TTSConfig {
     }
 
     pub fn get_cache_file_path() -> Result<std::path::PathBuf> {
-        Ok(novel_catch_dir()?.join("tts_config.json"))
+        data_path("tts_config.json")
     }
 
     pub fn load() -> Result<Self> {
-        match File::open(Self::get_cache_file_path()?) {
-            Ok(file) => Ok(serde_json::from_reader(file)?),
-            Err(_) => Ok(Self::default()),
-        }
+        Ok(store::load_json(&Self::get_cache_file_path()?)?.unwrap_or_default())
     }
 
     pub fn save(&self) -> Result<()> {
-        let file = File::create(Self::get_cache_file_path()?)?;
-        serde_json::to_writer_pretty(file, self)?;
-        Ok(())
+        store::save_json(&Self::get_cache_file_path()?, self)
     }
 
     pub fn increase_speed(&mut self) {
@@ -0,0 +1,74 @@
+//! 系统剪贴板与摘录文件。
+//!
+//! 剪贴板走 OSC 52 转义序列:由终端模拟器代为写入系统剪贴板,不依赖本机剪贴板进程,
+//! SSH 远程会话里同样可用。tmux 内需经 DCS passthrough 包一层(且 tmux 需开启
+//! `set -g allow-passthrough on` 或 `set-clipboard on`)。终端不支持 OSC 52 时序列被静默忽略,
+//! 故另提供追加写入 `~/.novel/clippings.txt` 的摘录方式兜底。
+
+use crate::{Result, data_dir::data_path};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+/// 构造把 `text` 写入系统剪贴板(`c` 选择区)的 OSC 52 序列。
+pub fn osc52_sequence(text: &str, in_tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+    if in_tmux {
+        // DCS passthrough:内层 ESC 需双写。
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
+}
+
+/// 经 OSC 52 把文本交给终端写入系统剪贴板。
+pub fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    let in_tmux = std::env::var_os("TMUX").is_some();
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(osc52_sequence(text, in_tmux).as_bytes())?;
+    stdout.flush()
+}
+
+/// 摘录文件路径:`~/.novel/clippings.txt`。
+pub fn clippings_path() -> Result<PathBuf> {
+    data_path("clippings.txt")
+}
+
+/// 以「来源 + 时间」为抬头把摘录追加到摘录文件,返回文件路径。
+pub fn append_clipping(source: &str, text: &str) -> Result<PathBuf> {
+    let path = clippings_path()?;
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(
+        file,
+        "── {} · {}\n{}\n",
+        source,
+        chrono::Local::now().format("%Y-%m-%d %H:%M"),
+        text.trim_end()
+    )?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc52_encodes_utf8_as_base64() {
+        assert_eq!(
+            osc52_sequence("你好", false),
+            format!("\x1b]52;c;{}\x07", STANDARD.encode("你好"))
+        );
+        assert_eq!(osc52_sequence("hi", false), "\x1b]52;c;aGk=\x07");
+    }
+
+    #[test]
+    fn osc52_wraps_for_tmux_passthrough() {
+        assert_eq!(
+            osc52_sequence("hi", true),
+            "\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\"
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use ratatui::{
+    layout::{Constraint, Margin},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Wrap},
+};
+use ratatui_kit::{
+    AnyElement, Hooks, Props, UseTheme, component, element,
+    prelude::{Modal, Placement, View},
+};
+
+use crate::{dict::DictEntry, theme::AppChromeTheme};
+
+#[derive(Debug, Clone, Props, Default)]
+pub struct DictModalProps {
+    pub word: String,
+    pub entries: Vec<DictEntry>,
+    /// 无释义时显示的提示(加载中 / 未找到词典 / 未收录)。
+    pub tip: String,
+    pub open: bool,
+}
+
+/// 查词浮层:贴底显示,尽量少遮挡光标所在的正文。
+#[component]
+pub fn DictModal(props: &DictModalProps, hooks: Hooks) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_component_theme::<AppChromeTheme>();
+
+    let mut lines = Vec::new();
+    for entry in &props.entries {
+        let mut head = vec![Span::styled(entry.word.clone(), theme.title)];
+        if !entry.pinyin.is_empty() {
+            head.push(Span::styled(format!("  {}", entry.pinyin), theme.highlight));
+        }
+        head.push(Span::styled(format!("  〔{}〕", entry.source), theme.muted));
+        lines.push(Line::from(head));
+        for (i, definition) in entry.definitions.iter().enumerate() {
+            lines.push(Line::from(format!("{}. {}", i + 1, definition)).style(theme.text));
+        }
+        lines.push(Line::default());
+    }
+    if lines.is_empty() {
+        lines.push(Line::from(props.tip.clone()).style(theme.empty));
+    }
+
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+        Block::bordered()
+            .style(theme.border)
+            .not_dim()
+            .title(Line::from(props.word.clone()).centered().style(theme.title)),
+    );
+
+    element!(Modal(
+        width: Constraint::Percentage(70),
+        height: Constraint::Percentage(40),
+        placement: Placement::Bottom,
+        open: props.open,
+        // 非阻塞浮层:光标移动键仍由阅读页处理,浮层内容随光标所在词实时刷新。
+        blocks_lower: false,
+        style: Style::new().dim(),
+    ){
+        View(margin: Margin::new(0, 1)){
+            widget(paragraph)
+        }
+    })
+}
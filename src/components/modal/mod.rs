@@ -7,3 +7,5 @@ pub mod shortcut_info_modal;
 pub use shortcut_info_modal::*;
 pub mod browser_prompt;
 pub use browser_prompt::*;
+pub mod dict_modal;
+pub use dict_modal::*;
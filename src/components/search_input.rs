@@ -1,3 +1,4 @@
+use crossterm::event::KeyCode;
 use ratatui::layout::Constraint;
 use ratatui_kit::{
     AnyElement, Handler, Hooks, Props, component, element, prelude::SearchInput as KitSearchInput,
@@ -13,6 +14,8 @@ pub struct SearchInputProps {
     pub clear_on_escape: bool,
     pub is_editing: bool,
     pub on_clear: Handler<'static, ()>,
+    /// 进入输入态的按键,缺省为 `s`。
+    pub activate_key: Option<KeyCode>,
 }
 
 /// 搜索框项目 wrapper:保留 TRNovel props 形状,委托框架 `SearchInput` 的输入层与主题。
@@ -28,5 +31,6 @@ pub fn SearchInput(props: &mut SearchInputProps, _hooks: Hooks) -> impl Into<Any
         on_clear: props.on_clear.take(),
         clear_on_submit: props.clear_on_submit,
         clear_on_escape: props.clear_on_escape,
+        activate_key: props.activate_key.unwrap_or(KeyCode::Char('s')),
     ))
 }
@@ -0,0 +1,327 @@
+//! 数据目录布局。
+//!
+//! 默认所有数据都放在 `~/.novel`,TTS 模型放在 `~/.novel-tts`。按以下优先级可以换到别处:
+//! 1. `--data-dir <目录>`;
+//! 2. 环境变量 `TRNOVEL_HOME`;
+//! 3. Linux 上设 `TRNOVEL_XDG=1`:配置放 `$XDG_CONFIG_HOME/trnovel`,阅读进度、书架等状态放
+//!    `$XDG_STATE_HOME/trnovel`,章节缓存与 TTS 模型放 `$XDG_CACHE_HOME/trnovel`,
+//!    首次启用时自动从旧目录迁移。
+//!
+//! 前两种方式把数据(含 TTS 模型)全放在指定目录下,方便分开工作机、测试沙盒等多份配置。
+//! 各模块只按首层文件名用 [`data_path`] 取路径,不关心它实际落在哪个目录。
+
+use crate::Result;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+/// 指定数据目录的环境变量。
+pub const HOME_ENV: &str = "TRNOVEL_HOME";
+/// 启用 XDG 布局的环境变量(仅 Linux)。
+pub const XDG_ENV: &str = "TRNOVEL_XDG";
+
+const APP_NAME: &str = "trnovel";
+const LEGACY_DIR: &str = ".novel";
+const LEGACY_TTS_DIR: &str = ".novel-tts";
+const TTS_DIR: &str = "tts";
+
+static LAYOUT: OnceLock<DataLayout> = OnceLock::new();
+
+/// 数据的类别,决定 XDG 布局下放在哪个目录。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataKind {
+    /// 用户写的配置:外观、快捷键、书源、同步账号等。
+    Config,
+    /// 程序维护的状态:阅读进度、书架、下载队列、登录态等。
+    State,
+    /// 删了也能重新生成的缓存:章节正文、HTTP 响应、字体映射表、TTS 模型。
+    Cache,
+}
+
+impl DataKind {
+    /// 按数据目录下的首层文件名归类,未列出的一律算作状态。
+    ///
+    /// 锁文件与损坏留底跟着原文件走。
+    pub fn of(name: &str) -> Self {
+        let name = name.split(".corrupt-").next().unwrap_or(name);
+        match name.trim_end_matches(".lock") {
+            "appearance.json"
+            | "reader-display.json"
+            | "tts_config.json"
+            | "chapter-cache.json"
+            | "keybindings.toml"
+            | "toc_rules.json"
+            | "book_sources.json"
+            | "sync.json"
+            | "proxy.json"
+            | "browser_assist.on"
+            | "dicts" => DataKind::Config,
+            "chapters" | "http-cache" | "gen-fontmap" | TTS_DIR => DataKind::Cache,
+            _ => DataKind::State,
+        }
+    }
+}
+
+/// 各类数据所在的目录。单目录布局下三者相同。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataLayout {
+    pub config: PathBuf,
+    pub state: PathBuf,
+    pub cache: PathBuf,
+    /// TTS 模型目录。
+    pub tts: PathBuf,
+}
+
+impl DataLayout {
+    /// 全部数据放在 `dir` 下,TTS 模型放在 `dir/tts`。
+    pub fn single(dir: PathBuf) -> Self {
+        Self {
+            config: dir.clone(),
+            state: dir.clone(),
+            tts: dir.join(TTS_DIR),
+            cache: dir,
+        }
+    }
+
+    /// 旧版布局:`~/.novel` 与 `~/.novel-tts`。
+    pub fn legacy(home: &Path) -> Self {
+        Self {
+            tts: home.join(LEGACY_TTS_DIR),
+            ..Self::single(home.join(LEGACY_DIR))
+        }
+    }
+
+    /// XDG 布局,参数是 `$XDG_CONFIG_HOME`、`$XDG_STATE_HOME`、`$XDG_CACHE_HOME`。
+    pub fn xdg(config: &Path, state: &Path, cache: &Path) -> Self {
+        let cache = cache.join(APP_NAME);
+        Self {
+            config: config.join(APP_NAME),
+            state: state.join(APP_NAME),
+            tts: cache.join(TTS_DIR),
+            cache,
+        }
+    }
+
+    pub fn dir(&self, kind: DataKind) -> &Path {
+        match kind {
+            DataKind::Config => &self.config,
+            DataKind::State => &self.state,
+            DataKind::Cache => &self.cache,
+        }
+    }
+
+    /// 相对路径 `name`(`/` 分隔)的完整路径,按首层名字决定所在目录。
+    pub fn path(&self, name: &str) -> PathBuf {
+        let first = name.split('/').next().unwrap_or(name);
+        self.dir(DataKind::of(first)).join(name)
+    }
+
+    /// 去重后的数据目录,遍历全部数据(备份、统计占用)时使用。
+    pub fn roots(&self) -> Vec<PathBuf> {
+        let mut roots: Vec<PathBuf> = Vec::new();
+        for dir in [&self.config, &self.state, &self.cache] {
+            if !roots.contains(dir) {
+                roots.push(dir.clone());
+            }
+        }
+        roots
+    }
+
+    /// 把旧目录下的数据按类别搬到本布局的目录里,返回搬过去的名字。
+    ///
+    /// 目标已存在的条目保持原样(旧数据留在原处);旧目录搬空后一并删除。
+    pub fn migrate_from(&self, legacy: &DataLayout) -> Result<Vec<String>> {
+        let mut moved = Vec::new();
+        if let Ok(entries) = fs::read_dir(&legacy.state) {
+            for entry in entries {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().to_string();
+                let target = self.path(&name);
+                if target != entry.path() && move_entry(&entry.path(), &target)? {
+                    moved.push(name);
+                }
+            }
+            remove_if_empty(&legacy.state);
+        }
+        if legacy.tts != self.tts && move_entry(&legacy.tts, &self.tts)? {
+            moved.push(LEGACY_TTS_DIR.to_string());
+        }
+        Ok(moved)
+    }
+}
+
+/// 把 `from` 搬到 `to`;`from` 不存在或 `to` 已存在时不动,返回是否搬了。
+fn move_entry(from: &Path, to: &Path) -> Result<bool> {
+    if !from.exists() || to.exists() {
+        return Ok(false);
+    }
+    if let Some(dir) = to.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    if fs::rename(from, to).is_err() {
+        // 跨文件系统时改名会失败,退回到复制后删除。
+        copy_all(from, to)?;
+        if from.is_dir() {
+            fs::remove_dir_all(from)?;
+        } else {
+            fs::remove_file(from)?;
+        }
+    }
+    Ok(true)
+}
+
+fn copy_all(from: &Path, to: &Path) -> Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_all(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+fn remove_if_empty(dir: &Path) {
+    if fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_none()) {
+        let _ = fs::remove_dir(dir);
+    }
+}
+
+fn env_path(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+fn xdg_enabled() -> bool {
+    cfg!(target_os = "linux")
+        && std::env::var(XDG_ENV).is_ok_and(|value| matches!(value.as_str(), "1" | "true"))
+}
+
+fn home_dir() -> Result<PathBuf> {
+    Ok(dirs::home_dir().ok_or("无法获取用户主目录")?)
+}
+
+/// 按 `--data-dir` > `TRNOVEL_HOME` > `TRNOVEL_XDG` > 默认的顺序决定布局。
+///
+/// 启用 XDG 布局时一并返回需要迁移的旧布局。
+fn resolve(data_dir: Option<PathBuf>) -> Result<(DataLayout, Option<DataLayout>)> {
+    if let Some(dir) = data_dir.or_else(|| env_path(HOME_ENV)) {
+        return Ok((DataLayout::single(dir), None));
+    }
+    let home = home_dir()?;
+    let legacy = DataLayout::legacy(&home);
+    if xdg_enabled() {
+        let config = dirs::config_dir().unwrap_or_else(|| home.join(".config"));
+        let state = dirs::state_dir().unwrap_or_else(|| home.join(".local/state"));
+        let cache = dirs::cache_dir().unwrap_or_else(|| home.join(".cache"));
+        return Ok((DataLayout::xdg(&config, &state, &cache), Some(legacy)));
+    }
+    Ok((legacy, None))
+}
+
+/// 启动时确定数据目录,需在读写任何数据之前调用。
+///
+/// XDG 布局下会先把旧目录的数据迁移过来。重复调用时沿用第一次的结果。
+pub fn init(data_dir: Option<PathBuf>) -> Result<&'static DataLayout> {
+    if let Some(layout) = LAYOUT.get() {
+        return Ok(layout);
+    }
+    let (layout, legacy) = resolve(data_dir)?;
+    if let Some(legacy) = legacy {
+        match layout.migrate_from(&legacy) {
+            Ok(moved) if !moved.is_empty() => eprintln!(
+                "已把 {} 下的 {} 项数据迁移到 XDG 目录",
+                legacy.state.display(),
+                moved.len()
+            ),
+            Ok(_) => {}
+            Err(e) => eprintln!("迁移 {} 失败:{e}", legacy.state.display()),
+        }
+    }
+    let layout = LAYOUT.get_or_init(|| layout);
+    novel_tts::download::set_cache_dir(layout.tts.clone());
+    Ok(layout)
+}
+
+/// 当前的数据目录布局;未调用 [`init`] 时按环境变量决定。
+pub fn layout() -> Result<&'static DataLayout> {
+    init(None)
+}
+
+/// 数据目录下首层文件名 `name` 的完整路径,所在目录不存在时先创建。
+pub fn data_path(name: &str) -> Result<PathBuf> {
+    let layout = layout()?;
+    let dir = layout.dir(DataKind::of(name));
+    fs::create_dir_all(dir)?;
+    Ok(dir.join(name))
+}
+
+/// 全部数据目录(去重)。
+pub fn data_roots() -> Result<Vec<PathBuf>> {
+    Ok(layout()?.roots())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("trnovel-data-dir-{name}"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn xdg_layout_sorts_entries_by_kind() {
+        let layout = DataLayout::xdg(Path::new("/c"), Path::new("/s"), Path::new("/k"));
+        assert_eq!(
+            layout.path("appearance.json"),
+            Path::new("/c/trnovel/appearance.json")
+        );
+        assert_eq!(
+            layout.path("history.json"),
+            Path::new("/s/trnovel/history.json")
+        );
+        assert_eq!(layout.path("chapters"), Path::new("/k/trnovel/chapters"));
+        assert_eq!(layout.tts, Path::new("/k/trnovel/tts"));
+        assert_eq!(DataLayout::single("/d".into()).roots().len(), 1);
+    }
+
+    #[test]
+    fn migrate_moves_legacy_entries_and_keeps_existing_targets() {
+        let home = dir("migrate");
+        let legacy = DataLayout::legacy(&home);
+        fs::create_dir_all(legacy.state.join("chapters/src")).unwrap();
+        fs::write(legacy.state.join("chapters/src/a.txt"), "正文").unwrap();
+        fs::write(legacy.state.join("history.json"), "[]").unwrap();
+        fs::write(legacy.state.join("appearance.json"), "{\"old\":true}").unwrap();
+        fs::create_dir_all(&legacy.tts).unwrap();
+        fs::write(legacy.tts.join("model.onnx"), "m").unwrap();
+
+        let layout = DataLayout::xdg(&home.join("c"), &home.join("s"), &home.join("k"));
+        fs::create_dir_all(&layout.config).unwrap();
+        fs::write(layout.path("appearance.json"), "{}").unwrap();
+
+        let mut moved = layout.migrate_from(&legacy).unwrap();
+        moved.sort();
+        assert_eq!(moved, vec![".novel-tts", "chapters", "history.json"]);
+        assert_eq!(
+            fs::read_to_string(layout.path("chapters").join("src/a.txt")).unwrap(),
+            "正文"
+        );
+        assert_eq!(
+            fs::read_to_string(layout.path("appearance.json")).unwrap(),
+            "{}"
+        );
+        assert!(layout.tts.join("model.onnx").exists());
+        // 目标已存在的配置留在旧目录,旧目录因此保留。
+        assert!(legacy.state.join("appearance.json").exists());
+        assert!(!legacy.tts.exists());
+    }
+}
@@ -0,0 +1,143 @@
+//! CC-CEDICT 文本格式:`繁體 简体 [pin1 yin1] /释义一/释义二/`,`#` 开头为注释。
+
+use super::DictEntry;
+use std::collections::HashMap;
+
+/// 解析整份 CC-CEDICT 文本,按繁、简两种字形建索引。
+pub fn parse(text: &str, source: &str) -> HashMap<String, Vec<DictEntry>> {
+    let mut entries: HashMap<String, Vec<DictEntry>> = HashMap::new();
+    for line in text.lines() {
+        let Some((traditional, simplified, entry)) = parse_line(line, source) else {
+            continue;
+        };
+        if traditional != simplified {
+            entries
+                .entry(traditional.to_string())
+                .or_default()
+                .push(entry.clone());
+        }
+        entries
+            .entry(simplified.to_string())
+            .or_default()
+            .push(entry);
+    }
+    entries
+}
+
+/// 解析单行,返回 `(繁体, 简体, 词条)`;注释、空行与格式不符的行返回 `None`。
+fn parse_line<'a>(line: &'a str, source: &str) -> Option<(&'a str, &'a str, DictEntry)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (traditional, rest) = line.split_once(' ')?;
+    let (simplified, rest) = rest.split_once(' ')?;
+    let rest = rest.strip_prefix('[')?;
+    let (pinyin, rest) = rest.split_once(']')?;
+    let definitions = rest
+        .trim()
+        .trim_matches('/')
+        .split('/')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(str::to_string)
+        .collect();
+    Some((
+        traditional,
+        simplified,
+        DictEntry {
+            word: simplified.to_string(),
+            pinyin: numbered_to_marked(pinyin),
+            definitions,
+            source: source.to_string(),
+        },
+    ))
+}
+
+/// 数字声调拼音转声调符号:`ni3 hao3` → `nǐ hǎo`,`lu:4` → `lǜ`。
+///
+/// 标调规则:有 a/e 标在 a/e 上,`ou` 标在 o 上,否则标在最后一个元音上;
+/// 轻声(5)与不带数字的音节(专名里的英文字母等)原样保留。
+pub fn numbered_to_marked(pinyin: &str) -> String {
+    pinyin
+        .split_whitespace()
+        .map(mark_syllable)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn mark_syllable(syllable: &str) -> String {
+    let syllable = syllable.replace("u:", "ü").replace("U:", "Ü");
+    let Some(tone) = syllable
+        .chars()
+        .last()
+        .and_then(|c| c.to_digit(10))
+        .filter(|t| (1..=5).contains(t))
+    else {
+        return syllable;
+    };
+    let body: Vec<char> = syllable[..syllable.len() - 1].chars().collect();
+    if tone == 5 {
+        return body.into_iter().collect();
+    }
+    let is_vowel = |c: char| "aeiouüAEIOUÜ".contains(c);
+    let lower: String = body.iter().collect::<String>().to_lowercase();
+    let target = if let Some(i) = lower.find(['a', 'e']) {
+        Some(lower[..i].chars().count())
+    } else if let Some(i) = lower.find("ou") {
+        Some(lower[..i].chars().count())
+    } else {
+        body.iter().rposition(|&c| is_vowel(c))
+    };
+    let Some(target) = target else {
+        return body.into_iter().collect();
+    };
+    body.iter()
+        .enumerate()
+        .map(|(i, &c)| if i == target { with_tone(c, tone) } else { c })
+        .collect()
+}
+
+fn with_tone(vowel: char, tone: u32) -> char {
+    const TABLE: [(char, [char; 4]); 12] = [
+        ('a', ['ā', 'á', 'ǎ', 'à']),
+        ('e', ['ē', 'é', 'ě', 'è']),
+        ('i', ['ī', 'í', 'ǐ', 'ì']),
+        ('o', ['ō', 'ó', 'ǒ', 'ò']),
+        ('u', ['ū', 'ú', 'ǔ', 'ù']),
+        ('ü', ['ǖ', 'ǘ', 'ǚ', 'ǜ']),
+        ('A', ['Ā', 'Á', 'Ǎ', 'À']),
+        ('E', ['Ē', 'É', 'Ě', 'È']),
+        ('I', ['Ī', 'Í', 'Ǐ', 'Ì']),
+        ('O', ['Ō', 'Ó', 'Ǒ', 'Ò']),
+        ('U', ['Ū', 'Ú', 'Ǔ', 'Ù']),
+        ('Ü', ['Ǖ', 'Ǘ', 'Ǚ', 'Ǜ']),
+    ];
+    TABLE
+        .iter()
+        .find(|(base, _)| *base == vowel)
+        .map_or(vowel, |(_, marked)| marked[(tone - 1) as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_line_and_indexes_both_scripts() {
+        let text = "# CC-CEDICT\n俠客 侠客 [xia2 ke4] /chivalrous person/knight-errant/\n";
+        let entries = parse(text, "cedict");
+        let entry = &entries["侠客"][0];
+        assert_eq!(entry.pinyin, "xiá kè");
+        assert_eq!(entry.definitions, ["chivalrous person", "knight-errant"]);
+        assert_eq!(entries["俠客"][0].word, "侠客");
+    }
+
+    #[test]
+    fn marks_tones_by_pinyin_rules() {
+        assert_eq!(numbered_to_marked("ni3 hao3"), "nǐ hǎo");
+        assert_eq!(numbered_to_marked("lu:4 gou3"), "lǜ gǒu");
+        assert_eq!(numbered_to_marked("xiu1 zhuo2"), "xiū zhuó");
+        assert_eq!(numbered_to_marked("de5 A A4"), "de A À");
+    }
+}
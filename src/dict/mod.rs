@@ -0,0 +1,243 @@
+//! 离线词典:加载 `~/.novel/dicts` 下的 StarDict 与 CC-CEDICT 词典,供阅读页查词。
+//!
+//! 目录里放 StarDict 的 `xxx.ifo`(及同名 `.idx`/`.dict[.dz]`)或 CC-CEDICT 文本
+//! (`*.u8`、文件名含 `cedict` 的 `.txt`,均可再 gzip 为 `.gz`)即可,全程不联网。
+//! 查词按「正向最长匹配」切词:光标所在段落从头切分,取覆盖光标的那个词。
+
+mod cedict;
+mod stardict;
+
+use stardict::StarDict;
+
+use crate::{Result, data_dir::data_path};
+use flate2::read::GzDecoder;
+use std::{collections::HashMap, io::Read, ops::Range, path::Path, path::PathBuf};
+
+/// 一条释义。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DictEntry {
+    pub word: String,
+    /// 带声调符号的拼音;词典未提供读音时为空。
+    pub pinyin: String,
+    pub definitions: Vec<String>,
+    /// 来源词典名(CC-CEDICT 为文件名,StarDict 为 `bookname`)。
+    pub source: String,
+}
+
+enum Source {
+    Cedict(HashMap<String, Vec<DictEntry>>),
+    StarDict(StarDict),
+}
+
+impl Source {
+    fn contains(&self, word: &str) -> bool {
+        match self {
+            Source::Cedict(entries) => entries.contains_key(word),
+            Source::StarDict(dict) => dict.contains(word),
+        }
+    }
+
+    fn lookup(&self, word: &str) -> Vec<DictEntry> {
+        match self {
+            Source::Cedict(entries) => entries.get(word).cloned().unwrap_or_default(),
+            Source::StarDict(dict) => dict.lookup(word),
+        }
+    }
+}
+
+/// 最长匹配的窗口上限:再长的词条(外文短语、例句式词头)不参与切词。
+const MAX_WORD_CHARS: usize = 16;
+
+/// 已加载的全部词典。查词时按加载顺序汇总各词典的释义。
+#[derive(Default)]
+pub struct Dictionary {
+    sources: Vec<Source>,
+    /// 所有词条中最长的字数,限定最长匹配的窗口。
+    max_chars: usize,
+}
+
+impl Dictionary {
+    /// 词典目录:`~/.novel/dicts`(不存在时创建,方便用户直接放文件)。
+    pub fn dir() -> Result<PathBuf> {
+        let dir = data_path("dicts")?;
+        if !dir.exists() {
+            std::fs::create_dir_all(&dir)?;
+        }
+        Ok(dir)
+    }
+
+    /// 加载词典目录下全部可识别的词典,返回 `(词典, 加载失败的告警)`。
+    /// 单个词典损坏不影响其余词典。
+    pub fn load() -> Result<(Self, Vec<String>)> {
+        let mut paths = std::fs::read_dir(Self::dir()?)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        let mut dictionary = Self::default();
+        let mut warnings = Vec::new();
+        for path in paths {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let result = if name.ends_with(".ifo") {
+                StarDict::load(&path).map(Source::StarDict)
+            } else if is_cedict(&name) {
+                read_maybe_gz(&path).map(|bytes| {
+                    Source::Cedict(cedict::parse(&String::from_utf8_lossy(&bytes), &name))
+                })
+            } else {
+                continue;
+            };
+            match result {
+                Ok(source) => dictionary.push(source),
+                Err(e) => warnings.push(format!("词典 {name} 加载失败:{e}")),
+            }
+        }
+        Ok((dictionary, warnings))
+    }
+
+    fn push(&mut self, source: Source) {
+        let max_chars = match &source {
+            Source::Cedict(entries) => entries.keys().map(|k| k.chars().count()).max(),
+            Source::StarDict(dict) => dict.max_chars(),
+        };
+        self.max_chars = self
+            .max_chars
+            .max(max_chars.unwrap_or(0).min(MAX_WORD_CHARS));
+        self.sources.push(source);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.sources.iter().any(|s| s.contains(word))
+    }
+
+    pub fn lookup(&self, word: &str) -> Vec<DictEntry> {
+        self.sources.iter().flat_map(|s| s.lookup(word)).collect()
+    }
+
+    /// 正向最长匹配切词:返回 `text` 内各词的字节区间;未收录的字单独成词。
+    pub fn segment(&self, text: &str) -> Vec<Range<usize>> {
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let byte_at = |i: usize| chars.get(i).map_or(text.len(), |&(b, _)| b);
+        let mut segments = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let longest = (2..=self.max_chars.min(chars.len() - i))
+                .rev()
+                .find(|&n| self.contains(&text[byte_at(i)..byte_at(i + n)]))
+                .unwrap_or(1);
+            segments.push(byte_at(i)..byte_at(i + longest));
+            i += longest;
+        }
+        segments
+    }
+
+    /// 覆盖字节偏移 `offset` 的词:只在其所在段落(两个 `\n` 之间)内切词。
+    pub fn word_at(&self, text: &str, offset: usize) -> Range<usize> {
+        let offset = offset.min(text.len());
+        let start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let end = text[offset..].find('\n').map_or(text.len(), |i| offset + i);
+        self.segment(&text[start..end])
+            .into_iter()
+            .map(|r| start + r.start..start + r.end)
+            .find(|r| r.contains(&offset))
+            .unwrap_or(offset..offset)
+    }
+
+    /// 下一个词的起点(跨过换行);已是末词时返回原词起点。
+    pub fn next_word(&self, text: &str, offset: usize) -> usize {
+        let current = self.word_at(text, offset);
+        let mut next = current.end;
+        while text[next..].starts_with('\n') {
+            next += 1;
+        }
+        if next >= text.len() {
+            current.start
+        } else {
+            self.word_at(text, next).start
+        }
+    }
+
+    /// 上一个词的起点(跨过换行);已是首词时返回 0。
+    pub fn prev_word(&self, text: &str, offset: usize) -> usize {
+        let current = self.word_at(text, offset);
+        let mut prev = current.start;
+        while prev > 0 && text[..prev].ends_with('\n') {
+            prev -= 1;
+        }
+        match text[..prev].chars().next_back() {
+            Some(ch) => self.word_at(text, prev - ch.len_utf8()).start,
+            None => 0,
+        }
+    }
+}
+
+fn is_cedict(name: &str) -> bool {
+    let name = name.strip_suffix(".gz").unwrap_or(name).to_lowercase();
+    name.ends_with(".u8") || (name.contains("cedict") && name.ends_with(".txt"))
+}
+
+/// 读文件,`.gz`/`.dz` 结尾的先 gzip 解压。
+fn read_maybe_gz(path: &Path) -> Result<Vec<u8>> {
+    let bytes = std::fs::read(path)?;
+    let compressed = path
+        .extension()
+        .is_some_and(|ext| ext == "gz" || ext == "dz");
+    if !compressed {
+        return Ok(bytes);
+    }
+    let mut out = Vec::new();
+    GzDecoder::new(bytes.as_slice()).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dictionary(text: &str) -> Dictionary {
+        let mut dictionary = Dictionary::default();
+        dictionary.push(Source::Cedict(cedict::parse(text, "test")));
+        dictionary
+    }
+
+    const CEDICT: &str = "\
+江湖 江湖 [jiang1 hu2] /rivers and lakes/
+江湖客 江湖客 [jiang1 hu2 ke4] /wanderer/
+客 客 [ke4] /guest/
+";
+
+    #[test]
+    fn segments_by_forward_longest_match() {
+        let dict = dictionary(CEDICT);
+        let text = "一个江湖客来";
+        let words: Vec<&str> = dict.segment(text).into_iter().map(|r| &text[r]).collect();
+        assert_eq!(words, ["一", "个", "江湖客", "来"]);
+    }
+
+    #[test]
+    fn word_at_and_word_motions_stay_in_paragraph() {
+        let dict = dictionary(CEDICT);
+        let text = "江湖客\n客";
+        // 光标落在「湖」上,取到整个「江湖客」。
+        assert_eq!(&text[dict.word_at(text, 3)], "江湖客");
+        let second = dict.next_word(text, 0);
+        assert_eq!(&text[dict.word_at(text, second)], "客");
+        assert_eq!(second, "江湖客\n".len());
+        assert_eq!(dict.prev_word(text, second), 0);
+        assert_eq!(dict.next_word(text, second), second);
+    }
+
+    #[test]
+    fn recognizes_cedict_file_names() {
+        assert!(is_cedict("cedict_ts.u8"));
+        assert!(is_cedict("cedict_1_0_ts_utf-8_mdbg.txt.gz"));
+        assert!(!is_cedict("notes.txt"));
+    }
+}
@@ -0,0 +1,250 @@
+//! StarDict 词典:`.ifo` 描述 + `.idx`(可 gzip)索引 + `.dict`/`.dict.dz` 正文。
+//!
+//! 只把索引建成内存表、正文整块读入,查词时再按偏移解码对应词条,避免大词典一次性
+//! 展开全部释义。`.dict.dz` 是 gzip 兼容的 dictzip,按普通 gzip 整体解压即可。
+
+use super::{DictEntry, read_maybe_gz};
+use crate::Result;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Default)]
+pub struct StarDict {
+    pub name: String,
+    /// 词 → 正文中的 `(偏移, 长度)` 列表(同词多条释义时多项)。
+    index: HashMap<String, Vec<(u64, u32)>>,
+    data: Vec<u8>,
+    /// `.ifo` 的 `sametypesequence`;为空表示每个字段自带类型标记。
+    same_type_sequence: String,
+}
+
+impl StarDict {
+    /// 从 `.ifo` 路径加载同名的 `.idx`/`.dict` 文件。
+    pub fn load(ifo_path: &Path) -> Result<Self> {
+        let ifo = std::fs::read_to_string(ifo_path)?;
+        let mut name = String::new();
+        let mut offset_bits = 32;
+        let mut same_type_sequence = String::new();
+        for line in ifo.lines() {
+            match line.split_once('=') {
+                Some(("bookname", v)) => name = v.trim().to_string(),
+                Some(("idxoffsetbits", v)) => offset_bits = v.trim().parse().unwrap_or(32),
+                Some(("sametypesequence", v)) => same_type_sequence = v.trim().to_string(),
+                _ => {}
+            }
+        }
+        if name.is_empty() {
+            name = ifo_path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+        }
+
+        let idx = read_maybe_gz(&sibling(ifo_path, &["idx", "idx.gz"])?)?;
+        let data = read_maybe_gz(&sibling(ifo_path, &["dict", "dict.dz"])?)?;
+        Ok(Self {
+            name,
+            index: parse_idx(&idx, offset_bits == 64),
+            data,
+            same_type_sequence,
+        })
+    }
+
+    /// 最长词条的字数。
+    pub fn max_chars(&self) -> Option<usize> {
+        self.index.keys().map(|k| k.chars().count()).max()
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.index.contains_key(word)
+    }
+
+    pub fn lookup(&self, word: &str) -> Vec<DictEntry> {
+        let Some(locations) = self.index.get(word) else {
+            return Vec::new();
+        };
+        locations
+            .iter()
+            .filter_map(|&(offset, size)| {
+                let start = usize::try_from(offset).ok()?;
+                let raw = self.data.get(start..start.checked_add(size as usize)?)?;
+                Some(self.decode(word, raw))
+            })
+            .collect()
+    }
+
+    /// 按 StarDict 字段类型解码一条正文:`t`/`y` 为读音,文本类字段去标记后作释义,
+    /// 图片/音频等二进制字段跳过。
+    fn decode(&self, word: &str, raw: &[u8]) -> DictEntry {
+        let mut pinyin = String::new();
+        let mut definitions = Vec::new();
+        for (kind, field) in fields(raw, &self.same_type_sequence) {
+            let text = String::from_utf8_lossy(field);
+            match kind {
+                't' | 'y' => pinyin = text.trim().to_string(),
+                'm' | 'l' | 'g' | 'x' | 'h' | 'k' | 'w' => definitions.extend(
+                    strip_markup(&text)
+                        .lines()
+                        .map(str::trim)
+                        .filter(|l| !l.is_empty())
+                        .map(str::to_string),
+                ),
+                _ => {}
+            }
+        }
+        DictEntry {
+            word: word.to_string(),
+            pinyin,
+            definitions,
+            source: self.name.clone(),
+        }
+    }
+}
+
+/// 找 `.ifo` 同目录同名、扩展名为候选之一的文件。
+fn sibling(ifo_path: &Path, extensions: &[&str]) -> Result<PathBuf> {
+    extensions
+        .iter()
+        .map(|ext| ifo_path.with_extension(ext))
+        .find(|p| p.exists())
+        .ok_or_else(|| {
+            format!(
+                "StarDict 词典 {} 缺少 .{} 文件",
+                ifo_path.display(),
+                extensions[0]
+            )
+            .into()
+        })
+}
+
+/// `.idx`:重复的 `词\0 + 偏移(u32/u64 大端) + 长度(u32 大端)`。截断的尾部忽略。
+fn parse_idx(idx: &[u8], offset_64: bool) -> HashMap<String, Vec<(u64, u32)>> {
+    let offset_len = if offset_64 { 8 } else { 4 };
+    let mut index: HashMap<String, Vec<(u64, u32)>> = HashMap::new();
+    let mut rest = idx;
+    while let Some(nul) = rest.iter().position(|&b| b == 0) {
+        let word = String::from_utf8_lossy(&rest[..nul]).to_string();
+        rest = &rest[nul + 1..];
+        if rest.len() < offset_len + 4 {
+            break;
+        }
+        let offset = rest[..offset_len]
+            .iter()
+            .fold(0u64, |acc, &b| (acc << 8) | b as u64);
+        let size = u32::from_be_bytes(rest[offset_len..offset_len + 4].try_into().unwrap());
+        rest = &rest[offset_len + 4..];
+        index.entry(word).or_default().push((offset, size));
+    }
+    index
+}
+
+/// 拆出一条正文里的 `(类型, 字段)` 列表。
+///
+/// 有 `sametypesequence` 时字段不带类型标记,最后一个字段占满剩余字节;否则每个字段
+/// 以类型字符开头。小写类型以 `\0` 结尾,大写类型以 u32 大端长度开头。
+fn fields<'a>(raw: &'a [u8], same_type_sequence: &str) -> Vec<(char, &'a [u8])> {
+    let mut result = Vec::new();
+    let mut rest = raw;
+    let mut take = |kind: char, rest: &mut &'a [u8], is_last: bool| {
+        let field: &'a [u8] = if is_last {
+            std::mem::take(rest)
+        } else if kind.is_ascii_lowercase() {
+            let end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+            let field = &rest[..end];
+            *rest = rest.get(end + 1..).unwrap_or_default();
+            field
+        } else {
+            let len = rest
+                .get(..4)
+                .map_or(0, |b| u32::from_be_bytes(b.try_into().unwrap()) as usize);
+            let field = rest.get(4..4 + len).unwrap_or_default();
+            *rest = rest.get(4 + len..).unwrap_or_default();
+            field
+        };
+        result.push((kind, field));
+    };
+    if same_type_sequence.is_empty() {
+        while let Some((&kind, tail)) = rest.split_first() {
+            rest = tail;
+            take(kind as char, &mut rest, false);
+        }
+    } else {
+        let kinds: Vec<char> = same_type_sequence.chars().collect();
+        for (i, &kind) in kinds.iter().enumerate() {
+            take(kind, &mut rest, i + 1 == kinds.len());
+        }
+    }
+    result
+}
+
+/// 去掉 html/pango/xdxf 标签并还原常见实体;`<br>` 折成换行。
+fn strip_markup(text: &str) -> String {
+    let text = text
+        .replace("<br>", "\n")
+        .replace("<br/>", "\n")
+        .replace("<br />", "\n");
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for ch in text.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(ch),
+            _ => {}
+        }
+    }
+    out.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idx_entry(word: &str, offset: u32, size: u32) -> Vec<u8> {
+        let mut bytes = word.as_bytes().to_vec();
+        bytes.push(0);
+        bytes.extend(offset.to_be_bytes());
+        bytes.extend(size.to_be_bytes());
+        bytes
+    }
+
+    #[test]
+    fn looks_up_same_type_sequence_entries() {
+        let data = "tiě ér\0<b>铁</b>制的物件<br>又指铁匠".as_bytes().to_vec();
+        let idx = idx_entry("铁儿", 0, data.len() as u32);
+        let dict = StarDict {
+            name: "测试".into(),
+            index: parse_idx(&idx, false),
+            data,
+            same_type_sequence: "tm".into(),
+        };
+        let entries = dict.lookup("铁儿");
+        assert_eq!(entries[0].pinyin, "tiě ér");
+        assert_eq!(entries[0].definitions, ["铁制的物件", "又指铁匠"]);
+        assert!(dict.lookup("铁").is_empty());
+    }
+
+    #[test]
+    fn decodes_typed_fields_without_sequence() {
+        let mut raw = b"mplain\0".to_vec();
+        raw.push(b'W');
+        raw.extend(3u32.to_be_bytes());
+        raw.extend(b"wav");
+        raw.extend(b"hsecond<i>!</i>\0");
+        let kinds: Vec<(char, &[u8])> = fields(&raw, "");
+        assert_eq!(
+            kinds,
+            vec![
+                ('m', &b"plain"[..]),
+                ('W', &b"wav"[..]),
+                ('h', &b"second<i>!</i>"[..])
+            ]
+        );
+    }
+}
@@ -3,36 +3,156 @@
 //! 用于校验 AI 生成的书源:读文件 → 构建 [`parse_book_source::Engine`] → 跑
 //! [`parse_book_source::diagnose`] 做全流程体检 → 打印逐项结果。所有错误都作为
 //! 「配置」失败项展示,不向外冒泡。
+//!
+//! `--record <目录>` 把体检中的每次取页连同书源录进目录;`--replay <目录>` 不联网、原样回放,
+//! 用来离线复现问题报告里附带的录制。`--trace <操作>` 在报告后打印该操作的求值追踪树,
+//! 看清字段为空是丢在取页、选择器、下标还是某个清洗步。
+//!
+//! 联网体检通过时清掉该书源的连续失败记录,因连续失败被聚合搜索、换源跳过的书源由此恢复。
 
-use parse_book_source::{BookSource, BrowserFetcher, BrowserOptions, Engine, diagnose};
+use clap::ValueEnum;
+use parse_book_source::fetch::FetchOp;
+use parse_book_source::fetch::replay::{RecordingFetcher, ReplayFetcher};
+use parse_book_source::{BookSource, BrowserFetcher, Engine, diagnose};
 use std::path::Path;
+use std::sync::Arc;
+
+/// 录制目录里书源 JSON 的文件名。
+const SOURCE_FILE: &str = "source.json";
+
+/// `--trace` 可追踪的操作。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum)]
+pub enum TraceOp {
+    Search,
+    Explore,
+    BookInfo,
+    Toc,
+    Content,
+}
 
-/// 体检指定路径的书源 JSON 并打印报告。
-pub async fn run(path: &Path) {
-    let json = match std::fs::read_to_string(path) {
+impl From<TraceOp> for FetchOp {
+    fn from(op: TraceOp) -> Self {
+        match op {
+            TraceOp::Search => FetchOp::Search,
+            TraceOp::Explore => FetchOp::Explore,
+            TraceOp::BookInfo => FetchOp::BookInfo,
+            TraceOp::Toc => FetchOp::Toc,
+            TraceOp::Content => FetchOp::Content,
+        }
+    }
+}
+
+/// 体检书源 JSON 并打印报告;`path` 省略时取回放目录里的 [`SOURCE_FILE`]。
+pub async fn run(
+    path: Option<&Path>,
+    record: Option<&Path>,
+    replay: Option<&Path>,
+    trace: Option<TraceOp>,
+) {
+    let path = match (path, replay) {
+        (Some(path), _) => path.to_path_buf(),
+        (None, Some(dir)) => dir.join(SOURCE_FILE),
+        (None, None) => return print_config_error("缺少书源", "请指定书源 JSON 文件"),
+    };
+    let json = match std::fs::read_to_string(&path) {
         Ok(j) => j,
         Err(e) => return print_config_error("读取文件失败", e),
     };
-    let source = match BookSource::from_json(&json) {
+    let mut source = match BookSource::from_json(&json) {
         Ok(s) => s,
         Err(e) => return print_config_error("JSON 解析失败", e),
     };
-    // 带浏览器构建:渲染型 op(`render-fetcher`,如番茄搜索)才能真正验证(headless 渲染 +
-    // CDP 拦截);非渲染 op 不开浏览器(EscalatingFetcher 仅在 render/撞挑战时才启动)。
-    // 探测不到浏览器(CI/沙箱)→ None,等同纯 reqwest:渲染 op 优雅降级标 ✗。
-    let browser = BrowserFetcher::detect(BrowserOptions::default());
-    let engine = match Engine::with_browser_assist(source, browser) {
-        Ok(e) => e,
-        Err(e) => return print_config_error("构建引擎失败", e),
+
+    let mut recorder = None;
+    let engine = if let Some(dir) = replay {
+        match ReplayFetcher::load(dir) {
+            Ok(fetcher) => {
+                println!("回放 {}:{} 次取页\n", dir.display(), fetcher.len());
+                Engine::with_fetcher(source, Arc::new(fetcher))
+            }
+            Err(e) => return print_config_error("读取录制失败", e),
+        }
+    } else {
+        // 带浏览器构建:渲染型 op(`render-fetcher`,如番茄搜索)才能真正验证(headless 渲染 +
+        // CDP 拦截);非渲染 op 不开浏览器(EscalatingFetcher 仅在 render/撞挑战时才启动)。
+        // 探测不到浏览器(CI/沙箱)→ None,等同纯 reqwest:渲染 op 优雅降级标 ✗。
+        crate::cache::apply_global_proxy(&mut source);
+        let browser = BrowserFetcher::detect(crate::browser_assist::browser_options());
+        let engine = match Engine::with_browser_assist(source, browser) {
+            Ok(e) => e,
+            Err(e) => return print_config_error("构建引擎失败", e),
+        };
+        match record {
+            Some(dir) => {
+                let mut error = None;
+                let engine =
+                    engine.wrap_fetcher(|inner| match RecordingFetcher::new(inner.clone(), dir) {
+                        Ok(fetcher) => {
+                            let fetcher = Arc::new(fetcher);
+                            recorder = Some(fetcher.clone());
+                            fetcher
+                        }
+                        Err(e) => {
+                            error = Some(e);
+                            inner
+                        }
+                    });
+                if let Some(e) = error {
+                    return print_config_error("创建录制目录失败", e);
+                }
+                if let Err(e) = std::fs::write(dir.join(SOURCE_FILE), &json) {
+                    return print_config_error("保存书源失败", e);
+                }
+                engine
+            }
+            None => engine,
+        }
     };
 
+    let engine = match trace {
+        Some(op) => engine.with_trace(op.into()),
+        None => engine,
+    };
     let report = diagnose(&engine).await;
     print!("{report}");
     if report.healthy() {
+        // 联网体检通过即恢复该书源:清零连续失败,重新参与聚合搜索与换源。回放不算。
+        if replay.is_none() {
+            crate::cache::source_health::mark_diagnosed(engine.source_url());
+        }
         println!("\n✓ 全部通过(○ 为未配置/跳过)");
     } else {
         println!("\n✗ 存在异常项,请检查上面标 ✗ 的规则");
     }
+    if let (Some(recorder), Some(dir)) = (recorder, record) {
+        println!(
+            "\n已录下 {} 次取页到 {},用 `trn doctor --replay {}` 离线回放",
+            recorder.recorded(),
+            dir.display(),
+            dir.display()
+        );
+    }
+    if trace.is_some() {
+        print_trace(&engine);
+    }
+}
+
+/// 打印求值追踪树;该操作没跑到(未配置或前一步失败)时给出提示。
+fn print_trace(engine: &Engine) {
+    let stages = engine.take_trace();
+    if stages.is_empty() {
+        return println!(
+            "
+求值追踪:该操作未执行(未配置,或前一步失败未能走到)"
+        );
+    }
+    println!(
+        "
+求值追踪"
+    );
+    for stage in &stages {
+        print!("{stage}");
+    }
 }
 
 /// 在体检尚未真正开始(读文件/解析/建引擎阶段)就失败时,打成「配置」✗ 项。
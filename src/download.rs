@@ -0,0 +1,251 @@
+//! 下载管理器:把整本或指定章节范围的网络小说下载到本地,供离线阅读。
+//!
+//! 队列持久化在 `~/.novel/downloads.json`(见 [`Downloads`]),运行态放在全局原子
+//! [`DOWNLOADS`] 里,下载页与阅读页订阅它显示进度。每本书一个后台任务逐章下载,
+//! 同一书源的章节请求共用一个信号量限制并发;单章失败按指数退避重试,
+//! 重试用尽记为失败,任务跑完后可在下载页整体重试。
+
+use crate::{
+    ChapterCache, DownloadStatus, DownloadTask, Downloads, Result, book_source::BookSourceCache,
+    browser_assist::build_engine,
+};
+use parse_book_source::Engine;
+use ratatui_kit::Atom;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, LazyLock, Mutex},
+    time::Duration,
+};
+use tokio::sync::Semaphore;
+
+/// 下载队列(进程级):后台任务写入,下载页 / 阅读页 `use_atom(&DOWNLOADS)` 订阅。
+pub static DOWNLOADS: Atom<Downloads> = Atom::new(Downloads::default);
+
+/// 同一书源同时在下载的章节数上限。
+pub const PER_SOURCE_CONCURRENCY: usize = 2;
+
+/// 单章最多重试次数(不含首次请求)。
+pub const MAX_RETRIES: u32 = 3;
+
+/// 每下载这么多章落一次盘;任务结束、暂停时也会落盘。
+const SAVE_EVERY: usize = 10;
+
+/// 正在跑的任务(按 book 链接),防止同一本书被重复启动。
+static RUNNING: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Default::default);
+
+/// 每个书源一个信号量。
+static SOURCE_LIMITS: LazyLock<Mutex<HashMap<String, Arc<Semaphore>>>> =
+    LazyLock::new(Default::default);
+
+/// 启动时加载队列并续传上次没下完的任务。
+pub fn start() -> Result<()> {
+    let mut downloads = Downloads::load()?;
+    let resumable = downloads.resumable();
+    for book_url in &resumable {
+        if let Some(task) = downloads.get_mut(book_url) {
+            task.status = DownloadStatus::Queued;
+        }
+    }
+    DOWNLOADS.set(downloads);
+    for book_url in resumable {
+        spawn(book_url);
+    }
+    Ok(())
+}
+
+/// 加入下载队列并立即开始。
+pub fn enqueue(task: DownloadTask) -> Result<()> {
+    let book_url = task.book_url.clone();
+    update(|downloads| downloads.enqueue(task))?;
+    spawn(book_url);
+    Ok(())
+}
+
+/// 暂停 / 继续。暂停在当前章下完后生效。
+pub fn toggle_pause(book_url: &str) -> Result<()> {
+    let mut resume = false;
+    update(|downloads| {
+        if let Some(task) = downloads.get_mut(book_url) {
+            task.status = match task.status {
+                DownloadStatus::Paused => {
+                    resume = true;
+                    DownloadStatus::Queued
+                }
+                DownloadStatus::Queued | DownloadStatus::Running => DownloadStatus::Paused,
+                status => status,
+            };
+        }
+    })?;
+    if resume {
+        spawn(book_url.to_string());
+    }
+    Ok(())
+}
+
+/// 失败的章节重新下载。
+pub fn retry(book_url: &str) -> Result<()> {
+    update(|downloads| downloads.retry(book_url))?;
+    spawn(book_url.to_string());
+    Ok(())
+}
+
+/// 删除任务及已下载的正文;正在跑的任务会在当前章结束后退出。
+pub fn remove(book_url: &str) -> Result<()> {
+    let mut removed = None;
+    update(|downloads| removed = downloads.remove(book_url))?;
+    if let Some(task) = removed {
+        let cache = ChapterCache::open()?;
+        for chapter in task.chapters.iter().filter(|c| c.done) {
+            cache.remove_download(&task.source_url, &chapter.url)?;
+        }
+    }
+    Ok(())
+}
+
+/// 改动队列并落盘。
+fn update(f: impl FnOnce(&mut Downloads)) -> Result<()> {
+    let state = DOWNLOADS.state();
+    let mut downloads = state.write();
+    f(&mut downloads);
+    downloads.save()
+}
+
+fn spawn(book_url: String) {
+    if !RUNNING.lock().unwrap().insert(book_url.clone()) {
+        return;
+    }
+    tokio::spawn(async move {
+        if let Err(e) = run(&book_url).await {
+            let _ = update(|downloads| {
+                if let Some(task) = downloads.get_mut(&book_url) {
+                    task.error = Some(e.to_string());
+                    task.status = DownloadStatus::Failed;
+                }
+            });
+        }
+        RUNNING.lock().unwrap().remove(&book_url);
+        // 收尾之后、移出 RUNNING 之前排进来的章节,这期间的 `spawn` 什么也没做:再起一轮。
+        if DOWNLOADS.state().read().has_work(&book_url) {
+            spawn(book_url);
+        }
+    });
+}
+
+fn source_limit(source_url: &str) -> Arc<Semaphore> {
+    SOURCE_LIMITS
+        .lock()
+        .unwrap()
+        .entry(source_url.to_string())
+        .or_insert_with(|| Arc::new(Semaphore::new(PER_SOURCE_CONCURRENCY)))
+        .clone()
+}
+
+/// 逐章下载一个任务,直到下完、暂停或被删除。
+async fn run(book_url: &str) -> Result<()> {
+    let Some(task) = DOWNLOADS.state().read().get(book_url).cloned() else {
+        return Ok(());
+    };
+    if !matches!(
+        task.status,
+        DownloadStatus::Queued | DownloadStatus::Running
+    ) {
+        return Ok(());
+    }
+    let sources = BookSourceCache::load()?;
+    let source = sources
+        .find_book_source(&task.source_url, &task.source_name)
+        .ok_or(format!("书源「{}」已被删除", task.source_name))?;
+    let engine = build_engine(source.clone())?.with_book_vars(task.book_vars.clone());
+    let cache = ChapterCache::open()?;
+    let limit = source_limit(&task.source_url);
+
+    set_status(book_url, DownloadStatus::Running)?;
+    let mut pending: Vec<_> = task.pending().cloned().collect();
+    let mut n = 0;
+    loop {
+        for chapter in pending {
+            {
+                let state = DOWNLOADS.state();
+                let mut downloads = state.write();
+                match downloads.get_mut(book_url) {
+                    // 暂停或删除:已下的章节落盘后退出。
+                    None => return Ok(()),
+                    Some(task) if task.status == DownloadStatus::Paused => return downloads.save(),
+                    // 暂停后在当前章没下完时又继续了:沿用本任务接着下。
+                    Some(task) => task.status = DownloadStatus::Running,
+                }
+            }
+
+            let text = match cache.chapter(&task.source_url, &chapter.url) {
+                // 阅读时已缓存过的章节直接转存,不再联网。
+                Some(text) => Ok(text),
+                None => fetch_with_retry(&engine, &chapter.url, Some(&limit)).await,
+            };
+            let saved =
+                text.and_then(|text| cache.put_download(&task.source_url, &chapter.url, &text));
+
+            let state = DOWNLOADS.state();
+            let mut downloads = state.write();
+            if let Some(c) = downloads
+                .get_mut(book_url)
+                .and_then(|t| t.chapters.iter_mut().find(|c| c.index == chapter.index))
+            {
+                c.done = saved.is_ok();
+                c.failed = saved.is_err();
+            }
+            n += 1;
+            if n % SAVE_EVERY == 0 {
+                downloads.save()?;
+            }
+        }
+
+        // 下载期间追加的范围、重试的章节接着下;取下一批与收尾在同一把锁里,不会漏掉。
+        let state = DOWNLOADS.state();
+        let mut downloads = state.write();
+        match downloads.next_batch(book_url) {
+            Some(next) => pending = next,
+            None => return downloads.save(),
+        }
+    }
+}
+
+fn set_status(book_url: &str, status: DownloadStatus) -> Result<()> {
+    update(|downloads| {
+        if let Some(task) = downloads.get_mut(book_url) {
+            task.status = status;
+        }
+    })
+}
+
+/// 请求一章正文。超时、连不上、5xx 等临时失败按 1s、2s、4s… 退避重试,反爬拦截、登录失效
+/// 等直接返回;书源配了 `http.retry` 时取页层已经重试过,这里不再重复。
+///
+/// `limit` 是书源的并发名额,只在请求期间占用,退避等待时让给其他章节。
+pub(crate) async fn fetch_with_retry(
+    engine: &Engine,
+    url: &str,
+    limit: Option<&Semaphore>,
+) -> Result<String> {
+    let retries = match &engine.source().http.retry {
+        Some(retry) if retry.max > 0 => 0,
+        _ => MAX_RETRIES,
+    };
+    let mut attempt = 0;
+    loop {
+        let result = {
+            let _permit = match limit {
+                Some(limit) => Some(limit.acquire().await),
+                None => None,
+            };
+            engine.content(url).await
+        };
+        match result {
+            Ok(text) => return Ok(text),
+            Err(e) if attempt < retries && e.is_transient() => {
+                tokio::time::sleep(Duration::from_secs(1 << attempt)).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
@@ -22,6 +22,12 @@ pub enum Errors {
 
     #[error(transparent)]
     Task(#[from] tokio::task::JoinError),
+
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
 }
 
 impl From<String> for Errors {
@@ -0,0 +1,610 @@
+//! 导出:把一本书写成 TXT 或 EPUB 3,方便拷到电子书阅读器上看。
+//!
+//! 网络小说优先用已下载 / 已缓存的章节,缺的再联网补齐;本地小说按识别出的目录分章。
+//! `export` 子命令与书架页(`e` / `Shift+E`)共用这里的逻辑。
+
+use crate::{
+    Bookshelf, NetworkNovelCache, Result,
+    book_source::BookSourceCache,
+    history::History,
+    novel::{Novel, VolumeMarker, local_novel::LocalNovel, network_novel::NetworkNovel},
+    update::reqwest_engine,
+    utils::get_md5_string,
+};
+use clap::ValueEnum;
+use futures::{StreamExt, TryStreamExt, stream};
+use parse_book_source::{BookSource, Engine, source::Proxy};
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Seek, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+/// 网络小说导出时同时在取的章节数(引擎的 rateLimit 照样生效)。
+const CONCURRENCY: usize = crate::download::PER_SOURCE_CONCURRENCY;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, ValueEnum)]
+pub enum ExportFormat {
+    Txt,
+    #[default]
+    Epub,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Txt => "txt",
+            ExportFormat::Epub => "epub",
+        }
+    }
+}
+
+/// 封面图片;只收阅读器普遍支持的格式。
+#[derive(Debug, Clone)]
+pub struct Cover {
+    pub bytes: Vec<u8>,
+    pub media_type: &'static str,
+}
+
+impl Cover {
+    /// 按文件头识别图片格式,认不出来返回 `None`。
+    pub fn from_bytes(bytes: Vec<u8>) -> Option<Self> {
+        let media_type = match bytes.as_slice() {
+            [0xFF, 0xD8, 0xFF, ..] => "image/jpeg",
+            [0x89, b'P', b'N', b'G', ..] => "image/png",
+            [b'G', b'I', b'F', b'8', ..] => "image/gif",
+            [
+                b'R',
+                b'I',
+                b'F',
+                b'F',
+                _,
+                _,
+                _,
+                _,
+                b'W',
+                b'E',
+                b'B',
+                b'P',
+                ..,
+            ] => "image/webp",
+            _ => return None,
+        };
+        Some(Self { bytes, media_type })
+    }
+
+    fn extension(&self) -> &'static str {
+        self.media_type.trim_start_matches("image/")
+    }
+}
+
+/// 导出用的一章:标题 + 去掉空行、首尾空白后的段落。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportChapter {
+    pub title: String,
+    pub paragraphs: Vec<String>,
+}
+
+impl ExportChapter {
+    /// 正文开头重复的章节标题(本地小说按标题行切章,正文总以标题开头)会被去掉。
+    pub fn new(title: String, text: &str) -> Self {
+        let mut paragraphs: Vec<String> = text
+            .lines()
+            .map(|line| line.trim_matches(|c: char| c.is_whitespace()))
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect();
+        if paragraphs
+            .first()
+            .is_some_and(|first| *first == title.trim())
+        {
+            paragraphs.remove(0);
+        }
+        Self { title, paragraphs }
+    }
+}
+
+/// 待写出的一本书。
+#[derive(Debug, Clone, Default)]
+pub struct ExportBook {
+    /// 本地为小说路径,网络为 book 链接;EPUB 的唯一标识由它生成。
+    pub id: String,
+    pub title: String,
+    pub author: String,
+    pub intro: String,
+    pub cover: Option<Cover>,
+    pub chapters: Vec<ExportChapter>,
+    pub volumes: Vec<VolumeMarker>,
+}
+
+impl ExportBook {
+    /// 写到 `path`;先写临时文件再改名,中途失败不会留下半个文件。
+    pub fn save(&self, format: ExportFormat, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("part");
+        let file = File::create(&tmp)?;
+        match format {
+            ExportFormat::Txt => self.write_txt(BufWriter::new(file))?,
+            ExportFormat::Epub => self.write_epub(BufWriter::new(file))?,
+        }
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    /// 从第 `index` 章开始的卷。
+    fn volume_at(&self, index: usize) -> Option<&VolumeMarker> {
+        self.volumes.iter().find(|v| v.first_chapter_index == index)
+    }
+
+    pub fn write_txt(&self, mut w: impl Write) -> Result<()> {
+        writeln!(w, "《{}》", self.title)?;
+        if !self.author.is_empty() {
+            writeln!(w, "作者:{}", self.author)?;
+        }
+        for line in self.intro.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            writeln!(w, "\n　　{line}")?;
+        }
+        for (index, chapter) in self.chapters.iter().enumerate() {
+            if let Some(volume) = self.volume_at(index) {
+                writeln!(w, "\n\n{}", volume.title)?;
+            }
+            writeln!(w, "\n\n{}\n", chapter.title)?;
+            for paragraph in &chapter.paragraphs {
+                writeln!(w, "　　{paragraph}")?;
+            }
+        }
+        w.flush()?;
+        Ok(())
+    }
+
+    pub fn write_epub(&self, w: impl Write + Seek) -> Result<()> {
+        let mut zip = ZipWriter::new(w);
+        // 规范要求 mimetype 是第一个文件且不压缩。
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let deflated = SimpleFileOptions::default();
+        let mut add = |name: &str, bytes: &[u8], options| -> Result<()> {
+            zip.start_file(name, options)?;
+            zip.write_all(bytes)?;
+            Ok(())
+        };
+
+        add("mimetype", b"application/epub+zip", stored)?;
+        add("META-INF/container.xml", CONTAINER.as_bytes(), deflated)?;
+        add("OEBPS/style.css", STYLE.as_bytes(), deflated)?;
+        add("OEBPS/content.opf", self.opf().as_bytes(), deflated)?;
+        add("OEBPS/nav.xhtml", self.nav().as_bytes(), deflated)?;
+        if let Some(cover) = &self.cover {
+            add(
+                &format!("OEBPS/cover.{}", cover.extension()),
+                &cover.bytes,
+                stored,
+            )?;
+            let body = format!(
+                r#"<div class="cover"><img src="cover.{}" alt="{}"/></div>"#,
+                cover.extension(),
+                escape(&self.title)
+            );
+            add(
+                "OEBPS/cover.xhtml",
+                xhtml(&self.title, &body).as_bytes(),
+                deflated,
+            )?;
+        }
+        add(
+            "OEBPS/title.xhtml",
+            xhtml(&self.title, &self.title_page()).as_bytes(),
+            deflated,
+        )?;
+        for (index, chapter) in self.chapters.iter().enumerate() {
+            let mut body = format!("<h2>{}</h2>\n", escape(&chapter.title));
+            for paragraph in &chapter.paragraphs {
+                body.push_str(&format!("<p>{}</p>\n", escape(paragraph)));
+            }
+            add(
+                &format!("OEBPS/{}", chapter_file(index)),
+                xhtml(&chapter.title, &body).as_bytes(),
+                deflated,
+            )?;
+        }
+        zip.finish()?.flush()?;
+        Ok(())
+    }
+
+    fn title_page(&self) -> String {
+        let mut body = format!(r#"<h1 class="title">{}</h1>"#, escape(&self.title));
+        if !self.author.is_empty() {
+            body.push_str(&format!(
+                r#"<p class="author">{}</p>"#,
+                escape(&self.author)
+            ));
+        }
+        for line in self.intro.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            body.push_str(&format!("<p>{}</p>", escape(line)));
+        }
+        body
+    }
+
+    fn opf(&self) -> String {
+        let mut metadata = format!(
+            "<dc:identifier id=\"book-id\">urn:trnovel:{}</dc:identifier>\n\
+             <dc:title>{}</dc:title>\n<dc:language>zh</dc:language>\n\
+             <meta property=\"dcterms:modified\">{}</meta>\n",
+            get_md5_string(&self.id),
+            escape(&self.title),
+            chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+        );
+        if !self.author.is_empty() {
+            metadata.push_str(&format!(
+                "<dc:creator>{}</dc:creator>\n",
+                escape(&self.author)
+            ));
+        }
+        if !self.intro.trim().is_empty() {
+            metadata.push_str(&format!(
+                "<dc:description>{}</dc:description>\n",
+                escape(self.intro.trim())
+            ));
+        }
+
+        let mut manifest = String::from(
+            "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n\
+             <item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n\
+             <item id=\"title\" href=\"title.xhtml\" media-type=\"application/xhtml+xml\"/>\n",
+        );
+        let mut spine = String::new();
+        if let Some(cover) = &self.cover {
+            // 老阅读器认 `<meta name="cover">`,EPUB 3 认 `properties="cover-image"`,两个都写。
+            metadata.push_str("<meta name=\"cover\" content=\"cover-image\"/>\n");
+            manifest.push_str(&format!(
+                "<item id=\"cover-image\" href=\"cover.{}\" media-type=\"{}\" properties=\"cover-image\"/>\n\
+                 <item id=\"cover\" href=\"cover.xhtml\" media-type=\"application/xhtml+xml\"/>\n",
+                cover.extension(),
+                cover.media_type
+            ));
+            spine.push_str("<itemref idref=\"cover\"/>\n");
+        }
+        spine.push_str("<itemref idref=\"title\"/>\n<itemref idref=\"nav\"/>\n");
+        for index in 0..self.chapters.len() {
+            manifest.push_str(&format!(
+                "<item id=\"c{index}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>\n",
+                chapter_file(index)
+            ));
+            spine.push_str(&format!("<itemref idref=\"c{index}\"/>\n"));
+        }
+
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\" xml:lang=\"zh\">\n\
+             <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n{metadata}</metadata>\n\
+             <manifest>\n{manifest}</manifest>\n\
+             <spine>\n{spine}</spine>\n\
+             </package>\n"
+        )
+    }
+
+    /// 导航文档:有分卷时按卷嵌套,卷条目指向该卷第一章;卷前的章节平铺在最前。
+    fn nav(&self) -> String {
+        let link = |index: usize, title: &str| {
+            format!("<a href=\"{}\">{}</a>", chapter_file(index), escape(title))
+        };
+        let mut list = String::new();
+        let mut in_volume = false;
+        for (index, chapter) in self.chapters.iter().enumerate() {
+            if let Some(volume) = self.volume_at(index) {
+                if in_volume {
+                    list.push_str("</ol></li>\n");
+                }
+                list.push_str(&format!("<li>{}<ol>\n", link(index, &volume.title)));
+                in_volume = true;
+            }
+            list.push_str(&format!("<li>{}</li>\n", link(index, &chapter.title)));
+        }
+        if in_volume {
+            list.push_str("</ol></li>\n");
+        }
+        let body =
+            format!("<nav epub:type=\"toc\" id=\"toc\"><h1>目录</h1>\n<ol>\n{list}</ol></nav>");
+        xhtml("目录", &body)
+    }
+}
+
+fn chapter_file(index: usize) -> String {
+    format!("chapter-{:05}.xhtml", index + 1)
+}
+
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+const STYLE: &str = "h1.title { margin-top: 30%; text-align: center; }
+p.author { text-align: center; text-indent: 0; }
+h2 { margin: 1em 0; text-align: center; }
+p { margin: 0.3em 0; text-indent: 2em; line-height: 1.6; }
+div.cover { text-align: center; }
+div.cover img { max-width: 100%; max-height: 100%; }
+";
+
+fn xhtml(title: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" xml:lang=\"zh\" lang=\"zh\">\n\
+         <head><meta charset=\"UTF-8\"/><title>{}</title><link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/></head>\n\
+         <body>\n{body}\n</body>\n</html>\n",
+        escape(title)
+    )
+}
+
+/// XML 转义;顺带丢掉 XML 不允许出现的控制字符(网页正文里偶尔混有)。
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// 读出一本书准备导出;每取完一章调用一次 `on_progress(已完成, 总数)`。
+///
+/// `id` 与历史记录相同:本地为小说路径,网络为 book 链接(须打开阅读过,有阅读存档)。
+/// `build` 决定网络小说的引擎怎么装配,同 [`crate::update::check_updates`]。
+pub async fn load_book(
+    id: &str,
+    sources: &BookSourceCache,
+    build: fn(BookSource) -> parse_book_source::Result<Engine>,
+    on_progress: impl Fn(usize, usize),
+) -> Result<ExportBook> {
+    if Path::new(id).is_file() {
+        return load_local(id, on_progress).await;
+    }
+
+    let cache =
+        NetworkNovelCache::try_from(id).map_err(|_| "找不到这本书的缓存,请先打开阅读一次")?;
+    let source = sources
+        .find_book_source(&cache.book_source_url, &cache.book_source_name)
+        .ok_or(format!("书源「{}」已被删除", cache.book_source_name))?;
+    let engine = build(source.clone())?.with_book_vars(cache.book_vars);
+    let mut novel = NetworkNovel::new(cache.book_list_item, engine);
+    let info = novel.fetch_book_info().await?;
+    let (chapters, volumes) = novel.request_toc().await?;
+    novel.set_chapters(&chapters);
+    novel.set_volumes(volumes.clone());
+
+    Ok(ExportBook {
+        id: id.to_string(),
+        cover: fetch_cover(&info.cover, novel.engine.source().http.proxy.as_ref()).await,
+        title: info.name,
+        author: info.author,
+        intro: info.intro,
+        chapters: collect_chapters(&novel, CONCURRENCY, on_progress).await?,
+        volumes,
+    })
+}
+
+async fn load_local(path: &str, on_progress: impl Fn(usize, usize)) -> Result<ExportBook> {
+    let mut novel = LocalNovel::from_path(path).await?;
+    if novel.get_chapters().is_none() {
+        let (chapters, volumes) = novel.request_toc().await?;
+        novel.set_chapters(&chapters);
+        novel.set_volumes(volumes);
+    }
+    Ok(ExportBook {
+        id: novel.get_id(),
+        title: novel
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        // 本地文件读到的是整段字节区间,逐章顺序读即可,不必并发。
+        chapters: collect_chapters(&novel, 1, on_progress).await?,
+        volumes: novel.get_volumes().to_vec(),
+        ..Default::default()
+    })
+}
+
+/// 按目录顺序取出全部章节正文;任何一章取不到都算导出失败,免得导出残缺的书。
+async fn collect_chapters<N: Novel>(
+    novel: &N,
+    concurrency: usize,
+    on_progress: impl Fn(usize, usize),
+) -> Result<Vec<ExportChapter>> {
+    let names = novel.get_chapters_names()?;
+    let total = names.len();
+    let done = AtomicUsize::new(0);
+    let (done, on_progress) = (&done, &on_progress);
+    stream::iter(names)
+        .map(|(title, index)| async move {
+            let text = novel
+                .chapter_content(index)
+                .await
+                .map_err(|e| format!("第 {} 章「{title}」获取失败:{e}", index + 1))?;
+            on_progress(done.fetch_add(1, Ordering::Relaxed) + 1, total);
+            Ok(ExportChapter::new(title, &text))
+        })
+        .buffered(concurrency)
+        .try_collect()
+        .await
+}
+
+/// 下载封面,与书源的其他请求走同一个代理;失败或格式不认识就不带封面,不影响导出。
+async fn fetch_cover(url: &str, proxy: Option<&Proxy>) -> Option<Cover> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return None;
+    }
+    let client = crate::cache::http_client(proxy).ok()?;
+    let request = async {
+        client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await
+    };
+    let bytes = tokio::time::timeout(Duration::from_secs(15), request)
+        .await
+        .ok()?
+        .ok()?;
+    Cover::from_bytes(bytes.to_vec())
+}
+
+/// 把命令行里的书名 / 路径 / 链接解析成书的 id:本地文件路径、有阅读存档的网络小说链接,
+/// 或书架、历史记录里的书名。
+pub fn resolve_book(book: &str) -> Result<String> {
+    let path = Path::new(book);
+    if path.is_file() {
+        return Ok(path.canonicalize()?.to_string_lossy().to_string());
+    }
+    if NetworkNovelCache::cache_path(book)?.exists() {
+        return Ok(book.to_string());
+    }
+    if let Some(found) = Bookshelf::load()?.books.iter().find(|b| b.title() == book) {
+        return Ok(found.id.clone());
+    }
+    let history = History::load()?;
+    let found = history.histories.iter().find(|(_, item)| match item {
+        crate::HistoryItem::Local(item) => item.title == book,
+        crate::HistoryItem::Network(item) => item.title == book,
+    });
+    found.map(|(id, _)| id.clone()).ok_or_else(|| {
+        format!("找不到《{book}》:请传本地小说路径、网络小说链接,或书架 / 历史记录里的书名").into()
+    })
+}
+
+/// 默认导出位置:系统「下载」目录(取不到时为当前目录)下的 `<书名>.<扩展名>`。
+pub fn default_path(title: &str, format: ExportFormat) -> PathBuf {
+    let name: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let name = if name.trim().is_empty() {
+        "novel"
+    } else {
+        name.trim()
+    };
+    dirs::download_dir()
+        .unwrap_or_default()
+        .join(format!("{name}.{}", format.extension()))
+}
+
+/// `export` 子命令:导出一本书,打印进度与输出路径。
+pub async fn run(book: &str, format: ExportFormat, out: Option<&Path>) {
+    let id = match resolve_book(book) {
+        Ok(id) => id,
+        Err(e) => return eprintln!("✗ {e}"),
+    };
+    let sources = match BookSourceCache::load() {
+        Ok(sources) => sources,
+        Err(e) => return eprintln!("✗ 读取书源缓存失败: {e}"),
+    };
+    let book = match load_book(&id, &sources, reqwest_engine, |done, total| {
+        eprint!("\r导出中 {done}/{total}");
+    })
+    .await
+    {
+        Ok(book) => book,
+        Err(e) => return eprintln!("\n✗ 导出失败: {e}"),
+    };
+    eprintln!();
+
+    let path = out.map_or_else(|| default_path(&book.title, format), Path::to_path_buf);
+    match book.save(format, &path) {
+        Ok(()) => println!(
+            "✓ 已导出《{}》({} 章)→ {}",
+            book.title,
+            book.chapters.len(),
+            path.display()
+        ),
+        Err(e) => eprintln!("✗ 写入 {} 失败: {e}", path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read};
+
+    fn book() -> ExportBook {
+        ExportBook {
+            id: "/book".to_string(),
+            title: "测试<书>".to_string(),
+            author: "作者".to_string(),
+            intro: "简介第一行\n\n简介第二行".to_string(),
+            cover: Cover::from_bytes(vec![0x89, b'P', b'N', b'G', 0, 0]),
+            chapters: vec![
+                ExportChapter::new("序章".to_string(), "  序章\n\n  开始了。\n"),
+                ExportChapter::new("第一章".to_string(), "第一段\r\n第二段 & 结尾"),
+                ExportChapter::new("第二章".to_string(), "第三段"),
+            ],
+            volumes: vec![VolumeMarker {
+                title: "第一卷".to_string(),
+                first_chapter_index: 1,
+            }],
+        }
+    }
+
+    #[test]
+    fn txt_has_headings_volumes_and_indented_paragraphs() {
+        let mut out = Vec::new();
+        book().write_txt(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("《测试<书>》\n作者:作者\n"));
+        // 正文里重复的章节标题被去掉,只保留作为标题的那一行。
+        assert_eq!(text.matches("序章").count(), 1);
+        assert!(text.contains("\n\n第一卷\n\n\n第一章\n\n　　第一段\n　　第二段 & 结尾\n"));
+    }
+
+    #[test]
+    fn epub_nests_chapters_under_volumes() {
+        let mut out = Cursor::new(Vec::new());
+        book().write_epub(&mut out).unwrap();
+        let mut zip = zip::ZipArchive::new(out).unwrap();
+
+        let mimetype = zip.by_index(0).unwrap();
+        assert_eq!(mimetype.name(), "mimetype");
+        assert_eq!(mimetype.compression(), CompressionMethod::Stored);
+        drop(mimetype);
+
+        let mut read = |name: &str| {
+            let mut text = String::new();
+            zip.by_name(name)
+                .unwrap()
+                .read_to_string(&mut text)
+                .unwrap();
+            text
+        };
+        let nav = read("OEBPS/nav.xhtml");
+        assert!(nav.contains(
+            "<li><a href=\"chapter-00001.xhtml\">序章</a></li>\n\
+             <li><a href=\"chapter-00002.xhtml\">第一卷</a><ol>\n\
+             <li><a href=\"chapter-00002.xhtml\">第一章</a></li>\n\
+             <li><a href=\"chapter-00003.xhtml\">第二章</a></li>\n\
+             </ol></li>"
+        ));
+        let opf = read("OEBPS/content.opf");
+        assert!(opf.contains("<dc:title>测试&lt;书&gt;</dc:title>"));
+        assert!(opf.contains("properties=\"cover-image\""));
+        assert!(read("OEBPS/chapter-00002.xhtml").contains("<p>第二段 &amp; 结尾</p>"));
+    }
+}
@@ -181,10 +181,12 @@ fn to_sfnt(bytes: Vec<u8>) -> Result<Vec<u8>> {
     }
 }
 
-/// 从 URL 或本地路径载入字体字节。
+/// 从 URL 或本地路径载入字体字节;URL 走全局默认代理。
 async fn load_font(src: &str) -> Result<Vec<u8>> {
     if src.starts_with("http://") || src.starts_with("https://") {
-        Ok(reqwest::get(src)
+        Ok(crate::cache::http_client(None)?
+            .get(src)
+            .send()
             .await?
             .error_for_status()?
             .bytes()
@@ -197,13 +199,15 @@ async fn load_font(src: &str) -> Result<Vec<u8>> {
 
 /// 确保基准字体存在(缓存到 `~/.novel/gen-fontmap/`),首次自动下载 Noto。
 async fn ensure_noto() -> Result<Vec<u8>> {
-    let dir = crate::utils::novel_catch_dir()?.join("gen-fontmap");
+    let dir = crate::data_dir::data_path("gen-fontmap")?;
     let path: PathBuf = dir.join("NotoSansCJKsc-Regular.otf");
     if path.exists() {
         return Ok(std::fs::read(&path)?);
     }
     eprintln!("未指定 --base-font,自动下载基准字体 Noto Sans CJK SC(~16MB)…");
-    let bytes = reqwest::get(NOTO_URL)
+    let bytes = crate::cache::http_client(None)?
+        .get(NOTO_URL)
+        .send()
         .await?
         .error_for_status()?
         .bytes()
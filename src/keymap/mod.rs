@@ -31,6 +31,13 @@ pub enum ReaderAction {
     ToggleReadMode,
     ToggleInfo,
     ToggleTts,
+    ToggleSelect,
+    CancelSelect,
+    Yank,
+    YankToFile,
+    Lookup,
+    ToggleNames,
+    ChangeSource,
 }
 
 /// 全应用键位表。挂 `Arc` 供 `use_keymap_handler` 每帧克隆(引用计数,非深拷贝)。
@@ -49,6 +56,7 @@ impl Default for AppKeymap {
 
 /// 默认表逐键对照迁移前的硬编码键位(含 i/I、t/T 的大小写双绑定;方向键绑在
 /// 首位,帮助/提示取首键显示时保持「↑ / ↓」的既有视觉习惯)。
+/// 隐藏标题由 `v` 让位给选择模式(vim 的可视模式键),改为 `f`。
 fn reader_defaults() -> Keymap<ReaderAction> {
     Keymap::builder()
         .bind(ReaderAction::ScrollUp, ["up", "k"])
@@ -73,7 +81,7 @@ fn reader_defaults() -> Keymap<ReaderAction> {
         .desc(ReaderAction::VolumeDown, "减小音量")
         .bind(ReaderAction::TogglePlay, ["p"])
         .desc(ReaderAction::TogglePlay, "播放/暂停")
-        .bind(ReaderAction::ToggleTitle, ["v"])
+        .bind(ReaderAction::ToggleTitle, ["f"])
         .desc(ReaderAction::ToggleTitle, "隐藏/显示标题")
         .bind(ReaderAction::ToggleReadMode, ["tab"])
         .desc(ReaderAction::ToggleReadMode, "切换章节选择/阅读模式")
@@ -81,6 +89,20 @@ fn reader_defaults() -> Keymap<ReaderAction> {
         .desc(ReaderAction::ToggleInfo, "打开/关闭快捷键帮助")
         .bind(ReaderAction::ToggleTts, ["t", "T"])
         .desc(ReaderAction::ToggleTts, "打开/关闭TTS设置")
+        .bind(ReaderAction::ToggleSelect, ["v"])
+        .desc(ReaderAction::ToggleSelect, "进入/退出选择模式")
+        .bind(ReaderAction::CancelSelect, ["esc"])
+        .desc(ReaderAction::CancelSelect, "取消选择")
+        .bind(ReaderAction::Yank, ["y"])
+        .desc(ReaderAction::Yank, "复制选中文字到剪贴板")
+        .bind(ReaderAction::YankToFile, ["Y"])
+        .desc(ReaderAction::YankToFile, "复制并追加到摘录文件")
+        .bind(ReaderAction::Lookup, ["d"])
+        .desc(ReaderAction::Lookup, "查词(离线词典)")
+        .bind(ReaderAction::ToggleNames, ["n"])
+        .desc(ReaderAction::ToggleNames, "打开/关闭人物索引")
+        .bind(ReaderAction::ChangeSource, ["c"])
+        .desc(ReaderAction::ChangeSource, "换源(网络小说)")
         .build()
 }
 
@@ -91,10 +113,9 @@ fn reader_defaults() -> Keymap<ReaderAction> {
 /// 未知操作)由 crate 降级为逐条告警。
 pub fn load_keymap() -> (AppKeymap, Vec<String>) {
     let mut keymap = AppKeymap::default();
-    let Ok(dir) = crate::utils::novel_catch_dir() else {
+    let Ok(path) = crate::data_dir::data_path("keybindings.toml") else {
         return (keymap, Vec::new());
     };
-    let path = dir.join("keybindings.toml");
     if !path.exists() {
         return (keymap, Vec::new());
     }
@@ -13,6 +13,16 @@ fn reader_defaults_build_and_match_legacy_keys() {
     assert_eq!(km.action_for(key!('+')), Some(ReaderAction::VolumeUp));
     assert_eq!(km.action_for(key!('-')), Some(ReaderAction::VolumeDown));
     assert_eq!(km.action_for(key!(tab)), Some(ReaderAction::ToggleReadMode));
+    // v 让位给选择模式,隐藏标题改为 f。
+    assert_eq!(km.action_for(key!(v)), Some(ReaderAction::ToggleSelect));
+    assert_eq!(km.action_for(key!(f)), Some(ReaderAction::ToggleTitle));
+    assert_eq!(km.action_for(key!(y)), Some(ReaderAction::Yank));
+    assert_eq!(km.action_for(key!(d)), Some(ReaderAction::Lookup));
+    assert_eq!(km.action_for(key!(n)), Some(ReaderAction::ToggleNames));
+    assert_eq!(
+        km.action_for(key!(shift - y)),
+        Some(ReaderAction::YankToFile)
+    );
     // i/I、t/T 大小写双绑定(I = shift-i)。
     assert_eq!(km.action_for(key!(i)), Some(ReaderAction::ToggleInfo));
     assert_eq!(
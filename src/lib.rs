@@ -4,14 +4,19 @@ use app::App;
 use clap::{Parser, Subcommand};
 use ratatui_kit::{ElementExt, element};
 use std::{env, ffi::OsString, fmt::Debug, fs, path::PathBuf};
-use utils::novel_catch_dir;
 
 pub mod app;
+pub mod backup;
 pub mod browser_assist;
 pub mod cache;
+pub mod clipboard;
 pub mod components;
+pub mod data_dir;
+pub mod dict;
 pub mod doctor;
+pub mod download;
 pub mod errors;
+pub mod export;
 pub mod file_list;
 pub mod gen_fontmap;
 pub mod hooks;
@@ -20,13 +25,41 @@ pub mod keymap;
 pub mod login;
 pub mod novel;
 pub mod pages;
+pub mod proxy;
+pub mod selection;
 pub mod state;
+pub mod storage;
+pub mod sync;
 pub mod theme;
+pub mod update;
 pub mod utils;
 
 pub use cache::*;
 pub use errors::Result;
 
+/// 测试共用工具。
+#[cfg(test)]
+pub(crate) mod testutil {
+    use parse_book_source::BookSource;
+
+    /// 最小书源:名为 `name`,网址为 `https://{name}.example`。
+    pub(crate) fn source(name: &str) -> BookSource {
+        serde_json::from_value(serde_json::json!({
+            "schema": "trnovel-booksource/v2",
+            "name": name,
+            "url": format!("https://{name}.example"),
+            "bookInfo": {},
+            "toc": {
+                "list": {"via": "css", "select": "a"},
+                "name": {"via": "css", "select": "a"},
+                "url": {"via": "css", "select": "a"}
+            },
+            "content": {"value": {"via": "css", "select": "p"}}
+        }))
+        .expect("minimal book source")
+    }
+}
+
 use crate::app::AppProps;
 
 pub async fn run() -> Result<()> {
@@ -45,6 +78,7 @@ where
             Ok(())
         }
     };
+    cache::source_health::flush().await;
     parse_book_source::shutdown_render_pool().await;
     result
 }
@@ -55,15 +89,91 @@ where
     A: Into<OsString> + Clone,
 {
     let trnovel = TRNovel::parse_from(args);
+    data_dir::init(trnovel.data_dir.clone())?;
 
     if let Some(Commands::Clear) = trnovel.subcommand {
-        fs::remove_dir_all(novel_catch_dir()?)?;
+        for root in data_dir::data_roots()? {
+            match fs::remove_dir_all(root) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        return Ok(());
+    }
+
+    // 缓存管理:非 TUI,统计或清理 ~/.novel 下的缓存后退出。
+    if let Some(Commands::Cache { action }) = &trnovel.subcommand {
+        storage::run(action.as_ref());
         return Ok(());
     }
 
     // 书源体检:非 TUI,跑全流程后打印 ✓/✗ 列表并退出。
-    if let Some(Commands::Doctor { path }) = &trnovel.subcommand {
-        doctor::run(path).await;
+    if let Some(Commands::Doctor {
+        path,
+        record,
+        replay,
+        trace,
+    }) = &trnovel.subcommand
+    {
+        doctor::run(
+            path.as_deref(),
+            record.as_deref(),
+            replay.as_deref(),
+            *trace,
+        )
+        .await;
+        return Ok(());
+    }
+
+    // 追更检查:非 TUI,检查书架上网络小说的新章节,打印汇总表后退出。
+    if let Some(Commands::Update) = &trnovel.subcommand {
+        update::run().await;
+        return Ok(());
+    }
+
+    // 导出:非 TUI,把一本书写成 TXT / EPUB 后退出。
+    if let Some(Commands::Export { book, format, out }) = &trnovel.subcommand {
+        export::run(book, *format, out.as_deref()).await;
+        return Ok(());
+    }
+
+    // 备份 / 恢复:非 TUI,打包或还原 ~/.novel 下的用户数据后退出。
+    if let Some(Commands::Backup { out, include_login }) = &trnovel.subcommand {
+        backup::run_backup(out.as_deref(), *include_login);
+        return Ok(());
+    }
+    if let Some(Commands::Restore { path, mode }) = &trnovel.subcommand {
+        backup::run_restore(path, *mode);
+        return Ok(());
+    }
+
+    // 同步:非 TUI,与 WebDAV 远端合并一次后退出;带 --url 时先保存配置。
+    if let Some(Commands::Sync {
+        url,
+        username,
+        dir,
+        no_auto,
+    }) = &trnovel.subcommand
+    {
+        sync::run(
+            url.as_deref(),
+            username.as_deref(),
+            dir.as_deref(),
+            *no_auto,
+        )
+        .await;
+        return Ok(());
+    }
+
+    // 代理:非 TUI,保存 / 查看 / 删除全局默认代理后退出。
+    if let Some(Commands::Proxy {
+        url,
+        username,
+        no_proxy,
+        clear,
+    }) = &trnovel.subcommand
+    {
+        proxy::run(url.as_deref(), username.as_deref(), no_proxy, *clear);
         return Ok(());
     }
 
@@ -84,10 +194,15 @@ where
         return Ok(());
     }
 
+    // 配置了自动同步时,进入界面前拉取其他设备的进度,退出后推送本机的进度。
+    sync::auto_sync().await;
+
     let props = AppProps { trnovel };
 
     element!(App(..props)).fullscreen().await?;
 
+    sync::auto_sync().await;
+
     Ok(())
 }
 
@@ -109,6 +224,7 @@ where
   TRNovel 是一个终端小说阅读器，支持以下功能。
     - 本地小说
     - 网络小说
+    - 我的书架
     - 历史记录
     - 主题设置
 
@@ -118,6 +234,10 @@ where
 "#
 )]
 pub struct TRNovel {
+    /// 数据目录,默认 ~/.novel;也可用环境变量 TRNOVEL_HOME 指定
+    #[arg(long, global = true, value_name = "DIR")]
+    pub data_dir: Option<PathBuf>,
+
     #[command(subcommand)]
     pub subcommand: Option<Commands>,
 }
@@ -132,6 +252,12 @@ pub enum Commands {
     #[command(short_flag = 'c')]
     Clear,
 
+    /// 缓存管理：按类别查看磁盘占用,清理孤立存档、删除单本书的数据或只清空选定的类别
+    Cache {
+        #[command(subcommand)]
+        action: Option<storage::CacheAction>,
+    },
+
     /// 网络模式，使用网络小说源
     #[command(short_flag = 'n')]
     Network,
@@ -147,11 +273,93 @@ pub enum Commands {
     #[command(short_flag = 'H')]
     History,
 
+    /// 书架模式，按分组、标签管理收藏的小说
+    #[command(short_flag = 's')]
+    Shelf,
+
+    /// 追更检查：检查书架上网络小说的新章节并打印汇总表(不进入界面)
+    #[command(short_flag = 'u')]
+    Update,
+
+    /// 导出小说：把一本书写成 TXT 或 EPUB(网络小说优先用已下载的章节,缺的联网补齐)
+    #[command(short_flag = 'e')]
+    Export {
+        /// 本地小说路径、网络小说链接,或书架 / 历史记录里的书名
+        book: String,
+        /// 导出格式
+        #[arg(short, long, value_enum, default_value_t)]
+        format: export::ExportFormat,
+        /// 输出文件路径(缺省为系统下载目录下的 <书名>.<格式>)
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+
+    /// 备份：把历史记录、书架、书源、设置等用户数据打包成一个文件(不含章节缓存)
+    Backup {
+        /// 输出文件路径(缺省为当前目录下的 trnovel-backup-<时间>.zip)
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+        /// 同时备份书源登录态,用口令加密(口令也可通过环境变量 TRNOVEL_BACKUP_PASSPHRASE 传入)
+        #[arg(long)]
+        include_login: bool,
+    },
+
+    /// 恢复：从 backup 生成的文件还原用户数据
+    Restore {
+        /// 备份文件路径
+        path: PathBuf,
+        /// 恢复模式
+        #[arg(short, long, value_enum, default_value_t)]
+        mode: backup::RestoreMode,
+    },
+
+    /// 同步：通过 WebDAV 在多台设备之间合并阅读进度、书架、历史记录、书源与设置
+    Sync {
+        /// 设置 WebDAV 地址(同时保存下面的用户名等配置)
+        #[arg(long)]
+        url: Option<String>,
+        /// WebDAV 用户名;密码在终端输入,或通过环境变量 TRNOVEL_SYNC_PASSWORD 传入
+        #[arg(short, long)]
+        username: Option<String>,
+        /// 远端存放同步数据的目录(默认 trnovel)
+        #[arg(long)]
+        dir: Option<String>,
+        /// 不在启动和退出时自动同步
+        #[arg(long)]
+        no_auto: bool,
+    },
+
+    /// 代理：设置全局默认代理(HTTP / HTTPS / SOCKS5),没配 http.proxy 的书源都走它;不带参数时查看当前配置
+    Proxy {
+        /// 代理地址,如 socks5://127.0.0.1:1080
+        #[arg(conflicts_with = "clear")]
+        url: Option<String>,
+        /// 代理用户名;密码在终端输入,或通过环境变量 TRNOVEL_PROXY_PASSWORD 传入
+        #[arg(short, long, requires = "url")]
+        username: Option<String>,
+        /// 不走代理的主机,逗号分隔(域名同时匹配子域,也可写 IP / CIDR)
+        #[arg(long, value_delimiter = ',', requires = "url")]
+        no_proxy: Vec<String>,
+        /// 删除全局代理,恢复直连
+        #[arg(long)]
+        clear: bool,
+    },
+
     /// 体检书源：全流程验证书源 JSON,逐项报告 ✓/✗(用于校验 AI 生成的书源)
     #[command(short_flag = 'd')]
     Doctor {
-        /// 书源 JSON 文件路径
-        path: PathBuf,
+        /// 书源 JSON 文件路径;回放时可省略,默认用录制目录里的 source.json
+        #[arg(required_unless_present = "replay")]
+        path: Option<PathBuf>,
+        /// 把体检中的每次取页录到这个目录(连同书源),可整个附在问题报告里
+        #[arg(long, value_name = "目录", conflicts_with = "replay")]
+        record: Option<PathBuf>,
+        /// 不联网,回放 --record 录下的目录
+        #[arg(long, value_name = "目录")]
+        replay: Option<PathBuf>,
+        /// 追踪这一操作的规则求值,报告后打印每个规则节点的输入、匹配、清洗步骤与变量
+        #[arg(long, value_enum, value_name = "操作")]
+        trace: Option<doctor::TraceOp>,
     },
 
     /// 导入书源：把书源 JSON(本地文件或 URL)写入 ~/.novel,使其在网络小说里可用
@@ -8,7 +8,7 @@ use crate::cache::{load_source_state, save_source_state};
 use anyhow::anyhow;
 use parse_book_source::cookie::{merge_cookie_str, registrable_domain};
 use parse_book_source::{
-    BookSource, BrowserFetcher, BrowserOptions, Fetcher, LoginCriteria, LoginSignal, ReqwestFetcher,
+    BookSource, BrowserFetcher, Fetcher, LoginCriteria, LoginSignal, ReqwestFetcher,
 };
 use std::collections::BTreeMap;
 use std::sync::Arc;
@@ -50,6 +50,8 @@ pub async fn script_login(mut source: BookSource, login_info: Option<String>) ->
     if source.http.timeout.is_none() {
         source.http.timeout = Some(30_000);
     }
+    // 登录脚本的 `net.*` 与阅读时走同一代理。
+    crate::cache::apply_global_proxy(&mut source);
     let url = source.url.clone();
     let mut state = load_source_state(&url);
     if let Some(plain) = login_info {
@@ -72,12 +74,14 @@ pub async fn script_login(mut source: BookSource, login_info: Option<String>) ->
 
 /// 浏览器登录:headful 打开 `loginUrl` 让用户手动登录,提取 cookie(含 HttpOnly)+ localStorage 里的
 /// JWT 落盘。`signal` 由 UI 持同一 `Arc`:用户「完成」置 `done`、「取消」置 `cancel`。
-pub async fn browser_login(source: BookSource, signal: LoginSignal) -> crate::Result<()> {
+pub async fn browser_login(mut source: BookSource, signal: LoginSignal) -> crate::Result<()> {
+    crate::cache::apply_global_proxy(&mut source);
     let target = source.login_url.trim();
     if target.is_empty() {
         return Err(anyhow!("该书源未配置 loginUrl,无法浏览器登录").into());
     }
-    let browser = BrowserFetcher::detect(BrowserOptions::default())
+    let browser = BrowserFetcher::detect(crate::browser_assist::browser_options())
+        .map(|browser| browser.with_proxy(source.http.proxy.clone()))
         .ok_or_else(|| anyhow!("未探测到系统浏览器(Chrome/Edge/Brave/…),无法浏览器登录"))?;
     // 复位上次尝试残留的 done/cancel 标志(信号跨重试共享同一 Arc):
     // 残留 cancel 会让重试首轮即判「用户取消」;残留 done 会把未登录空产物当成功落盘。
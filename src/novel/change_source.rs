@@ -0,0 +1,394 @@
+//! 换源:在其他书源里找到同一本书,按章节名把阅读进度搬过去。
+//!
+//! 候选按书名、作者的相似度排序,分数相同时最新章节更靠后的排前面;换源后在新目录里
+//! 找与当前章节同名(或同序号)的一章接着读,找不到时退回原来的章节下标。
+
+use crate::{
+    Result,
+    cache::{
+        NetworkNovelCache, SourceHealth,
+        source_health::{self, FailureKind},
+    },
+    history::HistoryItem,
+    novel::{Novel, network_novel::NetworkNovel},
+};
+use futures::{StreamExt, stream};
+use parse_book_source::{BookListItem, BookSource, Chapter};
+use std::time::{Duration, Instant};
+
+/// 单个书源搜索的超时,慢源不拖住整个列表。
+pub(crate) const SEARCH_TIMEOUT: Duration = Duration::from_secs(20);
+/// 同时搜索的书源数。
+const CONCURRENCY: usize = 4;
+
+/// 一个换源候选。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub source: BookSource,
+    pub item: BookListItem,
+    /// 书名、作者的匹配分,越高越像同一本书。
+    pub score: u32,
+    /// 从最新章节名里读出的章节序号。
+    pub latest: Option<u64>,
+}
+
+/// 全部书源的搜索结果。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Candidates {
+    pub items: Vec<Candidate>,
+    /// 参与搜索的书源数。
+    pub searched: usize,
+    /// 搜索失败或超时的书源数。
+    pub failed: usize,
+    /// 因连续失败被跳过的书源数。
+    pub skipped: usize,
+}
+
+/// 只留文字与数字并转小写,比较书名、作者、章节名时忽略空白、标点与书名号。
+pub(crate) fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 书名、作者的匹配分;书名对不上返回 `None`。
+fn score(name: &str, author: &str, item: &BookListItem) -> Option<u32> {
+    let (name, found) = (normalize(name), normalize(&item.info.name));
+    if name.is_empty() || found.is_empty() {
+        return None;
+    }
+    let name_score = if name == found {
+        100
+    } else if name.contains(&found) || found.contains(&name) {
+        40
+    } else {
+        return None;
+    };
+    let (author, found) = (normalize(author), normalize(&item.info.author));
+    let author_score = if author.is_empty() || found.is_empty() {
+        10
+    } else if author.contains(&found) || found.contains(&author) {
+        50
+    } else {
+        0
+    };
+    Some(name_score + author_score)
+}
+
+/// 分数高的在前,分数相同时最新章节序号大的在前。
+fn rank(candidates: &mut [Candidate]) {
+    candidates.sort_by(|a, b| b.score.cmp(&a.score).then(b.latest.cmp(&a.latest)));
+}
+
+/// 解析中文数字(`一百二十三`、`两千零五`),也接受阿拉伯数字;空串不是数字。
+fn parse_number(text: &str) -> Option<u64> {
+    if let Ok(number) = text.parse() {
+        return Some(number);
+    }
+    // 其余字符都会让下面直接返回 `None`,非空就一定读到了数字或单位。
+    if text.is_empty() {
+        return None;
+    }
+    let (mut total, mut section, mut digit) = (0u64, 0u64, 0u64);
+    for c in text.chars() {
+        match c {
+            '零' | '〇' => digit = 0,
+            '一' => digit = 1,
+            '二' | '两' => digit = 2,
+            '三' => digit = 3,
+            '四' => digit = 4,
+            '五' => digit = 5,
+            '六' => digit = 6,
+            '七' => digit = 7,
+            '八' => digit = 8,
+            '九' => digit = 9,
+            '十' | '百' | '千' => {
+                let unit = match c {
+                    '十' => 10,
+                    '百' => 100,
+                    _ => 1000,
+                };
+                section += digit.max(1) * unit;
+                digit = 0;
+            }
+            '万' => {
+                total += (section + digit) * 10_000;
+                (section, digit) = (0, 0);
+            }
+            _ => return None,
+        }
+    }
+    Some(total + section + digit)
+}
+
+/// 从章节名里读出章节序号与去掉序号后的标题:`第十二章 风起` → `(12, "风起")`,
+/// `12. 风起` → `(12, "风起")`。
+fn split_title(title: &str) -> (Option<u64>, String) {
+    let title = title.trim();
+    if let Some(rest) = title.strip_prefix('第')
+        && let Some(end) = rest.find(['章', '回', '节', '话', '集'])
+        && let Some(number) = parse_number(rest[..end].trim())
+    {
+        let body = &rest[end..];
+        let body = &body[body.chars().next().map_or(0, char::len_utf8)..];
+        return (Some(number), normalize(body));
+    }
+    let digits: String = title.chars().take_while(char::is_ascii_digit).collect();
+    match digits.parse() {
+        Ok(number) => (Some(number), normalize(&title[digits.len()..])),
+        Err(_) => (None, normalize(title)),
+    }
+}
+
+/// 在新目录里找与 `title` 对应的章节:先比完整章节名,再比章节序号,最后比去掉序号后的标题。
+/// 有多章符合时取下标离 `hint`(原来的章节下标)最近的一章。
+pub fn match_chapter(chapters: &[Chapter], title: &str, hint: usize) -> Option<usize> {
+    let closest =
+        |matches: &mut dyn Iterator<Item = usize>| matches.min_by_key(|index| index.abs_diff(hint));
+    let chapters: Vec<(usize, &Chapter)> = chapters
+        .iter()
+        .enumerate()
+        .filter(|(_, chapter)| !chapter.is_volume)
+        .collect();
+
+    let full = normalize(title);
+    if !full.is_empty()
+        && let Some(index) = closest(
+            &mut chapters
+                .iter()
+                .filter(|(_, c)| normalize(&c.title) == full)
+                .map(|(i, _)| *i),
+        )
+    {
+        return Some(index);
+    }
+
+    let (number, body) = split_title(title);
+    let split: Vec<(usize, (Option<u64>, String))> = chapters
+        .iter()
+        .map(|(i, c)| (*i, split_title(&c.title)))
+        .collect();
+    if number.is_some()
+        && let Some(index) = closest(
+            &mut split
+                .iter()
+                .filter(|(_, (n, _))| *n == number)
+                .map(|(i, _)| *i),
+        )
+    {
+        return Some(index);
+    }
+    if body.is_empty() {
+        return None;
+    }
+    closest(
+        &mut split
+            .iter()
+            .filter(|(_, (_, b))| *b == body)
+            .map(|(i, _)| *i),
+    )
+}
+
+/// 在一个书源里按书名搜索,留下像同一本书的结果;搜索失败或超时返回 `None`。
+async fn search_source(source: BookSource, book: &NetworkNovelCache) -> Option<Vec<Candidate>> {
+    let name = &book.book_list_item.info.name;
+    let author = &book.book_list_item.info.author;
+    let engine = crate::browser_assist::build_engine(source.clone()).ok()?;
+    let started = Instant::now();
+    let list = match tokio::time::timeout(SEARCH_TIMEOUT, engine.search(name, 1, 20)).await {
+        Err(_) => {
+            source_health::record_failure(&source.url, FailureKind::Network);
+            return None;
+        }
+        Ok(Err(e)) => {
+            source_health::record_error(&source.url, &e);
+            return None;
+        }
+        Ok(Ok(list)) => {
+            source_health::record_success(&source.url, started.elapsed());
+            list
+        }
+    };
+    Some(
+        list.items
+            .into_iter()
+            .filter(|item| {
+                source.url != book.book_source_url || item.book_url != book.book_list_item.book_url
+            })
+            .filter_map(|item| {
+                Some(Candidate {
+                    score: score(name, author, &item)?,
+                    latest: split_title(&item.info.last_chapter).0,
+                    source: source.clone(),
+                    item,
+                })
+            })
+            .collect(),
+    )
+}
+
+/// 在全部配置了搜索的书源里按书名搜索,挑出同一本书的候选(不含当前这本)。
+/// 连续失败而降级的书源跳过。
+pub async fn search(sources: &[BookSource], book: &NetworkNovelCache) -> Candidates {
+    let sources: Vec<BookSource> = sources
+        .iter()
+        .filter(|s| s.search.is_some())
+        .cloned()
+        .collect();
+    let (sources, skipped) = SourceHealth::load().unwrap_or_default().triage(&sources);
+    let skipped = skipped.len();
+    let searched = sources.len();
+
+    let results: Vec<Option<Vec<Candidate>>> = stream::iter(sources)
+        .map(|source| search_source(source, book))
+        .buffer_unordered(CONCURRENCY)
+        .collect()
+        .await;
+
+    let failed = results.iter().filter(|r| r.is_none()).count();
+    let mut items: Vec<Candidate> = results.into_iter().flatten().flatten().collect();
+    rank(&mut items);
+    Candidates {
+        items,
+        searched,
+        failed,
+        skipped,
+    }
+}
+
+/// 换源结果。没找到对应章节时沿用原来的章节下标、从章首读起。
+#[derive(Debug, Clone)]
+pub struct Switched {
+    /// 已加载好目录、定位到对应章节的新书。
+    pub novel: NetworkNovel,
+    /// 新书源下的存档,已写盘。
+    pub item: HistoryItem,
+}
+
+/// 切换到 `candidate`:拉取详情与目录,搬运阅读进度,在新书源下重写阅读存档并删掉旧存档。
+pub async fn switch(book: &NetworkNovelCache, candidate: &Candidate) -> Result<Switched> {
+    let engine = crate::browser_assist::build_engine(candidate.source.clone())?;
+    let mut novel = NetworkNovel::new(candidate.item.clone(), engine);
+    novel.fetch_book_info().await?;
+    let (chapters, volumes) = novel.request_toc().await?;
+    if chapters.is_empty() {
+        return Err("新书源的目录是空的".into());
+    }
+    novel.set_chapters(&chapters);
+    novel.set_volumes(volumes);
+
+    let matched = match_chapter(&chapters, &book.current_chapter_name, book.current_chapter);
+    novel.current_chapter = matched.unwrap_or(book.current_chapter.min(chapters.len() - 1));
+    novel.line_percent = if matched.is_some() {
+        book.line_percent
+    } else {
+        0.0
+    };
+
+    let item = novel.to_history_item()?;
+    let old = NetworkNovelCache::cache_path(&book.book_list_item.book_url)?;
+    if old != novel.cache_path()? {
+        match std::fs::remove_file(old) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    Ok(Switched { novel, item })
+}
+
+/// 详情页换源用的存档:读过的书取阅读存档,没读过的从第一章开始。
+pub fn detail_snapshot(novel: &NetworkNovel) -> NetworkNovelCache {
+    let source = novel.engine.source();
+    let mut book = NetworkNovelCache::try_from(novel.book_list_item.book_url.as_str())
+        .unwrap_or_else(|_| NetworkNovelCache {
+            book_list_item: novel.book_list_item.clone(),
+            book_source_url: source.url.clone(),
+            book_source_name: source.name.clone(),
+            current_chapter: 0,
+            current_chapter_name: String::new(),
+            line_percent: 0.0,
+            chapter_percent: 0.0,
+            book_vars: novel.engine.book_vars(),
+        });
+    // 详情页拿到的书名、作者比搜索列表里的全。
+    if let Some(info) = &novel.book_info {
+        book.book_list_item.info = info.clone();
+    }
+    book
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parse_book_source::BookInfo;
+
+    fn chapters(titles: &[&str]) -> Vec<Chapter> {
+        titles
+            .iter()
+            .map(|title| Chapter {
+                title: title.to_string(),
+                url: String::new(),
+                is_volume: false,
+            })
+            .collect()
+    }
+
+    fn item(name: &str, author: &str) -> BookListItem {
+        BookListItem {
+            info: BookInfo {
+                name: name.to_string(),
+                author: author.to_string(),
+                ..Default::default()
+            },
+            book_url: String::new(),
+        }
+    }
+
+    #[test]
+    fn chapters_match_by_title_number_or_body() {
+        let toc = chapters(&["序章", "第一章 开端", "第二章 风起", "第3章 云涌"]);
+        assert_eq!(match_chapter(&toc, "第二章  风起", 0), Some(2));
+        assert_eq!(match_chapter(&toc, "2. 风起云", 0), Some(2));
+        assert_eq!(match_chapter(&toc, "第三章 云涌(求月票)", 0), Some(3));
+        assert_eq!(match_chapter(&toc, "序章", 3), Some(0));
+        assert_eq!(match_chapter(&toc, "番外", 1), None);
+        assert_eq!(parse_number("一百二十三"), Some(123));
+        assert_eq!(parse_number("两千零五"), Some(2005));
+        assert_eq!(parse_number("十"), Some(10));
+        assert_eq!(parse_number(""), None);
+        assert_eq!(split_title("第章 风起").0, None);
+    }
+
+    #[test]
+    fn candidates_rank_by_name_author_then_latest_chapter() {
+        assert_eq!(
+            score("诡秘之主", "爱潜水的乌贼", &item("诡秘之主", "乌贼")),
+            Some(150)
+        );
+        assert_eq!(
+            score("诡秘之主", "爱潜水的乌贼", &item("诡秘之主", "别人")),
+            Some(100)
+        );
+        assert_eq!(
+            score("诡秘之主", "", &item("《诡秘之主》全本", "")),
+            Some(50)
+        );
+        assert_eq!(score("诡秘之主", "", &item("宿命之环", "")), None);
+
+        let candidate = |score, latest| Candidate {
+            source: crate::testutil::source("s"),
+            item: item("a", ""),
+            score,
+            latest,
+        };
+        let mut list = vec![
+            candidate(100, Some(5)),
+            candidate(150, None),
+            candidate(100, Some(9)),
+        ];
+        rank(&mut list);
+        let order: Vec<_> = list.iter().map(|c| (c.score, c.latest)).collect();
+        assert_eq!(order, vec![(150, None), (100, Some(9)), (100, Some(5))]);
+    }
+}
@@ -0,0 +1,271 @@
+//! 聚合搜索:用同一个关键词并发搜索全部书源,结果边到边合并。
+//!
+//! 书名、作者(忽略空白、标点)都相同的结果算同一本书,合并成一条并记下收录它的书源;
+//! 每个书源单独超时,失败或被反爬拦截的书源只记在状态里,不影响其他书源。
+//! 每个书源的结果记入 [`source_health`],连续失败而降级的书源不参与搜索。
+
+use crate::cache::source_health::{self, FailureKind};
+use crate::novel::change_source::{SEARCH_TIMEOUT, normalize};
+use futures::{Stream, StreamExt, stream};
+use parse_book_source::{BookListItem, BookSource};
+use std::time::Instant;
+
+/// 同时搜索的书源数。
+const CONCURRENCY: usize = 8;
+/// 每个书源取的结果数。
+const PAGE_SIZE: u32 = 20;
+
+/// 单个书源的搜索状态。
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceStatus {
+    Searching,
+    /// 搜索完成,附命中条数。
+    Done(usize),
+    /// 被反爬挑战拦截。
+    Challenged,
+    TimedOut,
+    Failed(String),
+}
+
+impl SourceStatus {
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, SourceStatus::Searching)
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(
+            self,
+            SourceStatus::Challenged | SourceStatus::TimedOut | SourceStatus::Failed(_)
+        )
+    }
+
+    /// 状态栏里的简短说明。
+    pub fn label(&self) -> String {
+        match self {
+            SourceStatus::Searching => "搜索中".to_string(),
+            SourceStatus::Done(count) => format!("{count} 条"),
+            SourceStatus::Challenged => "被反爬拦截".to_string(),
+            SourceStatus::TimedOut => "超时".to_string(),
+            SourceStatus::Failed(_) => "失败".to_string(),
+        }
+    }
+}
+
+/// 某个书源里搜到的一条结果。
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceHit {
+    pub source: BookSource,
+    pub item: BookListItem,
+}
+
+/// 合并后的一本书。
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergedBook {
+    /// 合并用的键:规整后的书名与作者。
+    key: (String, String),
+    /// 收录这本书的书源,按结果到达的先后排列。
+    pub hits: Vec<SourceHit>,
+}
+
+impl MergedBook {
+    /// 展示用的条目:取信息最全(简介、最新章节非空)的那条。
+    pub fn item(&self) -> &BookListItem {
+        self.hits
+            .iter()
+            .map(|hit| &hit.item)
+            .max_by_key(|item| {
+                (!item.info.intro.is_empty()) as u8 + (!item.info.last_chapter.is_empty()) as u8
+            })
+            .expect("合并结果至少有一个书源")
+    }
+
+    /// 收录这本书的书源名,用 ` / ` 连接。
+    pub fn source_names(&self) -> String {
+        self.hits
+            .iter()
+            .map(|hit| hit.source.name.as_str())
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+}
+
+/// 一次聚合搜索的进度与已合并的结果。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GlobalSearch {
+    /// 参与搜索的书源名与状态,下标与 [`search`] 产出的下标一致。
+    pub sources: Vec<(String, SourceStatus)>,
+    /// 合并后的结果,按第一次出现的先后排列,结果陆续到达时已有条目的位置不变。
+    pub books: Vec<MergedBook>,
+    /// 因连续失败被跳过的书源名。
+    pub skipped: Vec<String>,
+}
+
+impl GlobalSearch {
+    pub fn new(sources: &[BookSource]) -> Self {
+        Self {
+            sources: sources
+                .iter()
+                .map(|source| (source.name.clone(), SourceStatus::Searching))
+                .collect(),
+            books: vec![],
+            skipped: vec![],
+        }
+    }
+
+    /// 记下第 `index` 个书源的结果,把命中合并进列表。
+    pub fn finish(&mut self, index: usize, source: &BookSource, result: SourceResult) {
+        let status = match result {
+            Ok(items) => {
+                let count = items.len();
+                for item in items {
+                    self.merge(source, item);
+                }
+                SourceStatus::Done(count)
+            }
+            Err(status) => status,
+        };
+        if let Some((_, slot)) = self.sources.get_mut(index) {
+            *slot = status;
+        }
+    }
+
+    fn merge(&mut self, source: &BookSource, item: BookListItem) {
+        let key = (normalize(&item.info.name), normalize(&item.info.author));
+        if key.0.is_empty() {
+            return;
+        }
+        let hit = SourceHit {
+            source: source.clone(),
+            item,
+        };
+        match self.books.iter_mut().find(|book| book.key == key) {
+            // 同一书源里重复的结果只留第一条。
+            Some(book) if book.hits.iter().any(|h| h.source.url == source.url) => {}
+            Some(book) => book.hits.push(hit),
+            None => self.books.push(MergedBook {
+                key,
+                hits: vec![hit],
+            }),
+        }
+    }
+
+    pub fn finished(&self) -> usize {
+        self.sources
+            .iter()
+            .filter(|(_, status)| status.is_finished())
+            .count()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.finished() == self.sources.len()
+    }
+
+    /// 失败的书源及原因,用于状态栏。
+    pub fn failures(&self) -> Vec<String> {
+        self.sources
+            .iter()
+            .filter(|(_, status)| status.is_failed())
+            .map(|(name, status)| format!("{name}({})", status.label()))
+            .collect()
+    }
+}
+
+/// 单个书源的搜索结果;失败时带上失败类型。
+pub type SourceResult = std::result::Result<Vec<BookListItem>, SourceStatus>;
+
+/// 参与聚合搜索的书源:配置了搜索规则的才算。
+pub fn searchable(sources: &[BookSource]) -> Vec<BookSource> {
+    sources
+        .iter()
+        .filter(|source| source.search.is_some())
+        .cloned()
+        .collect()
+}
+
+async fn search_source(source: &BookSource, key: &str) -> SourceResult {
+    let engine = crate::browser_assist::build_engine(source.clone())
+        .map_err(|e| SourceStatus::Failed(e.to_string()))?;
+    let started = Instant::now();
+    let result = tokio::time::timeout(SEARCH_TIMEOUT, engine.search(key, 1, PAGE_SIZE)).await;
+    match &result {
+        Err(_) => source_health::record_failure(&source.url, FailureKind::Network),
+        Ok(Err(e)) => source_health::record_error(&source.url, e),
+        Ok(Ok(_)) => source_health::record_success(&source.url, started.elapsed()),
+    }
+    match result {
+        Err(_) => Err(SourceStatus::TimedOut),
+        Ok(Err(e)) if e.is_challenge() => Err(SourceStatus::Challenged),
+        Ok(Err(e)) => Err(SourceStatus::Failed(e.to_string())),
+        Ok(Ok(list)) => Ok(list.items),
+    }
+}
+
+/// 并发搜索 `sources`,哪个书源先返回就先产出哪个:`(书源下标, 结果)`。
+pub fn search(sources: Vec<BookSource>, key: String) -> impl Stream<Item = (usize, SourceResult)> {
+    stream::iter(sources.into_iter().enumerate())
+        .map(move |(index, source)| {
+            let key = key.clone();
+            async move { (index, search_source(&source, &key).await) }
+        })
+        .buffer_unordered(CONCURRENCY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::source;
+    use parse_book_source::BookInfo;
+
+    fn item(name: &str, author: &str, url: &str) -> BookListItem {
+        BookListItem {
+            info: BookInfo {
+                name: name.to_string(),
+                author: author.to_string(),
+                ..Default::default()
+            },
+            book_url: url.to_string(),
+        }
+    }
+
+    #[test]
+    fn merges_same_book_across_sources() {
+        let (a, b) = (source("a"), source("b"));
+        let mut search = GlobalSearch::new(&[a.clone(), b.clone()]);
+        search.finish(
+            1,
+            &b,
+            Ok(vec![
+                item("《诡秘之主》", "爱潜水的乌贼", "b/1"),
+                item("诡秘", "x", "b/2"),
+            ]),
+        );
+        assert_eq!(search.finished(), 1);
+        assert!(!search.is_done());
+
+        search.finish(
+            0,
+            &a,
+            Ok(vec![
+                item("诡秘之主", "爱潜水的乌贼 ", "a/1"),
+                item("诡秘之主", "爱潜水的乌贼", "a/2"),
+                item("诡秘之主", "别人", "a/3"),
+            ]),
+        );
+        assert!(search.is_done());
+        let names: Vec<String> = search.books.iter().map(MergedBook::source_names).collect();
+        assert_eq!(names, ["b / a", "b", "a"]);
+        assert_eq!(search.books[0].hits[1].item.book_url, "a/1");
+    }
+
+    #[test]
+    fn failures_do_not_block_results() {
+        let (a, b, c) = (source("a"), source("b"), source("c"));
+        let mut search = GlobalSearch::new(&[a.clone(), b.clone(), c.clone()]);
+        search.finish(0, &a, Err(SourceStatus::Challenged));
+        search.finish(2, &c, Err(SourceStatus::TimedOut));
+        search.finish(1, &b, Ok(vec![item("书", "", "b/1")]));
+        assert!(search.is_done());
+        assert_eq!(search.books.len(), 1);
+        assert_eq!(search.failures(), ["a(被反爬拦截)", "c(超时)"]);
+    }
+}
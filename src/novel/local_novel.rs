@@ -95,6 +95,38 @@ impl LocalNovel {
             "Unsupported encoding",
         ))
     }
+
+    /// 按目录记录的字节区间读出第 `index` 章并解码。
+    async fn read_chapter(&self, index: usize) -> Result<String> {
+        let chapters = self.get_chapters_result()?;
+        let start = if index == 0 {
+            0
+        } else {
+            chapters.get(index).ok_or(anyhow!("章节不存在"))?.1
+        };
+        let is_last = chapters.is_empty() || index + 1 >= chapters.len();
+        let end = chapters.get(index + 1).map(|chapter| chapter.1);
+
+        let file = self.file.clone();
+        let encoding = self.encoding;
+        let mut file = file.lock().await;
+
+        let end = if is_last {
+            file.metadata().await?.len() as usize
+        } else {
+            end.ok_or(anyhow!("找不到下一章"))?
+        };
+
+        let mut buffer = vec![0; end - start];
+        file.seek(SeekFrom::Start(start as u64)).await?;
+        file.read_exact(&mut buffer).await?;
+
+        let (str, _, has_error) = encoding.decode(&buffer);
+        if has_error {
+            return Err(anyhow::anyhow!("解码错误").into());
+        }
+        Ok(str.to_string())
+    }
 }
 
 impl Novel for LocalNovel {
@@ -133,40 +165,19 @@ impl Novel for LocalNovel {
     }
 
     async fn get_content(&self) -> Result<String> {
-        let start = if self.current_chapter == 0 {
-            0
-        } else {
-            let (_, start) = self.get_chapters_result()?[self.current_chapter];
-            start.to_owned()
-        };
-        let is_last = self.get_chapters_result()?.is_empty()
-            || self.current_chapter + 1 >= self.get_chapters_result()?.len();
-
-        let end = self.chapters.as_ref().and_then(|chapters| {
-            chapters
-                .get(self.current_chapter + 1)
-                .map(|chapter| chapter.1)
-        });
-
-        let file = self.file.clone();
-        let encoding = self.encoding;
-        let mut file = file.lock().await;
+        self.read_chapter(self.current_chapter).await
+    }
 
-        let end = if is_last {
-            file.metadata().await?.len() as usize
-        } else {
-            end.ok_or(anyhow!("找不到下一章"))?
-        };
+    async fn offline_chapter(&self, index: usize) -> Result<Option<String>> {
+        self.read_chapter(index).await.map(Some)
+    }
 
-        let mut buffer = vec![0; end - start];
-        file.seek(SeekFrom::Start(start as u64)).await?;
-        file.read_exact(&mut buffer).await?;
+    async fn chapter_content(&self, index: usize) -> Result<String> {
+        self.read_chapter(index).await
+    }
 
-        let (str, _, has_error) = encoding.decode(&buffer);
-        if has_error {
-            return Err(anyhow::anyhow!("解码错误").into());
-        }
-        Ok(str.to_string())
+    fn cache_path(&self) -> Result<PathBuf> {
+        LocalNovelCache::cache_path(&self.path)
     }
 
     fn get_current_chapter_name(&self) -> Result<String> {
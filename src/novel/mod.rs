@@ -1,4 +1,7 @@
+pub mod change_source;
+pub mod global_search;
 pub mod local_novel;
+pub mod name_index;
 pub mod network_novel;
 pub mod novel_core;
 pub mod toc_rule;
@@ -0,0 +1,397 @@
+//! 人物 / 专名索引:从全书正文里找出高频的「像专名」的汉字串,外加用户手动添加的名字,
+//! 统计各自的首次出场、出场章节与提及次数,供阅读页跳转。
+//!
+//! 候选词是连续汉字里的 2~4 字片段(遇标点、常见虚词/动词即断开),满足其一才算专名:
+//! 以常见姓氏开头;或后面常跟「说 / 道 / 笑 / 问」这类动作字。被更长候选几乎完全覆盖的
+//! 片段(「张无」之于「张无忌」)会被并入长词。只是启发式,漏掉或误收的名字可手动增删。
+//!
+//! 索引与书籍缓存放在一起:`<书籍缓存>.names.json`(见 [`NameIndex::cache_path`])。
+
+use super::Novel;
+use crate::{Result, cache::store};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+/// 自动候选至少出现的次数。
+const MIN_MENTIONS: usize = 5;
+/// 后接动作字的占比达到该值即视为人名。
+const ACTION_RATIO: f64 = 0.15;
+/// 长词覆盖短词的比例:短词的出现次数里长词占到该比例,短词即并入长词。
+const COVER_RATIO: f64 = 0.7;
+/// 自动候选的上限(按提及次数取前 N)。
+const MAX_AUTO_NAMES: usize = 300;
+
+/// 切断候选片段的常见虚词、代词与高频动词(它们几乎不出现在人名中间)。
+const STOP_CHARS: &str = "的了着过是在和与及或就也都还又再才只已很太更最不没非未别把被让给对从向往到于以为这那哪此其之我你他她它们谁什么怎吗呢吧啊呀哦嘛么个些每各该要会能可想说道问答笑看听见去来时候得地而且但却因所如若虽然即便自己经";
+/// 紧跟在人名后面的动作字。
+const ACTION_CHARS: &str = "说道问笑叹喝叫答怒哼点摇看望走听想心身手脸眼";
+/// 常见单姓。
+const SURNAMES: &str = "赵钱孙李周吴郑王冯陈褚卫蒋沈韩杨朱秦尤许何吕施张孔曹严华金魏陶姜戚谢邹喻柏水窦章云苏潘葛奚范彭郎鲁韦昌马苗凤花方俞任袁柳酆鲍史唐费廉岑薛雷贺倪汤滕殷罗毕郝邬安常乐于时傅皮卞齐康伍余元卜顾孟平黄和穆萧尹姚邵湛汪祁毛禹狄米贝明臧计伏成戴谈宋茅庞熊纪舒屈项祝董梁杜阮蓝闵席季麻强贾路娄危江童颜郭梅盛林刁钟徐邱骆高夏蔡田樊胡凌霍虞万支柯管卢莫房裘缪干解应宗丁宣邓郁单杭洪包诸左石崔吉龚程邢裴陆荣翁荀羊惠甄曲封储靳段富巫乌焦巴弓牧山谷车侯全班秋仲伊宫宁仇栾甘厉戎祖武符刘景詹束龙叶幸司韶黎薄印宿白怀蒲台从鄂索咸籍赖卓蔺屠蒙池乔阴胥苍双闻莘党翟谭贡劳姬申扶堵冉宰郦雍桑桂濮牛寿通边扈燕冀浦尚农温别庄晏柴瞿阎慕连茹习艾鱼容向古易慎戈廖庾终居衡步都耿满弘匡国文寇广禄阙东欧殳沃利蔚越夔隆师巩聂晁勾敖融冷辛阚那简饶空曾沙养鞠须丰巢关蒯相查后荆红游竺权盖益桓公岳";
+/// 常见复姓。
+const COMPOUND_SURNAMES: [&str; 24] = [
+    "欧阳", "司马", "上官", "诸葛", "东方", "慕容", "令狐", "独孤", "南宫", "皇甫", "公孙", "长孙",
+    "宇文", "尉迟", "轩辕", "西门", "夏侯", "端木", "赫连", "司徒", "呼延", "澹台", "拓跋", "百里",
+];
+
+/// 名字在某一章的出场:章节下标、提及次数与本章首次提及的相对位置(0~1,可直接作阅读进度)。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Appearance {
+    pub chapter: usize,
+    pub count: usize,
+    pub percent: f64,
+}
+
+/// 一个名字的统计。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NameEntry {
+    pub name: String,
+    pub total: usize,
+    /// 出场章节,按章节顺序;首项即首次出场。
+    pub chapters: Vec<Appearance>,
+    /// 用户手动添加的名字(不受自动筛选影响)。
+    #[serde(default)]
+    pub user_added: bool,
+}
+
+impl NameEntry {
+    pub fn first(&self) -> Option<&Appearance> {
+        self.chapters.first()
+    }
+
+    /// 第一个不早于 `chapter` 的出场在 [`Self::chapters`] 中的位置(都早于它时取最后一项)。
+    pub fn nearest(&self, chapter: usize) -> usize {
+        self.chapters
+            .iter()
+            .position(|a| a.chapter >= chapter)
+            .unwrap_or(self.chapters.len().saturating_sub(1))
+    }
+}
+
+/// 一本书的人物 / 专名索引。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NameIndex {
+    pub names: Vec<NameEntry>,
+    /// 用户手动添加的名字:重建索引时保留并始终统计。
+    #[serde(default)]
+    pub user_names: Vec<String>,
+    /// 用户删除的自动候选:重建索引时不再收录。
+    #[serde(default)]
+    pub excluded: Vec<String>,
+    /// 建索引时目录的章节数(目录变动后提示重建)。
+    pub chapter_count: usize,
+    /// 实际扫描到正文的章节数(网络小说只扫离线可读的章节)。
+    pub indexed_chapters: usize,
+    pub built_at: DateTime<Local>,
+}
+
+impl NameIndex {
+    /// 索引文件路径:书籍缓存 `xxx.json` 旁的 `xxx.names.json`。
+    pub fn cache_path(book_cache: &Path) -> PathBuf {
+        book_cache.with_extension("names.json")
+    }
+
+    /// 读取已缓存的索引;不存在时返回 `None`。
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        match File::open(path) {
+            Ok(file) => Ok(Some(serde_json::from_reader(file)?)),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// 删除一个名字:用户添加的从名单里去掉,自动识别的记入排除名单,重建时不再收录。
+    pub fn remove(&mut self, name: &str) {
+        let Some(pos) = self.names.iter().position(|n| n.name == name) else {
+            return;
+        };
+        let entry = self.names.remove(pos);
+        if entry.user_added {
+            self.user_names.retain(|n| n != name);
+        } else if !self.excluded.iter().any(|n| n == name) {
+            self.excluded.push(entry.name);
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        store::save_json(path, self)
+    }
+}
+
+/// 扫描全书可离线读取的章节并建索引。
+///
+/// 正文逐章读入后在阻塞线程上统计,避免长篇小说占住异步运行时。
+pub async fn build_name_index<T>(
+    novel: &T,
+    user_names: Vec<String>,
+    excluded: Vec<String>,
+) -> Result<NameIndex>
+where
+    T: Novel + Sync,
+{
+    let chapter_count = novel.get_chapters().map_or(0, |c| c.len());
+    let mut texts = Vec::new();
+    for index in 0..chapter_count {
+        if let Some(text) = novel.offline_chapter(index).await? {
+            texts.push((index, text));
+        }
+    }
+    let index = tokio::task::spawn_blocking(move || {
+        let mut builder = NameIndexBuilder::new(user_names, excluded);
+        for (index, text) in &texts {
+            builder.add_chapter(*index, text);
+        }
+        builder.finish(chapter_count)
+    })
+    .await?;
+    Ok(index)
+}
+
+#[derive(Debug)]
+struct GramStats {
+    total: usize,
+    followed_by_action: usize,
+    chapters: Vec<Appearance>,
+}
+
+/// 逐章累积统计的索引构建器。
+#[derive(Debug, Default)]
+pub struct NameIndexBuilder {
+    grams: HashMap<String, GramStats>,
+    user: HashMap<String, GramStats>,
+    user_names: Vec<String>,
+    excluded: HashSet<String>,
+    indexed_chapters: usize,
+}
+
+impl NameIndexBuilder {
+    pub fn new(user_names: Vec<String>, excluded: Vec<String>) -> Self {
+        Self {
+            user_names,
+            excluded: excluded.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn add_chapter(&mut self, chapter: usize, text: &str) {
+        self.indexed_chapters += 1;
+        let len = text.len().max(1) as f64;
+
+        // 本章的 (次数, 首次字节偏移, 后接动作字次数)。
+        let mut local: HashMap<&str, (usize, usize, usize)> = HashMap::new();
+        for run in han_runs(text) {
+            let chars: Vec<(usize, char)> = run.char_indices().collect();
+            let run_start = run.as_ptr() as usize - text.as_ptr() as usize;
+            let byte_at = |i: usize| chars.get(i).map_or(run.len(), |&(b, _)| b);
+            for start in 0..chars.len() {
+                for n in 2..=4 {
+                    if start + n > chars.len() {
+                        break;
+                    }
+                    let gram = &run[byte_at(start)..byte_at(start + n)];
+                    let end = run_start + byte_at(start + n);
+                    let followed = text[end..]
+                        .chars()
+                        .next()
+                        .is_some_and(|c| ACTION_CHARS.contains(c));
+                    let stat = local
+                        .entry(gram)
+                        .or_insert((0, run_start + byte_at(start), 0));
+                    stat.0 += 1;
+                    stat.2 += followed as usize;
+                }
+            }
+        }
+
+        // 只有本章出现两次以上、或此前已收录的片段才进全局表,控制长篇的内存占用。
+        for (gram, (count, offset, followed)) in local {
+            if count < 2 && !self.grams.contains_key(gram) {
+                continue;
+            }
+            merge(
+                self.grams.entry(gram.to_string()),
+                chapter,
+                count,
+                offset as f64 / len,
+                followed,
+            );
+        }
+
+        for name in &self.user_names {
+            let mut offsets = text.match_indices(name.as_str()).map(|(i, _)| i);
+            let Some(first) = offsets.next() else {
+                continue;
+            };
+            merge(
+                self.user.entry(name.clone()),
+                chapter,
+                1 + offsets.count(),
+                first as f64 / len,
+                0,
+            );
+        }
+    }
+
+    pub fn finish(self, chapter_count: usize) -> NameIndex {
+        let mut candidates: Vec<(String, GramStats)> = self
+            .grams
+            .into_iter()
+            .filter(|(gram, stats)| {
+                stats.total >= MIN_MENTIONS
+                    && !self.excluded.contains(gram)
+                    && !self.user_names.contains(gram)
+                    && looks_like_name(gram, stats)
+            })
+            .collect();
+
+        // 长词优先:被已保留的长词覆盖的短片段并入长词。
+        candidates.sort_by_key(|(gram, _)| std::cmp::Reverse(gram.chars().count()));
+        let mut kept: Vec<(String, GramStats)> = Vec::new();
+        for (gram, stats) in candidates {
+            let covered = kept.iter().any(|(longer, longer_stats)| {
+                longer.contains(gram.as_str())
+                    && longer_stats.total as f64 >= stats.total as f64 * COVER_RATIO
+            });
+            if !covered {
+                kept.push((gram, stats));
+            }
+        }
+        kept.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.total));
+        kept.truncate(MAX_AUTO_NAMES);
+
+        let mut names: Vec<NameEntry> = self
+            .user
+            .into_iter()
+            .map(|(name, stats)| entry(name, stats, true))
+            .chain(
+                kept.into_iter()
+                    .map(|(name, stats)| entry(name, stats, false)),
+            )
+            .collect();
+        names.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+
+        NameIndex {
+            names,
+            user_names: self.user_names,
+            excluded: self.excluded.into_iter().collect(),
+            chapter_count,
+            indexed_chapters: self.indexed_chapters,
+            built_at: Local::now(),
+        }
+    }
+}
+
+fn merge(
+    slot: std::collections::hash_map::Entry<'_, String, GramStats>,
+    chapter: usize,
+    count: usize,
+    percent: f64,
+    followed: usize,
+) {
+    let stats = slot.or_insert_with(|| GramStats {
+        total: 0,
+        followed_by_action: 0,
+        chapters: Vec::new(),
+    });
+    stats.total += count;
+    stats.followed_by_action += followed;
+    stats.chapters.push(Appearance {
+        chapter,
+        count,
+        percent,
+    });
+}
+
+fn entry(name: String, mut stats: GramStats, user_added: bool) -> NameEntry {
+    stats.chapters.sort_by_key(|a| a.chapter);
+    NameEntry {
+        name,
+        total: stats.total,
+        chapters: stats.chapters,
+        user_added,
+    }
+}
+
+fn looks_like_name(gram: &str, stats: &GramStats) -> bool {
+    let len = gram.chars().count();
+    let surname = gram.chars().next().is_some_and(|c| SURNAMES.contains(c)) && len <= 3;
+    let compound = COMPOUND_SURNAMES.iter().any(|s| gram.starts_with(s)) && len >= 3;
+    let acting = stats.followed_by_action as f64 >= stats.total as f64 * ACTION_RATIO;
+    surname || compound || acting
+}
+
+/// 按非汉字与 [`STOP_CHARS`] 切出的连续汉字片段。
+fn han_runs(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !is_han(c) || STOP_CHARS.contains(c))
+        .filter(|run| run.chars().nth(1).is_some())
+}
+
+fn is_han(c: char) -> bool {
+    matches!(c, '\u{4e00}'..='\u{9fff}' | '\u{3400}'..='\u{4dbf}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(chapters: &[&str], user_names: &[&str]) -> NameIndex {
+        let mut builder = NameIndexBuilder::new(
+            user_names.iter().map(|s| s.to_string()).collect(),
+            Vec::new(),
+        );
+        for (i, text) in chapters.iter().enumerate() {
+            builder.add_chapter(i, text);
+        }
+        builder.finish(chapters.len())
+    }
+
+    #[test]
+    fn finds_surname_names_and_merges_fragments() {
+        let chapter = "张无忌说:走吧。张无忌笑了。赵敏道:好。张无忌点头,赵敏看着他。";
+        let index = build(&[chapter, chapter, "没有人。", chapter], &[]);
+        let names: Vec<&str> = index.names.iter().map(|n| n.name.as_str()).collect();
+        assert!(names.contains(&"张无忌"), "{names:?}");
+        assert!(names.contains(&"赵敏"), "{names:?}");
+        // 「张无」「无忌」被「张无忌」覆盖,不单独出现。
+        assert!(!names.contains(&"张无"), "{names:?}");
+        assert!(!names.contains(&"无忌"), "{names:?}");
+
+        let zhang = index.names.iter().find(|n| n.name == "张无忌").unwrap();
+        assert_eq!(zhang.total, 9);
+        let chapters: Vec<(usize, usize)> = zhang
+            .chapters
+            .iter()
+            .map(|a| (a.chapter, a.count))
+            .collect();
+        assert_eq!(chapters, [(0, 3), (1, 3), (3, 3)]);
+        assert_eq!(zhang.first().unwrap().percent, 0.0);
+        assert_eq!(zhang.nearest(2), 2);
+        assert_eq!(zhang.nearest(9), 2);
+    }
+
+    #[test]
+    fn user_names_are_always_counted() {
+        let index = build(&["一灯大师出场。", "又见一灯大师。"], &["一灯大师"]);
+        let entry = &index.names[0];
+        assert_eq!(entry.name, "一灯大师");
+        assert!(entry.user_added);
+        assert_eq!(entry.chapters.len(), 2);
+        assert_eq!(entry.chapters[1].count, 1);
+        // 「又见」之后才提到:首次位置不在章首。
+        assert!(entry.chapters[1].percent > 0.0);
+    }
+
+    #[test]
+    fn cache_path_sits_next_to_book_cache() {
+        assert_eq!(
+            NameIndex::cache_path(Path::new("/tmp/local/abc.json")),
+            PathBuf::from("/tmp/local/abc.names.json")
+        );
+    }
+}
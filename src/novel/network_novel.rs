@@ -1,11 +1,22 @@
 use super::{Novel, NovelChapters, VolumeMarker};
 use crate::{
-    Result, book_source::BookSourceCache, cache::NetworkNovelCache, errors::Errors,
+    Result,
+    book_source::BookSourceCache,
+    cache::{
+        ChapterCache, DownloadChapter, DownloadStatus, DownloadTask, NetworkNovelCache,
+        source_health::{self, FailureKind},
+    },
     history::HistoryItem,
 };
 use anyhow::anyhow;
-use parse_book_source::{BookInfo, BookListItem, Chapter, Engine};
-use std::ops::{Deref, DerefMut};
+use chrono::Local;
+use parse_book_source::{BookInfo, BookListItem, BookSourceError, Chapter, Engine};
+use std::{
+    collections::HashSet,
+    ops::{Deref, DerefMut, Range},
+    path::PathBuf,
+    time::Instant,
+};
 
 /// 网络小说:持有 v2 `Engine`(廉价 Clone、内部 Arc,无需外层 Mutex)。
 #[derive(Debug, Clone)]
@@ -14,6 +25,8 @@ pub struct NetworkNovel {
     pub engine: Engine,
     pub book_info: Option<BookInfo>,
     pub novel_chapters: NovelChapters<Chapter>,
+    /// 离线缓存;缓存目录不可用时为 `None`,此时退化为每次联网。
+    pub chapter_cache: Option<ChapterCache>,
 }
 
 impl NetworkNovel {
@@ -39,6 +52,7 @@ impl NetworkNovel {
                 chapters: None,
                 volumes: Vec::new(),
             },
+            chapter_cache: ChapterCache::open().ok(),
         })
     }
 
@@ -48,12 +62,87 @@ impl NetworkNovel {
             engine,
             book_info: None,
             novel_chapters: NovelChapters::new(),
+            chapter_cache: ChapterCache::open().ok(),
         }
     }
 
     pub fn set_book_info(&mut self, book_info: &BookInfo) {
         self.book_info = Some(book_info.clone());
     }
+
+    /// 拉取书籍详情并留一份快照;联网失败时退回快照,没网也能打开读过的书。
+    pub async fn fetch_book_info(&mut self) -> Result<BookInfo> {
+        let book_url = &self.book_list_item.book_url;
+        let started = Instant::now();
+        let result = self.engine.book_info(book_url).await;
+        self.record(started, &result, |_| false);
+        let info = match result {
+            Ok(info) => {
+                self.put_snapshot("info", &info);
+                info
+            }
+            Err(e) => self.snapshot("info").ok_or(e)?,
+        };
+        self.set_book_info(&info);
+        Ok(info)
+    }
+
+    /// 读取一章正文:优先离线缓存,未命中再联网并写入缓存。
+    async fn fetch_chapter(&self, chapter: &Chapter) -> Result<String> {
+        let source_url = self.engine.source_url();
+        if let Some(text) = self
+            .chapter_cache
+            .as_ref()
+            .and_then(|cache| cache.chapter(source_url, &chapter.url))
+        {
+            return Ok(text);
+        }
+        let started = Instant::now();
+        let result = self.engine.content(&chapter.url).await;
+        self.record(started, &result, |text| text.trim().is_empty());
+        let text = result?;
+        if let Some(cache) = &self.chapter_cache {
+            // 写缓存失败(磁盘满、权限)不影响本次阅读。
+            let _ = cache.put_chapter(source_url, &chapter.url, &text);
+        }
+        Ok(text)
+    }
+
+    /// 把一次联网操作的结果记入书源健康记录;`empty` 判定结果为空时按解析为空记失败。
+    fn record<T>(
+        &self,
+        started: Instant,
+        result: &std::result::Result<T, BookSourceError>,
+        empty: impl FnOnce(&T) -> bool,
+    ) {
+        let url = self.engine.source_url();
+        match result {
+            Ok(value) if empty(value) => {
+                source_health::record_failure(url, FailureKind::ParseEmpty)
+            }
+            Ok(_) => source_health::record_success(url, started.elapsed()),
+            Err(e) => source_health::record_error(url, e),
+        }
+    }
+
+    fn snapshot<T: serde::de::DeserializeOwned>(&self, kind: &str) -> Option<T> {
+        self.chapter_cache.as_ref()?.book(
+            self.engine.source_url(),
+            &self.book_list_item.book_url,
+            kind,
+        )
+    }
+
+    fn put_snapshot<T: serde::Serialize>(&self, kind: &str, value: &T) {
+        if let Some(cache) = &self.chapter_cache {
+            let _ = cache.put_book(
+                self.engine.source_url(),
+                &self.book_list_item.book_url,
+                kind,
+                value,
+            );
+        }
+    }
 }
 
 impl Deref for NetworkNovel {
@@ -84,8 +173,15 @@ impl Novel for NetworkNovel {
     async fn request_toc(&self) -> Result<(Vec<Self::Chapter>, Vec<VolumeMarker>)> {
         let book_info = self.book_info.as_ref().ok_or("book_info is none")?;
         // 引擎已把卷条目拆出(目录 isVolume),直接得到扁平章节 + 卷元数据。
-        let toc = self.engine.toc(&book_info.toc_url).await?;
-        let volumes = toc
+        let started = Instant::now();
+        let result = self.engine.toc(&book_info.toc_url).await;
+        self.record(started, &result, |toc| toc.chapters.is_empty());
+        let toc = match result {
+            Ok(toc) => toc,
+            // 没网时用上次的目录快照,已缓存的章节照样能读。
+            Err(e) => return self.snapshot("toc").ok_or(e.into()),
+        };
+        let volumes: Vec<VolumeMarker> = toc
             .volumes
             .into_iter()
             .map(|v| VolumeMarker {
@@ -93,7 +189,9 @@ impl Novel for NetworkNovel {
                 first_chapter_index: v.first_chapter_index,
             })
             .collect();
-        Ok((toc.chapters, volumes))
+        let toc = (toc.chapters, volumes);
+        self.put_snapshot("toc", &toc);
+        Ok(toc)
     }
 
     fn get_chapters_names(&self) -> Result<Vec<(String, usize)>> {
@@ -107,10 +205,105 @@ impl Novel for NetworkNovel {
 
     async fn get_content(&self) -> Result<String> {
         let chapter = self.get_current_chapter()?;
-        self.engine
-            .content(&chapter.url)
-            .await
-            .map_err(Errors::from)
+        self.fetch_chapter(&chapter).await
+    }
+
+    async fn offline_chapter(&self, index: usize) -> Result<Option<String>> {
+        let (Some(cache), Some(chapter)) = (
+            &self.chapter_cache,
+            self.get_chapters().and_then(|c| c.get(index)),
+        ) else {
+            return Ok(None);
+        };
+        Ok(cache.chapter(self.engine.source_url(), &chapter.url))
+    }
+
+    async fn chapter_content(&self, index: usize) -> Result<String> {
+        let chapter = self.get_chapters_result()?.get(index).ok_or("章节不存在")?;
+        if let Some(text) = self
+            .chapter_cache
+            .as_ref()
+            .and_then(|cache| cache.chapter(self.engine.source_url(), &chapter.url))
+        {
+            return Ok(text);
+        }
+        // 整本导出时不写阅读缓存,免得把最近读过的章节挤出去。
+        crate::download::fetch_with_retry(&self.engine, &chapter.url, None).await
+    }
+
+    async fn prefetch(&self) {
+        let (Some(cache), Some(chapters)) = (&self.chapter_cache, self.get_chapters()) else {
+            return;
+        };
+        let next = chapters
+            .iter()
+            .skip(self.current_chapter + 1)
+            .take(cache.config().prefetch);
+        for chapter in next {
+            if cache.contains(self.engine.source_url(), &chapter.url) {
+                continue;
+            }
+            // 引擎克隆共用同一个取页器,书源配置的 rateLimit 对预取同样生效;
+            // 一章失败多半是断网,后面的也不必再试。
+            if self.fetch_chapter(chapter).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    fn downloadable(&self) -> bool {
+        true
+    }
+
+    fn download(&self, range: Range<usize>) -> Result<()> {
+        let chapters = self.get_chapters_result()?;
+        let chapters: Vec<DownloadChapter> = chapters
+            .get(range.clone())
+            .ok_or("章节范围超出目录")?
+            .iter()
+            .zip(range)
+            .map(|(chapter, index)| DownloadChapter {
+                index,
+                title: chapter.title.clone(),
+                url: chapter.url.clone(),
+                done: false,
+                failed: false,
+            })
+            .collect();
+        // 从详情页直接下载的书还没有阅读存档;补一份,离线时才能从历史记录 / 下载页打开。
+        if !self.cache_path()?.exists() {
+            NetworkNovelCache::try_from(self)?.save()?;
+        }
+        let source = self.engine.source();
+        crate::download::enqueue(DownloadTask {
+            book_url: self.book_list_item.book_url.clone(),
+            title: self
+                .book_info
+                .as_ref()
+                .map_or_else(|| self.book_list_item.info.name.clone(), |i| i.name.clone()),
+            source_url: source.url.clone(),
+            source_name: source.name.clone(),
+            book_vars: self.engine.book_vars(),
+            chapters,
+            status: DownloadStatus::Queued,
+            created_at: Local::now(),
+            error: None,
+        })
+    }
+
+    fn downloaded_chapters(&self) -> HashSet<usize> {
+        crate::download::DOWNLOADS
+            .state()
+            .read()
+            .downloaded(&self.book_list_item.book_url)
+    }
+
+    fn source_snapshot(&self) -> Option<NetworkNovelCache> {
+        NetworkNovelCache::try_from(self).ok()
+    }
+
+    fn cache_path(&self) -> Result<PathBuf> {
+        NetworkNovelCache::cache_path(&self.book_list_item.book_url)
     }
 
     fn to_history_item(&self) -> Result<HistoryItem> {
@@ -1,7 +1,11 @@
-use crate::{Result, history::HistoryItem};
+use crate::{Result, cache::NetworkNovelCache, history::HistoryItem};
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
-use std::ops::{Deref, DerefMut};
+use std::{
+    collections::HashSet,
+    ops::{Deref, DerefMut, Range},
+    path::PathBuf,
+};
 
 /// 卷标记（分卷元数据）。
 ///
@@ -122,6 +126,40 @@ pub trait Novel: Deref<Target = NovelChapters<Self::Chapter>> + DerefMut + Sized
         &self,
     ) -> impl Future<Output = Result<(Vec<Self::Chapter>, Vec<VolumeMarker>)>> + Send;
 
+    /// 不联网即可读到的第 `index` 章正文;没有离线副本时返回 `None`。
+    fn offline_chapter(&self, index: usize) -> impl Future<Output = Result<Option<String>>> + Send;
+
+    /// 读取第 `index` 章正文(导出用):有离线副本用副本,否则联网;不改变阅读进度。
+    fn chapter_content(&self, index: usize) -> impl Future<Output = Result<String>> + Send;
+
+    /// 在后台预取当前章之后的几章到离线缓存;没有离线缓存的小说什么也不做。
+    fn prefetch(&self) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// 能否下载到本地离线阅读;本地小说本来就在磁盘上,不需要。
+    fn downloadable(&self) -> bool {
+        false
+    }
+
+    /// 把目录里 `range` 范围的章节加入下载队列。
+    fn download(&self, _range: Range<usize>) -> Result<()> {
+        Err("本地小说不需要下载".into())
+    }
+
+    /// 已下载完成的章节下标,目录里据此标记。
+    fn downloaded_chapters(&self) -> HashSet<usize> {
+        HashSet::new()
+    }
+
+    /// 换源用的书籍存档;只有网络小说能换源。
+    fn source_snapshot(&self) -> Option<NetworkNovelCache> {
+        None
+    }
+
+    /// 书籍缓存文件路径;人物索引等附属数据存放在它旁边。
+    fn cache_path(&self) -> Result<PathBuf>;
+
     fn get_current_chapter_name(&self) -> Result<String>;
 
     fn to_history_item(&self) -> Result<HistoryItem>;
@@ -11,8 +11,8 @@
 //! - **可配置**：内置默认规则集作兜底；若 `~/.novel/toc_rules.json` 存在则合并用户规则，
 //!   解析失败时安全回退到默认（呼应 issue #49）。
 
+use crate::data_dir::data_path;
 use crate::novel::VolumeMarker;
-use crate::utils::novel_catch_dir;
 use serde::{Deserialize, Serialize};
 
 /// 默认标题最大字符数（超过则视为正文）。
@@ -148,10 +148,9 @@ impl TocRuleSet {
     pub fn load() -> Self {
         let mut set = Self::builtin();
 
-        let Ok(dir) = novel_catch_dir() else {
+        let Ok(path) = data_path("toc_rules.json") else {
             return set;
         };
-        let path = dir.join("toc_rules.json");
         if let Ok(content) = std::fs::read_to_string(&path) {
             set.merge_user_json(&content);
         }
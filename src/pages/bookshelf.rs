@@ -0,0 +1,405 @@
+use crate::{
+    Bookshelf, HistoryItem, ShelfBook,
+    book_source::BookSourceCache,
+    browser_assist::build_engine,
+    components::{
+        ConfirmModal, KeyShortcutInfo, ShortcutInfoModal, WarningModal, list_select::ListSelect,
+        search_input::SearchInput,
+    },
+    export::{ExportFormat, default_path, load_book},
+    pages::select_history::open_book,
+    theme::AppChromeTheme,
+    update::{apply_results, check_updates},
+};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    text::{Line, Span, Text},
+    widgets::{Block, Padding, Paragraph, Tabs, Widget, WidgetRef},
+};
+use ratatui_kit::prelude::*;
+use tui_widget_list::{ListBuildContext, ListState};
+
+pub struct ShelfItem {
+    pub book: ShelfBook,
+    pub selected: bool,
+    pub theme: AppChromeTheme,
+}
+
+impl Widget for ShelfItem {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        self.render_ref(area, buf);
+    }
+}
+
+impl WidgetRef for ShelfItem {
+    fn render_ref(&self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let block = if self.selected {
+            Block::bordered()
+                .padding(Padding::horizontal(0))
+                .style(self.theme.selected)
+        } else {
+            Block::bordered().padding(Padding::horizontal(0))
+        };
+
+        let [top, bottom] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(block.inner(area));
+        block.render(area, buf);
+
+        let [bottom_left, bottom_right] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(bottom);
+
+        let text_color = if self.selected {
+            self.theme.text.patch(self.theme.selected)
+        } else {
+            self.theme.text
+        };
+        let meta = self.theme.meta_label.patch(text_color);
+
+        let (current_chapter, kind) = match &self.book.item {
+            HistoryItem::Local(item) => (item.current_chapter.clone(), "本地小说".to_string()),
+            HistoryItem::Network(item) => (
+                item.current_chapter.clone(),
+                format!("书源：{}", item.book_source),
+            ),
+        };
+
+        let mut title = Vec::new();
+        if self.book.pinned {
+            title.push(Span::styled("📌 ", self.theme.highlight));
+        }
+        title.push(Span::from(self.book.title().to_string()));
+        if self.book.new_chapters() > 0 {
+            title.push(Span::styled(
+                format!("  +{} 新", self.book.new_chapters()),
+                self.theme.highlight,
+            ));
+        }
+        title.push(Span::styled(format!("  [{}]", self.book.group), meta));
+        for tag in &self.book.tags {
+            title.push(Span::styled(format!(" #{tag}"), self.theme.highlight));
+        }
+
+        Paragraph::new(Text::from(vec![
+            Line::from(title),
+            Line::from(current_chapter).centered(),
+        ]))
+        .style(text_color)
+        .render(top, buf);
+
+        Span::from(kind).style(meta).render(bottom_left, buf);
+
+        let updated = self
+            .book
+            .updated_at
+            .map(|t| format!(" · 更新 {}", t.format("%Y-%m-%d")))
+            .unwrap_or_default();
+        Text::from(format!(
+            "{:.2}% {}{}",
+            self.book.percent(),
+            self.book.last_read_at().format("%Y-%m-%d %H:%M"),
+            updated
+        ))
+        .style(meta)
+        .right_aligned()
+        .render(bottom_right, buf);
+    }
+}
+
+/// 书架页:按分组 / 标签筛选,置顶与多种排序;书籍只由用户手动增删。
+#[component]
+pub fn BookshelfPage(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_component_theme::<AppChromeTheme>();
+    let bookshelf = *hooks.use_context::<State<Option<Bookshelf>>>();
+    let book_sources = *hooks.use_context::<State<Option<BookSourceCache>>>();
+    let mut navigate = hooks.use_navigate();
+
+    let mut filter_index = hooks.use_state(|| 0usize);
+    let mut delete_modal_open = hooks.use_state(|| false);
+    let mut info_modal_open = hooks.use_state(|| false);
+    let mut error = hooks.use_state(|| None::<String>);
+    // 追更检查的进度 / 结果提示;`checking` 为真时不重复触发。
+    let notice = hooks.use_state(String::new);
+    let checking = hooks.use_state(|| false);
+    let exporting = hooks.use_state(|| false);
+    let state = hooks.use_state(ListState::default);
+
+    let shelf = bookshelf.read().clone().unwrap_or_default();
+    let filters = shelf.filters();
+    // 分组 / 标签被清空后标签页会变少,越界时回落到最后一项。
+    let current_filter = filter_index.get().min(filters.len() - 1);
+    let filter = filters[current_filter].clone();
+    let books = shelf.view(&filter);
+    let selected = state.read().selected.and_then(|i| books.get(i).cloned());
+    let is_editing = !delete_modal_open.get() && !info_modal_open.get();
+
+    hooks.use_event_handler(EventScope::Current, EventPriority::Normal, {
+        let selected_id = selected.as_ref().map(|b| b.id.clone());
+        let filters_len = filters.len();
+        move |event| {
+            let Event::Key(key) = event else {
+                return EventResult::Ignored;
+            };
+            if key.kind != KeyEventKind::Press {
+                return EventResult::Ignored;
+            }
+            match key.code {
+                KeyCode::Char('i') | KeyCode::Char('I') => {
+                    info_modal_open.set(!info_modal_open.get());
+                }
+                _ if !is_editing => return EventResult::Ignored,
+                KeyCode::Tab => {
+                    filter_index.set((current_filter + 1) % filters_len);
+                    state.write().select(Some(0));
+                }
+                KeyCode::BackTab => {
+                    filter_index.set((current_filter + filters_len - 1) % filters_len);
+                    state.write().select(Some(0));
+                }
+                KeyCode::Char('o') | KeyCode::Char('O') => {
+                    update_shelf(bookshelf, error, |shelf| shelf.sort = shelf.sort.next());
+                }
+                KeyCode::Char('p') | KeyCode::Char('P') => {
+                    if let Some(id) = &selected_id {
+                        update_shelf(bookshelf, error, |shelf| shelf.toggle_pin(id));
+                    }
+                }
+                KeyCode::Char('d') | KeyCode::Char('D') => {
+                    if selected_id.is_some() {
+                        delete_modal_open.set(true);
+                    }
+                }
+                KeyCode::Char('u') | KeyCode::Char('U') => {
+                    if !checking.get() {
+                        spawn_update_check(bookshelf, book_sources, notice, checking, error);
+                    }
+                }
+                KeyCode::Char(c @ ('e' | 'E')) => {
+                    if let Some(id) = &selected_id
+                        && !exporting.get()
+                    {
+                        let format = if c == 'e' {
+                            ExportFormat::Epub
+                        } else {
+                            ExportFormat::Txt
+                        };
+                        spawn_export(id.clone(), format, book_sources, notice, exporting, error);
+                    }
+                }
+                _ => return EventResult::Ignored,
+            }
+            EventResult::Consumed
+        }
+    });
+
+    let tabs = Tabs::new(filters.iter().map(|f| {
+        let count = shelf.view(f).len();
+        Line::from(format!("{}({count})", f.label()))
+    }))
+    .select(current_filter)
+    .style(theme.muted)
+    .highlight_style(theme.selected)
+    .divider("|");
+
+    let selected_id = selected.as_ref().map(|b| b.id.clone());
+    let group_placeholder = format!(
+        "按m移动到分组(当前:{};现有:{})",
+        selected.as_ref().map(|b| b.group.as_str()).unwrap_or("-"),
+        shelf.all_groups().join(" / ")
+    );
+
+    element!(View {
+        View(height: Constraint::Length(1)) {
+            widget(tabs)
+        }
+        View(height: Constraint::Length(3), flex_direction: Direction::Horizontal) {
+            SearchInput(
+                placeholder: group_placeholder,
+                clear_on_submit: true,
+                clear_on_escape: true,
+                activate_key: Some(KeyCode::Char('m')),
+                is_editing: is_editing && selected.is_some(),
+                on_submit: {
+                    let id = selected_id.clone();
+                    move |group: String| {
+                        if let Some(id) = &id {
+                            update_shelf(bookshelf, error, |shelf| shelf.move_to_group(id, &group));
+                        }
+                        true
+                    }
+                },
+            )
+            SearchInput(
+                value: selected.as_ref().map(|b| b.tags.join(" ")).unwrap_or_default(),
+                placeholder: "按t编辑标签(空格或逗号分隔)",
+                activate_key: Some(KeyCode::Char('t')),
+                is_editing: is_editing && selected.is_some(),
+                on_submit: {
+                    let id = selected_id.clone();
+                    move |tags: String| {
+                        if let Some(id) = &id {
+                            update_shelf(bookshelf, error, |shelf| shelf.set_tags(id, &tags));
+                        }
+                        true
+                    }
+                },
+            )
+        }
+        ListSelect<ShelfBook>(
+            state: state,
+            is_editing: is_editing,
+            items: books.clone(),
+            top_title: Line::from("书架").centered().style(theme.title),
+            bottom_title: Line::from(format!(
+                "排序:{}(o 切换) · {}/{} 本{}",
+                shelf.sort.label(),
+                state.read().selected.map_or(0, |i| i + 1).min(books.len()),
+                books.len(),
+                if notice.read().is_empty() { String::new() } else { format!(" · {}", *notice.read()) }
+            ))
+            .style(theme.meta_label.not_dim()),
+            render_item: {
+                let books = books.clone();
+                move |context: &ListBuildContext| {
+                    (
+                        ShelfItem {
+                            book: books[context.index].clone(),
+                            selected: context.is_selected,
+                            theme,
+                        }
+                        .into(),
+                        5,
+                    )
+                }
+            },
+            empty_message: "书架是空的:在历史记录页按 a 把书加入书架",
+            on_select: move |book: ShelfBook| {
+                open_book(&mut navigate, &book.id, &book.item);
+            },
+        )
+        ConfirmModal(
+            title: "警告",
+            content: format!(
+                "确认把《{}》移出书架吗?(历史记录与阅读进度不受影响)",
+                selected.as_ref().map(|b| b.title().to_string()).unwrap_or_default()
+            ),
+            open: delete_modal_open.get(),
+            on_confirm: move |_| {
+                if let Some(id) = &selected_id {
+                    update_shelf(bookshelf, error, |shelf| shelf.remove(id));
+                    let index = state.read().selected.unwrap_or(0);
+                    state.write().select(Some(index.saturating_sub(1)));
+                }
+                delete_modal_open.set(false);
+            },
+            on_cancel: move |_| {
+                delete_modal_open.set(false);
+            },
+        )
+        ShortcutInfoModal(
+            key_shortcut_info: KeyShortcutInfo::new(vec![
+                ("选择下一本", "J / ▼"),
+                ("选择上一本", "K / ▲"),
+                ("打开", "Enter"),
+                ("切换分组/标签", "Tab / Shift+Tab"),
+                ("切换排序", "O"),
+                ("置顶/取消置顶", "P"),
+                ("移动到分组", "M"),
+                ("编辑标签", "T"),
+                ("移出书架", "D"),
+                ("检查更新", "U"),
+                ("导出 EPUB", "E"),
+                ("导出 TXT", "Shift+E"),
+            ]),
+            open: info_modal_open.get(),
+        )
+        WarningModal(
+            tip: error.read().clone().unwrap_or_default(),
+            is_error: true,
+            open: error.read().is_some(),
+            on_close: move |_: ()| { error.set(None); },
+        )
+    })
+}
+
+/// 改动书架并立即落盘;保存失败只提示,内存里的改动保留。
+fn update_shelf(
+    bookshelf: State<Option<Bookshelf>>,
+    mut error: State<Option<String>>,
+    f: impl FnOnce(&mut Bookshelf),
+) {
+    if let Some(shelf) = bookshelf.write().as_mut() {
+        f(shelf);
+        if let Err(e) = shelf.save() {
+            error.set(Some(format!("保存书架失败:{e}")));
+        }
+    }
+}
+
+/// 后台检查书架上网络小说的更新,进度与结果写进 `notice`,完成后把结果落盘。
+fn spawn_update_check(
+    bookshelf: State<Option<Bookshelf>>,
+    book_sources: State<Option<BookSourceCache>>,
+    mut notice: State<String>,
+    mut checking: State<bool>,
+    error: State<Option<String>>,
+) {
+    let (Some(shelf), Some(sources)) = (bookshelf.read().clone(), book_sources.read().clone())
+    else {
+        return;
+    };
+    checking.set(true);
+    notice.set("正在检查更新…".to_string());
+    tokio::spawn(async move {
+        let results = check_updates(&shelf, &sources, build_engine, |done, total| {
+            notice.clone().set(format!("正在检查更新 {done}/{total}"));
+        })
+        .await;
+        update_shelf(bookshelf, error, |shelf| apply_results(shelf, &results));
+        let updated = results.iter().filter(|r| r.new_chapters() > 0).count();
+        let failed = results.iter().filter(|r| r.outcome.is_err()).count();
+        notice.set(match (results.len(), failed) {
+            (0, _) => "书架上没有网络小说".to_string(),
+            (_, 0) => format!("检查完成:{updated} 本有更新"),
+            _ => format!("检查完成:{updated} 本有更新,{failed} 本失败"),
+        });
+        checking.set(false);
+    });
+}
+
+/// 后台导出一本书到系统下载目录,进度与结果写进 `notice`。
+fn spawn_export(
+    id: String,
+    format: ExportFormat,
+    book_sources: State<Option<BookSourceCache>>,
+    mut notice: State<String>,
+    mut exporting: State<bool>,
+    mut error: State<Option<String>>,
+) {
+    let Some(sources) = book_sources.read().clone() else {
+        return;
+    };
+    exporting.set(true);
+    notice.set("正在导出…".to_string());
+    tokio::spawn(async move {
+        let result = async {
+            let book = load_book(&id, &sources, build_engine, |done, total| {
+                notice.clone().set(format!("正在导出 {done}/{total}"));
+            })
+            .await?;
+            let path = default_path(&book.title, format);
+            book.save(format, &path)?;
+            crate::Result::Ok(path)
+        }
+        .await;
+        match result {
+            Ok(path) => notice.set(format!("已导出到 {}", path.display())),
+            Err(e) => {
+                notice.set(String::new());
+                error.set(Some(format!("导出失败:{e}")));
+            }
+        }
+        exporting.set(false);
+    });
+}
@@ -0,0 +1,287 @@
+use crate::{
+    Downloads, History,
+    components::{
+        ConfirmModal, KeyShortcutInfo, ShortcutInfoModal, WarningModal, list_select::ListSelect,
+    },
+    download::{self, DOWNLOADS},
+    storage::{self, BookData, Category, Size},
+    theme::AppChromeTheme,
+    utils::format_bytes,
+};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Layout},
+    text::{Line, Span, Text},
+    widgets::{Block, Padding, Widget, WidgetRef},
+};
+use ratatui_kit::prelude::*;
+use tui_widget_list::{ListBuildContext, ListState};
+
+/// 缓存管理页的一行:先列各类别,再列有缓存数据的书。
+#[derive(Debug, Clone, PartialEq)]
+pub enum CacheRow {
+    Category(Category, Size),
+    Book(BookData),
+}
+
+impl CacheRow {
+    fn title(&self) -> String {
+        match self {
+            CacheRow::Category(category, _) => category.label().to_string(),
+            CacheRow::Book(book) => format!("《{}》", book.title),
+        }
+    }
+
+    fn size(&self) -> Size {
+        match self {
+            CacheRow::Category(_, size) => *size,
+            CacheRow::Book(book) => book.size,
+        }
+    }
+}
+
+pub struct CacheRowItem {
+    pub row: CacheRow,
+    pub selected: bool,
+    pub theme: AppChromeTheme,
+}
+
+impl Widget for CacheRowItem {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        self.render_ref(area, buf);
+    }
+}
+
+impl WidgetRef for CacheRowItem {
+    fn render_ref(&self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let block = if self.selected {
+            Block::bordered()
+                .padding(Padding::horizontal(1))
+                .style(self.theme.selected)
+        } else {
+            Block::bordered().padding(Padding::horizontal(1))
+        };
+        let [left, right] = Layout::horizontal([Constraint::Fill(1), Constraint::Length(24)])
+            .areas(block.inner(area));
+        block.render(area, buf);
+
+        let text_color = if self.selected {
+            self.theme.text.patch(self.theme.selected)
+        } else {
+            self.theme.text
+        };
+        let meta = self.theme.meta_label.patch(text_color);
+
+        let kind = match &self.row {
+            CacheRow::Category(..) => "类别",
+            CacheRow::Book(book) if book.source_url.is_some() => "网络小说",
+            CacheRow::Book(_) => "本地小说",
+        };
+        Line::from(vec![
+            Span::styled(format!("{kind}  "), meta),
+            Span::from(self.row.title()),
+        ])
+        .style(text_color)
+        .render(left, buf);
+
+        let size = self.row.size();
+        Text::from(format!(
+            "{} 个文件  {}",
+            size.files,
+            format_bytes(size.bytes as usize)
+        ))
+        .style(meta)
+        .right_aligned()
+        .render(right, buf);
+    }
+}
+
+fn load_rows() -> Result<Vec<CacheRow>, String> {
+    let usage = storage::usage().map_err(|e| e.to_string())?;
+    let books = storage::books().map_err(|e| e.to_string())?;
+    Ok(Category::ALL
+        .iter()
+        .map(|category| CacheRow::Category(*category, usage.get(*category)))
+        .chain(books.into_iter().map(CacheRow::Book))
+        .collect())
+}
+
+/// 缓存管理页:查看各类缓存的占用,清空某一类、删除单本书的数据、清理孤立存档。
+///
+/// 历史记录与下载队列常驻内存,先改内存里的状态再动磁盘,免得退出时又被写回去。
+#[component]
+pub fn CacheSetting(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_component_theme::<AppChromeTheme>();
+    let history = *hooks.use_context::<State<Option<History>>>();
+
+    let mut confirm_open = hooks.use_state(|| false);
+    let mut info_modal_open = hooks.use_state(|| false);
+    let mut notice = hooks.use_state(String::new);
+    let mut error = hooks.use_state(|| None::<String>);
+    let mut version = hooks.use_state(|| 0usize);
+    let state = hooks.use_state(ListState::default);
+
+    let (rows, load_error) = match hooks.use_memo(load_rows, version.get()) {
+        Ok(rows) => (rows, None),
+        Err(e) => (Vec::new(), Some(format!("统计缓存失败:{e}"))),
+    };
+    let selected = state.read().selected.and_then(|i| rows.get(i).cloned());
+    let is_editing = !confirm_open.get() && !info_modal_open.get();
+
+    hooks.use_event_handler(EventScope::Current, EventPriority::Normal, {
+        let has_selected = selected.is_some();
+        move |event| {
+            let Event::Key(key) = event else {
+                return EventResult::Ignored;
+            };
+            if key.kind != KeyEventKind::Press {
+                return EventResult::Ignored;
+            }
+            match key.code {
+                KeyCode::Char('i') | KeyCode::Char('I') => {
+                    info_modal_open.set(!info_modal_open.get());
+                }
+                _ if !is_editing => return EventResult::Ignored,
+                KeyCode::Char('d') | KeyCode::Char('D') => {
+                    if has_selected {
+                        confirm_open.set(true);
+                    }
+                }
+                KeyCode::Char('p') | KeyCode::Char('P') => {
+                    match storage::prune() {
+                        Ok(freed) => notice.set(format!("已清理孤立存档:{freed}")),
+                        Err(e) => error.set(Some(e.to_string())),
+                    }
+                    version.set(version.get() + 1);
+                }
+                _ => return EventResult::Ignored,
+            }
+            EventResult::Consumed
+        }
+    });
+
+    let total = rows
+        .iter()
+        .filter_map(|row| match row {
+            CacheRow::Category(_, size) => Some(size.bytes),
+            CacheRow::Book(_) => None,
+        })
+        .sum::<u64>();
+    let http = storage::http_cache_stats().counts();
+    let requests = http.hits + http.revalidated + http.misses;
+    let http_summary = if requests == 0 {
+        String::new()
+    } else {
+        format!(
+            " · 本次 HTTP 缓存命中 {}/{}",
+            http.hits + http.revalidated,
+            requests
+        )
+    };
+
+    element!(View {
+        ListSelect<CacheRow>(
+            state: state,
+            is_editing: is_editing,
+            items: rows.clone(),
+            top_title: Line::from("缓存管理").centered().style(theme.title),
+            bottom_title: Line::from(format!(
+                "可清理 {}{}{}",
+                format_bytes(total as usize),
+                http_summary,
+                if notice.read().is_empty() { String::new() } else { format!(" · {}", *notice.read()) }
+            ))
+            .style(theme.meta_label.not_dim()),
+            render_item: {
+                let rows = rows.clone();
+                move |context: &ListBuildContext| {
+                    (
+                        CacheRowItem {
+                            row: rows[context.index].clone(),
+                            selected: context.is_selected,
+                            theme,
+                        }
+                        .into(),
+                        3,
+                    )
+                }
+            },
+            empty_message: load_error.unwrap_or_else(|| "没有可清理的缓存".to_string()),
+        )
+        ConfirmModal(
+            title: "警告",
+            content: match &selected {
+                Some(CacheRow::Category(category, _)) => {
+                    format!("确认清空{}吗?", category.label())
+                }
+                Some(CacheRow::Book(book)) => format!(
+                    "确认删除《{}》的缓存、下载与历史记录吗?(书架上的收藏保留)",
+                    book.title
+                ),
+                None => String::new(),
+            },
+            open: confirm_open.get(),
+            on_confirm: move |_| {
+                let result = match &selected {
+                    Some(CacheRow::Category(category, _)) => clear(*category, history),
+                    Some(CacheRow::Book(book)) => remove_book(book, history),
+                    None => Ok(Size::default()),
+                };
+                match result {
+                    Ok(freed) => notice.set(format!("已释放 {freed}")),
+                    Err(e) => error.set(Some(e)),
+                }
+                version.set(version.get() + 1);
+                confirm_open.set(false);
+            },
+            on_cancel: move |_| {
+                confirm_open.set(false);
+            },
+        )
+        ShortcutInfoModal(
+            key_shortcut_info: KeyShortcutInfo::new(vec![
+                ("选择下一个", "J / ▼"),
+                ("选择上一个", "K / ▲"),
+                ("清空类别 / 删除这本书的数据", "D"),
+                ("清理孤立的网络小说存档", "P"),
+            ]),
+            open: info_modal_open.get(),
+        )
+        WarningModal(
+            tip: error.read().clone().unwrap_or_default(),
+            is_error: true,
+            open: error.read().is_some(),
+            on_close: move |_: ()| { error.set(None); },
+        )
+    })
+}
+
+fn clear(category: Category, history: State<Option<History>>) -> Result<Size, String> {
+    match category {
+        Category::History => {
+            let size = storage::usage().map_err(|e| e.to_string())?.get(category);
+            match history.write().as_mut() {
+                Some(history) => {
+                    history.set_histories(Vec::new());
+                    history.save().map_err(|e| e.to_string())?;
+                    Ok(size)
+                }
+                None => storage::clear(category).map_err(|e| e.to_string()),
+            }
+        }
+        Category::Downloads => {
+            // 先清空内存里的队列,后台任务发现任务没了会自行退出。
+            DOWNLOADS.set(Downloads::default());
+            storage::clear(category).map_err(|e| e.to_string())
+        }
+        _ => storage::clear(category).map_err(|e| e.to_string()),
+    }
+}
+
+fn remove_book(book: &BookData, history: State<Option<History>>) -> Result<Size, String> {
+    download::remove(&book.id).map_err(|e| e.to_string())?;
+    if let Some(history) = history.write().as_mut() {
+        history.remove(&book.id);
+    }
+    storage::remove_book(book).map_err(|e| e.to_string())
+}
@@ -0,0 +1,223 @@
+use crate::{
+    DownloadStatus, DownloadTask,
+    components::{
+        ConfirmModal, KeyShortcutInfo, ShortcutInfoModal, WarningModal, list_select::ListSelect,
+    },
+    download::{self, DOWNLOADS},
+    pages::network_novel::book_detail::BookDetailState,
+    theme::AppChromeTheme,
+};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Layout},
+    text::{Line, Span, Text},
+    widgets::{Block, Gauge, Padding, Paragraph, Widget, WidgetRef},
+};
+use ratatui_kit::prelude::*;
+use tui_widget_list::{ListBuildContext, ListState};
+
+pub struct DownloadItem {
+    pub task: DownloadTask,
+    pub selected: bool,
+    pub theme: AppChromeTheme,
+}
+
+impl Widget for DownloadItem {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        self.render_ref(area, buf);
+    }
+}
+
+impl WidgetRef for DownloadItem {
+    fn render_ref(&self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let block = if self.selected {
+            Block::bordered()
+                .padding(Padding::horizontal(0))
+                .style(self.theme.selected)
+        } else {
+            Block::bordered().padding(Padding::horizontal(0))
+        };
+
+        let [top, gauge, bottom] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(block.inner(area));
+        block.render(area, buf);
+
+        let text_color = if self.selected {
+            self.theme.text.patch(self.theme.selected)
+        } else {
+            self.theme.text
+        };
+        let meta = self.theme.meta_label.patch(text_color);
+
+        let task = &self.task;
+        let status_style = match task.status {
+            DownloadStatus::Failed => self.theme.highlight,
+            _ => meta,
+        };
+        Paragraph::new(Line::from(vec![
+            Span::from(task.title.clone()),
+            Span::styled(format!("  {}", task.status.label()), status_style),
+        ]))
+        .style(text_color)
+        .render(top, buf);
+
+        let total = task.chapters.len().max(1);
+        let done = task.done_count();
+        Gauge::default()
+            .gauge_style(self.theme.highlight)
+            .ratio(done as f64 / total as f64)
+            .label(format!("{done}/{}", task.chapters.len()))
+            .render(gauge, buf);
+
+        let [bottom_left, bottom_right] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(bottom);
+        Span::from(format!("书源：{}", task.source_name))
+            .style(meta)
+            .render(bottom_left, buf);
+        let detail = match (&task.error, task.failed_count()) {
+            (Some(error), _) => error.clone(),
+            (None, 0) => task.created_at.format("%Y-%m-%d %H:%M").to_string(),
+            (None, failed) => format!("{failed} 章失败,按 r 重试"),
+        };
+        Text::from(detail)
+            .style(meta)
+            .right_aligned()
+            .render(bottom_right, buf);
+    }
+}
+
+/// 下载管理页:查看下载进度,暂停 / 继续、重试失败章节、删除任务。
+#[component]
+pub fn DownloadsPage(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_component_theme::<AppChromeTheme>();
+    let downloads = hooks.use_atom(&DOWNLOADS);
+    let mut navigate = hooks.use_navigate();
+
+    let mut delete_modal_open = hooks.use_state(|| false);
+    let mut info_modal_open = hooks.use_state(|| false);
+    let mut error = hooks.use_state(|| None::<String>);
+    let state = hooks.use_state(ListState::default);
+
+    let tasks = downloads.read().tasks.clone();
+    let selected = state.read().selected.and_then(|i| tasks.get(i).cloned());
+    let is_editing = !delete_modal_open.get() && !info_modal_open.get();
+
+    hooks.use_event_handler(EventScope::Current, EventPriority::Normal, {
+        let selected_url = selected.as_ref().map(|t| t.book_url.clone());
+        move |event| {
+            let Event::Key(key) = event else {
+                return EventResult::Ignored;
+            };
+            if key.kind != KeyEventKind::Press {
+                return EventResult::Ignored;
+            }
+            let result = match key.code {
+                KeyCode::Char('i') | KeyCode::Char('I') => {
+                    info_modal_open.set(!info_modal_open.get());
+                    Ok(())
+                }
+                _ if !is_editing => return EventResult::Ignored,
+                KeyCode::Char('p') | KeyCode::Char('P') => match &selected_url {
+                    Some(url) => download::toggle_pause(url),
+                    None => Ok(()),
+                },
+                KeyCode::Char('r') | KeyCode::Char('R') => match &selected_url {
+                    Some(url) => download::retry(url),
+                    None => Ok(()),
+                },
+                KeyCode::Char('d') | KeyCode::Char('D') => {
+                    if selected_url.is_some() {
+                        delete_modal_open.set(true);
+                    }
+                    Ok(())
+                }
+                _ => return EventResult::Ignored,
+            };
+            if let Err(e) = result {
+                error.set(Some(e.to_string()));
+            }
+            EventResult::Consumed
+        }
+    });
+
+    let running = tasks
+        .iter()
+        .filter(|t| t.status == DownloadStatus::Running)
+        .count();
+
+    element!(View {
+        ListSelect<DownloadTask>(
+            state: state,
+            is_editing: is_editing,
+            items: tasks.clone(),
+            top_title: Line::from("下载管理").centered().style(theme.title),
+            bottom_title: Line::from(format!(
+                "{}/{} 个任务 · {running} 个下载中",
+                state.read().selected.map_or(0, |i| i + 1).min(tasks.len()),
+                tasks.len()
+            ))
+            .style(theme.meta_label.not_dim()),
+            render_item: {
+                let tasks = tasks.clone();
+                move |context: &ListBuildContext| {
+                    (
+                        DownloadItem {
+                            task: tasks[context.index].clone(),
+                            selected: context.is_selected,
+                            theme,
+                        }
+                        .into(),
+                        5,
+                    )
+                }
+            },
+            empty_message: "暂无下载任务:在书籍详情页按 d 下载全书,或在目录里按 D 下载章节范围",
+            on_select: move |task: DownloadTask| {
+                navigate.push_with_state("/book-detail", BookDetailState::from_cache(task.book_url));
+            },
+        )
+        ConfirmModal(
+            title: "警告",
+            content: format!(
+                "确认删除《{}》的下载任务吗?已下载的正文也会一并删除。",
+                selected.as_ref().map(|t| t.title.clone()).unwrap_or_default()
+            ),
+            open: delete_modal_open.get(),
+            on_confirm: move |_| {
+                if let Some(task) = &selected {
+                    if let Err(e) = download::remove(&task.book_url) {
+                        error.set(Some(e.to_string()));
+                    }
+                    let index = state.read().selected.unwrap_or(0);
+                    state.write().select(Some(index.saturating_sub(1)));
+                }
+                delete_modal_open.set(false);
+            },
+            on_cancel: move |_| {
+                delete_modal_open.set(false);
+            },
+        )
+        ShortcutInfoModal(
+            key_shortcut_info: KeyShortcutInfo::new(vec![
+                ("选择下一个", "J / ▼"),
+                ("选择上一个", "K / ▲"),
+                ("打开书籍", "Enter"),
+                ("暂停/继续", "P"),
+                ("重试失败章节", "R"),
+                ("删除任务", "D"),
+            ]),
+            open: info_modal_open.get(),
+        )
+        WarningModal(
+            tip: error.read().clone().unwrap_or_default(),
+            is_error: true,
+            open: error.read().is_some(),
+            on_close: move |_: ()| { error.set(None); },
+        )
+    })
+}
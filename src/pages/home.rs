@@ -68,11 +68,23 @@ pub fn Home(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
                                 navigate.push("/book-source");
                             }
                             2 => {
-                                navigate.push("/select-history");
+                                navigate.push("/global-search");
                             }
                             3 => {
+                                navigate.push("/bookshelf");
+                            }
+                            4 => {
+                                navigate.push("/downloads");
+                            }
+                            5 => {
+                                navigate.push("/select-history");
+                            }
+                            6 => {
                                 navigate.push("/theme-setting");
                             }
+                            7 => {
+                                navigate.push("/cache-setting");
+                            }
                             _ => {}
                         }
                     }
@@ -98,8 +110,12 @@ pub fn Home(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
     let list = List::new(vec![
         Line::from("本地小说").centered(),
         Line::from("网络小说").centered(),
+        Line::from("全局搜索").centered(),
+        Line::from("我的书架").centered(),
+        Line::from("下载管理").centered(),
         Line::from("历史记录").centered(),
         Line::from("主题设置").centered(),
+        Line::from("缓存管理").centered(),
     ])
     .style(theme.text)
     .highlight_style(theme.selected);
@@ -116,7 +132,7 @@ pub fn Home(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
             ){
                 widget(info_txt)
             }
-            View(height:Constraint::Length(4)){
+            View(height:Constraint::Length(8)){
                 stateful(list,state)
             }
             ShortcutInfoModal(
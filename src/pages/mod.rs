@@ -1,5 +1,8 @@
 pub mod read_novel;
 pub use read_novel::ReadNovel;
+pub mod bookshelf;
+pub mod cache_setting;
+pub mod downloads;
 pub mod home;
 pub mod local_novel;
 pub mod network_novel;
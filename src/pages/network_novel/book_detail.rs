@@ -13,7 +13,8 @@ use crate::{
     components::{Loading, WarningModal},
     errors::Errors,
     hooks::UseInitState,
-    novel::network_novel::NetworkNovel,
+    novel::{Novel, change_source, network_novel::NetworkNovel},
+    pages::network_novel::change_source::ChangeSourceState,
     theme::AppChromeTheme,
 };
 
@@ -50,6 +51,7 @@ pub fn BookDetail(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
     let size = hooks.use_previous_size();
     let theme = hooks.use_component_theme::<AppChromeTheme>();
     let mut navigate = hooks.use_navigate();
+    let mut notice = hooks.use_state(String::new);
 
     let (book_source_parser, loading, error) = hooks.use_init_state(async move {
         let mut novel = match &(*book_detail_state) {
@@ -66,12 +68,7 @@ pub fn BookDetail(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
             }
         };
 
-        let res = novel
-            .engine
-            .book_info(&novel.book_list_item.book_url)
-            .await?;
-
-        novel.set_book_info(&res);
+        let res = novel.fetch_book_info().await?;
 
         book_info.set(Some(res));
 
@@ -92,6 +89,32 @@ pub fn BookDetail(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
                 }
                 EventResult::Consumed
             }
+            KeyCode::Char('c') | KeyCode::Char('C') => {
+                if let Some(novel) = book_source_parser.read().as_ref() {
+                    navigate.push_with_state(
+                        "/change-source",
+                        ChangeSourceState {
+                            book: change_source::detail_snapshot(novel),
+                            from_reader: false,
+                        },
+                    );
+                }
+                EventResult::Consumed
+            }
+            KeyCode::Char('d') | KeyCode::Char('D') => {
+                if let Some(novel) = book_source_parser.read().clone() {
+                    notice.set("正在获取目录…".to_string());
+                    tokio::spawn(async move {
+                        notice.set(match download_book(novel).await {
+                            Ok(count) => {
+                                format!("已加入下载队列:共 {count} 章,可在主页「下载管理」查看进度")
+                            }
+                            Err(e) => format!("下载失败:{e}"),
+                        });
+                    });
+                }
+                EventResult::Consumed
+            }
             _ => EventResult::Ignored,
         }
     });
@@ -140,8 +163,15 @@ pub fn BookDetail(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
     ])
     .wrap(Wrap { trim: true });
 
+    let hint = if notice.read().is_empty() {
+        "Enter 开始阅读 · d 下载全书 · c 换源".to_string()
+    } else {
+        notice.read().clone()
+    };
+
     element!(Border(
         top_title: Line::from("小说详情").centered().style(theme.title),
+        bottom_title: Line::from(hint).centered().style(theme.meta_label.not_dim()),
         border_style: theme.border,
     ){
         {if loading.get(){
@@ -176,3 +206,12 @@ pub fn BookDetail(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
         )
     })
 }
+
+/// 拉取目录后把整本书加入下载队列,返回章节数。
+async fn download_book(mut novel: NetworkNovel) -> crate::Result<usize> {
+    let (chapters, volumes) = novel.request_toc().await?;
+    novel.set_chapters(&chapters);
+    novel.set_volumes(volumes);
+    novel.download(0..chapters.len())?;
+    Ok(chapters.len())
+}
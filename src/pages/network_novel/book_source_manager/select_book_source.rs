@@ -10,6 +10,7 @@ use tui_widget_list::{ListBuildContext, ListState};
 
 use crate::{
     book_source::BookSourceCache,
+    cache::{SourceHealth, source_health::SourceRecord},
     components::{ConfirmModal, list_select::ListSelect},
     theme::AppChromeTheme,
 };
@@ -19,6 +20,8 @@ pub struct BookSourceListItem {
     pub selected: bool,
     /// 该书源是否已登录(per-source 状态存有有效 cookie/loginHeader)。
     pub logged_in: bool,
+    /// 该书源的健康记录,没联网用过时为 `None`。
+    pub health: Option<SourceRecord>,
     pub theme: AppChromeTheme,
 }
 
@@ -63,6 +66,28 @@ impl WidgetRef for BookSourceListItem {
                 self.theme.meta_label.patch(text_style),
             ));
         }
+        // 健康徽标:降级的书源标出最近的失败类型(错误色),最近失败过的标灰,
+        // 其余显示成功率与平均耗时。
+        if let Some(health) = &self.health {
+            let rate = health.success_rate().unwrap_or(0);
+            let error = health.last_error.map_or("失败", |kind| kind.label());
+            if health.is_degraded() {
+                name_spans.push(Span::styled(
+                    format!("  ✗ 已降级·{error}"),
+                    text_style.patch(self.theme.error),
+                ));
+            } else if health.consecutive_failures > 0 {
+                name_spans.push(Span::styled(
+                    format!("  ! {error}·{rate}%"),
+                    self.theme.meta_label.patch(text_style),
+                ));
+            } else if health.successes > 0 {
+                name_spans.push(Span::styled(
+                    format!("  ✓ {rate}%·{}ms", health.latency_ms),
+                    text_style.patch(self.theme.success),
+                ));
+            }
+        }
         Line::from(name_spans).centered().render(top, buf);
 
         Line::from(format!("网址: {}", item.url))
@@ -111,6 +136,12 @@ pub fn SelectBookSource(
         .iter()
         .map(|s| crate::login::is_logged_in(&s.url))
         .collect();
+    // 健康记录同样在回到列表页时重读,刚跑过的搜索、阅读结果能立即反映出来。
+    let health = SourceHealth::load().unwrap_or_default();
+    let health: Vec<Option<SourceRecord>> = book_sources
+        .iter()
+        .map(|s| health.get(&s.url).cloned())
+        .collect();
 
     let book_sources_keys = book_sources.clone();
     hooks.use_event_handler(EventScope::Current, EventPriority::Normal, move |event| {
@@ -163,12 +194,14 @@ pub fn SelectBookSource(
             top_title: Line::from("选择书源 (回车确认)").style(theme.title).centered(),
             render_item:{
                 let logged_in=logged_in.clone();
+                let health=health.clone();
                 move |context: &ListBuildContext| {
                     let item = &book_sources[context.index];
                     (BookSourceListItem{
                         book_source: item.clone(),
                         selected: context.is_selected,
                         logged_in: logged_in.get(context.index).copied().unwrap_or(false),
+                        health: health.get(context.index).cloned().flatten(),
                         theme,
                     }.into(), 5)
                 }
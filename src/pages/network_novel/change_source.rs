@@ -0,0 +1,254 @@
+use crate::{
+    Bookshelf, History,
+    book_source::BookSourceCache,
+    cache::NetworkNovelCache,
+    components::{KeyShortcutInfo, ShortcutInfoModal, WarningModal, list_select::ListSelect},
+    errors::Errors,
+    hooks::UseInitState,
+    novel::{
+        Novel,
+        change_source::{self, Candidate, Candidates},
+    },
+    pages::network_novel::book_detail::BookDetailState,
+    theme::AppChromeTheme,
+};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Layout},
+    text::{Line, Span, Text},
+    widgets::{Block, Padding, Widget, WidgetRef},
+};
+use ratatui_kit::prelude::*;
+use tui_widget_list::{ListBuildContext, ListState};
+
+/// 换源页的路由状态。
+#[derive(Debug, Clone)]
+pub struct ChangeSourceState {
+    /// 原书的阅读存档:书名、作者、书源与进度。
+    pub book: NetworkNovelCache,
+    /// 从阅读页进入时换源后接着读,否则回到新书源的详情页。
+    pub from_reader: bool,
+}
+
+pub struct CandidateItem {
+    pub candidate: Candidate,
+    pub selected: bool,
+    pub theme: AppChromeTheme,
+}
+
+impl Widget for CandidateItem {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        self.render_ref(area, buf);
+    }
+}
+
+impl WidgetRef for CandidateItem {
+    fn render_ref(&self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let block = if self.selected {
+            Block::bordered()
+                .padding(Padding::horizontal(1))
+                .style(self.theme.selected)
+        } else {
+            Block::bordered().padding(Padding::horizontal(1))
+        };
+        let [left, right] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(block.inner(area));
+        block.render(area, buf);
+
+        let text_color = if self.selected {
+            self.theme.text.patch(self.theme.selected)
+        } else {
+            self.theme.text
+        };
+        let meta = self.theme.meta_label.patch(text_color);
+        let info = &self.candidate.item.info;
+
+        Line::from(vec![
+            Span::styled(format!("{}  ", self.candidate.source.name), meta),
+            Span::from(format!("《{}》", info.name)),
+            Span::styled(
+                if info.author.is_empty() {
+                    String::new()
+                } else {
+                    format!(" {}", info.author)
+                },
+                meta,
+            ),
+        ])
+        .style(text_color)
+        .render(left, buf);
+
+        Text::from(format!("最新:{}", info.last_chapter))
+            .style(meta)
+            .right_aligned()
+            .render(right, buf);
+    }
+}
+
+/// 换源页:在全部书源里搜同一本书,选中后把阅读进度搬到新书源。
+///
+/// 历史记录与书架里的条目改指新书;原书源下的阅读存档删掉,已下载的章节保留。
+#[component]
+pub fn ChangeSource(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
+    let route_state = hooks.use_route_state::<ChangeSourceState>();
+    let book_source_cache = *hooks.use_context::<State<Option<BookSourceCache>>>();
+    let history = *hooks.use_context::<State<Option<History>>>();
+    let bookshelf = *hooks.use_context::<State<Option<Bookshelf>>>();
+    let theme = hooks.use_component_theme::<AppChromeTheme>();
+    let navigate = hooks.use_navigate();
+
+    let mut info_modal_open = hooks.use_state(|| false);
+    let mut switching = hooks.use_state(|| false);
+    let mut error = hooks.use_state(|| None::<String>);
+    let state = hooks.use_state(ListState::default);
+
+    let (candidates, _, search_error) = hooks.use_init_state({
+        let book = route_state.book.clone();
+        async move {
+            let sources = book_source_cache
+                .read()
+                .as_ref()
+                .map(|cache| cache.book_sources.clone())
+                .unwrap_or_default();
+            Ok::<Candidates, Errors>(change_source::search(&sources, &book).await)
+        }
+    });
+
+    hooks.use_event_handler(EventScope::Current, EventPriority::Normal, move |event| {
+        let Event::Key(key) = event else {
+            return EventResult::Ignored;
+        };
+        if key.kind != KeyEventKind::Press {
+            return EventResult::Ignored;
+        }
+        match key.code {
+            KeyCode::Char('i') | KeyCode::Char('I') => {
+                info_modal_open.set(!info_modal_open.get());
+                EventResult::Consumed
+            }
+            _ => EventResult::Ignored,
+        }
+    });
+
+    let searching = candidates.read().is_none() && search_error.read().is_none();
+    let result = candidates.read().clone().unwrap_or_default();
+    let items = result.items.clone();
+    let book = &route_state.book;
+
+    let bottom_title = if switching.get() {
+        "正在换源…".to_string()
+    } else if searching {
+        String::new()
+    } else {
+        let mut title = format!("{} 个候选 · 搜索了 {} 个书源", items.len(), result.searched);
+        if result.failed > 0 {
+            title.push_str(&format!(",{} 个失败", result.failed));
+        }
+        if result.skipped > 0 {
+            title.push_str(&format!(" · 跳过 {} 个连续失败的书源", result.skipped));
+        }
+        title
+    };
+    let progress = if book.current_chapter_name.is_empty() {
+        String::new()
+    } else {
+        format!(" · 读到「{}」", book.current_chapter_name)
+    };
+
+    element!(View {
+        ListSelect<Candidate>(
+            state: state,
+            is_editing: !info_modal_open.get() && !switching.get() && error.read().is_none(),
+            items: items.clone(),
+            top_title: Line::from(format!(
+                "换源:《{}》 当前书源 {}{progress}",
+                book.book_list_item.info.name, book.book_source_name
+            ))
+            .centered()
+            .style(theme.title),
+            bottom_title: Line::from(bottom_title).style(theme.meta_label.not_dim()),
+            loading: searching,
+            loading_tip: "正在所有书源里搜索…".to_string(),
+            render_item: {
+                let items = items.clone();
+                move |context: &ListBuildContext| {
+                    (
+                        CandidateItem {
+                            candidate: items[context.index].clone(),
+                            selected: context.is_selected,
+                            theme,
+                        }
+                        .into(),
+                        3,
+                    )
+                }
+            },
+            empty_message: search_error
+                .read()
+                .as_ref()
+                .map(|e| format!("搜索失败:{e}"))
+                .unwrap_or_else(|| "其他书源里没有找到这本书".to_string()),
+            on_select: {
+                let book = route_state.book.clone();
+                let from_reader = route_state.from_reader;
+                move |candidate: Candidate| {
+                    if switching.get() {
+                        return;
+                    }
+                    switching.set(true);
+                    let book = book.clone();
+                    let mut navigate = navigate;
+                    tokio::spawn(async move {
+                        match change_source::switch(&book, &candidate).await {
+                            Ok(switched) => {
+                                let old_id = &book.book_list_item.book_url;
+                                let new_id = switched.novel.get_id();
+                                if let Some(history) = history.write().as_mut()
+                                    && history.histories.iter().any(|(id, _)| id == old_id)
+                                {
+                                    history.remove(old_id);
+                                    history.add(&new_id, switched.item.clone());
+                                    if let Err(e) = history.save() {
+                                        error.set(Some(e.to_string()));
+                                    }
+                                }
+                                if let Some(shelf) = bookshelf.write().as_mut() {
+                                    shelf.replace(old_id, &new_id, switched.item);
+                                    if let Err(e) = shelf.save() {
+                                        error.set(Some(e.to_string()));
+                                    }
+                                }
+                                // 回到换源前的页面,再把它换成新书源下的同一页。
+                                navigate.back();
+                                if from_reader {
+                                    navigate.replace_with_state("/network-novel", switched.novel);
+                                } else {
+                                    navigate.replace_with_state(
+                                        "/book-detail",
+                                        BookDetailState::from_cache(new_id),
+                                    );
+                                }
+                            }
+                            Err(e) => error.set(Some(format!("换源失败:{e}"))),
+                        }
+                        switching.set(false);
+                    });
+                }
+            },
+        )
+        ShortcutInfoModal(
+            key_shortcut_info: KeyShortcutInfo::new(vec![
+                ("选择下一个", "J / ▼"),
+                ("选择上一个", "K / ▲"),
+                ("换到这个书源", "Enter"),
+            ]),
+            open: info_modal_open.get(),
+        )
+        WarningModal(
+            tip: error.read().clone().unwrap_or_default(),
+            is_error: true,
+            open: error.read().is_some(),
+            on_close: move |_: ()| { error.set(None); },
+        )
+    })
+}
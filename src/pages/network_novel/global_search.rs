@@ -0,0 +1,325 @@
+use crate::{
+    book_source::BookSourceCache,
+    cache::SourceHealth,
+    components::{
+        KeyShortcutInfo, ShortcutInfoModal, WarningModal, list_select::ListSelect,
+        search_input::SearchInput, select::Select,
+    },
+    novel::global_search::{self, GlobalSearch, MergedBook, SourceHit},
+    pages::network_novel::book_detail::BookDetailState,
+    theme::AppChromeTheme,
+};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use futures::StreamExt;
+use ratatui::{
+    layout::{Constraint, Layout, Margin},
+    style::Style,
+    text::{Line, Span, Text},
+    widgets::{Block, ListItem, Padding, Widget, WidgetRef},
+};
+use ratatui_kit::prelude::*;
+use tui_widget_list::{ListBuildContext, ListState};
+
+pub struct MergedBookItem {
+    pub book: MergedBook,
+    pub selected: bool,
+    pub theme: AppChromeTheme,
+}
+
+impl Widget for MergedBookItem {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        self.render_ref(area, buf);
+    }
+}
+
+impl WidgetRef for MergedBookItem {
+    fn render_ref(&self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let block = if self.selected {
+            Block::bordered()
+                .padding(Padding::horizontal(1))
+                .style(self.theme.selected)
+        } else {
+            Block::bordered().padding(Padding::horizontal(1))
+        };
+        let [first, second] = Layout::vertical([Constraint::Length(1), Constraint::Length(1)])
+            .areas(block.inner(area));
+        let [left, right] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(first);
+        block.render(area, buf);
+
+        let text_color = if self.selected {
+            self.theme.text.patch(self.theme.selected)
+        } else {
+            self.theme.text
+        };
+        let meta = self.theme.meta_label.patch(text_color);
+        let info = &self.book.item().info;
+
+        Line::from(vec![
+            Span::from(format!("《{}》", info.name)),
+            Span::styled(
+                if info.author.is_empty() {
+                    String::new()
+                } else {
+                    format!(" {}", info.author)
+                },
+                meta,
+            ),
+        ])
+        .style(text_color)
+        .render(left, buf);
+
+        if !info.last_chapter.is_empty() {
+            Text::from(format!("最新:{}", info.last_chapter))
+                .style(meta)
+                .right_aligned()
+                .render(right, buf);
+        }
+
+        Line::from(vec![
+            Span::styled(format!("{} 个书源:", self.book.hits.len()), meta),
+            Span::from(self.book.source_names()),
+        ])
+        .style(text_color)
+        .render(second, buf);
+    }
+}
+
+/// 书源选择弹窗里的一项:书源名与该书源下的最新章节。
+#[derive(Debug, Clone)]
+pub struct SourceChoice(pub SourceHit);
+
+impl From<SourceChoice> for ListItem<'_> {
+    fn from(value: SourceChoice) -> Self {
+        let SourceHit { source, item } = value.0;
+        if item.info.last_chapter.is_empty() {
+            ListItem::new(source.name)
+        } else {
+            ListItem::new(format!("{}  最新:{}", source.name, item.info.last_chapter))
+        }
+    }
+}
+
+/// 聚合搜索页:用同一个关键词并发搜索全部书源,结果按书名 + 作者合并后陆续出现。
+///
+/// 只有一个书源收录的书直接进详情页,多个书源收录时先选书源。
+#[component]
+pub fn GlobalSearchPage(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
+    let book_source_cache = *hooks.use_context::<State<Option<BookSourceCache>>>();
+    let theme = hooks.use_component_theme::<AppChromeTheme>();
+    let mut navigate = hooks.use_navigate();
+
+    let mut info_modal_open = hooks.use_state(|| false);
+    let mut error = hooks.use_state(|| None::<String>);
+    // 第几次提交与关键词:重复提交同一个关键词也重新搜索。
+    let mut query = hooks.use_state(|| None::<(u64, String)>);
+    let mut progress = hooks.use_state(|| None::<GlobalSearch>);
+    let mut picking = hooks.use_state(|| None::<MergedBook>);
+    let list_state = hooks.use_state(ListState::default);
+    let picker_state = hooks.use_state(ratatui::widgets::ListState::default);
+
+    // 换关键词或离开本页时,上一轮还没返回的书源随 future 一起丢弃。
+    hooks.use_async_effect(
+        {
+            let query = query.read().clone();
+            async move {
+                let Some((_, key)) = query else {
+                    return;
+                };
+                let sources = book_source_cache
+                    .read()
+                    .as_ref()
+                    .map(|cache| global_search::searchable(&cache.book_sources))
+                    .unwrap_or_default();
+                // 连续失败的书源先不搜,`trn doctor` 体检通过后恢复。
+                let (sources, skipped) = SourceHealth::load().unwrap_or_default().triage(&sources);
+                progress.set(Some(GlobalSearch {
+                    skipped: skipped.into_iter().map(|source| source.name).collect(),
+                    ..GlobalSearch::new(&sources)
+                }));
+                let mut results = std::pin::pin!(global_search::search(sources.clone(), key));
+                while let Some((index, result)) = results.next().await {
+                    if let Some(progress) = progress.write().as_mut() {
+                        progress.finish(index, &sources[index], result);
+                    }
+                }
+            }
+        },
+        query.read().clone(),
+    );
+
+    hooks.use_event_handler(EventScope::Current, EventPriority::Normal, move |event| {
+        let Event::Key(key) = event else {
+            return EventResult::Ignored;
+        };
+        if key.kind != KeyEventKind::Press {
+            return EventResult::Ignored;
+        }
+        match key.code {
+            KeyCode::Esc if picking.read().is_some() => {
+                picking.set(None);
+                EventResult::Consumed
+            }
+            KeyCode::Char('i') | KeyCode::Char('I') => {
+                info_modal_open.set(!info_modal_open.get());
+                EventResult::Consumed
+            }
+            _ => EventResult::Ignored,
+        }
+    });
+
+    let mut open = move |hit: SourceHit| match crate::browser_assist::build_engine(hit.source) {
+        Ok(engine) => {
+            navigate.push_with_state("/book-detail", BookDetailState::new(hit.item, engine));
+        }
+        Err(e) => error.set(Some(e.to_string())),
+    };
+
+    let current = progress.read().clone();
+    let books = current
+        .as_ref()
+        .map(|progress| progress.books.clone())
+        .unwrap_or_default();
+    let searching = current.as_ref().is_some_and(|progress| !progress.is_done());
+
+    let status = match &current {
+        None => String::new(),
+        Some(progress) if progress.sources.is_empty() && !progress.skipped.is_empty() => format!(
+            "书源都因连续失败被跳过({}),用 trn doctor 体检通过后恢复",
+            progress.skipped.join("、")
+        ),
+        Some(progress) if progress.sources.is_empty() => "没有配置了搜索的书源".to_string(),
+        Some(progress) => {
+            let mut status = if searching {
+                format!(
+                    "正在搜索 {}/{} 个书源 · 已找到 {} 本",
+                    progress.finished(),
+                    progress.sources.len(),
+                    books.len()
+                )
+            } else {
+                format!(
+                    "搜索了 {} 个书源 · 找到 {} 本",
+                    progress.sources.len(),
+                    books.len()
+                )
+            };
+            let failures = progress.failures();
+            if !failures.is_empty() {
+                status.push_str(&format!(" · 失败:{}", failures.join("、")));
+            }
+            if !progress.skipped.is_empty() {
+                status.push_str(&format!(
+                    " · 跳过连续失败的书源:{}",
+                    progress.skipped.join("、")
+                ));
+            }
+            status
+        }
+    };
+
+    let pick_choices = picking
+        .read()
+        .as_ref()
+        .map(|book| {
+            book.hits
+                .iter()
+                .cloned()
+                .map(SourceChoice)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let is_editing = !info_modal_open.get() && picking.read().is_none() && error.read().is_none();
+
+    element!(View {
+        SearchInput(
+            is_editing: is_editing,
+            placeholder: "按s键输入书名或作者,在全部书源里搜索",
+            on_submit: move |text: String| {
+                let text = text.trim().to_string();
+                if text.is_empty() {
+                    return false;
+                }
+                let count = query.read().as_ref().map_or(0, |(count, _)| count + 1);
+                query.set(Some((count, text)));
+                list_state.write().select(Some(0));
+                true
+            },
+        )
+        ListSelect<MergedBook>(
+            state: list_state,
+            is_editing: is_editing,
+            items: books.clone(),
+            top_title: Line::from("全局搜索").centered().style(theme.title),
+            bottom_title: Line::from(status).style(theme.meta_label.not_dim()),
+            // 只在一条结果都还没有时显示加载态,有结果后边搜边显示。
+            loading: searching && books.is_empty(),
+            loading_tip: "正在所有书源里搜索…".to_string(),
+            render_item: {
+                let books = books.clone();
+                move |context: &ListBuildContext| {
+                    (
+                        MergedBookItem {
+                            book: books[context.index].clone(),
+                            selected: context.is_selected,
+                            theme,
+                        }
+                        .into(),
+                        4,
+                    )
+                }
+            },
+            empty_message: if current.is_some() {
+                "没有找到相关书籍"
+            } else {
+                "输入书名或作者,在全部书源里搜索"
+            },
+            on_select: move |book: MergedBook| {
+                if book.hits.len() == 1 {
+                    open(book.hits[0].clone());
+                } else {
+                    picker_state.write().select(Some(0));
+                    picking.set(Some(book));
+                }
+            },
+        )
+        Modal(
+            width: Constraint::Percentage(50),
+            height: Constraint::Percentage(50),
+            style: Style::default().dim(),
+            open: picking.read().is_some(),
+        ) {
+            View(
+                margin: Margin::new(1, 1),
+            ){
+                Select<SourceChoice>(
+                    items: pick_choices,
+                    state: picker_state,
+                    on_select: move |choice: SourceChoice| {
+                        picking.set(None);
+                        open(choice.0);
+                    },
+                    top_title: Line::from("选择书源").style(theme.title).centered(),
+                    is_editing: picking.read().is_some() && error.read().is_none(),
+                    empty_message: "暂无书源".to_string(),
+                )
+            }
+        }
+        ShortcutInfoModal(
+            key_shortcut_info: KeyShortcutInfo::new(vec![
+                ("搜索", "S"),
+                ("选择下一个", "J / ▼"),
+                ("选择上一个", "K / ▲"),
+                ("查看详情", "Enter"),
+                ("关闭书源选择", "Esc"),
+            ]),
+            open: info_modal_open.get(),
+        )
+        WarningModal(
+            tip: error.read().clone().unwrap_or_default(),
+            is_error: true,
+            open: error.read().is_some(),
+            on_close: move |_: ()| { error.set(None); },
+        )
+    })
+}
@@ -1,4 +1,6 @@
 pub mod book_detail;
 pub mod book_source_login;
 pub mod book_source_manager;
+pub mod change_source;
+pub mod global_search;
 pub mod select_books;
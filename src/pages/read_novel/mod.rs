@@ -1,10 +1,14 @@
 use crate::{
-    History,
+    Bookshelf, History,
     components::{KeyShortcutInfo, Loading, ShortcutInfoModal, WarningModal},
     errors::Errors,
     hooks::UseInitState,
     keymap::{ReaderAction, display_keys},
-    novel::{Novel, VolumeMarker},
+    novel::{
+        Novel, VolumeMarker,
+        name_index::{NameIndex, build_name_index},
+    },
+    pages::network_novel::change_source::ChangeSourceState,
 };
 use futures::FutureExt;
 use ratatui::layout::Direction;
@@ -19,6 +23,8 @@ use tokio::sync::Notify;
 use tokio::time::{Duration, sleep};
 mod tts;
 pub use tts::*;
+mod name_index;
+pub use name_index::*;
 
 #[component]
 pub fn ReadNovel<T>(mut hooks: Hooks) -> impl Into<AnyElement<'static>>
@@ -27,17 +33,24 @@ where
 {
     let route_state = hooks.use_route_state::<T::Args>();
     let history = *hooks.use_context::<State<Option<History>>>();
+    let bookshelf = *hooks.use_context::<State<Option<Bookshelf>>>();
     let mut chapters = hooks.use_state(std::vec::Vec::new);
     let mut volumes = hooks.use_state(Vec::<VolumeMarker>::new);
     let mut current_chapter = hooks.use_state(|| 0usize);
     let mut content = hooks.use_state(String::default);
     let mut is_read_mode = hooks.use_state(|| false);
     let mut is_tts_open = hooks.use_state(|| false);
+    let mut names_open = hooks.use_state(|| false);
+    let mut name_index = hooks.use_state(|| None::<Arc<NameIndex>>);
+    let names_building = hooks.use_state(|| false);
     let (width, height) = hooks.use_terminal_size();
+    // 订阅下载队列:下载进度变化时刷新目录里的已下载标记。
+    hooks.use_atom(&crate::download::DOWNLOADS);
 
     let mut content_loading = hooks.use_state(|| false);
     let mut info_modal_open = hooks.use_state(|| false);
     let mut line_percent = hooks.use_state(|| 0.0);
+    let mut navigate = hooks.use_navigate();
 
     let (novel, loading, error) = hooks.use_init_state(async move {
         let args = route_state.as_ref().clone();
@@ -65,6 +78,9 @@ where
             content_loading.set(false);
             line_percent.set(res.line_percent);
 
+            let ahead = res.clone();
+            tokio::spawn(async move { ahead.prefetch().await });
+
             Ok::<T, Errors>(res)
         })
         .await?
@@ -81,6 +97,12 @@ where
 
                 if let Some(history) = history.as_mut() {
                     let history_item = novel.to_history_item().expect("to_history_item failed");
+                    // 在书架上的书同步刷新进度快照;书架与历史记录各自独立保存。
+                    if let Some(shelf) = bookshelf.write().as_mut() {
+                        let chapter_count = novel.get_chapters().map_or(0, |c| c.len());
+                        shelf.record_read(&novel.get_id(), history_item.clone(), chapter_count);
+                        let _ = shelf.save();
+                    }
                     history.add(&novel.get_id(), history_item);
                     history.save().expect("save history failed");
                 }
@@ -102,6 +124,7 @@ where
             });
 
             let novel = novel.read().clone();
+            let ahead = novel.clone();
             let content_result = novel.map(|n| tokio::spawn(async move { n.get_content().await }));
 
             if let Some(fut) = content_result {
@@ -123,6 +146,11 @@ where
             notify.notify_one();
             content_loading.set(false);
             let _ = show_loading_handle.await;
+
+            // 正文到手后预取后面几章;换章时本 effect 被替换,没跑完的预取随之取消。
+            if let Some(novel) = ahead {
+                novel.prefetch().await;
+            }
         },
         current_chapter.get(),
     );
@@ -142,7 +170,40 @@ where
                 info_modal_open.set(!info_modal_open.get());
                 EventResult::Consumed
             }
-            ReaderAction::ToggleTts if !info_modal_open.get() => {
+            ReaderAction::ToggleNames if !info_modal_open.get() && !is_tts_open.get() => {
+                let open = !names_open.get();
+                names_open.set(open);
+                is_read_mode.set(true);
+                // 首次打开:读缓存的索引,没有缓存才扫描全书。
+                if open && name_index.read().is_none() && !names_building.get() {
+                    spawn_name_index(
+                        novel.read().clone(),
+                        None,
+                        name_index,
+                        names_building,
+                        error,
+                    );
+                }
+                EventResult::Consumed
+            }
+            ReaderAction::ChangeSource
+                if !info_modal_open.get() && !is_tts_open.get() && !names_open.get() =>
+            {
+                // 只有网络小说能换源;带上当前滚动位置,换源后接着读。
+                let Some(mut book) = novel.read().as_ref().and_then(|n| n.source_snapshot()) else {
+                    return EventResult::Ignored;
+                };
+                book.line_percent = line_percent.get();
+                navigate.push_with_state(
+                    "/change-source",
+                    ChangeSourceState {
+                        book,
+                        from_reader: true,
+                    },
+                );
+                EventResult::Consumed
+            }
+            ReaderAction::ToggleTts if !info_modal_open.get() && !names_open.get() => {
                 // 听书设置面板(TTSManager)只在阅读模式(is_read_mode)渲染。若在章节选择模式
                 // 按 t,直接切到阅读模式并打开,避免「翻转 is_tts_open 却无 UI」的死输入,以及
                 // 之后 Tab 进阅读模式时面板意外已开的状态错位。
@@ -183,7 +244,7 @@ where
         { if is_read_mode.get() {
             element!(View{
                 ReadContent(
-                    is_scroll: !is_tts_open.get() && !info_modal_open.get(),
+                    is_scroll: !is_tts_open.get() && !info_modal_open.get() && !names_open.get(),
                     width: width,
                     height: height,
                     content: content.read().clone(),
@@ -231,9 +292,57 @@ where
                     open: is_tts_open.get(),
                     is_editing: is_tts_open.get() && !info_modal_open.get(),
                 )
+                NameIndexPanel(
+                    open: names_open.get(),
+                    is_editing: names_open.get() && !info_modal_open.get(),
+                    index: name_index.read().clone(),
+                    building: names_building.get(),
+                    chapters: chapters.read().clone(),
+                    current_chapter: current_chapter.get(),
+                    on_jump: move |(index, percent): (usize, f64)| {
+                        if let Some(novel) = novel.write().as_mut() {
+                            if let Err(e) = novel.set_chapter(index) {
+                                error.write().replace(e);
+                                return;
+                            }
+                            current_chapter.set(index);
+                            line_percent.set(percent);
+                            names_open.set(false);
+                        }
+                    },
+                    on_add: move |name: String| {
+                        // 新名字要在全书里重新计数,沿用原有名单重建。
+                        let (mut user_names, excluded) = name_lists(&name_index.read());
+                        if !user_names.contains(&name) {
+                            user_names.push(name);
+                        }
+                        let excluded = excluded.into_iter().filter(|n| !user_names.contains(n)).collect();
+                        spawn_name_index(novel.read().clone(), Some((user_names, excluded)), name_index, names_building, error);
+                    },
+                    on_remove: move |name: String| {
+                        let Some(mut index) = name_index.read().as_deref().cloned() else {
+                            return;
+                        };
+                        index.remove(&name);
+                        if let Some(novel) = novel.read().as_ref()
+                            && let Err(e) = novel
+                                .cache_path()
+                                .and_then(|path| index.save(&NameIndex::cache_path(&path)))
+                        {
+                            error.write().replace(e);
+                        }
+                        name_index.set(Some(Arc::new(index)));
+                    },
+                    on_close: move |_| names_open.set(false),
+                    on_rebuild: move |_| {
+                        if !names_building.get() {
+                            spawn_name_index(novel.read().clone(), Some(name_lists(&name_index.read())), name_index, names_building, error);
+                        }
+                    },
+                )
                 ShortcutInfoModal(
                     // 阅读页 action 的键名从 keymap 动态取(重绑后帮助随之更新);
-                    // TTS 面板内部键(组件自处理,未迁移)保持硬编码。
+                    // TTS / 人物索引面板内部键(组件自处理,未迁移)保持硬编码。
                     key_shortcut_info: {
                         let dk = |label: &str, action| (label.to_string(), display_keys(&reader_keymap, action));
                         let sk = |label: &str, keys: &str| (label.to_string(), keys.to_string());
@@ -249,6 +358,18 @@ where
                                 sk("增大速度/音量", "→ / L"),
                                 sk("切换自动播放", "← / →"),
                             ])
+                        } else if names_open.get() {
+                            KeyShortcutInfo(vec![
+                                dk("关闭人物索引", ReaderAction::ToggleNames),
+                                sk("上一项", "↑ / K"),
+                                sk("下一项", "↓ / J"),
+                                sk("跳到首次出场/所选章节", "Enter"),
+                                sk("查看出场章节", "→ / L"),
+                                sk("返回名字列表", "← / H"),
+                                sk("添加名字", "A"),
+                                sk("删除名字", "X"),
+                                sk("重建索引", "R"),
+                            ])
                         } else {
                             KeyShortcutInfo(vec![
                                 dk("切换章节选择模式", ReaderAction::ToggleReadMode),
@@ -265,6 +386,12 @@ where
                                 dk("下一页", ReaderAction::PageDown),
                                 dk("跳到开头", ReaderAction::GoTop),
                                 dk("跳到结尾", ReaderAction::GoBottom),
+                                dk("进入/退出选择模式", ReaderAction::ToggleSelect),
+                                dk("复制选中文字", ReaderAction::Yank),
+                                dk("复制并追加到摘录文件", ReaderAction::YankToFile),
+                                dk("查词(离线词典)", ReaderAction::Lookup),
+                                dk("人物索引", ReaderAction::ToggleNames),
+                                dk("换源(网络小说)", ReaderAction::ChangeSource),
                             ])
                         }
                     },
@@ -278,6 +405,15 @@ where
                     chapters: chapters.read().clone(),
                     volumes: volumes.read().clone(),
                     default_value: current_chapter.get(),
+                    downloadable: novel.read().as_ref().is_some_and(|n| n.downloadable()),
+                    downloaded: novel.read().as_ref().map(|n| n.downloaded_chapters()).unwrap_or_default(),
+                    on_download: move |range| {
+                        if let Some(novel) = novel.read().as_ref()
+                            && let Err(e) = novel.download(range)
+                        {
+                            error.write().replace(e);
+                        }
+                    },
                     on_select: move |index| {
                         if let Some(novel) = novel.write().as_mut() {
                             if let Err(e)=novel.set_chapter(index){
@@ -307,6 +443,8 @@ where
                         ("选择下一章".to_string(), "↓ / J".to_string()),
                         ("确认选择章节".to_string(), "Enter".to_string()),
                         ("搜索章节".to_string(), "S".to_string()),
+                        ("标记/取消下载起点(网络小说)".to_string(), "M".to_string()),
+                        ("下载起点到光标处/光标处到末章".to_string(), "Shift+D".to_string()),
                     ]),
                     open: info_modal_open.get(),
                 )
@@ -320,3 +458,51 @@ where
     })
     .into_any()
 }
+
+/// 当前索引里用户添加 / 删除的名字,重建时沿用。
+fn name_lists(index: &Option<Arc<NameIndex>>) -> (Vec<String>, Vec<String>) {
+    index
+        .as_ref()
+        .map(|index| (index.user_names.clone(), index.excluded.clone()))
+        .unwrap_or_default()
+}
+
+/// 后台准备人物索引并写回书籍缓存旁的索引文件。
+///
+/// `lists` 为 `None` 时优先读已缓存的索引;否则按给定的 `(用户添加, 用户删除)` 名单扫描全书重建。
+fn spawn_name_index<T>(
+    novel: Option<T>,
+    lists: Option<(Vec<String>, Vec<String>)>,
+    mut name_index: State<Option<Arc<NameIndex>>>,
+    mut building: State<bool>,
+    error: State<Option<Errors>>,
+) where
+    T: Novel + Send + Sync + 'static,
+{
+    let Some(novel) = novel else {
+        return;
+    };
+    building.set(true);
+    tokio::spawn(async move {
+        let result = async {
+            let path = NameIndex::cache_path(&novel.cache_path()?);
+            if lists.is_none()
+                && let Some(index) = NameIndex::load(&path)?
+            {
+                return Ok(index);
+            }
+            let (user_names, excluded) = lists.unwrap_or_default();
+            let index = build_name_index(&novel, user_names, excluded).await?;
+            index.save(&path)?;
+            Ok::<_, Errors>(index)
+        }
+        .await;
+        match result {
+            Ok(index) => name_index.set(Some(Arc::new(index))),
+            Err(e) => {
+                error.write().replace(e);
+            }
+        }
+        building.set(false);
+    });
+}
@@ -0,0 +1,277 @@
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Direction, Margin},
+    style::Style,
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState},
+};
+use ratatui_kit::prelude::*;
+use std::sync::Arc;
+
+use super::ChapterName;
+use crate::{
+    components::search_input::SearchInput, novel::name_index::NameIndex, theme::AppChromeTheme,
+};
+
+#[derive(Props, Default)]
+pub struct NameIndexPanelProps {
+    pub open: bool,
+    pub is_editing: bool,
+    pub index: Option<Arc<NameIndex>>,
+    /// 正在扫描全书建索引。
+    pub building: bool,
+    pub chapters: Vec<ChapterName>,
+    pub current_chapter: usize,
+    /// 跳到 `(章节下标, 章内进度)`。
+    pub on_jump: Handler<'static, (usize, f64)>,
+    pub on_add: Handler<'static, String>,
+    pub on_remove: Handler<'static, String>,
+    pub on_rebuild: Handler<'static, ()>,
+    pub on_close: Handler<'static, ()>,
+}
+
+/// 人物索引面板:左侧名字列表,右侧所选名字的出场章节。
+///
+/// 名字列表里 Enter 跳到首次出场,`l`/→ 进入章节列表(光标落在当前章或之后最近的出场),
+/// 章节列表里 Enter 跳到该章首次提及处,`h`/←/Esc 返回名字列表。
+#[component]
+pub fn NameIndexPanel(
+    props: &mut NameIndexPanelProps,
+    mut hooks: Hooks,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_component_theme::<AppChromeTheme>();
+    let names_state = hooks.use_state(ListState::default);
+    let chapters_state = hooks.use_state(ListState::default);
+    let mut in_chapters = hooks.use_state(|| false);
+
+    let index = props.index.clone();
+    let names_len = index.as_ref().map_or(0, |i| i.names.len());
+    hooks.use_effect(
+        move || {
+            let mut state = names_state.write();
+            if names_len == 0 {
+                state.select(None);
+            } else if state.selected().is_none_or(|i| i >= names_len) {
+                state.select(Some(0));
+            }
+        },
+        names_len,
+    );
+
+    let selected = names_state
+        .read()
+        .selected()
+        .and_then(|i| index.as_ref()?.names.get(i).cloned());
+
+    let is_editing = props.open && props.is_editing;
+    let current_chapter = props.current_chapter;
+    let mut on_jump = props.on_jump.take();
+    let mut on_remove = props.on_remove.take();
+    let mut on_rebuild = props.on_rebuild.take();
+    let mut on_close = props.on_close.take();
+
+    hooks.use_event_handler(EventScope::Current, EventPriority::Normal, {
+        let selected = selected.clone();
+        move |event| {
+            let Event::Key(key) = event else {
+                return EventResult::Ignored;
+            };
+            if key.kind != KeyEventKind::Press || !is_editing {
+                return EventResult::Ignored;
+            }
+            let appearances = selected.as_ref().map_or(0, |e| e.chapters.len());
+            match key.code {
+                KeyCode::Char('j') | KeyCode::Down => {
+                    if in_chapters.get() {
+                        chapters_state.write().select_next();
+                    } else {
+                        names_state.write().select_next();
+                    }
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    if in_chapters.get() {
+                        chapters_state.write().select_previous();
+                    } else {
+                        names_state.write().select_previous();
+                    }
+                }
+                KeyCode::Char('l') | KeyCode::Right if appearances > 0 => {
+                    if let Some(entry) = &selected {
+                        chapters_state
+                            .write()
+                            .select(Some(entry.nearest(current_chapter)));
+                    }
+                    in_chapters.set(true);
+                }
+                KeyCode::Char('h') | KeyCode::Left => in_chapters.set(false),
+                KeyCode::Enter => {
+                    let appearance = selected.as_ref().and_then(|entry| {
+                        if in_chapters.get() {
+                            entry.chapters.get(chapters_state.read().selected()?)
+                        } else {
+                            entry.first()
+                        }
+                    });
+                    if let Some(a) = appearance {
+                        on_jump((a.chapter, a.percent));
+                    }
+                }
+                KeyCode::Char('x') => {
+                    if let Some(entry) = &selected {
+                        on_remove(entry.name.clone());
+                        in_chapters.set(false);
+                    }
+                }
+                KeyCode::Char('r') => on_rebuild(()),
+                KeyCode::Esc if in_chapters.get() => in_chapters.set(false),
+                KeyCode::Esc => on_close(()),
+                _ => return EventResult::Ignored,
+            }
+            EventResult::Consumed
+        }
+    });
+
+    let names: Vec<ListItem> = index
+        .iter()
+        .flat_map(|i| &i.names)
+        .map(|entry| {
+            let mut spans = vec![
+                Span::styled(entry.name.clone(), theme.text),
+                Span::styled(format!("  {}", entry.total), theme.muted),
+            ];
+            if entry.user_added {
+                spans.push(Span::styled(" ★", theme.highlight));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let chapter_title = |chapter: usize| {
+        props
+            .chapters
+            .get(chapter)
+            .map_or_else(|| format!("第 {} 章", chapter + 1), |c| c.0.clone())
+    };
+    let appearances: Vec<ListItem> = selected
+        .iter()
+        .flat_map(|entry| entry.chapters.iter().enumerate())
+        .map(|(i, a)| {
+            let marker = if a.chapter == current_chapter {
+                "▶ "
+            } else {
+                "  "
+            };
+            let mut spans = vec![
+                Span::styled(marker, theme.highlight),
+                Span::styled(chapter_title(a.chapter), theme.text),
+                Span::styled(format!("  ×{}", a.count), theme.muted),
+            ];
+            if i == 0 {
+                spans.push(Span::styled("  首次出场", theme.highlight));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let names_empty = names.is_empty();
+    let names_list = List::new(names).highlight_style(if in_chapters.get() {
+        Style::default()
+    } else {
+        theme.selected
+    });
+    let chapters_list = List::new(appearances).highlight_style(if in_chapters.get() {
+        theme.selected
+    } else {
+        Style::default()
+    });
+
+    let status = match &index {
+        _ if props.building => "正在扫描全书建立索引…".to_string(),
+        Some(index) => {
+            let mut status = format!(
+                "已扫描 {}/{} 章 · {} 个名字",
+                index.indexed_chapters,
+                index.chapter_count,
+                index.names.len()
+            );
+            if index.indexed_chapters < index.chapter_count {
+                status.push_str("(其余章节无离线正文)");
+            }
+            if index.chapter_count != props.chapters.len() {
+                status.push_str(" · 目录已变化,按 r 重建");
+            }
+            status
+        }
+        None => "尚未建立索引,按 r 扫描全书".to_string(),
+    };
+    let empty_tip = if props.building || index.is_none() {
+        status.clone()
+    } else {
+        "没有识别出人名,按 a 手动添加".to_string()
+    };
+
+    let mut on_add = props.on_add.take();
+    let hint = "Enter 跳到首次出场 · l 出场章节 · a 添加 · x 删除 · r 重建";
+
+    element!(Modal(
+        width: Constraint::Percentage(80),
+        height: Constraint::Percentage(80),
+        open: props.open,
+        // 非阻塞浮层:n/Tab/i 由父 ReadNovel 的 root handler 处理,需在面板打开时仍可用;
+        // 背景 ReadContent 已用 `is_scroll` 门控,不会抢 j/k/Enter。
+        blocks_lower: false,
+        margin: Margin::new(1, 1),
+        style: Style::default().dim(),
+    ) {
+        View(margin: Margin::new(1, 1)) {
+            SearchInput(
+                placeholder: "按a添加名字(Enter 确认,Esc 取消)",
+                // 输入态自带独占输入层,面板的 j/k/x 等键在输入时不会误触。
+                activate_key: Some(KeyCode::Char('a')),
+                is_editing: is_editing,
+                clear_on_submit: true,
+                clear_on_escape: true,
+                on_submit: move |name: String| {
+                    let name = name.trim().to_string();
+                    if !name.is_empty() {
+                        on_add(name);
+                    }
+                    true
+                },
+            )
+            View(flex_direction: Direction::Horizontal) {
+                View(width: Constraint::Percentage(40)) {
+                    Border(
+                        border_style: theme.border.not_dim(),
+                        top_title: Some(Line::from("人物索引").centered().style(theme.title)),
+                        bottom_title: Some(Line::from(status).centered().style(theme.muted)),
+                    ) {
+                        { if names_empty {
+                            element!(Text(text: empty_tip, style: theme.empty, wrap: true)).into_any()
+                        } else {
+                            element!(View {
+                                stateful(names_list, names_state)
+                            }).into_any()
+                        } }
+                    }
+                }
+                View(width: Constraint::Percentage(60)) {
+                    Border(
+                        border_style: theme.border.not_dim(),
+                        top_title: Some(
+                            Line::from(selected.as_ref().map_or_else(
+                                || "出场章节".to_string(),
+                                |e| format!("{} · 出场 {} 章", e.name, e.chapters.len()),
+                            ))
+                            .centered()
+                            .style(theme.title),
+                        ),
+                        bottom_title: Some(Line::from(hint).centered().style(theme.muted)),
+                    ) {
+                        stateful(chapters_list, chapters_state)
+                    }
+                }
+            }
+        }
+    })
+}
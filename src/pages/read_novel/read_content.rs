@@ -1,8 +1,10 @@
 use crate::{
-    TTSConfig,
-    components::Loading,
+    TTSConfig, clipboard,
+    components::{DictModal, Loading},
+    dict::Dictionary,
     hooks::UseScrollbar,
     keymap::{ReaderAction, display_first_key},
+    selection::{Motion, Selection, TextPos, WrappedText},
     theme::ReaderTheme,
 };
 use novel_tts::utils::TextSegment;
@@ -14,7 +16,7 @@ use ratatui::{
 };
 use ratatui_kit::prelude::*;
 use ratatui_kit_keymap::UseKeymapHandler;
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 /// 章节边界的「再按一次」确认态,防止读到章末/章首时误触 ↓/↑ 直接跳章。
 /// 到边界的首次 ↓/↑ 只武装并在底部状态栏提示,连续第二次才真正翻章;
@@ -67,6 +69,13 @@ pub fn ReadContent(
     let mut on_next = props.on_next.take();
     // 章末/章首「再按一次」确认态(防误触跳章)。
     let mut edge = hooks.use_state(|| Edge::None);
+    // 可视选择模式:`v` 起选、移动键扩展、`y` 复制;`None` 即未在选择。
+    let mut selection = hooks.use_state(|| None::<Selection>);
+    // 复制结果等一次性提示,下一次按键即清除。
+    let mut notice = hooks.use_state(|| None::<String>);
+    // 查词光标:原文字节偏移,`None` 即未在查词;所在词按词典最长匹配切分。
+    let mut word_cursor = hooks.use_state(|| None::<usize>);
+    let mut dictionary = hooks.use_atom(&crate::state::DICTIONARY);
 
     // 自动播放下一章节
     if is_listening_done.get() && tts_config.read().auto_play {
@@ -90,6 +99,8 @@ pub fn ReadContent(
                 chapter_tts.cancel();
             }
             is_listening.set(false);
+            selection.set(None);
+            word_cursor.set(None);
         },
         props.content.clone(),
     );
@@ -180,12 +191,53 @@ pub fn ReadContent(
         tts_config.read().voice,
     );
 
+    // 折行结果与选区坐标共用同一宽度,保证屏幕上的第 N 行就是选区的第 N 行。
+    let wrapped = hooks.use_memo(
+        || {
+            Arc::new(WrappedText::new(
+                &props.content,
+                (props.width as usize).saturating_sub(2),
+            ))
+        },
+        (props.content.clone(), props.width),
+    );
+
+    // 查词光标所在的词:词典未加载时退化为单字。
+    let dict = dictionary.read().clone().unwrap_or_default();
+    let lookup_range = word_cursor
+        .get()
+        .map(|offset| dict.word_at(wrapped.source(), offset));
+    let lookup_word = lookup_range
+        .clone()
+        .map(|range| wrapped.source()[range].to_string())
+        .unwrap_or_default();
+    let lookup_entries = hooks.use_memo(
+        || dict.lookup(&lookup_word),
+        (lookup_word.clone(), dictionary.read().is_some()),
+    );
+    let lookup_tip = match dictionary.read().as_ref() {
+        None => "词典加载中...".to_string(),
+        Some(dict) if dict.is_empty() => format!(
+            "未找到词典:请把 StarDict 或 CC-CEDICT 词典放入 {}",
+            Dictionary::dir()
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_else(|_| "~/.novel/dicts".to_string())
+        ),
+        Some(_) => format!("词典中未收录「{lookup_word}」"),
+    };
+    // 反显:选择模式的选区优先,其次是查词光标所在的词。
+    let highlighted = selection
+        .get()
+        .or_else(|| lookup_range.map(|range| wrapped.selection_of(range)));
+
     let paragraph = hooks.use_memo(
         || {
             // 包成 TextParagraph(Send + Sync):0.30 起 owned Paragraph 内含 Block 而非 Send,
             // 无法直接存入 use_memo 的状态体系。TextParagraph Deref 到 Paragraph,后续 line_count/
             // 渲染照常。
-            let paragraph = if let Some(segment) = highlight_range.read().as_ref()
+            let paragraph = if let Some(selection) = highlighted.as_ref() {
+                Paragraph::new(wrapped.lines(Some(selection), theme.selection))
+            } else if let Some(segment) = highlight_range.read().as_ref()
                 && is_listening.get()
             {
                 Paragraph::new(highlight(
@@ -208,6 +260,8 @@ pub fn ReadContent(
             props.content.clone(),
             props.width,
             theme.tts_highlight,
+            highlighted,
+            theme.selection,
         ),
     );
 
@@ -238,6 +292,9 @@ pub fn ReadContent(
     // PageUp/PageDown 翻一整屏。步长须与上方 line_count 的可见高度(height - 3:
     // 上下边框 + 底部状态栏)保持一致;终端过矮时至少滚 1 行。
     let page_lines = (props.height as usize).saturating_sub(3).max(1);
+    let chapter_name = props.chapter_name.clone();
+    let is_loading = props.is_loading;
+    let selected_text = wrapped.clone();
     // 按语义 action 分发(键位可经 ~/.novel/keybindings.toml 自定义);
     // 页面级 action(模式/浮层切换)不在本组件处理,Ignored 交给上层。
     let reader_keymap = hooks.use_atom(&crate::state::KEYMAP).read().reader.clone();
@@ -249,6 +306,67 @@ pub fn ReadContent(
             if !is_scroll {
                 return EventResult::Ignored;
             }
+            if notice.read().is_some() {
+                notice.set(None);
+            }
+            // 选择模式下移动键只移动选区光标(不翻章、不武装边界),光标出屏时视口跟随。
+            if let Some(mut current) = selection.get() {
+                let motion = match action {
+                    ReaderAction::ScrollUp => Some(Motion::Up(1)),
+                    ReaderAction::ScrollDown => Some(Motion::Down(1)),
+                    ReaderAction::PrevChapter => Some(Motion::Left),
+                    ReaderAction::NextChapter => Some(Motion::Right),
+                    ReaderAction::PageUp => Some(Motion::Up(page_lines)),
+                    ReaderAction::PageDown => Some(Motion::Down(page_lines)),
+                    ReaderAction::GoTop => Some(Motion::Top),
+                    ReaderAction::GoBottom => Some(Motion::Bottom),
+                    _ => None,
+                };
+                if let Some(motion) = motion {
+                    current.extend(&selected_text, motion);
+                    selection.set(Some(current));
+                    if let Some(percent) =
+                        follow_row(current.cursor.row, current_line, page_lines, line_count)
+                    {
+                        line_percent.set(percent);
+                    }
+                    return EventResult::Consumed;
+                }
+            }
+            // 查词光标:←/→ 按词跳,其余移动键按行/页移动后吸附到所在词的词首。
+            if let Some(offset) = word_cursor.get() {
+                let dict = dictionary.read().clone().unwrap_or_default();
+                let source = selected_text.source();
+                let vertical = match action {
+                    ReaderAction::ScrollUp => Some(Motion::Up(1)),
+                    ReaderAction::ScrollDown => Some(Motion::Down(1)),
+                    ReaderAction::PageUp => Some(Motion::Up(page_lines)),
+                    ReaderAction::PageDown => Some(Motion::Down(page_lines)),
+                    ReaderAction::GoTop => Some(Motion::Top),
+                    ReaderAction::GoBottom => Some(Motion::Bottom),
+                    _ => None,
+                };
+                let next = match action {
+                    ReaderAction::PrevChapter => Some(dict.prev_word(source, offset)),
+                    ReaderAction::NextChapter => Some(dict.next_word(source, offset)),
+                    _ => vertical.map(|motion| {
+                        let pos = selected_text.step(selected_text.pos_of(offset), motion);
+                        dict.word_at(source, selected_text.offset_of(pos)).start
+                    }),
+                };
+                if let Some(next) = next {
+                    word_cursor.set(Some(next));
+                    if let Some(percent) = follow_row(
+                        selected_text.pos_of(next).row,
+                        current_line,
+                        page_lines,
+                        line_count,
+                    ) {
+                        line_percent.set(percent);
+                    }
+                    return EventResult::Consumed;
+                }
+            }
             match action {
                 ReaderAction::ScrollUp => {
                     if current_line > 0 {
@@ -361,6 +479,76 @@ pub fn ReadContent(
                     }
                     EventResult::Consumed
                 }
+                ReaderAction::ToggleSelect if !is_loading => {
+                    if selection.get().is_some() {
+                        selection.set(None);
+                    } else {
+                        word_cursor.set(None);
+                        // 从视口首行行首起选,与 vim 的可视模式一致:起点即光标。
+                        let start = selected_text.clamp(TextPos::new(current_line, 0));
+                        selection.set(Some(Selection::new(start)));
+                        edge.set(Edge::None);
+                    }
+                    EventResult::Consumed
+                }
+                ReaderAction::CancelSelect
+                    if selection.get().is_some() || word_cursor.get().is_some() =>
+                {
+                    selection.set(None);
+                    word_cursor.set(None);
+                    EventResult::Consumed
+                }
+                ReaderAction::Lookup if !is_loading => {
+                    if word_cursor.get().is_some() {
+                        word_cursor.set(None);
+                        return EventResult::Consumed;
+                    }
+                    selection.set(None);
+                    edge.set(Edge::None);
+                    let start = selected_text.offset_of(TextPos::new(current_line, 0));
+                    word_cursor.set(Some(start));
+                    if dictionary.read().is_none() {
+                        // 大词典解析耗时,放到阻塞线程;加载完成前浮层显示「加载中」。
+                        tokio::spawn(async move {
+                            match tokio::task::spawn_blocking(Dictionary::load).await {
+                                Ok(Ok((loaded, warnings))) => {
+                                    if let Some(warning) = warnings.into_iter().next() {
+                                        notice.set(Some(warning));
+                                    }
+                                    dictionary.set(Some(Arc::new(loaded)));
+                                }
+                                Ok(Err(e)) => {
+                                    word_cursor.set(None);
+                                    notice.set(Some(format!("词典加载失败:{e}")));
+                                }
+                                Err(e) => {
+                                    word_cursor.set(None);
+                                    notice.set(Some(format!("词典加载失败:{e}")));
+                                }
+                            }
+                        });
+                    }
+                    EventResult::Consumed
+                }
+                ReaderAction::Yank | ReaderAction::YankToFile => {
+                    let Some(current) = selection.get() else {
+                        return EventResult::Ignored;
+                    };
+                    let text = selected_text.text(&current);
+                    let mut message = match clipboard::copy_to_clipboard(&text) {
+                        Ok(()) => format!("已复制 {} 字", text.chars().count()),
+                        Err(e) => format!("复制失败:{e}"),
+                    };
+                    if action == ReaderAction::YankToFile {
+                        match clipboard::append_clipping(&chapter_name, &text) {
+                            Ok(path) => message.push_str(&format!(",已摘录到 {}", path.display())),
+                            Err(e) => message.push_str(&format!(",摘录失败:{e}")),
+                        }
+                    }
+                    notice.set(Some(message));
+                    selection.set(None);
+                    EventResult::Consumed
+                }
                 ReaderAction::ToggleTitle => {
                     let mut display = *reader_display.read();
                     display.show_title = !display.show_title;
@@ -418,9 +606,25 @@ pub fn ReadContent(
             margin: Margin::new(1,0),
         ){
             widget(Line::from(format!("{current_line}/{line_count} 行")).style(theme.footer))
-            // 章末/章首「再按一次」确认提示(仅武装时显示;accent+bold 醒目;
-            // 键名从 keymap 动态取,重绑后提示的就是新键)。
-            widget(Line::from(match edge.get() {
+            // 选择模式提示 > 复制结果 > 章末/章首「再按一次」确认提示(仅武装时显示;
+            // accent+bold 醒目;键名从 keymap 动态取,重绑后提示的就是新键)。
+            widget(Line::from(if selection.read().is_some() {
+                format!(
+                    "● 选择中 · {} 复制 · {} 复制并摘录 · {} 取消",
+                    display_first_key(&reader_keymap, ReaderAction::Yank),
+                    display_first_key(&reader_keymap, ReaderAction::YankToFile),
+                    display_first_key(&reader_keymap, ReaderAction::CancelSelect),
+                )
+            } else if word_cursor.get().is_some() {
+                format!(
+                    "● 查词中 · {}/{} 按词移动 · {} 关闭",
+                    display_first_key(&reader_keymap, ReaderAction::PrevChapter),
+                    display_first_key(&reader_keymap, ReaderAction::NextChapter),
+                    display_first_key(&reader_keymap, ReaderAction::Lookup),
+                )
+            } else if let Some(notice) = notice.read().clone() {
+                format!("● {notice}")
+            } else { match edge.get() {
                 Edge::Next => format!(
                     "● 已到本章末尾 · 再按 {} 进入下一章",
                     display_first_key(&reader_keymap, ReaderAction::ScrollDown)
@@ -432,12 +636,31 @@ pub fn ReadContent(
                 Edge::AtLast => "● 已是全书最后一章".to_string(),
                 Edge::AtFirst => "● 已是第一章".to_string(),
                 Edge::None => String::new(),
-            }).style(theme.chapter).centered())
+            } }).style(theme.chapter).centered())
             widget(Line::from(format!("{:.2}% {}",props.chapter_percent, current_time.read().clone())).style(theme.progress).right_aligned())
         }
+        DictModal(
+            word: lookup_word,
+            entries: lookup_entries,
+            tip: lookup_tip,
+            open: word_cursor.get().is_some(),
+        )
     })
 }
 
+/// 光标行出屏时让视口跟随:返回新的滚动百分比;仍在屏内(或无需滚动)时返回 `None`。
+fn follow_row(row: usize, top: usize, page_lines: usize, line_count: usize) -> Option<f64> {
+    let new_top = if row < top {
+        row
+    } else if row >= top + page_lines {
+        row + 1 - page_lines
+    } else {
+        top
+    };
+    (new_top != top && line_count > 0)
+        .then(|| (new_top.min(line_count) as f64) / (line_count as f64))
+}
+
 pub fn highlight(
     text: &str,
     segment: &TextSegment,
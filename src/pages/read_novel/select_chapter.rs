@@ -1,10 +1,11 @@
 use crossterm::event::{Event, KeyCode, KeyEventKind};
 use ratatui::{
     layout::{Alignment, Constraint},
-    text::Line,
+    text::{Line, Span},
     widgets::{Block, Scrollbar},
 };
 use ratatui_kit::prelude::*;
+use std::{collections::HashSet, ops::Range};
 use tui_tree_widget::{TreeItem, TreeState};
 
 use crate::{components::search_input::SearchInput, novel::VolumeMarker, theme::AppChromeTheme};
@@ -34,6 +35,11 @@ pub struct SelectChapterProps {
     pub volumes: Vec<VolumeMarker>,
     pub on_select: Handler<'static, usize>,
     pub default_value: Option<usize>,
+    /// 是否支持下载(网络小说);为真时可用 `m` 标记范围起点、`D` 下载。
+    pub downloadable: bool,
+    /// 已下载完成的章节,目录里打勾。
+    pub downloaded: HashSet<usize>,
+    pub on_download: Handler<'static, Range<usize>>,
 }
 
 /// 定位某个扁平章节索引所属的卷（最后一个 `first_chapter_index <= idx` 的卷）。
@@ -74,18 +80,32 @@ pub fn SelectChapter(
 
     let is_editing = props.is_editing;
     let is_empty = props.chapters.is_empty();
+    let downloadable = props.downloadable;
+    let chapter_count = props.chapters.len();
+    // 下载范围的起点(扁平章节索引),`m` 设置、再按一次取消。
+    let mut range_start = hooks.use_state(|| None::<usize>);
 
     // 构建树节点：搜索态塌成扁平过滤列表；否则按卷分组（无卷则平铺）。
     let items = hooks.use_memo(
-        || build_items(&props.chapters, &props.volumes, &filter_text.read(), &theme),
+        || {
+            build_items(
+                &props.chapters,
+                &props.volumes,
+                &filter_text.read(),
+                &props.downloaded,
+                &theme,
+            )
+        },
         (
             filter_text.read().clone(),
             props.chapters.len(),
             props.volumes.len(),
+            props.downloaded.len(),
         ),
     );
 
     let mut on_select = props.on_select.take();
+    let mut on_download = props.on_download.take();
 
     hooks.use_event_handler(EventScope::Current, EventPriority::Normal, move |event| {
         let Event::Key(key) = event else {
@@ -120,13 +140,41 @@ pub fn SelectChapter(
                 }
                 EventResult::Consumed
             }
+            KeyCode::Char('m') if downloadable => {
+                if let Some(TocId::Chapter(idx)) = state.read().selected().last() {
+                    range_start.set((range_start.get() != Some(*idx)).then_some(*idx));
+                }
+                EventResult::Consumed
+            }
+            KeyCode::Char('D') if downloadable => {
+                // 有起点时下载起点到光标处(含两端),否则从光标处下载到最后一章。
+                if let Some(TocId::Chapter(idx)) = state.read().selected().last() {
+                    let range = match range_start.get() {
+                        Some(start) => start.min(*idx)..start.max(*idx) + 1,
+                        None => *idx..chapter_count,
+                    };
+                    on_download(range);
+                    range_start.set(None);
+                }
+                EventResult::Consumed
+            }
             _ => EventResult::Ignored,
         }
     });
 
+    let title = if !downloadable {
+        "目录".to_string()
+    } else if props.downloaded.len() == chapter_count && chapter_count > 0 {
+        "目录(已全部下载)".to_string()
+    } else if let Some(start) = range_start.get() {
+        let name = props.chapters.get(start).map_or("", |c| c.0.as_str());
+        format!("目录 · 下载起点:{name}(D 下载到光标处)")
+    } else {
+        format!("目录 · 已下载 {}/{chapter_count}", props.downloaded.len())
+    };
     let border = Block::bordered()
         .border_style(theme.border)
-        .title_top(Line::from("目录").style(theme.title).centered());
+        .title_top(Line::from(title).style(theme.title).centered());
 
     element!(View {
         SearchInput(
@@ -185,9 +233,20 @@ fn build_items(
     chapters: &[ChapterName],
     volumes: &[VolumeMarker],
     filter: &str,
+    downloaded: &HashSet<usize>,
     theme: &AppChromeTheme,
 ) -> Vec<TreeItem<'static, TocId>> {
-    let leaf = |c: &ChapterName| TreeItem::new_leaf(TocId::Chapter(c.1), c.0.clone());
+    let leaf = |c: &ChapterName| {
+        if downloaded.contains(&c.1) {
+            let text = Line::from(vec![
+                Span::from(c.0.clone()),
+                Span::styled(" ✓", theme.highlight),
+            ]);
+            TreeItem::new_leaf(TocId::Chapter(c.1), text)
+        } else {
+            TreeItem::new_leaf(TocId::Chapter(c.1), c.0.clone())
+        }
+    };
 
     // 搜索态：塌成扁平过滤列表。
     if !filter.is_empty() {
@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
 use crate::{
-    History, HistoryItem,
+    Bookshelf, History, HistoryItem,
     components::{ConfirmModal, KeyShortcutInfo, ShortcutInfoModal, list_select::ListSelect},
     pages::network_novel::book_detail::BookDetailState,
     theme::AppChromeTheme,
@@ -17,6 +17,8 @@ use tui_widget_list::{ListBuildContext, ListState};
 
 pub struct ListItem {
     pub history: HistoryItem,
+    /// 书架追更检查发现的新章节数,不在书架上为 0。
+    pub new_chapters: usize,
     pub selected: bool,
     pub theme: AppChromeTheme,
 }
@@ -74,8 +76,15 @@ impl WidgetRef for ListItem {
                 .render(bottom_right, buf);
             }
             HistoryItem::Network(item) => {
+                let mut title = vec![Span::from(item.title.clone())];
+                if self.new_chapters > 0 {
+                    title.push(Span::styled(
+                        format!("  +{} 新", self.new_chapters),
+                        self.theme.highlight,
+                    ));
+                }
                 Paragraph::new(Text::from(vec![
-                    Line::from(item.title.clone()),
+                    Line::from(title),
                     Line::from(item.current_chapter.clone()).centered(),
                 ]))
                 .style(text_color)
@@ -102,6 +111,8 @@ impl WidgetRef for ListItem {
 pub fn SelectHistory(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
     let theme = hooks.use_component_theme::<AppChromeTheme>();
     let history = hooks.use_context::<State<Option<History>>>();
+    let bookshelf = *hooks.use_context::<State<Option<Bookshelf>>>();
+    let mut notice = hooks.use_state(String::new);
 
     let mut navigate = hooks.use_navigate();
     let mut delete_modal_open = hooks.use_state(|| false);
@@ -136,6 +147,25 @@ pub fn SelectHistory(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
                 info_modal_open.set(!info_modal_open.get());
                 EventResult::Consumed
             }
+            KeyCode::Char('a') | KeyCode::Char('A') => {
+                let selected = state
+                    .read()
+                    .selected
+                    .and_then(|i| history.read().as_ref()?.histories.get(i).cloned());
+                if let Some((id, item)) = selected
+                    && let Some(shelf) = bookshelf.write().as_mut()
+                {
+                    let message = if !shelf.add(&id, item) {
+                        "已在书架上".to_string()
+                    } else if let Err(e) = shelf.save() {
+                        format!("保存书架失败:{e}")
+                    } else {
+                        "已加入书架".to_string()
+                    };
+                    notice.set(message);
+                }
+                EventResult::Consumed
+            }
             KeyCode::Char('d') | KeyCode::Char('D') => {
                 // 仅负责「打开」删除确认;关闭交给 ConfirmModal 自身(n/N/Esc)。原 else 分支
                 // (delete_modal 已开时再按 d 置 false)不可达——ConfirmModal 开时其独占层已截断本 root handler。
@@ -156,17 +186,24 @@ pub fn SelectHistory(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
             top_title: Line::from("历史记录").centered().style(theme.title),
             bottom_title: Line::from(
                 format!(
-                    "{}/{} 条",
+                    "{}/{} 条{}",
                     state.read().selected.unwrap_or(0)+1,
-                    histories.len())
+                    histories.len(),
+                    if notice.read().is_empty() { String::new() } else { format!(" · {}", *notice.read()) })
                 )
                 .style(theme.meta_label.not_dim()),
             render_item: {
                 move |context:&ListBuildContext| {
-                    let (_, item) = &histories[context.index];
+                    let (id, item) = &histories[context.index];
+                    let new_chapters = bookshelf
+                        .read()
+                        .as_ref()
+                        .and_then(|shelf| shelf.books.iter().find(|b| &b.id == id))
+                        .map_or(0, |b| b.new_chapters());
                     (
                         ListItem {
                             history: item.clone(),
+                            new_chapters,
                             selected: context.is_selected,
                             theme,
                         }.into(),
@@ -175,21 +212,8 @@ pub fn SelectHistory(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
                 }
             },
             empty_message: "暂无历史记录",
-            on_select: move |(path, item)| {
-                match item {
-                    HistoryItem::Local(_) => {
-                        navigate.push_with_state(
-                            "/local-novel",
-                            PathBuf::from(path),
-                        );
-                    }
-                    HistoryItem::Network(_) => {
-                        navigate.push_with_state(
-                            "/book-detail",
-                            BookDetailState::from_cache(path),
-                        );
-                    }
-                }
+            on_select: move |(path, item): (String, HistoryItem)| {
+                open_book(&mut navigate, &path, &item);
             },
         )
         ConfirmModal(
@@ -201,7 +225,7 @@ pub fn SelectHistory(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
                 if let Some(index) = selected
                     && let Some(histories) = history.write().as_mut()
                         && index < histories.histories.len() {
-                            histories.histories.remove(index);
+                            histories.remove_index(index);
                             state.write().select(Some(index.saturating_sub(1)));
                         }
                 delete_modal_open.set(false);
@@ -225,6 +249,7 @@ pub fn SelectHistory(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
                         ("选择上一个", "K / ▲"),
                         ("取消选择", "H / ◄"),
                         ("确认选择", "L / ► / Enter"),
+                        ("加入书架", "A"),
                         ("删除选中的历史记录", "D"),
                     ]
                 };
@@ -234,3 +259,15 @@ pub fn SelectHistory(mut hooks: Hooks) -> impl Into<AnyElement<'static>> {
         )
     })
 }
+
+/// 打开历史记录 / 书架里的一本书:本地直接进阅读页,网络先进详情页。
+pub fn open_book(navigate: &mut Navigate, id: &str, item: &HistoryItem) {
+    match item {
+        HistoryItem::Local(_) => {
+            navigate.push_with_state("/local-novel", PathBuf::from(id));
+        }
+        HistoryItem::Network(_) => {
+            navigate.push_with_state("/book-detail", BookDetailState::from_cache(id.to_string()));
+        }
+    }
+}
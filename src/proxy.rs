@@ -0,0 +1,57 @@
+//! `proxy` 子命令:设置 / 查看 / 删除全局默认代理([`ProxyConfig`])。
+//!
+//! 没配 `http.proxy` 的书源都走全局代理,包括浏览器辅助与登录脚本的请求。
+
+use crate::cache::ProxyConfig;
+use parse_book_source::fetch::proxy::check;
+use parse_book_source::source::Proxy;
+
+/// 带 `url` 时保存配置,带 `clear` 时删除配置,都不带时打印当前配置。
+pub fn run(url: Option<&str>, username: Option<&str>, no_proxy: &[String], clear: bool) {
+    if clear {
+        return match ProxyConfig::clear() {
+            Ok(()) => println!("✓ 已删除全局代理,没配代理的书源改为直连"),
+            Err(e) => eprintln!("✗ 删除代理配置失败: {e}"),
+        };
+    }
+    if let Some(url) = url {
+        let password = match (username, std::env::var("TRNOVEL_PROXY_PASSWORD")) {
+            (None, _) => None,
+            (Some(_), Ok(password)) => Some(password),
+            (Some(_), Err(_)) => match rpassword::prompt_password("代理密码:") {
+                Ok(password) => Some(password),
+                Err(e) => return eprintln!("✗ {e}"),
+            },
+        };
+        let config = ProxyConfig(Proxy {
+            url: url.to_string(),
+            username: username.map(str::to_string),
+            password,
+            no_proxy: no_proxy.to_vec(),
+        });
+        if let Err(e) = check(&config.0) {
+            return eprintln!("✗ {e}");
+        }
+        if let Err(e) = config.save() {
+            return eprintln!("✗ 保存代理配置失败: {e}");
+        }
+        return println!("✓ 已保存全局代理 → {}", describe(&config.0));
+    }
+    match ProxyConfig::load() {
+        Ok(Some(config)) => println!("全局代理: {}", describe(&config.0)),
+        Ok(None) => println!("未配置全局代理(直连);设置: trn proxy socks5://127.0.0.1:1080"),
+        Err(e) => eprintln!("✗ 读取代理配置失败: {e}"),
+    }
+}
+
+/// 展示用的代理说明,不含密码。
+fn describe(proxy: &Proxy) -> String {
+    let mut text = proxy.url.clone();
+    if let Some(username) = &proxy.username {
+        text.push_str(&format!("(用户 {username})"));
+    }
+    if !proxy.no_proxy.is_empty() {
+        text.push_str(&format!(",不走代理: {}", proxy.no_proxy.join(", ")));
+    }
+    text
+}
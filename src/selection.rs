@@ -0,0 +1,331 @@
+//! 正文选区模型:阅读区按终端宽度折行后的「行 / 列」坐标,与原文字节偏移互相映射。
+//!
+//! 折行规则与 `ReadContent` 渲染一致(`textwrap::fill` 即逐个 `\n` 段 `textwrap::wrap`),
+//! 故屏幕上看到的第 N 行第 M 字就是 [`TextPos { row: N, col: M }`](TextPos)。取文本时
+//! 回到原文切片,而非拼接折行结果 —— 折行吞掉的空格、原文的换行都会原样保留。
+//! 复制(yank)、查词、批注等需要「在正文里指一段字」的功能共用这一套模型。
+
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+
+/// 折行后的位置:`row` 为折行后的行号,`col` 为该行内的字符(char)下标。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct TextPos {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl TextPos {
+    pub fn new(row: usize, col: usize) -> Self {
+        Self { row, col }
+    }
+}
+
+/// 选区移动方式(与阅读页的语义 action 一一对应)。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    Up(usize),
+    Down(usize),
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// 可视选区:`anchor` 为起点(按下 `v` 的位置),`cursor` 随移动键变化;两端都**包含**在内。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Selection {
+    pub anchor: TextPos,
+    pub cursor: TextPos,
+}
+
+impl Selection {
+    /// 以单个位置起一个选区(起点即光标)。
+    pub fn new(pos: TextPos) -> Self {
+        Self {
+            anchor: pos,
+            cursor: pos,
+        }
+    }
+
+    /// 按文本顺序排好的 `(起点, 终点)`。
+    pub fn ordered(&self) -> (TextPos, TextPos) {
+        if self.anchor <= self.cursor {
+            (self.anchor, self.cursor)
+        } else {
+            (self.cursor, self.anchor)
+        }
+    }
+
+    /// 位置是否落在选区内(闭区间)。
+    pub fn contains(&self, pos: TextPos) -> bool {
+        let (start, end) = self.ordered();
+        start <= pos && pos <= end
+    }
+
+    /// 移动光标扩展/收缩选区,结果始终夹在正文范围内。
+    pub fn extend(&mut self, text: &WrappedText, motion: Motion) {
+        self.cursor = text.step(self.cursor, motion);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WrappedRow {
+    /// 本行在原文中的起始字节偏移。
+    start: usize,
+    text: String,
+}
+
+/// 原文 + 折行结果。按 `(content, width)` 构建一次,渲染与取文本共用。
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WrappedText {
+    source: String,
+    rows: Vec<WrappedRow>,
+}
+
+impl WrappedText {
+    pub fn new(source: &str, width: usize) -> Self {
+        let width = width.max(1);
+        let mut rows = Vec::new();
+        let mut base = 0;
+        for segment in source.split('\n') {
+            // wrap 默认选项(无缩进)下返回的都是原段的顺序子串:从上一行末尾往后找即可
+            // 定位偏移,折行处被吞掉的空白落在两行之间、不属于任何一行。
+            let mut cursor = 0;
+            for row in textwrap::wrap(segment, width) {
+                let offset = segment[cursor..]
+                    .find(row.as_ref())
+                    .map(|i| cursor + i)
+                    .unwrap_or(cursor);
+                cursor = (offset + row.len()).min(segment.len());
+                rows.push(WrappedRow {
+                    start: base + offset,
+                    text: row.into_owned(),
+                });
+            }
+            base += segment.len() + 1;
+        }
+        Self {
+            source: source.to_string(),
+            rows,
+        }
+    }
+
+    /// 原文。
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// 位置处字符在原文中的起始字节偏移。
+    pub fn offset_of(&self, pos: TextPos) -> usize {
+        self.char_span(pos).0
+    }
+
+    /// 原文字节偏移所在的位置;落在折行吞掉的空白里时归到前一行行尾。
+    pub fn pos_of(&self, offset: usize) -> TextPos {
+        let row = self
+            .rows
+            .partition_point(|r| r.start <= offset)
+            .saturating_sub(1);
+        let Some(wrapped) = self.rows.get(row) else {
+            return TextPos::default();
+        };
+        let within = offset.saturating_sub(wrapped.start).min(wrapped.text.len());
+        let col = wrapped
+            .text
+            .char_indices()
+            .take_while(|&(i, _)| i < within)
+            .count();
+        self.clamp(TextPos::new(row, col))
+    }
+
+    /// 覆盖原文字节区间 `range` 的选区(空区间退化为起点处的单字)。
+    pub fn selection_of(&self, range: std::ops::Range<usize>) -> Selection {
+        let start = self.pos_of(range.start);
+        let last = self.source[..range.end.max(range.start)]
+            .char_indices()
+            .next_back()
+            .filter(|&(i, _)| i >= range.start)
+            .map_or(start, |(i, _)| self.pos_of(i));
+        Selection {
+            anchor: start,
+            cursor: last,
+        }
+    }
+
+    /// 折行后的总行数。
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// 某一行的字符数(行号越界为 0)。
+    pub fn row_len(&self, row: usize) -> usize {
+        self.rows.get(row).map_or(0, |r| r.text.chars().count())
+    }
+
+    /// 把位置夹到合法范围:行不越过末行,列不越过行尾字符(空行列为 0)。
+    pub fn clamp(&self, pos: TextPos) -> TextPos {
+        let row = pos.row.min(self.rows.len().saturating_sub(1));
+        let col = pos.col.min(self.row_len(row).saturating_sub(1));
+        TextPos { row, col }
+    }
+
+    /// 按 [`Motion`] 走一步。左右移动在行首/行尾会跨到相邻行。
+    pub fn step(&self, pos: TextPos, motion: Motion) -> TextPos {
+        let pos = self.clamp(pos);
+        let last_row = self.rows.len().saturating_sub(1);
+        let next = match motion {
+            Motion::Up(n) => TextPos::new(pos.row.saturating_sub(n), pos.col),
+            Motion::Down(n) => TextPos::new((pos.row + n).min(last_row), pos.col),
+            Motion::Left if pos.col > 0 => TextPos::new(pos.row, pos.col - 1),
+            Motion::Left if pos.row > 0 => TextPos::new(pos.row - 1, usize::MAX),
+            Motion::Left => pos,
+            Motion::Right if pos.col + 1 < self.row_len(pos.row) => {
+                TextPos::new(pos.row, pos.col + 1)
+            }
+            Motion::Right if pos.row < last_row => TextPos::new(pos.row + 1, 0),
+            Motion::Right => pos,
+            Motion::Top => TextPos::default(),
+            Motion::Bottom => TextPos::new(last_row, usize::MAX),
+        };
+        self.clamp(next)
+    }
+
+    /// 位置处字符在原文中的 `[起, 止)` 字节区间(空行为零长区间)。
+    fn char_span(&self, pos: TextPos) -> (usize, usize) {
+        let pos = self.clamp(pos);
+        let Some(row) = self.rows.get(pos.row) else {
+            return (0, 0);
+        };
+        match row.text.char_indices().nth(pos.col) {
+            Some((i, ch)) => (row.start + i, row.start + i + ch.len_utf8()),
+            None => (row.start, row.start),
+        }
+    }
+
+    /// 选区对应的原文(含两端字符;跨行时保留原文的换行与空白)。
+    pub fn text(&self, selection: &Selection) -> String {
+        let (start, end) = selection.ordered();
+        let from = self.char_span(start).0;
+        let to = self.char_span(end).1.max(from);
+        self.source[from..to].to_string()
+    }
+
+    /// 渲染为行列表,选区内的字符套用 `style`。
+    pub fn lines(&self, selection: Option<&Selection>, style: Style) -> Vec<Line<'static>> {
+        self.rows
+            .iter()
+            .enumerate()
+            .map(|(row, wrapped)| {
+                let Some(selection) = selection else {
+                    return Line::from(wrapped.text.clone());
+                };
+                let (start, end) = selection.ordered();
+                if row < start.row || row > end.row {
+                    return Line::from(wrapped.text.clone());
+                }
+                let len = wrapped.text.chars().count();
+                let from = if row == start.row { start.col } else { 0 };
+                let to = if row == end.row {
+                    (end.col + 1).min(len)
+                } else {
+                    len
+                };
+                let byte = |col: usize| {
+                    wrapped
+                        .text
+                        .char_indices()
+                        .nth(col)
+                        .map_or(wrapped.text.len(), |(i, _)| i)
+                };
+                let (from, to) = (byte(from), byte(to).max(byte(from)));
+                Line::from(vec![
+                    Span::from(wrapped.text[..from].to_string()),
+                    Span::from(wrapped.text[from..to].to_string()).style(style),
+                    Span::from(wrapped.text[to..].to_string()),
+                ])
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_match_textwrap_fill() {
+        let content = "第一段很长很长很长很长的文字\n\nsecond paragraph with words";
+        let wrapped = WrappedText::new(content, 10);
+        let filled = textwrap::fill(content, 10);
+        let rows: Vec<&str> = wrapped.rows.iter().map(|r| r.text.as_str()).collect();
+        assert_eq!(rows, filled.lines().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn text_keeps_source_whitespace_and_newlines() {
+        let content = "hello brave world\n下一段";
+        let wrapped = WrappedText::new(content, 6);
+        // 折行:hello / brave / world / 下一段
+        let selection = Selection {
+            anchor: TextPos::new(0, 1),
+            cursor: TextPos::new(3, 1),
+        };
+        assert_eq!(wrapped.text(&selection), "ello brave world\n下一");
+        // 反向选择结果相同。
+        let reversed = Selection {
+            anchor: selection.cursor,
+            cursor: selection.anchor,
+        };
+        assert_eq!(wrapped.text(&reversed), wrapped.text(&selection));
+    }
+
+    #[test]
+    fn motions_cross_rows_and_clamp() {
+        let wrapped = WrappedText::new("一二三\n四五", 10);
+        let end_of_first = TextPos::new(0, 2);
+        assert_eq!(
+            wrapped.step(end_of_first, Motion::Right),
+            TextPos::new(1, 0)
+        );
+        assert_eq!(wrapped.step(TextPos::new(1, 0), Motion::Left), end_of_first);
+        // 下移到较短的行,列被夹到行尾。
+        assert_eq!(
+            wrapped.step(end_of_first, Motion::Down(5)),
+            TextPos::new(1, 1)
+        );
+        assert_eq!(wrapped.step(end_of_first, Motion::Top), TextPos::new(0, 0));
+        assert_eq!(
+            wrapped.step(end_of_first, Motion::Bottom),
+            TextPos::new(1, 1)
+        );
+    }
+
+    #[test]
+    fn offsets_round_trip_through_positions() {
+        let content = "hello brave world\n下一段";
+        let wrapped = WrappedText::new(content, 6);
+        let pos = TextPos::new(3, 1);
+        let offset = wrapped.offset_of(pos);
+        assert_eq!(&content[offset..], "一段");
+        assert_eq!(wrapped.pos_of(offset), pos);
+        // 「brave」前被折行吞掉的空格归到上一行行尾。
+        assert_eq!(wrapped.pos_of(5), TextPos::new(0, 4));
+        let selection = wrapped.selection_of(6..17);
+        assert_eq!(wrapped.text(&selection), "brave world");
+    }
+
+    #[test]
+    fn lines_highlight_only_selected_chars() {
+        let wrapped = WrappedText::new("一二三四", 10);
+        let selection = Selection {
+            anchor: TextPos::new(0, 1),
+            cursor: TextPos::new(0, 2),
+        };
+        let lines = wrapped.lines(Some(&selection), Style::new().bold());
+        let spans: Vec<&str> = lines[0].spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(spans, ["一", "二三", "四"]);
+    }
+}
@@ -9,8 +9,9 @@
 
 use novel_tts::NovelTTS;
 use ratatui_kit::Atom;
+use std::sync::Arc;
 
-use crate::{AppearanceConfig, ReaderDisplayConfig};
+use crate::{AppearanceConfig, ReaderDisplayConfig, dict::Dictionary};
 
 /// TUI 外观配置:驱动根 `PaletteProvider`,主题设置页写入并落盘。
 pub static APPEARANCE: Atom<AppearanceConfig> = Atom::new(AppearanceConfig::default);
@@ -21,6 +22,9 @@ pub static READER_DISPLAY: Atom<ReaderDisplayConfig> = Atom::new(ReaderDisplayCo
 /// 已加载的 TTS 模型句柄:阅读页加载后跨页面保留(`None` = 未加载)。
 pub static NOVEL_TTS: Atom<Option<NovelTTS>> = Atom::new(|| None);
 
+/// 已加载的离线词典:阅读页首次查词时后台加载,之后跨章节、跨书复用(`None` = 未加载)。
+pub static DICTIONARY: Atom<Option<Arc<Dictionary>>> = Atom::new(|| None);
+
 /// 全应用键位表:启动时从 `~/.novel/keybindings.toml` 合并(见 `crate::keymap`),
 /// 运行期只读;无配置文件时即内置默认表。
 pub static KEYMAP: Atom<crate::keymap::AppKeymap> = Atom::new(crate::keymap::AppKeymap::default);
@@ -0,0 +1,551 @@
+//! 缓存管理:按类别统计磁盘占用,清理没人引用的网络小说存档、删除单本书的数据、只清空选定的类别,
+//! 以及按书源清空 HTTP 响应缓存。
+//!
+//! `trn cache` 与「缓存管理」页共用这里的实现。书源、设置、登录态不属于任何类别,不会被清掉;
+//! 要连它们一起删用 `trn clear`。
+
+use crate::{
+    Bookshelf, ChapterCache, Downloads, HistoryItem, LocalNovelCache, NetworkNovelCache, Result,
+    book_source::BookSourceCache,
+    data_dir::{self, data_path},
+    history::History,
+    utils::{format_bytes, get_md5_string},
+};
+use clap::{Subcommand, ValueEnum};
+use parse_book_source::fetch::cache::{self as http_cache, CacheStats};
+use ratatui::text::Span;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+};
+
+/// 可单独清空的缓存类别。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum)]
+pub enum Category {
+    /// 历史记录
+    History,
+    /// 网络小说的阅读存档(进度、目录)
+    Network,
+    /// 本地小说的阅读存档(进度、章节切分)
+    Local,
+    /// 阅读时缓存的章节正文与书籍快照
+    Chapters,
+    /// 下载管理器下载的章节与下载队列
+    Downloads,
+    /// 朗读用的语音模型
+    Tts,
+    /// 内置浏览器的配置目录(含网页登录态)
+    Browser,
+    /// 书源网页的 HTTP 响应缓存
+    Http,
+}
+
+impl Category {
+    pub const ALL: [Category; 8] = [
+        Category::History,
+        Category::Network,
+        Category::Local,
+        Category::Chapters,
+        Category::Downloads,
+        Category::Tts,
+        Category::Browser,
+        Category::Http,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Category::History => "历史记录",
+            Category::Network => "网络小说存档",
+            Category::Local => "本地小说存档",
+            Category::Chapters => "章节缓存",
+            Category::Downloads => "已下载章节",
+            Category::Tts => "语音模型",
+            Category::Browser => "浏览器数据",
+            Category::Http => "HTTP 缓存",
+        }
+    }
+
+    /// 数据目录下相对路径所属的类别;书源、设置等不属于任何类别。
+    fn of(relative: &Path) -> Option<Self> {
+        let mut parts = relative.iter().map(|p| p.to_str().unwrap_or_default());
+        match parts.next()? {
+            "history.json" => Some(Category::History),
+            "downloads.json" => Some(Category::Downloads),
+            "network" => Some(Category::Network),
+            "local" => Some(Category::Local),
+            "browser-profile" => Some(Category::Browser),
+            "tts" => Some(Category::Tts),
+            HTTP_CACHE_DIR => Some(Category::Http),
+            "chapters" if parts.any(|p| p == "downloads") => Some(Category::Downloads),
+            "chapters" => Some(Category::Chapters),
+            _ => None,
+        }
+    }
+}
+
+/// HTTP 响应缓存所在的目录名,下面每个书源一个子目录。
+const HTTP_CACHE_DIR: &str = "http-cache";
+
+/// HTTP 响应缓存的根目录,`build_engine` 给每个引擎套缓存时用。
+pub fn http_cache_dir() -> Result<PathBuf> {
+    data_path(HTTP_CACHE_DIR)
+}
+
+/// 本次运行所有书源共用的 HTTP 缓存命中计数。
+pub fn http_cache_stats() -> Arc<CacheStats> {
+    static STATS: LazyLock<Arc<CacheStats>> = LazyLock::new(Arc::default);
+    STATS.clone()
+}
+
+/// 清空一个书源的 HTTP 缓存,`source` 是书源名或链接;返回书源名与删掉的文件数。
+pub fn purge_source(source: &str) -> Result<(String, usize)> {
+    let sources = BookSourceCache::load()?;
+    let found = sources
+        .iter()
+        .find(|s| s.url == source || s.name == source)
+        .ok_or_else(|| format!("没有找到书源「{source}」"))?;
+    let purged = http_cache::purge_source(&http_cache_dir()?, &found.url)?;
+    Ok((found.name.clone(), purged))
+}
+
+/// 文件数与字节数。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Size {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+impl Size {
+    fn add(&mut self, bytes: u64) {
+        self.files += 1;
+        self.bytes += bytes;
+    }
+}
+
+impl std::fmt::Display for Size {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} 个文件,{}",
+            self.files,
+            format_bytes(self.bytes as usize)
+        )
+    }
+}
+
+/// 各类别的磁盘占用。
+#[derive(Debug, Clone, Default)]
+pub struct Usage {
+    pub categories: BTreeMap<Category, Size>,
+    /// 书源、设置、登录态等不可清理的数据。
+    pub other: Size,
+}
+
+impl Usage {
+    pub fn get(&self, category: Category) -> Size {
+        self.categories.get(&category).copied().unwrap_or_default()
+    }
+
+    pub fn total(&self) -> Size {
+        self.categories
+            .values()
+            .fold(self.other, |total, size| Size {
+                files: total.files + size.files,
+                bytes: total.bytes + size.bytes,
+            })
+    }
+}
+
+fn walk_files(dir: &Path) -> impl Iterator<Item = (PathBuf, u64)> {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let len = entry.metadata().ok()?.len();
+            Some((entry.into_path(), len))
+        })
+}
+
+/// 统计数据目录与语音模型目录的占用。
+pub fn usage() -> Result<Usage> {
+    let layout = data_dir::layout()?;
+    let roots = layout.roots();
+    let mut usage = Usage::default();
+    for root in &roots {
+        for (path, len) in walk_files(root) {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            match Category::of(relative) {
+                Some(category) => usage.categories.entry(category).or_default().add(len),
+                None => usage.other.add(len),
+            }
+        }
+    }
+    let tts = usage.categories.entry(Category::Tts).or_default();
+    // 旧布局下语音模型在数据目录之外,单独统计。
+    if !roots.iter().any(|root| layout.tts.starts_with(root)) {
+        for (_, len) in walk_files(&layout.tts) {
+            tts.add(len);
+        }
+    }
+    Ok(usage)
+}
+
+/// 删除一批文件,返回实际删掉的量;已经不在的文件跳过。
+fn remove_files(files: impl IntoIterator<Item = PathBuf>) -> Result<Size> {
+    let mut freed = Size::default();
+    for path in files {
+        let len = match fs::metadata(&path) {
+            Ok(meta) => meta.len(),
+            Err(error) if error.kind() == ErrorKind::NotFound => continue,
+            Err(error) => return Err(error.into()),
+        };
+        fs::remove_file(&path)?;
+        freed.add(len);
+    }
+    Ok(freed)
+}
+
+fn remove_dir(dir: &Path) -> Result<Size> {
+    let freed = remove_files(walk_files(dir).map(|(path, _)| path).collect::<Vec<_>>())?;
+    match fs::remove_dir_all(dir) {
+        Err(error) if error.kind() != ErrorKind::NotFound => Err(error.into()),
+        _ => Ok(freed),
+    }
+}
+
+/// 清空一个类别,返回释放的空间。
+///
+/// 界面里历史记录与下载队列常驻内存,清这两类时调用方还要同步内存里的状态。
+pub fn clear(category: Category) -> Result<Size> {
+    let layout = data_dir::layout()?;
+    match category {
+        Category::History => {
+            let size = fs::metadata(History::get_cache_file_path()?)
+                .map(|meta| Size {
+                    files: 1,
+                    bytes: meta.len(),
+                })
+                .unwrap_or_default();
+            // 保留「上次打开的本地目录」,只清记录;History 在 drop 时落盘。
+            History::load()?.set_histories(Vec::new());
+            Ok(size)
+        }
+        Category::Network | Category::Local | Category::Browser | Category::Http => {
+            let dir = match category {
+                Category::Network => "network",
+                Category::Local => "local",
+                Category::Http => HTTP_CACHE_DIR,
+                _ => "browser-profile",
+            };
+            remove_dir(&layout.path(dir))
+        }
+        Category::Chapters | Category::Downloads => {
+            let files: Vec<PathBuf> = walk_files(&layout.path("chapters"))
+                .map(|(path, _)| path)
+                .filter(|path| {
+                    let relative = path.strip_prefix(&layout.cache).unwrap_or(path);
+                    Category::of(relative) == Some(category)
+                })
+                .collect();
+            let mut freed = remove_files(files)?;
+            if category == Category::Downloads {
+                let queue = remove_files([Downloads::path()?])?;
+                freed.files += queue.files;
+                freed.bytes += queue.bytes;
+            }
+            Ok(freed)
+        }
+        Category::Tts => remove_dir(&layout.tts),
+    }
+}
+
+/// 历史记录、书架与下载队列引用到的网络小说存档文件名(书籍链接的 md5)。
+fn referenced_network_books() -> Result<HashSet<String>> {
+    let history = History::load()?;
+    let shelf = Bookshelf::load()?;
+    let downloads = Downloads::load()?;
+    let history_ids = history
+        .histories
+        .iter()
+        .map(|(id, item)| (id, item))
+        .chain(shelf.books.iter().map(|book| (&book.id, &book.item)))
+        .filter(|(_, item)| matches!(item, HistoryItem::Network(_)))
+        .map(|(id, _)| id.clone());
+    let download_ids = downloads.tasks.iter().map(|task| task.book_url.clone());
+    Ok(history_ids
+        .chain(download_ids)
+        .map(get_md5_string)
+        .collect())
+}
+
+/// 清理没被历史记录、书架或下载队列引用的网络小说存档(连同它的人名索引)。
+pub fn prune() -> Result<Size> {
+    let referenced = referenced_network_books()?;
+    let dir = data_path("network")?;
+    let orphans: Vec<PathBuf> = walk_files(&dir)
+        .map(|(path, _)| path)
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let stem = name.split('.').next().unwrap_or_default();
+            !referenced.contains(stem)
+        })
+        .collect();
+    remove_files(orphans)
+}
+
+/// 一本有缓存数据的书。
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookData {
+    /// 历史记录里的 ID:网络小说为书籍链接,本地小说为文件路径。
+    pub id: String,
+    pub title: String,
+    /// 所属书源链接;本地小说为 `None`。
+    pub source_url: Option<String>,
+    /// 阅读存档文件。
+    pub cache: PathBuf,
+    pub size: Size,
+}
+
+impl BookData {
+    /// 这本书的全部缓存文件:阅读存档、人名索引,网络小说还有章节缓存与下载的正文。
+    pub fn files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = vec![
+            self.cache.clone(),
+            crate::novel::name_index::NameIndex::cache_path(&self.cache),
+        ];
+        if let Some(source_url) = &self.source_url {
+            files.extend(ChapterCache::open()?.book_files(source_url, &self.id));
+        }
+        files.retain(|path| path.exists());
+        Ok(files)
+    }
+}
+
+/// 列出有阅读存档的书,按占用从大到小。
+pub fn books() -> Result<Vec<BookData>> {
+    let layout = data_dir::layout()?;
+    let mut books = Vec::new();
+    for (kind, dir) in [
+        ("network", layout.path("network")),
+        ("local", layout.path("local")),
+    ] {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if !name.ends_with(".json") || name.ends_with(".names.json") {
+                continue;
+            }
+            let Ok(bytes) = fs::read(&path) else {
+                continue;
+            };
+            let book = if kind == "network" {
+                let Ok(cache) = serde_json::from_slice::<NetworkNovelCache>(&bytes) else {
+                    continue;
+                };
+                BookData {
+                    id: cache.book_list_item.book_url,
+                    title: cache.book_list_item.info.name,
+                    source_url: Some(cache.book_source_url),
+                    cache: path,
+                    size: Size::default(),
+                }
+            } else {
+                let Ok(cache) = serde_json::from_slice::<LocalNovelCache>(&bytes) else {
+                    continue;
+                };
+                BookData {
+                    id: cache.path.to_string_lossy().to_string(),
+                    title: cache
+                        .path
+                        .file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string(),
+                    source_url: None,
+                    cache: path,
+                    size: Size::default(),
+                }
+            };
+            books.push(book);
+        }
+    }
+    for book in &mut books {
+        for path in book.files()? {
+            book.size
+                .add(fs::metadata(path).map(|m| m.len()).unwrap_or_default());
+        }
+    }
+    books.sort_by_key(|book| std::cmp::Reverse(book.size.bytes));
+    Ok(books)
+}
+
+/// 按本地小说路径、网络小说链接或书名找到一本有缓存数据的书。
+pub fn find_book(book: &str) -> Result<BookData> {
+    let id = crate::export::resolve_book(book)?;
+    books()?
+        .into_iter()
+        .find(|data| data.id == id)
+        .ok_or_else(|| format!("《{book}》没有缓存数据").into())
+}
+
+/// 删除一本书的缓存数据、下载任务与历史记录;书架上的收藏保留。
+///
+/// 界面里下载队列与历史记录常驻内存,调用方要先从内存里删掉这本书。
+pub fn remove_book(book: &BookData) -> Result<Size> {
+    let freed = remove_files(book.files()?)?;
+    let mut downloads = Downloads::load()?;
+    if downloads.remove(&book.id).is_some() {
+        downloads.save()?;
+    }
+    // History 在 drop 时落盘。
+    History::load()?.remove(&book.id);
+    Ok(freed)
+}
+
+/// `cache` 子命令的操作。
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Subcommand)]
+pub enum CacheAction {
+    /// 按类别列出磁盘占用(默认)
+    List,
+    /// 清理没被历史记录、书架或下载队列引用的网络小说存档
+    Prune,
+    /// 删除一本书的缓存数据、下载任务与历史记录(书架上的收藏保留)
+    Remove {
+        /// 本地小说路径、网络小说链接,或书架 / 历史记录里的书名
+        book: String,
+    },
+    /// 清空一个书源的 HTTP 缓存
+    Purge {
+        /// 书源名或书源链接
+        source: String,
+    },
+    /// 清空选定的类别
+    Clear {
+        /// 要清空的类别,可以写多个
+        #[arg(value_enum, required = true, num_args = 1..)]
+        categories: Vec<Category>,
+    },
+}
+
+/// 对齐的占用表:类别、文件数、大小。
+fn usage_table(usage: &Usage) -> String {
+    let mut rows: Vec<[String; 4]> = Category::ALL
+        .iter()
+        .map(|category| {
+            let size = usage.get(*category);
+            [
+                category.label().to_string(),
+                category
+                    .to_possible_value()
+                    .map(|v| v.get_name().to_string())
+                    .unwrap_or_default(),
+                size.files.to_string(),
+                format_bytes(size.bytes as usize),
+            ]
+        })
+        .collect();
+    rows.push([
+        "其他(书源、设置、登录态)".to_string(),
+        "-".to_string(),
+        usage.other.files.to_string(),
+        format_bytes(usage.other.bytes as usize),
+    ]);
+    let total = usage.total();
+    rows.push([
+        "合计".to_string(),
+        String::new(),
+        total.files.to_string(),
+        format_bytes(total.bytes as usize),
+    ]);
+    let header = ["类别", "名称", "文件", "大小"].map(String::from);
+
+    let mut widths = [0usize; 4];
+    for row in std::iter::once(&header).chain(&rows) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(Span::raw(cell.as_str()).width());
+        }
+    }
+
+    let mut out = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| {
+                let pad = width - Span::raw(cell.as_str()).width();
+                format!("{cell}{}", " ".repeat(pad))
+            })
+            .collect();
+        out.push_str(cells.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// `cache` 子命令。
+pub fn run(action: Option<&CacheAction>) {
+    match action.unwrap_or(&CacheAction::List) {
+        CacheAction::List => match usage() {
+            Ok(usage) => print!("{}", usage_table(&usage)),
+            Err(e) => eprintln!("✗ 统计失败: {e}"),
+        },
+        CacheAction::Prune => match prune() {
+            Ok(freed) => println!("✓ 已清理孤立的网络小说存档:{freed}"),
+            Err(e) => eprintln!("✗ 清理失败: {e}"),
+        },
+        CacheAction::Remove { book } => match find_book(book).and_then(|data| {
+            let freed = remove_book(&data)?;
+            Ok((data, freed))
+        }) {
+            Ok((data, freed)) => println!("✓ 已删除《{}》的数据:{freed}", data.title),
+            Err(e) => eprintln!("✗ {e}"),
+        },
+        CacheAction::Purge { source } => match purge_source(source) {
+            Ok((name, files)) => println!("✓ 已清空书源「{name}」的 HTTP 缓存:{files} 个文件"),
+            Err(e) => eprintln!("✗ {e}"),
+        },
+        CacheAction::Clear { categories } => {
+            for category in categories {
+                match clear(*category) {
+                    Ok(freed) => println!("✓ 已清空{}:{freed}", category.label()),
+                    Err(e) => eprintln!("✗ 清空{}失败: {e}", category.label()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_paths_by_category() {
+        let of = |path: &str| Category::of(Path::new(path));
+        assert_eq!(of("history.json"), Some(Category::History));
+        assert_eq!(of("network/abc.json"), Some(Category::Network));
+        assert_eq!(of("network/abc.names.json"), Some(Category::Network));
+        assert_eq!(of("chapters/src/abc.txt"), Some(Category::Chapters));
+        assert_eq!(of("chapters/src/book.toc.json"), Some(Category::Chapters));
+        assert_eq!(
+            of("chapters/src/downloads/abc.txt"),
+            Some(Category::Downloads)
+        );
+        assert_eq!(of("downloads.json"), Some(Category::Downloads));
+        assert_eq!(
+            of("browser-profile/Default/Cookies"),
+            Some(Category::Browser)
+        );
+        assert_eq!(of("http-cache/0123abcd/ef.json"), Some(Category::Http));
+        // 书源、设置、登录态不属于任何类别,不会被清掉。
+        assert_eq!(of("book_sources.json"), None);
+        assert_eq!(of("source-state/abc.json"), None);
+        assert_eq!(of("keybindings.toml"), None);
+    }
+}
@@ -0,0 +1,955 @@
+//! WebDAV 同步:在多台设备之间合并阅读进度、书架、历史记录、书源与设置。
+//!
+//! 远端只放一个 `sync.json`([`SyncDoc`]),每个条目带最后修改时间,合并时逐条目取较新的一方;
+//! 删除记为墓碑条目,同样按时间参与合并。本地判断「哪些条目改过」靠上次同步结果的副本
+//! (`~/.novel/sync-base.json`):与副本相同的条目沿用副本里的时间,不同的取对应文件的修改时间。
+//!
+//! 本地小说按文件内容的哈希对应,不看路径,同一本书放在不同设备的不同目录也能对上;
+//! 本机没有的本地小说条目原样保留在远端,不会被当成删除。
+//!
+//! `trn sync` 手动同步;配置了自动同步时,启动前与退出后各同步一次。
+
+use crate::{
+    Bookshelf, HistoryItem, LocalNovelCache, NetworkNovelCache, Result, ShelfBook, SyncConfig,
+    book_source::BookSourceCache,
+    cache::store,
+    data_dir::{self, DataLayout},
+    history::History,
+};
+use chrono::{DateTime, Utc};
+use parse_book_source::BookSource;
+use reqwest::{Method, StatusCode, header};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// 同步文档格式版本。
+pub const SYNC_VERSION: u32 = 1;
+
+/// 远端同步文件名。
+const REMOTE_FILE: &str = "sync.json";
+
+/// 参与同步的设置文件(整文件为一个条目)。
+const SETTINGS: [&str; 6] = [
+    "appearance.json",
+    "reader-display.json",
+    "tts_config.json",
+    "chapter-cache.json",
+    "keybindings.toml",
+    "toc_rules.json",
+];
+
+/// 自动同步(启动 / 退出)的超时,网络不通时不拖住启动。
+const AUTO_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 远端在同步期间被别的设备改了时,最多重来几次。
+const MAX_ATTEMPTS: usize = 3;
+
+/// 条目的一个版本;`value` 为 `None` 表示已删除(墓碑)。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    pub updated_at: DateTime<Utc>,
+    pub value: Option<Value>,
+}
+
+pub type Collection = BTreeMap<String, Entry>;
+
+/// 同步文档:集合名 → 条目键 → 条目。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncDoc {
+    pub version: u32,
+    pub collections: BTreeMap<String, Collection>,
+}
+
+/// 参与同步的集合。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// 阅读进度:网络为整份阅读存档,本地为章节位置。
+    Progress,
+    History,
+    Shelf,
+    Sources,
+    Settings,
+}
+
+impl Kind {
+    const ALL: [Kind; 5] = [
+        Kind::Progress,
+        Kind::History,
+        Kind::Shelf,
+        Kind::Sources,
+        Kind::Settings,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Kind::Progress => "progress",
+            Kind::History => "history",
+            Kind::Shelf => "shelf",
+            Kind::Sources => "sources",
+            Kind::Settings => "settings",
+        }
+    }
+
+    /// 本地删掉条目时是否同步删除。进度与设置只会被覆盖,不跟着删。
+    fn syncs_deletion(&self) -> bool {
+        matches!(self, Kind::History | Kind::Shelf | Kind::Sources)
+    }
+}
+
+/// 本机当前的一个条目值,附修改时间的依据(文件修改时间或条目自带的时间)。
+type Current = BTreeMap<String, (Value, DateTime<Utc>)>;
+
+/// 合并两份集合:逐条目取修改时间较新的一方,时间相同以 `local` 为准。
+pub fn merge(local: &Collection, remote: &Collection) -> Collection {
+    let mut merged = remote.clone();
+    for (key, entry) in local {
+        match merged.get(key) {
+            Some(theirs) if theirs.updated_at > entry.updated_at => {}
+            _ => {
+                merged.insert(key.clone(), entry.clone());
+            }
+        }
+    }
+    merged
+}
+
+/// 由本机当前值与上次同步结果算出本机这一侧的集合。
+///
+/// - 与上次同步值相同的条目沿用上次的时间,没改过的条目不会盖掉别的设备的新改动;
+/// - 上次有、这次没有的条目:`deletable` 且 `resolvable` 时记为墓碑,否则原样保留。
+fn local_collection(
+    base: &Collection,
+    current: &Current,
+    deletable: bool,
+    resolvable: impl Fn(&str) -> bool,
+    deleted_at: DateTime<Utc>,
+) -> Collection {
+    let mut local = Collection::new();
+    for (key, (value, modified)) in current {
+        let entry = match base.get(key) {
+            Some(entry) if entry.value.as_ref() == Some(value) => entry.clone(),
+            _ => Entry {
+                updated_at: *modified,
+                value: Some(value.clone()),
+            },
+        };
+        local.insert(key.clone(), entry);
+    }
+    for (key, entry) in base {
+        if current.contains_key(key) {
+            continue;
+        }
+        let entry = if deletable && entry.value.is_some() && resolvable(key) {
+            Entry {
+                updated_at: deleted_at.max(entry.updated_at),
+                value: None,
+            }
+        } else {
+            entry.clone()
+        };
+        local.insert(key.clone(), entry);
+    }
+    local
+}
+
+/// 本机数据的快照。
+struct Snapshot {
+    layout: DataLayout,
+    /// 本机本地小说:内容哈希 → 路径。
+    paths: HashMap<String, PathBuf>,
+    /// 路径 → 内容哈希。
+    hashes: HashMap<PathBuf, String>,
+    current: BTreeMap<&'static str, Current>,
+    /// 各集合对应文件的修改时间,本地删除条目时作为删除时间。
+    modified: BTreeMap<&'static str, DateTime<Utc>>,
+}
+
+impl Snapshot {
+    fn collect(layout: &DataLayout) -> Result<Self> {
+        let mut snapshot = Self {
+            layout: layout.clone(),
+            paths: HashMap::new(),
+            hashes: HashMap::new(),
+            current: BTreeMap::new(),
+            modified: BTreeMap::new(),
+        };
+        let history = read_history(layout)?;
+        let shelf = Bookshelf::load()?;
+        let local_caches = read_dir_json::<LocalNovelCache>(&layout.path("local"));
+
+        let local_paths = local_caches
+            .iter()
+            .map(|(cache, _)| cache.path.clone())
+            .chain(history.iter().filter_map(|(id, item)| local_path(id, item)))
+            .chain(
+                shelf
+                    .books
+                    .iter()
+                    .filter_map(|b| local_path(&b.id, &b.item)),
+            );
+        for path in local_paths {
+            snapshot.hash(&path);
+        }
+
+        let mut progress = Current::new();
+        for (cache, modified) in read_dir_json::<NetworkNovelCache>(&layout.path("network")) {
+            let key = format!("net:{}", cache.book_list_item.book_url);
+            progress.insert(key, (serde_json::to_value(&cache)?, modified));
+        }
+        for (cache, modified) in &local_caches {
+            if let Some(key) = snapshot.local_key(&cache.path) {
+                let value = serde_json::json!({
+                    "currentChapter": cache.current_chapter,
+                    "linePercent": cache.line_percent,
+                });
+                progress.insert(key, (value, *modified));
+            }
+        }
+        snapshot.current.insert(Kind::Progress.name(), progress);
+
+        let mut entries = Current::new();
+        for (id, item) in &history {
+            if let Some(key) = snapshot.book_key(id, item) {
+                let read_at = item.last_read_at().to_utc();
+                entries.insert(key, (serde_json::to_value(item)?, read_at));
+            }
+        }
+        snapshot.current.insert(Kind::History.name(), entries);
+        snapshot.set_modified(Kind::History, "history.json");
+
+        let shelf_modified = snapshot.set_modified(Kind::Shelf, "bookshelf.json");
+        let mut entries = Current::new();
+        for book in &shelf.books {
+            if let Some(key) = snapshot.book_key(&book.id, &book.item) {
+                let mut value = serde_json::to_value(book)?;
+                // id 在各设备上不同(本地小说是路径),用条目键代替,值才能跨设备比较。
+                value["id"] = Value::String(key.clone());
+                entries.insert(key, (value, shelf_modified));
+            }
+        }
+        entries.insert(
+            "#groups".to_string(),
+            (serde_json::to_value(&shelf.groups)?, shelf_modified),
+        );
+        entries.insert(
+            "#sort".to_string(),
+            (serde_json::to_value(shelf.sort)?, shelf_modified),
+        );
+        snapshot.current.insert(Kind::Shelf.name(), entries);
+
+        let sources_modified = snapshot.set_modified(Kind::Sources, "book_sources.json");
+        let mut entries = Current::new();
+        for source in read_sources(layout)? {
+            if let Some(key) = source_key(&source) {
+                entries.insert(key, (source, sources_modified));
+            }
+        }
+        snapshot.current.insert(Kind::Sources.name(), entries);
+
+        let mut entries = Current::new();
+        for name in SETTINGS {
+            let path = layout.path(name);
+            if let Ok(text) = fs::read_to_string(&path) {
+                entries.insert(name.to_string(), (Value::String(text), modified_at(&path)));
+            }
+        }
+        snapshot.current.insert(Kind::Settings.name(), entries);
+        Ok(snapshot)
+    }
+
+    fn set_modified(&mut self, kind: Kind, file: &str) -> DateTime<Utc> {
+        let modified = modified_at(&self.layout.path(file));
+        self.modified.insert(kind.name(), modified);
+        modified
+    }
+
+    /// 计算并记下本地小说的内容哈希;文件不在了返回 `None`。
+    fn hash(&mut self, path: &Path) -> Option<String> {
+        if let Some(hash) = self.hashes.get(path) {
+            return Some(hash.clone());
+        }
+        let hash = format!("{:x}", md5::compute(fs::read(path).ok()?));
+        self.hashes.insert(path.to_path_buf(), hash.clone());
+        self.paths.insert(hash.clone(), path.to_path_buf());
+        Some(hash)
+    }
+
+    fn local_key(&self, path: &Path) -> Option<String> {
+        self.hashes.get(path).map(|hash| format!("local:{hash}"))
+    }
+
+    fn book_key(&self, id: &str, item: &HistoryItem) -> Option<String> {
+        match item {
+            HistoryItem::Local(_) => self.local_key(Path::new(id)),
+            HistoryItem::Network(_) => Some(format!("net:{id}")),
+        }
+    }
+
+    /// 条目键在本机对应的书 id;本机没有这本本地小说时为 `None`。
+    fn book_id(&self, key: &str) -> Option<String> {
+        if let Some(url) = key.strip_prefix("net:") {
+            return Some(url.to_string());
+        }
+        let hash = key.strip_prefix("local:")?;
+        Some(self.paths.get(hash)?.to_string_lossy().to_string())
+    }
+
+    /// 条目能否落到本机:本地小说条目要求本机有这本书。
+    fn resolvable(&self, key: &str) -> bool {
+        match key.strip_prefix("local:") {
+            Some(hash) => self.paths.contains_key(hash),
+            None => true,
+        }
+    }
+
+    /// 本机这一侧的同步文档。
+    fn doc(&self, base: &SyncDoc) -> SyncDoc {
+        let empty = Collection::new();
+        let collections = Kind::ALL
+            .iter()
+            .map(|kind| {
+                let name = kind.name();
+                let local = local_collection(
+                    base.collections.get(name).unwrap_or(&empty),
+                    &self.current[name],
+                    kind.syncs_deletion(),
+                    |key| self.resolvable(key),
+                    self.modified.get(name).copied().unwrap_or_else(Utc::now),
+                );
+                (name.to_string(), local)
+            })
+            .collect();
+        SyncDoc {
+            version: SYNC_VERSION,
+            collections,
+        }
+    }
+
+    /// 合并结果里本机能落地、且与本机当前值不同的条目键。
+    fn changed(&self, kind: Kind, merged: &Collection) -> Vec<String> {
+        let current = &self.current[kind.name()];
+        let live = merged
+            .iter()
+            .filter(|(key, _)| self.resolvable(key))
+            .filter(|(key, entry)| {
+                entry.value.as_ref() != current.get(key.as_str()).map(|(value, _)| value)
+            })
+            .map(|(key, _)| key.clone());
+        let deleted = current
+            .keys()
+            .filter(|key| !merged.contains_key(key.as_str()))
+            .cloned();
+        live.chain(deleted).collect()
+    }
+
+    /// 把合并结果写回本机,返回改动的条目数。
+    fn apply(&self, merged: &SyncDoc) -> Result<usize> {
+        let empty = Collection::new();
+        let mut applied = 0;
+        for kind in Kind::ALL {
+            let collection = merged.collections.get(kind.name()).unwrap_or(&empty);
+            let changed = self.changed(kind, collection);
+            if changed.is_empty() {
+                continue;
+            }
+            applied += changed.len();
+            match kind {
+                Kind::Progress => self.apply_progress(collection, &changed)?,
+                Kind::History => self.apply_history(collection)?,
+                Kind::Shelf => self.apply_shelf(collection)?,
+                Kind::Sources => self.apply_sources(collection)?,
+                Kind::Settings => self.apply_settings(collection, &changed)?,
+            }
+        }
+        Ok(applied)
+    }
+
+    fn apply_progress(&self, collection: &Collection, changed: &[String]) -> Result<()> {
+        for key in changed {
+            let Some(value) = collection.get(key).and_then(|e| e.value.clone()) else {
+                continue;
+            };
+            if key.starts_with("net:") {
+                serde_json::from_value::<NetworkNovelCache>(value)?.save()?;
+            } else if let Some(path) = key.strip_prefix("local:").and_then(|h| self.paths.get(h)) {
+                let Ok(mut cache) = LocalNovelCache::try_from(path.as_path()) else {
+                    continue;
+                };
+                let chapter = value["currentChapter"].as_u64().unwrap_or_default() as usize;
+                cache.current_chapter = chapter.min(cache.chapters.len().saturating_sub(1));
+                cache.line_percent = value["linePercent"].as_f64().unwrap_or_default();
+                cache.save()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_history(&self, collection: &Collection) -> Result<()> {
+        let mut history = History::load()?;
+        // 文件已不在的本地小说没有条目键,不参与同步,原样留着。
+        let mut histories: Vec<(String, HistoryItem)> = history
+            .histories
+            .iter()
+            .filter(|(id, item)| self.book_key(id, item).is_none())
+            .cloned()
+            .collect();
+        for (key, entry) in collection {
+            let (Some(id), Some(value)) = (self.book_id(key), &entry.value) else {
+                continue;
+            };
+            histories.push((id, serde_json::from_value(value.clone())?));
+        }
+        histories.sort_by_key(|(_, item)| std::cmp::Reverse(item.last_read_at()));
+        histories.truncate(History::MAX_LEN);
+        // History 在 drop 时落盘。
+        history.set_histories(histories);
+        Ok(())
+    }
+
+    fn apply_shelf(&self, collection: &Collection) -> Result<()> {
+        let mut shelf = Bookshelf::load()?;
+        let mut books: BTreeMap<String, ShelfBook> = BTreeMap::new();
+        for (key, entry) in collection {
+            let (Some(id), Some(value)) = (self.book_id(key), &entry.value) else {
+                continue;
+            };
+            let mut book: ShelfBook = serde_json::from_value(value.clone())?;
+            book.id = id.clone();
+            books.insert(id, book);
+        }
+        // 已有的书保持原来的顺序,新书排在后面。
+        let mut ordered: Vec<ShelfBook> = shelf
+            .books
+            .iter()
+            .filter_map(|book| match self.book_key(&book.id, &book.item) {
+                Some(_) => books.remove(&book.id),
+                None => Some(book.clone()),
+            })
+            .collect();
+        ordered.extend(books.into_values());
+        shelf.books = ordered;
+        if let Some(groups) = collection.get("#groups").and_then(|e| e.value.clone()) {
+            shelf.groups = serde_json::from_value(groups)?;
+        }
+        if let Some(sort) = collection.get("#sort").and_then(|e| e.value.clone()) {
+            shelf.sort = serde_json::from_value(sort)?;
+        }
+        shelf.save()
+    }
+
+    fn apply_sources(&self, collection: &Collection) -> Result<()> {
+        let values: Vec<Value> = collection
+            .values()
+            .filter_map(|e| e.value.clone())
+            .collect();
+        let sources = BookSource::from_value_many(Value::Array(values))
+            .map_err(parse_book_source::BookSourceError::from)?;
+        // BookSourceCache 在 drop 时落盘。
+        let mut cache = BookSourceCache::load()?;
+        cache.book_sources = sources;
+        Ok(())
+    }
+
+    fn apply_settings(&self, collection: &Collection, changed: &[String]) -> Result<()> {
+        for name in changed {
+            if let Some(Value::String(text)) = collection.get(name).and_then(|e| e.value.clone()) {
+                store::write_atomic(&self.layout.path(name), text.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn local_path(id: &str, item: &HistoryItem) -> Option<PathBuf> {
+    matches!(item, HistoryItem::Local(_)).then(|| PathBuf::from(id))
+}
+
+fn source_key(source: &Value) -> Option<String> {
+    Some(format!(
+        "{}\n{}",
+        source.get("url")?.as_str()?,
+        source.get("name")?.as_str()?
+    ))
+}
+
+fn modified_at(path: &Path) -> DateTime<Utc> {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+        .into()
+}
+
+/// 读目录下全部 JSON 文件,附文件修改时间;读不懂的文件跳过。
+fn read_dir_json<T: serde::de::DeserializeOwned>(dir: &Path) -> Vec<(T, DateTime<Utc>)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let value = serde_json::from_slice(&fs::read(&path).ok()?).ok()?;
+            Some((value, modified_at(&path)))
+        })
+        .collect()
+}
+
+/// 直接读历史记录文件(`History` 在 drop 时会落盘,只读时不用它)。
+fn read_history(layout: &DataLayout) -> Result<Vec<(String, HistoryItem)>> {
+    #[derive(Default, Deserialize)]
+    struct HistoryFile {
+        histories: Vec<(String, HistoryItem)>,
+    }
+    let file: Option<HistoryFile> = store::load_json(&layout.path("history.json"))?;
+    Ok(file.unwrap_or_default().histories)
+}
+
+/// 直接读书源缓存的原始 JSON(同上,`BookSourceCache` 在 drop 时会落盘)。
+fn read_sources(layout: &DataLayout) -> Result<Vec<Value>> {
+    let Some(value) = store::load_json::<Value>(&layout.path("book_sources.json"))? else {
+        return Ok(Vec::new());
+    };
+    let sources = match value {
+        Value::Object(mut map) => map.remove("book_sources").unwrap_or_default(),
+        other => other,
+    };
+    Ok(match sources {
+        Value::Array(sources) => sources,
+        _ => Vec::new(),
+    })
+}
+
+fn base_path(layout: &DataLayout) -> PathBuf {
+    layout.path("sync-base.json")
+}
+
+fn load_base(layout: &DataLayout) -> SyncDoc {
+    store::load_json(&base_path(layout))
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+/// 远端同步文件的版本标识,上传时据此判断读取之后有没有被别的设备改过。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemoteVersion {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+/// WebDAV 远端。
+pub struct WebDav {
+    client: reqwest::Client,
+    config: SyncConfig,
+}
+
+impl WebDav {
+    pub fn new(config: SyncConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?;
+        Ok(Self { client, config })
+    }
+
+    fn request(&self, method: Method, url: String) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, url);
+        if self.config.username.is_empty() {
+            request
+        } else {
+            request.basic_auth(&self.config.username, Some(&self.config.password))
+        }
+    }
+
+    /// 读取远端同步文件与它的版本标识;还没有时返回 `None`。
+    pub async fn get(&self) -> Result<Option<(SyncDoc, RemoteVersion)>> {
+        let response = self
+            .request(Method::GET, self.config.remote_url(REMOTE_FILE))
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = response.error_for_status()?;
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        };
+        let version = RemoteVersion {
+            etag: header(header::ETAG),
+            last_modified: header(header::LAST_MODIFIED),
+        };
+        let doc: SyncDoc = serde_json::from_slice(&response.bytes().await?)?;
+        if doc.version > SYNC_VERSION {
+            return Err("远端同步数据来自更新的版本,请先升级 TRNovel".into());
+        }
+        Ok(Some((doc, version)))
+    }
+
+    /// 上传同步文件。`base` 为读取时远端文件的版本,`None` 表示当时远端还没有文件。
+    /// 远端在读取之后被别的设备改过时返回 `false`:有 ETag 用 `If-Match` 判断,
+    /// 没有 ETag 退而用 `Last-Modified`,两者都不给的服务器无条件覆盖。
+    pub async fn put(&self, doc: &SyncDoc, base: Option<&RemoteVersion>) -> Result<bool> {
+        self.ensure_dir().await?;
+        let mut request = self
+            .request(Method::PUT, self.config.remote_url(REMOTE_FILE))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(doc)?);
+        request = match base {
+            None => request.header(header::IF_NONE_MATCH, "*"),
+            Some(RemoteVersion {
+                etag: Some(etag), ..
+            }) => request.header(header::IF_MATCH, etag),
+            Some(RemoteVersion {
+                last_modified: Some(modified),
+                ..
+            }) => request.header(header::IF_UNMODIFIED_SINCE, modified),
+            Some(_) => request,
+        };
+        let response = request.send().await?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Ok(false);
+        }
+        response.error_for_status()?;
+        Ok(true)
+    }
+
+    /// 建远端目录;已存在时服务器返回 405,不算错。
+    async fn ensure_dir(&self) -> Result<()> {
+        let method = Method::from_bytes(b"MKCOL").expect("MKCOL 是合法的方法名");
+        let response = self
+            .request(method, self.config.remote_dir())
+            .send()
+            .await?;
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::METHOD_NOT_ALLOWED => Ok(()),
+            _ => {
+                response.error_for_status()?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 一次同步的结果。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// 从远端拉下来写到本机的条目数。
+    pub pulled: usize,
+    /// 推到远端的条目数。
+    pub pushed: usize,
+}
+
+/// 同步一次:合并本机与远端,结果写回两边,并记为下次同步的基准。
+pub async fn sync(config: SyncConfig) -> Result<SyncReport> {
+    let layout = data_dir::layout()?;
+    let remote = WebDav::new(config)?;
+    let base = load_base(layout);
+    let snapshot = Snapshot::collect(layout)?;
+    let local = snapshot.doc(&base);
+
+    for _ in 0..MAX_ATTEMPTS {
+        let (remote_doc, version) = match remote.get().await? {
+            Some((doc, version)) => (doc, Some(version)),
+            None => (SyncDoc::default(), None),
+        };
+        let mut merged = SyncDoc {
+            version: SYNC_VERSION,
+            collections: BTreeMap::new(),
+        };
+        let mut pushed = 0;
+        let empty = Collection::new();
+        for kind in Kind::ALL {
+            let name = kind.name();
+            let theirs = remote_doc.collections.get(name).unwrap_or(&empty);
+            let collection = merge(&local.collections[name], theirs);
+            pushed += collection
+                .iter()
+                .filter(|(key, entry)| theirs.get(key.as_str()) != Some(entry))
+                .count();
+            merged.collections.insert(name.to_string(), collection);
+        }
+        if pushed > 0 && !remote.put(&merged, version.as_ref()).await? {
+            continue;
+        }
+        let pulled = snapshot.apply(&merged)?;
+        store::save_json(&base_path(layout), &merged)?;
+        return Ok(SyncReport { pulled, pushed });
+    }
+    Err("远端同步数据一直在被其他设备修改,请稍后再试".into())
+}
+
+/// 启动 / 退出时的自动同步:没配置或关闭了自动同步时什么也不做,出错只提示不中断。
+pub async fn auto_sync() {
+    let config = match SyncConfig::load() {
+        Ok(Some(config)) if config.auto => config,
+        Ok(_) => return,
+        Err(e) => return eprintln!("✗ 读取同步配置失败: {e}"),
+    };
+    match tokio::time::timeout(AUTO_TIMEOUT, sync(config)).await {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => eprintln!("✗ 同步失败: {e}"),
+        Err(_) => eprintln!("✗ 同步超时,已跳过"),
+    }
+}
+
+/// `sync` 子命令:不带参数时同步一次;带 `--url` 时保存配置。
+pub async fn run(url: Option<&str>, username: Option<&str>, dir: Option<&str>, no_auto: bool) {
+    if let Some(url) = url {
+        let password = match std::env::var("TRNOVEL_SYNC_PASSWORD") {
+            Ok(password) => password,
+            Err(_) if username.is_none() => String::new(),
+            Err(_) => match rpassword::prompt_password("WebDAV 密码:") {
+                Ok(password) => password,
+                Err(e) => return eprintln!("✗ {e}"),
+            },
+        };
+        let mut config = SyncConfig::new(
+            url.to_string(),
+            username.unwrap_or_default().to_string(),
+            password,
+        );
+        if let Some(dir) = dir {
+            config.dir = dir.to_string();
+        }
+        config.auto = !no_auto;
+        if let Err(e) = config.save() {
+            return eprintln!("✗ 保存同步配置失败: {e}");
+        }
+        println!("✓ 已保存同步配置 → {}", config.remote_url(REMOTE_FILE));
+    }
+
+    let config = match SyncConfig::load() {
+        Ok(Some(config)) => config,
+        Ok(None) => {
+            return eprintln!(
+                "✗ 还没有配置同步,先执行 trn sync --url <WebDAV 地址> --username <用户名>"
+            );
+        }
+        Err(e) => return eprintln!("✗ 读取同步配置失败: {e}"),
+    };
+    match sync(config).await {
+        Ok(report) => println!(
+            "✓ 同步完成:拉取 {} 项,推送 {} 项",
+            report.pulled, report.pushed
+        ),
+        Err(e) => eprintln!("✗ 同步失败: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(secs, 0).unwrap()
+    }
+
+    fn entry(secs: i64, value: Option<Value>) -> Entry {
+        Entry {
+            updated_at: at(secs),
+            value,
+        }
+    }
+
+    #[test]
+    fn merge_takes_the_newer_side_per_entry() {
+        let local = Collection::from([
+            ("a".to_string(), entry(10, Some(json!(1)))),
+            ("b".to_string(), entry(10, Some(json!(1)))),
+            ("c".to_string(), entry(30, None)),
+        ]);
+        let remote = Collection::from([
+            ("a".to_string(), entry(20, Some(json!(2)))),
+            ("b".to_string(), entry(5, Some(json!(2)))),
+            ("c".to_string(), entry(20, Some(json!(2)))),
+            ("d".to_string(), entry(1, Some(json!(2)))),
+        ]);
+        let merged = merge(&local, &remote);
+        assert_eq!(merged["a"].value, Some(json!(2)));
+        assert_eq!(merged["b"].value, Some(json!(1)));
+        // 本地较新的删除胜出。
+        assert_eq!(merged["c"].value, None);
+        assert_eq!(merged["d"].value, Some(json!(2)));
+    }
+
+    #[test]
+    fn unchanged_entries_keep_base_time_and_unknown_local_books_are_kept() {
+        let base = Collection::from([
+            ("net:a".to_string(), entry(10, Some(json!("读到第一章")))),
+            ("net:gone".to_string(), entry(10, Some(json!("x")))),
+            ("local:elsewhere".to_string(), entry(10, Some(json!("x")))),
+            ("net:changed".to_string(), entry(10, Some(json!("旧")))),
+        ]);
+        let current = Current::from([
+            ("net:a".to_string(), (json!("读到第一章"), at(50))),
+            ("net:changed".to_string(), (json!("新"), at(50))),
+        ]);
+        let local = local_collection(&base, &current, true, |k| !k.starts_with("local:"), at(60));
+
+        assert_eq!(local["net:a"], base["net:a"]);
+        assert_eq!(local["net:changed"], entry(50, Some(json!("新"))));
+        assert_eq!(local["net:gone"], entry(60, None));
+        // 本机没有的本地小说不算删除。
+        assert_eq!(local["local:elsewhere"], base["local:elsewhere"]);
+
+        // 进度类集合不同步删除。
+        let local = local_collection(&base, &current, false, |_| true, at(60));
+        assert_eq!(local["net:gone"], base["net:gone"]);
+    }
+
+    /// 模拟 WebDAV 服务器上的同步文件,按条件头决定 PUT 是否成功。
+    #[derive(Default)]
+    struct Server {
+        body: Option<Vec<u8>>,
+        revision: u32,
+        /// GET 时是否返回 ETag。
+        etag: bool,
+        /// 每次 PUT 带的条件头,没带时为 `-`。
+        conditions: Vec<String>,
+    }
+
+    impl Server {
+        fn etag(&self) -> String {
+            format!("\"r{}\"", self.revision)
+        }
+
+        fn handle(
+            &mut self,
+            method: &str,
+            headers: &HashMap<String, String>,
+            body: Vec<u8>,
+        ) -> (&'static str, String, Vec<u8>) {
+            match method {
+                "MKCOL" => ("405 Method Not Allowed", String::new(), vec![]),
+                "GET" => match &self.body {
+                    None => ("404 Not Found", String::new(), vec![]),
+                    Some(body) if self.etag => {
+                        ("200 OK", format!("ETag: {}\r\n", self.etag()), body.clone())
+                    }
+                    Some(body) => ("200 OK", String::new(), body.clone()),
+                },
+                "PUT" => {
+                    let condition = ["if-match", "if-none-match", "if-unmodified-since"]
+                        .into_iter()
+                        .find_map(|name| Some(format!("{name}: {}", headers.get(name)?)))
+                        .unwrap_or_else(|| "-".to_string());
+                    self.conditions.push(condition);
+                    let conflict = match (headers.get("if-match"), headers.get("if-none-match")) {
+                        (Some(etag), _) => *etag != self.etag(),
+                        (_, Some(_)) => self.body.is_some(),
+                        _ => false,
+                    };
+                    if conflict {
+                        return ("412 Precondition Failed", String::new(), vec![]);
+                    }
+                    self.body = Some(body);
+                    self.revision += 1;
+                    ("201 Created", String::new(), vec![])
+                }
+                _ => ("400 Bad Request", String::new(), vec![]),
+            }
+        }
+    }
+
+    fn read_request(
+        stream: &mut std::net::TcpStream,
+    ) -> Option<(String, HashMap<String, String>, Vec<u8>)> {
+        use std::io::{BufRead, BufReader, Read};
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let method = line.split_whitespace().next()?.to_string();
+        let mut headers = HashMap::new();
+        loop {
+            line.clear();
+            reader.read_line(&mut line).ok()?;
+            let Some((name, value)) = line.trim_end().split_once(':') else {
+                break;
+            };
+            headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+        }
+        let len = headers
+            .get("content-length")
+            .and_then(|len| len.parse().ok())
+            .unwrap_or(0);
+        let mut body = vec![0; len];
+        reader.read_exact(&mut body).ok()?;
+        Some((method, headers, body))
+    }
+
+    /// 起一个本地 WebDAV 桩,返回指向它的同步配置。
+    fn spawn_server(server: Arc<Mutex<Server>>) -> SyncConfig {
+        use std::io::Write;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let Some((method, headers, body)) = read_request(&mut stream) else {
+                    continue;
+                };
+                let (status, extra, body) = server.lock().unwrap().handle(&method, &headers, body);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n{extra}\r\n",
+                    body.len()
+                );
+                let _ = stream.write_all(&body);
+            }
+        });
+        SyncConfig::new(url, String::new(), String::new())
+    }
+
+    #[tokio::test]
+    async fn put_preconditions_follow_what_the_remote_returned() {
+        let server = Arc::new(Mutex::new(Server {
+            etag: true,
+            ..Default::default()
+        }));
+        let dav = WebDav::new(spawn_server(server.clone())).unwrap();
+        let doc = SyncDoc {
+            version: SYNC_VERSION,
+            collections: BTreeMap::new(),
+        };
+
+        // 首次上传:远端还没有文件。
+        assert!(dav.get().await.unwrap().is_none());
+        assert!(dav.put(&doc, None).await.unwrap());
+
+        // 带 ETag 更新;拿着旧 ETag 再传一次说明远端已被改过。
+        let (_, version) = dav.get().await.unwrap().unwrap();
+        assert_eq!(version.etag.as_deref(), Some("\"r1\""));
+        assert!(dav.put(&doc, Some(&version)).await.unwrap());
+        assert!(!dav.put(&doc, Some(&version)).await.unwrap());
+
+        // 服务器不给 ETag 时不能当成「远端没有文件」,否则每次都 412。
+        server.lock().unwrap().etag = false;
+        let (_, version) = dav.get().await.unwrap().unwrap();
+        assert_eq!(version, RemoteVersion::default());
+        assert!(dav.put(&doc, Some(&version)).await.unwrap());
+
+        assert_eq!(
+            server.lock().unwrap().conditions,
+            [
+                "if-none-match: *",
+                "if-match: \"r1\"",
+                "if-match: \"r1\"",
+                "-",
+            ]
+        );
+    }
+}
@@ -53,6 +53,7 @@ pub struct ReaderTheme {
     pub progress: Style,
     pub border: Style,
     pub tts_highlight: Style,
+    pub selection: Style,
 }
 
 impl ComponentTheme for ReaderTheme {
@@ -66,6 +67,7 @@ impl ComponentTheme for ReaderTheme {
             tts_highlight: Style::new()
                 .fg(palette.success)
                 .add_modifier(Modifier::BOLD),
+            selection: Style::new().fg(palette.on_accent).bg(palette.selection),
         }
     }
 }
@@ -0,0 +1,247 @@
+//! 追更检查:对书架上的每本网络小说重跑 `book_info` + `toc`,记录新增章节数。
+//!
+//! 书架页按 `u` 在后台触发;`update` 子命令不启动 TUI,检查完打印汇总表。
+//! 同一书源的请求限制并发,避免一次性把站点打满;不同书源之间互不等待。
+
+use crate::{
+    Bookshelf, HistoryItem, NetworkNovelCache, Result, book_source::BookSourceCache,
+    cache::load_source_state,
+};
+use futures::future::join_all;
+use parse_book_source::{BookSource, Engine};
+use ratatui::text::Span;
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::Semaphore;
+
+/// 同一书源同时在检查的书数上限。
+pub const PER_SOURCE_CONCURRENCY: usize = 2;
+
+/// 一本书的检查结果。
+#[derive(Debug, Clone)]
+pub struct UpdateResult {
+    pub id: String,
+    pub title: String,
+    pub source: String,
+    /// 检查前已知的章节数(书架记录的基线)。
+    pub known: usize,
+    /// `(最新章节数, 最新章节名)` 或错误信息。
+    pub outcome: std::result::Result<(usize, String), String>,
+}
+
+impl UpdateResult {
+    /// 本次检查后比基线多出的章节数;首次检查(基线为 0)不算新增。
+    pub fn new_chapters(&self) -> usize {
+        match &self.outcome {
+            Ok((count, _)) if self.known > 0 => count.saturating_sub(self.known),
+            _ => 0,
+        }
+    }
+}
+
+/// 检查书架上的全部网络小说;每检查完一本调用一次 `on_progress(已完成, 总数)`。
+///
+/// `build` 决定引擎怎么装配:TUI 里用 [`crate::browser_assist::build_engine`](撞挑战可弹窗授权),
+/// 命令行没有界面可弹,用 [`reqwest_engine`]。
+pub async fn check_updates(
+    shelf: &Bookshelf,
+    sources: &BookSourceCache,
+    build: fn(BookSource) -> parse_book_source::Result<Engine>,
+    on_progress: impl Fn(usize, usize),
+) -> Vec<UpdateResult> {
+    let books: Vec<_> = shelf
+        .books
+        .iter()
+        .filter_map(|book| match &book.item {
+            HistoryItem::Network(item) => Some((book, item.book_source.clone())),
+            HistoryItem::Local(_) => None,
+        })
+        .collect();
+    let total = books.len();
+    let done = std::sync::atomic::AtomicUsize::new(0);
+
+    let mut limits: HashMap<String, Arc<Semaphore>> = HashMap::new();
+    let tasks = books.into_iter().map(|(book, source)| {
+        let limit = limits
+            .entry(source.clone())
+            .or_insert_with(|| Arc::new(Semaphore::new(PER_SOURCE_CONCURRENCY)))
+            .clone();
+        let (done, on_progress) = (&done, &on_progress);
+        async move {
+            let outcome = {
+                let _permit = limit.acquire().await;
+                check_book(&book.id, sources, build)
+                    .await
+                    .map_err(|e| e.to_string())
+            };
+            let finished = done.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+            on_progress(finished, total);
+            UpdateResult {
+                id: book.id.clone(),
+                title: book.title().to_string(),
+                source,
+                known: book.chapter_count.max(book.latest_chapter_count),
+                outcome,
+            }
+        }
+    });
+    join_all(tasks).await
+}
+
+/// 重新拉取一本书的详情与目录,返回 `(章节数, 最新章节名)`。
+async fn check_book(
+    id: &str,
+    sources: &BookSourceCache,
+    build: fn(BookSource) -> parse_book_source::Result<Engine>,
+) -> Result<(usize, String)> {
+    let cache =
+        NetworkNovelCache::try_from(id).map_err(|_| "找不到这本书的缓存,请先打开阅读一次")?;
+    let source = sources
+        .find_book_source(&cache.book_source_url, &cache.book_source_name)
+        .ok_or(format!("书源「{}」已被删除", cache.book_source_name))?;
+    let engine = build(source.clone())?.with_book_vars(cache.book_vars);
+    let info = engine.book_info(&cache.book_list_item.book_url).await?;
+    let toc = engine.toc(&info.toc_url).await?;
+    let latest = toc
+        .chapters
+        .last()
+        .map(|c| c.title.clone())
+        .unwrap_or(info.last_chapter);
+    Ok((toc.chapters.len(), latest))
+}
+
+/// 不带浏览器的引擎:命令行下没有界面响应浏览器授权弹窗,撞挑战直接报错。
+pub fn reqwest_engine(mut source: BookSource) -> parse_book_source::Result<Engine> {
+    crate::cache::apply_global_proxy(&mut source);
+    let state = load_source_state(&source.url);
+    Ok(Engine::new(source)?
+        .with_login_header(state.login_header)
+        .with_cookies(&state.cookies))
+}
+
+/// 把检查结果写回书架(出错的书保持原样)。
+pub fn apply_results(shelf: &mut Bookshelf, results: &[UpdateResult]) {
+    for result in results {
+        if let Ok((count, latest)) = &result.outcome {
+            shelf.record_update(&result.id, *count, latest);
+        }
+    }
+}
+
+/// `update` 子命令:检查书架上所有网络小说的更新并打印汇总表。
+pub async fn run() {
+    let mut shelf = match Bookshelf::load() {
+        Ok(shelf) => shelf,
+        Err(e) => return eprintln!("✗ 读取书架失败: {e}"),
+    };
+    let sources = match BookSourceCache::load() {
+        Ok(sources) => sources,
+        Err(e) => return eprintln!("✗ 读取书源缓存失败: {e}"),
+    };
+
+    let results = check_updates(&shelf, &sources, reqwest_engine, |done, total| {
+        eprint!("\r检查中 {done}/{total}");
+    })
+    .await;
+    if results.is_empty() {
+        return println!("书架上没有网络小说(本地小说按文件修改时间记录更新,无需检查)。");
+    }
+    eprintln!();
+
+    apply_results(&mut shelf, &results);
+    if let Err(e) = shelf.save() {
+        eprintln!("✗ 保存书架失败: {e}");
+    }
+
+    print!("{}", summary_table(&results));
+    let updated = results.iter().filter(|r| r.new_chapters() > 0).count();
+    let failed = results.iter().filter(|r| r.outcome.is_err()).count();
+    println!(
+        "\n共 {} 本:{updated} 本有更新,{failed} 本检查失败",
+        results.len()
+    );
+}
+
+/// 对齐的汇总表:书名、书源、章节数、新增、最新章节(失败时为错误信息)。
+fn summary_table(results: &[UpdateResult]) -> String {
+    let rows: Vec<[String; 5]> = results
+        .iter()
+        .map(|r| match &r.outcome {
+            Ok((count, latest)) => [
+                r.title.clone(),
+                r.source.clone(),
+                count.to_string(),
+                match r.new_chapters() {
+                    0 => "-".to_string(),
+                    n => format!("+{n}"),
+                },
+                latest.clone(),
+            ],
+            Err(e) => [
+                r.title.clone(),
+                r.source.clone(),
+                "-".to_string(),
+                "✗".to_string(),
+                e.clone(),
+            ],
+        })
+        .collect();
+    let header = ["书名", "书源", "章节", "新增", "最新章节"].map(String::from);
+
+    let mut widths = [0usize; 5];
+    for row in std::iter::once(&header).chain(&rows) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(Span::raw(cell.as_str()).width());
+        }
+    }
+
+    let mut out = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| {
+                let pad = width - Span::raw(cell.as_str()).width();
+                format!("{cell}{}", " ".repeat(pad))
+            })
+            .collect();
+        out.push_str(cells.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(
+        title: &str,
+        known: usize,
+        outcome: std::result::Result<(usize, String), String>,
+    ) -> UpdateResult {
+        UpdateResult {
+            id: title.to_string(),
+            title: title.to_string(),
+            source: "测试".to_string(),
+            known,
+            outcome,
+        }
+    }
+
+    #[test]
+    fn summary_table_aligns_wide_characters() {
+        let table = summary_table(&[
+            result("斗破苍穹", 100, Ok((103, "第一百零三章".into()))),
+            result("abc", 0, Ok((20, "第二十章".into()))),
+            result("失败", 5, Err("超时".into())),
+        ]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].contains("+3"));
+        // 首次检查只建立基线,不显示新增。
+        assert!(lines[2].contains(" - "));
+        let source_column = |line: &str| Span::raw(&line[..line.find("测试").unwrap_or(0)]).width();
+        assert_eq!(source_column(lines[1]), source_column(lines[2]));
+        assert_eq!(source_column(lines[1]), source_column(lines[3]));
+    }
+}